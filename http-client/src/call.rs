@@ -0,0 +1,48 @@
+use crate::client::HttpClient;
+use hyper::header::{HeaderMap, HeaderName, HeaderValue};
+use jsonrpc::DeserializeOwned;
+use jsonrpsee_types::{error::Error, jsonrpc, traits::Value};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Method call with its options, created by [`HttpClient::call`].
+#[derive(Debug)]
+pub struct CallBuilder<'a, V> {
+	client: &'a HttpClient<V>,
+	method: String,
+	params: Result<jsonrpc::Params, Error>,
+	timeout: Option<Duration>,
+	headers: HeaderMap,
+}
+
+impl<'a, V: Value> CallBuilder<'a, V> {
+	pub(crate) fn new(client: &'a HttpClient<V>, method: String) -> Self {
+		Self { client, method, params: Ok(jsonrpc::Params::None), timeout: None, headers: HeaderMap::new() }
+	}
+
+	/// Sets the parameters of the call, a tuple or an array for positional parameters, a struct or a map for
+	/// named parameters.
+	///
+	/// The call fails if `params` is serialized into anything else.
+	pub fn params(mut self, params: impl Serialize) -> Self {
+		self.params = jsonrpc::Params::serialized(&params).map_err(|e| Error::Custom(e.to_string()));
+		self
+	}
+
+	/// Fails the call with [`Error::RequestTimeout`] if no response arrives within `timeout`.
+	pub fn timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = Some(timeout);
+		self
+	}
+
+	/// Adds an HTTP header to the request, which replaces the default header with the same name.
+	pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+		self.headers.append(name, value);
+		self
+	}
+
+	/// Sends the call and waits for the result.
+	pub async fn send<T: DeserializeOwned>(self) -> Result<T, Error> {
+		self.client.send_request(self.method, self.params?, self.headers, self.timeout).await.map(|(result, _)| result)
+	}
+}
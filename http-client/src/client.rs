@@ -1,162 +1,546 @@
-use crate::transport::HttpTransportClient;
+use crate::call::CallBuilder;
+#[cfg(feature = "rustls")]
+use crate::transport::TlsOptions;
+use crate::transport::{HttpTransportClient, ResponseMetadata};
 use async_trait::async_trait;
 use fnv::FnvHashMap;
+use futures::future::{self, Either};
+use futures_timer::Delay;
+use hyper::header::{HeaderMap, HeaderName, HeaderValue};
 use jsonrpc::DeserializeOwned;
 use jsonrpsee_types::{
-	error::{Error, Mismatch},
-	jsonrpc,
-	traits::Client,
+	error::{CallInfo, Error, Mismatch},
+	jsonrpc::{self, JsonValue, NumberOptions},
+	redact::{LogRedactor, Redact},
+	retry::RetryPolicy,
+	traits::{Client, Value},
+	validation::ResponseValidator,
 };
-use std::convert::TryInto;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 
 /// Http Client Builder.
 #[derive(Debug)]
-pub struct HttpClientBuilder {
+pub struct HttpClientBuilder<V = JsonValue> {
 	max_request_body_size: u32,
+	response_validator: Option<ResponseValidator>,
+	number_options: Option<NumberOptions>,
+	retry_policy: Option<RetryPolicy>,
+	redactor: LogRedactor,
+	headers: HeaderMap,
+	#[cfg(feature = "rustls")]
+	tls: TlsOptions,
+	marker: PhantomData<V>,
 }
 
-impl HttpClientBuilder {
+impl<V: Value> HttpClientBuilder<V> {
 	/// Sets the maximum size of a request body in bytes (default is 10 MiB).
 	pub fn max_request_body_size(mut self, size: u32) -> Self {
 		self.max_request_body_size = size;
 		self
 	}
 
+	/// Validate the results of method calls with `validator` before decoding them, results it rejects fail
+	/// with [`Error::InvalidResponseField`].
+	///
+	/// Disabled by default.
+	pub fn response_validator(mut self, validator: ResponseValidator) -> Self {
+		self.response_validator = Some(validator);
+		self
+	}
+
+	/// Apply `options` to the parameters of all calls and to the results of method calls, see [`NumberOptions`].
+	///
+	/// Parameters are already serialized when passed to the client, serialize them with
+	/// [`NumberOptions::to_value`] to also handle non-finite floats.
+	///
+	/// Disabled by default.
+	pub fn number_options(mut self, options: NumberOptions) -> Self {
+		self.number_options = Some(options);
+		self
+	}
+
+	/// Re-issue the method calls that failed with a transport error or a timeout according to `policy`, calls
+	/// answered with a JSON-RPC error are never retried.
+	///
+	/// Notifications and batch requests are not retried.
+	///
+	/// Disabled by default.
+	pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+		self.retry_policy = Some(policy);
+		self
+	}
+
+	/// Mask the params with `redact` before the requests and responses are logged, see
+	/// [`Redaction`](jsonrpsee_types::redact::Redaction) to mask params by position or object key.
+	///
+	/// By default, the params are logged as they are.
+	pub fn redact_logs(mut self, redact: impl Redact) -> Self {
+		self.redactor = LogRedactor::new(redact);
+		self
+	}
+
+	/// Add the HTTP header `name` to every request, such as an API key, in addition to the values already added
+	/// for `name`.
+	///
+	/// The headers replace the default `Content-Type` and `Accept` headers if they contain them, and are
+	/// replaced by the headers passed to [`HttpClient::request_with_headers`].
+	pub fn add_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+		self.headers.append(name, value);
+		self
+	}
+
+	/// Send `user_agent` as the `User-Agent` header of every request, for servers that reject requests without
+	/// one or with the one of another client.
+	///
+	/// No `User-Agent` header is sent by default.
+	pub fn set_user_agent(mut self, user_agent: HeaderValue) -> Self {
+		self.headers.insert(hyper::header::USER_AGENT, user_agent);
+		self
+	}
+
+	/// Enable or disable TLS session resumption for `https://` connections.
+	///
+	/// When enabled, sessions are cached in memory and shared by all clients of the process with the default TLS
+	/// options, so that reconnecting to the same endpoint skips the full TLS handshake.
+	///
+	/// Enabled by default.
+	#[cfg(feature = "rustls")]
+	pub fn tls_session_resumption(mut self, enabled: bool) -> Self {
+		self.tls.session_resumption = enabled;
+		self
+	}
+
+	/// Use `config` to establish `https://` connections, for example to authenticate with client certificates.
+	///
+	/// Sessions are stored by the session storage of `config`, shared by the clients built with the same
+	/// `config`, unless disabled with [`HttpClientBuilder::tls_session_resumption`]. HTTP/2 is only negotiated if
+	/// the ALPN protocols of `config` include `h2`.
+	///
+	/// By default, the root certificates of the OS are trusted.
+	#[cfg(feature = "rustls")]
+	pub fn tls_config(mut self, config: rustls::ClientConfig) -> Self {
+		self.tls.config = Some(std::sync::Arc::new(config));
+		self
+	}
+
+	/// Trust the DER-encoded root certificate `der`, such as the certificate of a private CA, in addition to the
+	/// root certificates of the OS or of [`HttpClientBuilder::tls_config`].
+	///
+	/// [`HttpClientBuilder::build`] fails if `der` isn't a valid certificate.
+	#[cfg(feature = "rustls")]
+	pub fn add_root_certificate(mut self, der: impl Into<Vec<u8>>) -> Self {
+		self.tls.root_certificates.push(der.into());
+		self
+	}
+
+	/// Accept any server certificate, including self-signed, expired or issued for another host, for local test
+	/// setups.
+	///
+	/// This exposes the requests to anyone able to intercept the connections, never enable it in production.
+	///
+	/// Disabled by default.
+	#[cfg(feature = "rustls")]
+	pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+		self.tls.accept_invalid_certs = accept;
+		self
+	}
+
+	/// Returns the options of the builder that are plain data, see [`ClientConfig`].
+	pub fn to_config(&self) -> ClientConfig {
+		ClientConfig {
+			max_request_body_size: self.max_request_body_size,
+			#[cfg(feature = "rustls")]
+			tls_session_resumption: self.tls.session_resumption,
+			number_options: self.number_options,
+		}
+	}
+
+	/// Set the intermediate value type responses are decoded into, defaults to [`JsonValue`].
+	pub fn value_type<T: Value>(self) -> HttpClientBuilder<T> {
+		HttpClientBuilder {
+			max_request_body_size: self.max_request_body_size,
+			response_validator: self.response_validator,
+			number_options: self.number_options,
+			retry_policy: self.retry_policy,
+			redactor: self.redactor,
+			headers: self.headers,
+			#[cfg(feature = "rustls")]
+			tls: self.tls,
+			marker: PhantomData,
+		}
+	}
+
 	/// Build the HTTP client with target to connect to.
-	pub fn build(self, target: impl AsRef<str>) -> Result<HttpClient, Error> {
+	pub fn build(self, target: impl AsRef<str>) -> Result<HttpClient<V>, Error> {
 		let transport = HttpTransportClient::new(target, self.max_request_body_size)
-			.map_err(|e| Error::TransportError(Box::new(e)))?;
-		Ok(HttpClient { transport, request_id: AtomicU64::new(0) })
+			.map_err(|e| Error::TransportError(Box::new(e)))?
+			.with_redactor(self.redactor)
+			.with_headers(self.headers);
+		#[cfg(feature = "rustls")]
+		let transport = transport.with_tls(&self.tls).map_err(|e| Error::TransportError(Box::new(e)))?;
+		Ok(HttpClient {
+			transport,
+			request_id: AtomicU64::new(0),
+			validator: self.response_validator,
+			number_options: self.number_options,
+			retry_policy: self.retry_policy,
+			marker: PhantomData,
+		})
 	}
 }
 
 impl Default for HttpClientBuilder {
 	fn default() -> Self {
-		Self { max_request_body_size: 10 * 1024 * 1024 }
+		Self {
+			max_request_body_size: 10 * 1024 * 1024,
+			response_validator: None,
+			number_options: None,
+			retry_policy: None,
+			redactor: LogRedactor::default(),
+			headers: HeaderMap::new(),
+			#[cfg(feature = "rustls")]
+			tls: TlsOptions::default(),
+			marker: PhantomData,
+		}
+	}
+}
+
+/// Options of a [`HttpClientBuilder`] as plain data, to load them with any serde format such as TOML, YAML or
+/// environment variables.
+///
+/// The fields are named after the methods of the builder. Missing fields take the defaults of the builder and
+/// unknown fields are rejected. The response validator, the retry policy, the redaction of the logs, the headers and
+/// the TLS options other than the session resumption are set on the builder returned by [`HttpClientBuilder::from`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClientConfig {
+	/// See [`HttpClientBuilder::max_request_body_size`].
+	pub max_request_body_size: u32,
+	/// See [`HttpClientBuilder::tls_session_resumption`].
+	#[cfg(feature = "rustls")]
+	pub tls_session_resumption: bool,
+	/// See [`HttpClientBuilder::number_options`].
+	pub number_options: Option<NumberOptions>,
+}
+
+impl Default for ClientConfig {
+	fn default() -> Self {
+		HttpClientBuilder::default().to_config()
+	}
+}
+
+/// Creates a builder with the options of `config`, the other options have their defaults.
+impl From<ClientConfig> for HttpClientBuilder {
+	fn from(config: ClientConfig) -> Self {
+		// NOTE: destructured without `..` such that new options can't be left out.
+		let ClientConfig {
+			max_request_body_size,
+			#[cfg(feature = "rustls")]
+			tls_session_resumption,
+			number_options,
+		} = config;
+		let builder = Self { max_request_body_size, number_options, ..Self::default() };
+		#[cfg(feature = "rustls")]
+		let builder = builder.tls_session_resumption(tls_session_resumption);
+		builder
 	}
 }
 
 /// JSON-RPC HTTP Client that provides functionality to perform method calls and notifications.
+///
+/// Responses are decoded into the intermediate value type `V` before being converted
+/// to the types requested by the user, see [`Value`].
 #[derive(Debug)]
-pub struct HttpClient {
+pub struct HttpClient<V = JsonValue> {
 	/// HTTP transport client.
 	transport: HttpTransportClient,
 	/// Request ID that wraps around when overflowing.
 	request_id: AtomicU64,
+	/// Validator of the results of method calls.
+	validator: Option<ResponseValidator>,
+	/// Handling of numbers in parameters and results.
+	number_options: Option<NumberOptions>,
+	/// Retries of the method calls.
+	retry_policy: Option<RetryPolicy>,
+	/// Intermediate value type.
+	marker: PhantomData<fn() -> V>,
 }
 
-#[async_trait]
-impl Client for HttpClient {
-	async fn notification<M, P>(&self, method: M, params: P) -> Result<(), Error>
+impl<V: Value> HttpClient<V> {
+	fn encode_params(&self, params: jsonrpc::Params) -> Result<jsonrpc::Params, Error> {
+		match self.number_options {
+			Some(options) => options.apply_params(params).map_err(Error::ParseError),
+			None => Ok(params),
+		}
+	}
+
+	fn decode<T: DeserializeOwned>(&self, value: V) -> Result<T, Error> {
+		match self.number_options {
+			Some(options) => options.decode(value),
+			None => value.decode(),
+		}
+		.map_err(Error::ParseError)
+	}
+
+	/// Prepare a call to `method`, the options of the call are set on the returned builder.
+	///
+	/// ```no_run
+	/// # async fn call(client: jsonrpsee_http_client::HttpClient) -> Result<(), jsonrpsee_types::error::Error> {
+	/// use jsonrpsee_http_client::{HeaderName, HeaderValue};
+	/// use std::time::Duration;
+	///
+	/// let sum: u64 = client
+	///     .call("add")
+	///     .params((1, 2))
+	///     .timeout(Duration::from_secs(5))
+	///     .header(HeaderName::from_static("x-request-id"), HeaderValue::from_static("42"))
+	///     .send()
+	///     .await?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn call(&self, method: impl Into<String>) -> CallBuilder<'_, V> {
+		CallBuilder::new(self, method.into())
+	}
+
+	/// Perform a request towards the server with additional HTTP `headers`, such as an idempotency key or
+	/// the credentials of the call.
+	///
+	/// The headers replace the default `Content-Type` and `Accept` headers if they contain them.
+	pub async fn request_with_headers<T, M, P>(&self, method: M, params: P, headers: HeaderMap) -> Result<T, Error>
 	where
-		M: Into<String> + Send,
-		P: Into<jsonrpc::Params> + Send,
+		T: DeserializeOwned,
+		M: Into<String>,
+		P: Into<jsonrpc::Params>,
 	{
-		let request = jsonrpc::Request::Single(jsonrpc::Call::Notification(jsonrpc::Notification {
-			jsonrpc: jsonrpc::Version::V2,
-			method: method.into(),
-			params: params.into(),
-		}));
-		self.transport.send_notification(request).await.map_err(|e| Error::TransportError(Box::new(e)))
+		self.send_request(method.into(), params.into(), headers, None).await.map(|(result, _)| result)
 	}
 
-	/// Perform a request towards the server.
-	async fn request<T, M, P>(&self, method: M, params: P) -> Result<T, Error>
+	/// Perform a request towards the server and return the HTTP metadata of the response with the result,
+	/// for example to read rate limit headers.
+	pub async fn request_with_metadata<T, M, P>(&self, method: M, params: P) -> Result<(T, ResponseMetadata), Error>
 	where
 		T: DeserializeOwned,
-		M: Into<String> + Send,
-		P: Into<jsonrpc::Params> + Send,
+		M: Into<String>,
+		P: Into<jsonrpc::Params>,
 	{
+		self.send_request(method.into(), params.into(), HeaderMap::new(), None).await
+	}
+
+	/// Sends a call to `method`, failing with [`Error::RequestTimeout`] if no response arrives within
+	/// `timeout`, and retries it according to the retry policy.
+	pub(crate) async fn send_request<T: DeserializeOwned>(
+		&self,
+		method: String,
+		params: jsonrpc::Params,
+		headers: HeaderMap,
+		timeout: Option<Duration>,
+	) -> Result<(T, ResponseMetadata), Error> {
+		let policy = match self.retry_policy.as_ref() {
+			Some(policy) => policy,
+			None => return self.send_once(method, params, headers, timeout).await,
+		};
+		let mut retries = 0;
+		loop {
+			let err = match self.send_once(method.clone(), params.clone(), headers.clone(), timeout).await {
+				Ok(res) => return Ok(res),
+				Err(err) => err,
+			};
+			match policy.next_retry(retries, &err) {
+				Some(delay) => {
+					log::debug!("retry call to {} in {:?} after: {}", method, delay, err);
+					Delay::new(delay).await;
+					retries += 1;
+				}
+				None => return Err(err),
+			}
+		}
+	}
+
+	async fn send_once<T: DeserializeOwned>(
+		&self,
+		method: String,
+		params: jsonrpc::Params,
+		headers: HeaderMap,
+		timeout: Option<Duration>,
+	) -> Result<(T, ResponseMetadata), Error> {
 		// NOTE: `fetch_add` wraps on overflow which is intended.
 		let id = self.request_id.fetch_add(1, Ordering::Relaxed);
+		let call = CallInfo::new(method.clone(), Some(jsonrpc::Id::Num(id)));
 		let request = jsonrpc::Request::Single(jsonrpc::Call::MethodCall(jsonrpc::MethodCall {
 			jsonrpc: jsonrpc::Version::V2,
-			method: method.into(),
-			params: params.into(),
+			method,
+			params: self.encode_params(params)?,
 			id: jsonrpc::Id::Num(id),
 		}));
 
-		let response = self
-			.transport
-			.send_request_and_wait_for_response(request)
-			.await
-			.map_err(|e| Error::TransportError(Box::new(e)))?;
+		let send = self.transport.send_request_with_metadata::<V>(request, headers);
+		let res = match timeout {
+			Some(timeout) => {
+				futures::pin_mut!(send);
+				match future::select(send, Delay::new(timeout)).await {
+					Either::Left((res, _)) => res,
+					Either::Right(_) => return Err(Error::RequestTimeout(call)),
+				}
+			}
+			None => send.await,
+		};
+		let (response, metadata) = res.map_err(|e| Error::TransportError(Box::new(e)))?;
 
-		let json_value = match response {
+		let value = match response {
 			jsonrpc::Response::Single(response) => match response.id() {
-				jsonrpc::Id::Num(n) if n == &id => response.try_into().map_err(Error::Request),
+				jsonrpc::Id::Num(n) if n == &id => response.into_result().map_err(Error::Request),
 				_ => Err(Error::InvalidRequestId),
 			},
-			jsonrpc::Response::Batch(_rps) => Err(Error::InvalidResponse(Mismatch {
-				expected: "Single response".into(),
-				got: "Batch Response".into(),
-			})),
-			jsonrpc::Response::Notif(_notif) => Err(Error::InvalidResponse(Mismatch {
-				expected: "Single response".into(),
-				got: "Notification Response".into(),
-			})),
+			jsonrpc::Response::Batch(_rps) => Err(Error::InvalidResponse(
+				Some(call.clone()),
+				Mismatch { expected: "Single response".into(), got: "Batch Response".into() },
+			)),
+			jsonrpc::Response::Notif(_) | jsonrpc::Response::Notification(_) => Err(Error::InvalidResponse(
+				Some(call.clone()),
+				Mismatch { expected: "Single response".into(), got: "Notification Response".into() },
+			)),
 		}?;
-		jsonrpc::from_value(json_value).map_err(Error::ParseError)
+		if let Some(validator) = self.validator.as_ref() {
+			validator.validate(&call.method, &value)?;
+		}
+		Ok((self.decode(value)?, metadata))
 	}
 
-	async fn batch_request<T, M, P>(&self, batch: Vec<(M, P)>) -> Result<Vec<T>, Error>
+	/// Sends a batch request and returns the results of its calls, in the order of the calls.
+	async fn send_batch<M, P>(&self, batch: Vec<(M, P)>) -> Result<Vec<Result<V, jsonrpc::Error>>, Error>
 	where
-		T: DeserializeOwned + Default + Clone,
-		M: Into<String> + Send,
-		P: Into<jsonrpc::Params> + Send,
+		M: Into<String>,
+		P: Into<jsonrpc::Params>,
 	{
 		let mut calls = Vec::with_capacity(batch.len());
 		// NOTE(niklasad1): `ID` is not necessarily monotonically increasing.
-		let mut ordered_requests = Vec::with_capacity(batch.len());
 		let mut request_set = FnvHashMap::with_capacity_and_hasher(batch.len(), Default::default());
 
+		let mut validated_methods = Vec::new();
+
 		for (pos, (method, params)) in batch.into_iter().enumerate() {
 			let id = self.request_id.fetch_add(1, Ordering::SeqCst);
+			let method = method.into();
+			if self.validator.is_some() {
+				validated_methods.push(method.clone());
+			}
 			calls.push(jsonrpc::Call::MethodCall(jsonrpc::MethodCall {
 				jsonrpc: jsonrpc::Version::V2,
-				method: method.into(),
-				params: params.into(),
+				method,
+				params: self.encode_params(params.into())?,
 				id: jsonrpc::Id::Num(id),
 			}));
-			ordered_requests.push(id);
 			request_set.insert(id, pos);
 		}
 
+		let batch_len = calls.len();
 		let batch_request = jsonrpc::Request::Batch(calls);
 		let response = self
 			.transport
-			.send_request_and_wait_for_response(batch_request)
+			.send_request_and_wait_for_response::<V>(batch_request)
 			.await
 			.map_err(|e| Error::TransportError(Box::new(e)))?;
 
-		match response {
-			jsonrpc::Response::Single(_) => Err(Error::InvalidResponse(Mismatch {
-				expected: "Batch response".into(),
-				got: "Single Response".into(),
-			})),
-			jsonrpc::Response::Notif(_notif) => Err(Error::InvalidResponse(Mismatch {
-				expected: "Batch response".into(),
-				got: "Notification response".into(),
-			})),
-			jsonrpc::Response::Batch(rps) => {
-				// NOTE: `T::default` is placeholder and will be replaced in loop below.
-				let mut responses = vec![T::default(); ordered_requests.len()];
-				for rp in rps {
-					let id = match rp.id().as_number() {
-						Some(n) => *n,
-						_ => return Err(Error::InvalidRequestId),
-					};
-					let pos = match request_set.get(&id) {
-						Some(pos) => *pos,
-						None => return Err(Error::InvalidRequestId),
-					};
-					let json_val: jsonrpc::JsonValue = rp.try_into().map_err(Error::Request)?;
-					let response = jsonrpc::from_value(json_val).map_err(Error::ParseError)?;
-					responses[pos] = response;
-				}
-				Ok(responses)
+		let rps = match response {
+			jsonrpc::Response::Single(_) => {
+				return Err(Error::InvalidResponse(
+					None,
+					Mismatch { expected: "Batch response".into(), got: "Single Response".into() },
+				))
 			}
+			jsonrpc::Response::Notif(_) | jsonrpc::Response::Notification(_) => {
+				return Err(Error::InvalidResponse(
+					None,
+					Mismatch { expected: "Batch response".into(), got: "Notification response".into() },
+				))
+			}
+			jsonrpc::Response::Batch(rps) => rps,
+		};
+
+		let mut responses: Vec<Option<Result<V, jsonrpc::Error>>> = (0..batch_len).map(|_| None).collect();
+		for rp in rps {
+			let pos = match rp.id().as_number().and_then(|id| request_set.get(id)) {
+				Some(pos) => *pos,
+				None => return Err(Error::InvalidRequestId),
+			};
+			let value = rp.into_result();
+			if let (Ok(value), Some(validator)) = (value.as_ref(), self.validator.as_ref()) {
+				validator.validate(&validated_methods[pos], value)?;
+			}
+			responses[pos] = Some(value);
+		}
+		let answered = responses.iter().filter(|rp| rp.is_some()).count();
+		if answered != batch_len {
+			return Err(Error::InvalidResponse(
+				None,
+				Mismatch { expected: format!("{} responses", batch_len), got: format!("{} responses", answered) },
+			));
 		}
+		Ok(responses.into_iter().flatten().collect())
+	}
+}
+
+#[async_trait]
+impl<V: Value> Client for HttpClient<V> {
+	async fn notification<M, P>(&self, method: M, params: P) -> Result<(), Error>
+	where
+		M: Into<String> + Send,
+		P: Into<jsonrpc::Params> + Send,
+	{
+		let request = jsonrpc::Request::Single(jsonrpc::Call::Notification(jsonrpc::Notification {
+			jsonrpc: jsonrpc::Version::V2,
+			method: method.into(),
+			params: self.encode_params(params.into())?,
+		}));
+		self.transport.send_notification(request).await.map_err(|e| Error::TransportError(Box::new(e)))
+	}
+
+	/// Perform a request towards the server.
+	async fn request<T, M, P>(&self, method: M, params: P) -> Result<T, Error>
+	where
+		T: DeserializeOwned,
+		M: Into<String> + Send,
+		P: Into<jsonrpc::Params> + Send,
+	{
+		self.send_request(method.into(), params.into(), HeaderMap::new(), None).await.map(|(result, _)| result)
+	}
+
+	async fn request_with_timeout<T, M, P>(&self, method: M, params: P, timeout: Duration) -> Result<T, Error>
+	where
+		T: DeserializeOwned,
+		M: Into<String> + Send,
+		P: Into<jsonrpc::Params> + Send,
+	{
+		self.send_request(method.into(), params.into(), HeaderMap::new(), Some(timeout)).await.map(|(result, _)| result)
+	}
+
+	async fn batch_request<T, M, P>(&self, batch: Vec<(M, P)>) -> Result<Vec<T>, Error>
+	where
+		T: DeserializeOwned + Default + Clone,
+		M: Into<String> + Send,
+		P: Into<jsonrpc::Params> + Send,
+	{
+		let values = self.send_batch(batch).await?;
+		values.into_iter().map(|val| self.decode(val.map_err(Error::Request)?)).collect()
+	}
+
+	async fn batch_request_results<T, M, P>(&self, batch: Vec<(M, P)>) -> Result<Vec<Result<T, jsonrpc::Error>>, Error>
+	where
+		T: DeserializeOwned,
+		M: Into<String> + Send,
+		P: Into<jsonrpc::Params> + Send,
+	{
+		let values = self.send_batch(batch).await?;
+		values
+			.into_iter()
+			.map(|val| match val {
+				Ok(val) => self.decode(val).map(Ok),
+				Err(err) => Ok(Err(err)),
+			})
+			.collect()
 	}
 }
@@ -17,6 +17,11 @@
 //!
 //! - `tokio1`: Enable to use the library with [`tokio 1.0`](https://docs.rs/tokio/1.2.0/tokio/) (mutually exclusive with `tokio02`)
 //! - `tokio0.2`: Enable to use the library with [`tokio 0.2`](https://docs.rs/tokio/0.2.25/tokio/index.html) (mutually exclusive with `tokio1`)
+//! - `rustls` (default): Enable the TLS options of [`HttpClientBuilder`] with the [`rustls`](https://docs.rs/rustls)
+//!   backend, such as custom root certificates and session resumption (requires `tokio1`). Without it, TLS sessions
+//!   are always resumed
+//!
+//! Only the `rustls` TLS backend is available, there is no `native-tls` backend yet.
 //!
 //! [feature flags]: https://doc.rust-lang.org/cargo/reference/manifest.html#the-features-section
 
@@ -26,21 +31,33 @@ compile_error!("feature `tokio1` and `tokio02` are mutably exclusive");
 #[cfg(not(any(feature = "tokio1", feature = "tokio02")))]
 compile_error!("feature `tokio1` or `tokio02` must be enabled for this crate");
 
+#[cfg(all(feature = "rustls", feature = "tokio02"))]
+compile_error!("feature `rustls` requires `tokio1`");
+
 #[cfg(all(feature = "tokio1", not(feature = "tokio02")))]
 extern crate hyper14 as hyper;
 #[cfg(all(feature = "tokio1", not(feature = "tokio02")))]
 extern crate hyper14_rustls as hyper_rustls;
+/// Re-exported for [`HttpClientBuilder::tls_config`].
+#[cfg(feature = "rustls")]
+pub extern crate rustls19 as rustls;
+#[cfg(feature = "rustls")]
+extern crate rustls_native_certs05 as rustls_native_certs;
 
 #[cfg(all(feature = "tokio02", not(feature = "tokio1")))]
 extern crate hyper13 as hyper;
 #[cfg(all(feature = "tokio02", not(feature = "tokio1")))]
 extern crate hyper13_rustls as hyper_rustls;
 
+mod call;
 mod client;
 mod transport;
 
 #[cfg(test)]
 mod tests;
 
-pub use client::{HttpClient, HttpClientBuilder};
-pub use transport::HttpTransportClient;
+pub use call::CallBuilder;
+pub use client::{ClientConfig, HttpClient, HttpClientBuilder};
+/// Re-exported for [`HttpClient::request_with_headers`] and [`HttpClientBuilder::add_header`].
+pub use hyper::header::{HeaderMap, HeaderName, HeaderValue};
+pub use transport::{HttpTransportClient, ResponseMetadata};
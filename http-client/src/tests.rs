@@ -25,6 +25,234 @@ async fn notification_works() {
 		.unwrap();
 }
 
+#[tokio::test]
+async fn request_with_headers_works() {
+	use crate::{HeaderMap, HeaderValue};
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	// Answers with the value of the `Idempotency-Key` header of the request.
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let uri = format!("http://{}", listener.local_addr().unwrap());
+	tokio::spawn(async move {
+		let (mut socket, _) = listener.accept().await.unwrap();
+		let mut buf = vec![0; 4096];
+		let len = socket.read(&mut buf).await.unwrap();
+		let request = String::from_utf8_lossy(&buf[..len]).to_lowercase();
+		let key = request.lines().find_map(|line| line.strip_prefix("idempotency-key: ")).unwrap_or("none");
+		let body = ok_response(key.into(), Id::Num(0));
+		let response = format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{}", body.len(), body);
+		socket.write_all(response.as_bytes()).await.unwrap();
+	});
+
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+	let mut headers = HeaderMap::new();
+	headers.insert("idempotency-key", HeaderValue::from_static("abc"));
+	let key: String = client.request_with_headers("say_hello", Params::None, headers).await.unwrap();
+	assert_eq!(key, "abc");
+}
+
+#[tokio::test]
+async fn builder_headers_are_sent_with_every_request() {
+	use crate::{HeaderMap, HeaderName, HeaderValue};
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	// Answers with the values of the `X-Api-Key` and `User-Agent` headers of the request.
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let uri = format!("http://{}", listener.local_addr().unwrap());
+	tokio::spawn(async move {
+		let (mut socket, _) = listener.accept().await.unwrap();
+		let mut buf = vec![0; 4096];
+		for id in 0..2 {
+			let len = socket.read(&mut buf).await.unwrap();
+			let request = String::from_utf8_lossy(&buf[..len]).to_lowercase();
+			let header = |name| request.lines().find_map(|line| line.strip_prefix(name)).unwrap_or("none").to_owned();
+			let body = ok_response(format!("{} {}", header("x-api-key: "), header("user-agent: ")).into(), Id::Num(id));
+			let response = format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{}", body.len(), body);
+			socket.write_all(response.as_bytes()).await.unwrap();
+		}
+	});
+
+	let client = HttpClientBuilder::default()
+		.add_header(HeaderName::from_static("x-api-key"), HeaderValue::from_static("key1"))
+		.set_user_agent(HeaderValue::from_static("my-app/1.0"))
+		.build(&uri)
+		.unwrap();
+	let headers: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(headers, "key1 my-app/1.0");
+
+	// The headers of a request replace those of the client.
+	let mut headers = HeaderMap::new();
+	headers.insert("x-api-key", HeaderValue::from_static("key2"));
+	let headers: String = client.request_with_headers("say_hello", Params::None, headers).await.unwrap();
+	assert_eq!(headers, "key2 my-app/1.0");
+}
+
+#[cfg(feature = "rustls")]
+#[tokio::test]
+async fn tls_options_trust_private_ca() {
+	use rustls::internal::pemfile;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	// `test-certs` of the WebSocket client holds a self-signed CA and a certificate for `localhost` issued by it.
+	let ca = pemfile::certs(&mut &include_bytes!("../../ws-client/test-certs/ca.pem")[..]).unwrap();
+	let certs = pemfile::certs(&mut &include_bytes!("../../ws-client/test-certs/localhost.pem")[..]).unwrap();
+	let mut keys =
+		pemfile::pkcs8_private_keys(&mut &include_bytes!("../../ws-client/test-certs/localhost.key")[..]).unwrap();
+	let mut server_config = rustls::ServerConfig::new(rustls::NoClientAuth::new());
+	server_config.set_single_cert(certs, keys.remove(0)).unwrap();
+	let acceptor = tokio_rustls::TlsAcceptor::from(std::sync::Arc::new(server_config));
+
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let uri = format!("https://localhost:{}", listener.local_addr().unwrap().port());
+	tokio::spawn(async move {
+		loop {
+			let (socket, _) = listener.accept().await.unwrap();
+			let mut socket = match acceptor.accept(socket).await {
+				Ok(socket) => socket,
+				Err(_) => continue,
+			};
+			let _len = socket.read(&mut vec![0; 4096]).await.unwrap();
+			let body = ok_response("hello".into(), Id::Num(0));
+			let response = format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{}", body.len(), body);
+			socket.write_all(response.as_bytes()).await.unwrap();
+		}
+	});
+
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+	let response: Result<String, _> = client.request("say_hello", Params::None).await;
+	assert!(response.is_err());
+
+	for builder in [
+		HttpClientBuilder::default().add_root_certificate(ca[0].0.clone()),
+		HttpClientBuilder::default().danger_accept_invalid_certs(true),
+	] {
+		let client = builder.build(&uri).unwrap();
+		let response: String = client.request("say_hello", Params::None).await.unwrap();
+		assert_eq!(response, "hello");
+	}
+
+	let err = HttpClientBuilder::default().add_root_certificate(b"not a certificate".to_vec()).build(&uri).unwrap_err();
+	assert!(matches!(err, Error::TransportError(_)));
+}
+
+#[tokio::test]
+async fn call_builder_sends_params_and_headers() {
+	use crate::{HeaderName, HeaderValue};
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	// Answers with the value of the `Idempotency-Key` header and the parameters of the request.
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let uri = format!("http://{}", listener.local_addr().unwrap());
+	tokio::spawn(async move {
+		let (mut socket, _) = listener.accept().await.unwrap();
+		let mut buf = vec![0; 4096];
+		let len = socket.read(&mut buf).await.unwrap();
+		let request = String::from_utf8_lossy(&buf[..len]).to_lowercase();
+		let key = request.lines().find_map(|line| line.strip_prefix("idempotency-key: ")).unwrap_or("none");
+		let (_, body) = request.split_once("\r\n\r\n").unwrap();
+		let params = serde_json::from_str::<JsonValue>(body).unwrap()["params"].to_string();
+		let body = ok_response(format!("{} {}", key, params).into(), Id::Num(0));
+		let response = format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{}", body.len(), body);
+		socket.write_all(response.as_bytes()).await.unwrap();
+	});
+
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+	let result: String = client
+		.call("say_hello")
+		.params((1, 2))
+		.timeout(std::time::Duration::from_secs(5))
+		.header(HeaderName::from_static("idempotency-key"), HeaderValue::from_static("abc"))
+		.send()
+		.await
+		.unwrap();
+	assert_eq!(result, "abc [1,2]");
+
+	let err = client.call("say_hello").params(1).send::<String>().await.unwrap_err();
+	assert!(matches!(err, Error::Custom(_)));
+}
+
+#[tokio::test]
+async fn request_with_metadata_works() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	// Answers with a rate limit header.
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	tokio::spawn(async move {
+		let (mut socket, _) = listener.accept().await.unwrap();
+		let _len = socket.read(&mut vec![0; 4096]).await.unwrap();
+		let body = ok_response("hello".into(), Id::Num(0));
+		let response =
+			format!("HTTP/1.1 200 OK\r\nx-ratelimit-remaining: 41\r\ncontent-length: {}\r\n\r\n{}", body.len(), body);
+		socket.write_all(response.as_bytes()).await.unwrap();
+	});
+
+	let client = HttpClientBuilder::default().build(format!("http://{}", addr)).unwrap();
+	let (result, metadata): (String, _) = client.request_with_metadata("say_hello", Params::None).await.unwrap();
+	assert_eq!(result, "hello");
+	assert_eq!(metadata.status_code, 200);
+	assert_eq!(metadata.headers.get("x-ratelimit-remaining").unwrap(), "41");
+	assert_eq!(metadata.remote_addr, Some(addr));
+}
+
+#[tokio::test]
+async fn request_with_timeout_works() {
+	// Accepts connections but never responds.
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let uri = format!("http://{}", listener.local_addr().unwrap());
+	tokio::spawn(async move {
+		let mut sockets = Vec::new();
+		while let Ok((socket, _)) = listener.accept().await {
+			sockets.push(socket);
+		}
+	});
+
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+	let err = client
+		.request_with_timeout::<JsonValue, _, _>("say_hello", Params::None, std::time::Duration::from_millis(50))
+		.await
+		.unwrap_err();
+	assert!(matches!(err, Error::RequestTimeout(ref call) if call.method == "say_hello"));
+	assert_eq!(err.to_string(), "Request timeout waiting for response to say_hello (id=0)");
+}
+
+#[tokio::test]
+async fn retry_policy_stops_at_json_rpc_errors() {
+	use jsonrpsee_types::retry::RetryPolicy;
+	use std::time::Duration;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	// Drops the first connection without responding and answers the next calls with an error.
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let uri = format!("http://{}", listener.local_addr().unwrap());
+	let (calls_tx, mut calls_rx) = tokio::sync::mpsc::unbounded_channel();
+	tokio::spawn(async move {
+		let mut dropped = false;
+		while let Ok((mut socket, _)) = listener.accept().await {
+			let mut buf = vec![0; 4096];
+			let len = socket.read(&mut buf).await.unwrap();
+			let request = String::from_utf8_lossy(&buf[..len]).into_owned();
+			let body = &request[request.find("\r\n\r\n").unwrap() + 4..];
+			let id: u64 = serde_json::from_str::<JsonValue>(body).unwrap()["id"].as_u64().unwrap();
+			calls_tx.send(id).unwrap();
+			if !std::mem::replace(&mut dropped, true) {
+				continue;
+			}
+			let body = method_not_found(Id::Num(id));
+			let response = format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{}", body.len(), body);
+			socket.write_all(response.as_bytes()).await.unwrap();
+		}
+	});
+
+	let policy = RetryPolicy::new(3, Duration::from_millis(10)).retry_on(|_| true);
+	let client = HttpClientBuilder::default().retry_policy(policy).build(&uri).unwrap();
+	let err = client.request::<JsonValue, _, _>("say_hello", Params::None).await.unwrap_err();
+	assert!(matches!(err, Error::Request(ref e) if e.code == ErrorCode::MethodNotFound));
+	assert_eq!(calls_rx.recv().await, Some(0));
+	assert_eq!(calls_rx.recv().await, Some(1));
+	assert!(calls_rx.try_recv().is_err());
+}
+
 #[tokio::test]
 async fn response_with_wrong_id() {
 	let err = run_request_with_response(ok_response("hello".into(), Id::Num(99))).await.unwrap_err();
@@ -65,7 +293,40 @@ async fn internal_error_works() {
 async fn subscription_response_to_request() {
 	let req = r#"{"jsonrpc":"2.0","method":"subscribe_hello","params":{"subscription":"3px4FrtxSYQ1zBKW154NoVnrDhrq764yQNCXEgZyM6Mu","result":"hello my friend"}}"#.to_string();
 	let err = run_request_with_response(req).await.unwrap_err();
-	assert!(matches!(err, Error::InvalidResponse(_)));
+	assert!(matches!(err, Error::InvalidResponse(Some(call), _) if call.method == "say_hello"));
+}
+
+#[tokio::test]
+async fn response_rejected_by_schema() {
+	use jsonrpsee_types::validation::{InvalidField, ResponseValidator, Schema};
+
+	let server_addr = http_server_with_hardcoded_response(ok_response("hello".into(), Id::Num(0))).await;
+	let uri = format!("http://{}", server_addr);
+	let validator = ResponseValidator::new().schema("say_hello", Schema::Number);
+	let client = HttpClientBuilder::default().response_validator(validator).build(&uri).unwrap();
+	let err = client.request::<JsonValue, _, _>("say_hello", Params::None).await.unwrap_err();
+	assert!(matches!(err, Error::InvalidResponseField(field) if field == InvalidField::new("$", "number", "string")));
+}
+
+#[tokio::test]
+async fn large_integer_results_follow_number_options() {
+	use jsonrpsee_types::jsonrpc::{NumberOptions, NumberPolicy};
+
+	let server_addr = http_server_with_hardcoded_response(ok_response(u64::MAX.into(), Id::Num(0))).await;
+	let uri = format!("http://{}", server_addr);
+	let options = |large_integers| NumberOptions { large_integers, ..Default::default() };
+
+	let client = HttpClientBuilder::default().number_options(options(NumberPolicy::Stringify)).build(&uri).unwrap();
+	let result: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(result, u64::MAX.to_string());
+
+	let client = HttpClientBuilder::default().number_options(options(NumberPolicy::Error)).build(&uri).unwrap();
+	let err = client.request::<u64, _, _>("say_hello", Params::None).await.unwrap_err();
+	assert!(matches!(err, Error::ParseError(_)));
+
+	let client = HttpClientBuilder::default().number_options(options(NumberPolicy::Error)).build(&uri).unwrap();
+	let err = client.request::<u64, _, _>("say_hello", Params::Array(vec![u64::MAX.into()])).await.unwrap_err();
+	assert!(matches!(err, Error::ParseError(_)));
 }
 
 #[tokio::test]
@@ -92,6 +353,25 @@ async fn batch_request_out_of_order_response() {
 	assert_eq!(response, vec!["hello".to_string(), "goodbye".to_string(), "here's your swag".to_string()]);
 }
 
+#[tokio::test]
+async fn batch_request_results_keep_errors_per_call() {
+	let batch_request = vec![("say_hello".to_string(), Params::None), ("say_goodbye".to_string(), Params::None)];
+	let server_response = r#"[{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}, {"jsonrpc":"2.0","result":"hello","id":0}]"#.to_string();
+	let server_addr = http_server_with_hardcoded_response(server_response).await;
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+	let results: Vec<Result<String, jsonrpc::Error>> = client.batch_request_results(batch_request).await.unwrap();
+	assert_eq!(results, vec![Ok("hello".to_string()), Err(jsonrpc::Error::method_not_found())]);
+}
+
+#[tokio::test]
+async fn batch_request_with_missing_response() {
+	let batch_request = vec![("say_hello".to_string(), Params::None), ("say_goodbye".to_string(), Params::None)];
+	let server_response = r#"[{"jsonrpc":"2.0","result":"hello","id":0}]"#.to_string();
+	let err = run_batch_request_with_response(batch_request, server_response).await.unwrap_err();
+	assert!(matches!(err, Error::InvalidResponse(None, _)));
+}
+
 async fn run_batch_request_with_response(batch: Vec<(String, Params)>, response: String) -> Result<Vec<String>, Error> {
 	let server_addr = http_server_with_hardcoded_response(response).await;
 	let uri = format!("http://{}", server_addr);
@@ -115,3 +395,13 @@ fn assert_jsonrpc_error_response(response: Error, code: ErrorCode, message: Stri
 		e @ _ => panic!("Expected error: \"{}\", got: {:?}", expected, e),
 	};
 }
+
+#[test]
+fn config_round_trips_through_builder() {
+	use crate::ClientConfig;
+
+	assert_eq!(serde_json::from_str::<ClientConfig>("{}").unwrap(), ClientConfig::default());
+	let config: ClientConfig =
+		serde_json::from_str(r#"{"max_request_body_size":1024,"tls_session_resumption":false}"#).unwrap();
+	assert_eq!(HttpClientBuilder::from(config.clone()).to_config(), config);
+}
@@ -6,10 +6,15 @@
 // that we need to be guaranteed that hyper doesn't re-use an existing connection if we ever reset
 // the JSON-RPC request id to a value that might have already been used.
 
-use hyper::client::{Client, HttpConnector};
+use hyper::client::{connect::HttpInfo, Client, HttpConnector};
+use hyper::header::HeaderMap;
 use hyper_rustls::HttpsConnector;
-use jsonrpsee_types::{error::GenericTransportError, jsonrpc};
+use jsonrpsee_types::{error::GenericTransportError, jsonrpc, redact::LogRedactor, traits::Value};
 use jsonrpsee_utils::http::hyper_helpers;
+use once_cell::sync::OnceCell;
+use std::net::SocketAddr;
+#[cfg(feature = "rustls")]
+use std::{fmt, sync::Arc};
 use thiserror::Error;
 
 const CONTENT_TYPE_JSON: &str = "application/json";
@@ -23,38 +28,86 @@ pub struct HttpTransportClient {
 	client: Client<HttpsConnector<HttpConnector>>,
 	/// Configurable max request body size
 	max_request_body_size: u32,
+	/// Masking of the params in logs.
+	redactor: LogRedactor,
+	/// Headers sent with every request.
+	headers: HeaderMap,
+}
+
+/// HTTP metadata of the response to a request.
+#[derive(Debug, Clone)]
+pub struct ResponseMetadata {
+	/// Status code of the response.
+	pub status_code: u16,
+	/// Headers of the response, for example rate limits such as `X-RateLimit-Remaining`.
+	pub headers: HeaderMap,
+	/// Address of the server that sent the response, if known.
+	pub remote_addr: Option<SocketAddr>,
 }
 
 impl HttpTransportClient {
 	/// Initializes a new HTTP client.
+	///
+	/// The TLS session cache is shared with every other client of the process with the default TLS options.
 	pub fn new(target: impl AsRef<str>, max_request_body_size: u32) -> Result<Self, Error> {
 		let target = url::Url::parse(target.as_ref()).map_err(|e| Error::Url(format!("Invalid URL: {}", e)))?;
 		if target.scheme() == "http" || target.scheme() == "https" {
-			#[cfg(feature = "tokio1")]
-			let connector = HttpsConnector::with_native_roots();
-			#[cfg(feature = "tokio02")]
-			let connector = HttpsConnector::new();
+			let connector = https_connector();
 			let client = Client::builder().build::<_, hyper::Body>(connector);
-			Ok(HttpTransportClient { client, target, max_request_body_size })
+			Ok(HttpTransportClient {
+				client,
+				target,
+				max_request_body_size,
+				redactor: LogRedactor::default(),
+				headers: HeaderMap::new(),
+			})
 		} else {
 			Err(Error::Url("URL scheme not supported, expects 'http' or 'https'".into()))
 		}
 	}
 
-	/// Send request.
-	async fn send_request(&self, request: jsonrpc::Request) -> Result<hyper::Response<hyper::Body>, Error> {
+	/// Mask the params of the logged messages with `redactor`.
+	pub(crate) fn with_redactor(mut self, redactor: LogRedactor) -> Self {
+		self.redactor = redactor;
+		self
+	}
+
+	/// Send `headers` with every request, replacing the default headers they contain.
+	pub(crate) fn with_headers(mut self, headers: HeaderMap) -> Self {
+		self.headers = headers;
+		self
+	}
+
+	/// Establish `https://` connections with `tls`, the connector is never shared if `tls` isn't the default.
+	#[cfg(feature = "rustls")]
+	pub(crate) fn with_tls(mut self, tls: &TlsOptions) -> Result<Self, Error> {
+		if !tls.is_default() {
+			self.client = Client::builder().build::<_, hyper::Body>(custom_https_connector(tls)?);
+		}
+		Ok(self)
+	}
+
+	/// Send request with `headers` in addition to the default headers and to the headers of the client,
+	/// replacing those they contain.
+	async fn send_request(
+		&self,
+		request: jsonrpc::Request,
+		headers: HeaderMap,
+	) -> Result<hyper::Response<hyper::Body>, Error> {
 		let body = jsonrpc::to_vec(&request).map_err(Error::Serialization)?;
-		log::debug!("send: {}", request);
+		log::debug!("send: {}", self.redactor.message(&request));
 
 		if body.len() > self.max_request_body_size as usize {
 			return Err(Error::RequestTooLarge);
 		}
 
-		let req = hyper::Request::post(self.target.as_str())
+		let mut req = hyper::Request::post(self.target.as_str())
 			.header(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static(CONTENT_TYPE_JSON))
 			.header(hyper::header::ACCEPT, hyper::header::HeaderValue::from_static(CONTENT_TYPE_JSON))
 			.body(From::from(body))
 			.expect("URI and request headers are valid; qed");
+		req.headers_mut().extend(self.headers.clone());
+		req.headers_mut().extend(headers);
 
 		let response = self.client.request(req).await.map_err(|e| Error::Http(Box::new(e)))?;
 		if response.status().is_success() {
@@ -66,24 +119,165 @@ impl HttpTransportClient {
 
 	/// Send notification.
 	pub async fn send_notification(&self, request: jsonrpc::Request) -> Result<(), Error> {
-		let _response = self.send_request(request).await?;
+		let _response = self.send_request(request, HeaderMap::new()).await?;
 		Ok(())
 	}
 
 	/// Send request and wait for response.
-	pub async fn send_request_and_wait_for_response(
+	pub async fn send_request_and_wait_for_response<V: Value>(
 		&self,
 		request: jsonrpc::Request,
-	) -> Result<jsonrpc::Response, Error> {
-		let response = self.send_request(request).await?;
+	) -> Result<jsonrpc::Response<V>, Error> {
+		self.send_request_with_headers(request, HeaderMap::new()).await
+	}
+
+	/// Send request with additional HTTP `headers` and wait for response.
+	pub async fn send_request_with_headers<V: Value>(
+		&self,
+		request: jsonrpc::Request,
+		headers: HeaderMap,
+	) -> Result<jsonrpc::Response<V>, Error> {
+		self.send_request_with_metadata(request, headers).await.map(|(response, _)| response)
+	}
+
+	/// Send request with additional HTTP `headers` and wait for response, returning its HTTP metadata too.
+	pub async fn send_request_with_metadata<V: Value>(
+		&self,
+		request: jsonrpc::Request,
+		headers: HeaderMap,
+	) -> Result<(jsonrpc::Response<V>, ResponseMetadata), Error> {
+		let response = self.send_request(request, headers).await?;
 		let (parts, body) = response.into_parts();
 		let body = hyper_helpers::read_response_to_body(&parts.headers, body, self.max_request_body_size).await?;
 
 		// Note that we don't check the Content-Type of the request. This is deemed
 		// unnecessary, as a parsing error while happen anyway.
-		let response: jsonrpc::Response = jsonrpc::from_slice(&body).map_err(Error::ParseError)?;
-		log::debug!("recv: {}", jsonrpc::to_string(&response).expect("request valid JSON; qed"));
-		Ok(response)
+		let response: jsonrpc::Response<V> = jsonrpc::from_slice(&body).map_err(Error::ParseError)?;
+		log::debug!("recv: {}", self.redactor.message(&response));
+		let metadata = ResponseMetadata {
+			status_code: parts.status.into(),
+			remote_addr: parts.extensions.get::<HttpInfo>().map(|info| info.remote_addr()),
+			headers: parts.headers,
+		};
+		Ok((response, metadata))
+	}
+}
+
+/// Returns the connector used to establish HTTP(S) connections with the default TLS options.
+///
+/// The TLS session cache lives in the connector's TLS configuration, so sharing one connector lets
+/// clients connecting to an endpoint that was already visited resume the earlier TLS session
+/// instead of doing a full handshake.
+fn https_connector() -> HttpsConnector<HttpConnector> {
+	static SHARED: OnceCell<HttpsConnector<HttpConnector>> = OnceCell::new();
+
+	SHARED
+		.get_or_init(|| {
+			#[cfg(feature = "tokio1")]
+			let connector = HttpsConnector::with_native_roots();
+			#[cfg(feature = "tokio02")]
+			let connector = HttpsConnector::new();
+			connector
+		})
+		.clone()
+}
+
+/// TLS options of a client, see [`HttpClientBuilder::tls_config`](crate::HttpClientBuilder::tls_config).
+#[cfg(feature = "rustls")]
+#[derive(Clone)]
+pub(crate) struct TlsOptions {
+	/// Configuration replacing the default one, which trusts the root certificates of the OS.
+	pub(crate) config: Option<Arc<rustls::ClientConfig>>,
+	/// DER-encoded certificates trusted in addition to those of the configuration.
+	pub(crate) root_certificates: Vec<Vec<u8>>,
+	/// Accept any server certificate.
+	pub(crate) accept_invalid_certs: bool,
+	/// Resume the TLS sessions of earlier connections.
+	pub(crate) session_resumption: bool,
+}
+
+#[cfg(feature = "rustls")]
+impl Default for TlsOptions {
+	fn default() -> Self {
+		Self { config: None, root_certificates: Vec::new(), accept_invalid_certs: false, session_resumption: true }
+	}
+}
+
+#[cfg(feature = "rustls")]
+impl TlsOptions {
+	fn is_default(&self) -> bool {
+		self.config.is_none()
+			&& self.root_certificates.is_empty()
+			&& !self.accept_invalid_certs
+			&& self.session_resumption
+	}
+}
+
+#[cfg(feature = "rustls")]
+impl fmt::Debug for TlsOptions {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("TlsOptions")
+			.field("config", &self.config.is_some())
+			.field("root_certificates", &self.root_certificates.len())
+			.field("accept_invalid_certs", &self.accept_invalid_certs)
+			.field("session_resumption", &self.session_resumption)
+			.finish()
+	}
+}
+
+#[cfg(feature = "rustls")]
+fn custom_https_connector(tls: &TlsOptions) -> Result<HttpsConnector<HttpConnector>, Error> {
+	let mut http = HttpConnector::new();
+	http.enforce_http(false);
+	Ok((http, custom_tls_config(tls)?).into())
+}
+
+#[cfg(feature = "rustls")]
+fn custom_tls_config(tls: &TlsOptions) -> Result<rustls::ClientConfig, Error> {
+	let mut config = match &tls.config {
+		Some(config) => (**config).clone(),
+		None => {
+			// NOTE: same defaults as `HttpsConnector::with_native_roots`, which panics instead of failing.
+			let mut config = rustls::ClientConfig::new();
+			config.root_store = match rustls_native_certs::load_native_certs() {
+				Ok(store) | Err((Some(store), _)) => store,
+				Err((None, e)) => return Err(Error::Tls(format!("Cannot access the native root certificates: {}", e))),
+			};
+			config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+			config
+		}
+	};
+	for der in &tls.root_certificates {
+		config
+			.root_store
+			.add(&rustls::Certificate(der.clone()))
+			.map_err(|e| Error::Tls(format!("Invalid root certificate: {:?}", e)))?;
+	}
+	if tls.accept_invalid_certs {
+		config.dangerous().set_certificate_verifier(Arc::new(AcceptAnyCertificate));
+	}
+	if !tls.session_resumption {
+		config.set_persistence(Arc::new(rustls::NoClientSessionStorage {}));
+		config.enable_tickets = false;
+	}
+	Ok(config)
+}
+
+/// Server certificate verifier accepting any certificate, see
+/// [`HttpClientBuilder::danger_accept_invalid_certs`](crate::HttpClientBuilder::danger_accept_invalid_certs).
+#[cfg(feature = "rustls")]
+struct AcceptAnyCertificate;
+
+#[cfg(feature = "rustls")]
+impl rustls::ServerCertVerifier for AcceptAnyCertificate {
+	fn verify_server_cert(
+		&self,
+		_roots: &rustls::RootCertStore,
+		_presented_certs: &[rustls::Certificate],
+		_dns_name: webpki::DNSNameRef,
+		_ocsp_response: &[u8],
+	) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
+		Ok(rustls::ServerCertVerified::assertion())
 	}
 }
 
@@ -121,6 +315,10 @@ pub enum Error {
 	/// Request body too large.
 	#[error("The request body was too large")]
 	RequestTooLarge,
+
+	/// Invalid TLS options, such as a root certificate that isn't DER-encoded.
+	#[error("Invalid TLS configuration: {0}")]
+	Tls(String),
 }
 
 impl<T> From<GenericTransportError<T>> for Error
@@ -137,9 +335,29 @@ where
 
 #[cfg(test)]
 mod tests {
-	use super::{Error, HttpTransportClient};
+	use super::{Error, HeaderMap, HttpTransportClient};
 	use jsonrpsee_types::jsonrpc::{Call, Id, MethodCall, Params, Request, Version};
 
+	#[cfg(feature = "rustls")]
+	#[test]
+	fn tls_session_resumption_can_be_disabled() {
+		use super::{custom_tls_config, Arc, TlsOptions};
+
+		let resumes = |tls: &TlsOptions| {
+			let config = custom_tls_config(tls).unwrap();
+			config.session_persistence.put(b"session".to_vec(), b"state".to_vec());
+			config.enable_tickets && config.session_persistence.get(b"session").is_some()
+		};
+		let tls = TlsOptions { accept_invalid_certs: true, ..TlsOptions::default() };
+		assert!(resumes(&tls));
+		assert!(!resumes(&TlsOptions { session_resumption: false, ..tls }));
+
+		// The flag applies to custom configurations too.
+		let tls = TlsOptions { config: Some(Arc::new(rustls::ClientConfig::new())), ..TlsOptions::default() };
+		assert!(resumes(&tls));
+		assert!(!resumes(&TlsOptions { session_resumption: false, ..tls }));
+	}
+
 	#[test]
 	fn invalid_http_url_rejected() {
 		let err = HttpTransportClient::new("ws://localhost:9933", 80).unwrap_err();
@@ -160,7 +378,7 @@ mod tests {
 		}));
 		let bytes = serde_json::to_vec(&request).unwrap();
 		assert_eq!(bytes.len(), 81);
-		let response = client.send_request(request).await.unwrap_err();
+		let response = client.send_request(request, HeaderMap::new()).await.unwrap_err();
 		assert!(matches!(response, Error::RequestTooLarge));
 	}
 }
@@ -26,11 +26,18 @@
 
 mod module;
 mod response;
+mod route;
 mod server;
 
 pub use jsonrpsee_utils::http::access_control::{AccessControl, AccessControlBuilder};
+pub use jsonrpsee_utils::http::forwarded::TrustedProxies;
+pub use jsonrpsee_utils::server_utils::{
+	FairScheduler, Idempotency, MethodDispatcher, MethodFlags, MethodNormalization, Transformers, Turn,
+	IDEMPOTENCY_KEY_PARAM,
+};
 pub use module::{RpcContextModule, RpcModule};
-pub use server::{Builder as HttpServerBuilder, Server as HttpServer};
+pub use route::Route;
+pub use server::{BatchOrder, Builder as HttpServerBuilder, Server as HttpServer, ServerConfig};
 
 #[cfg(test)]
 mod tests;
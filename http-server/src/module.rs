@@ -1,18 +1,19 @@
 use jsonrpsee_types::error::Error;
 use jsonrpsee_types::v2::{traits::RpcMethod, RpcError, RpcParams};
-use jsonrpsee_utils::server_utils::{send_response, Methods};
+use jsonrpsee_utils::server_utils::{send_response, Methods, WildcardMethods};
 use serde::Serialize;
 use std::sync::Arc;
 
 #[derive(Default)]
 pub struct RpcModule {
 	methods: Methods,
+	wildcards: WildcardMethods,
 }
 
 impl RpcModule {
 	/// Instantiate a new `RpcModule`.
 	pub fn new() -> Self {
-		RpcModule { methods: Methods::default() }
+		RpcModule { methods: Methods::default(), wildcards: WildcardMethods::default() }
 	}
 
 	/// Add context for this module, turning it into an `RpcContextModule`.
@@ -50,14 +51,43 @@ impl RpcModule {
 		Ok(())
 	}
 
-	pub(crate) fn into_methods(self) -> Methods {
-		self.methods
+	/// Register a new RPC method for the method names matching `pattern`, such as `debug_*`, which responds
+	/// with a given callback called with the name of the called method.
+	///
+	/// The pattern matches the names that start with the text before its single trailing `*`, the methods
+	/// registered by name take precedence.
+	pub fn register_wildcard_method<F, R>(&mut self, pattern: &'static str, callback: F) -> Result<(), Error>
+	where
+		R: Serialize,
+		F: Fn(&str, RpcParams) -> Result<R, RpcError> + Send + Sync + 'static,
+	{
+		self.wildcards.insert(
+			pattern,
+			Box::new(move |method, id, params, tx, _| {
+				let result = callback(method, params)?;
+
+				send_response(id, tx, result);
+
+				Ok(())
+			}),
+		)
+	}
+
+	/// Returns the methods registered by name and the methods registered for name patterns.
+	pub(crate) fn into_parts(self) -> (Methods, WildcardMethods) {
+		(self.methods, self.wildcards)
+	}
+
+	/// Returns the names of the methods registered by name.
+	pub(crate) fn method_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+		self.methods.keys().copied()
 	}
 
 	pub(crate) fn merge(&mut self, other: RpcModule) -> Result<(), Error> {
 		for name in other.methods.keys() {
 			self.verify_method_name(name)?;
 		}
+		self.wildcards.merge(other.wildcards)?;
 
 		for (name, callback) in other.methods {
 			self.methods.insert(name, callback);
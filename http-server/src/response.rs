@@ -65,6 +65,16 @@ pub fn too_large<S: Into<String>>(msg: S) -> hyper::Response<hyper::Body> {
 	from_template(hyper::StatusCode::PAYLOAD_TOO_LARGE, msg.into())
 }
 
+/// Create a response for too many requests (429)
+pub fn too_many_requests() -> hyper::Response<hyper::Body> {
+	from_template(hyper::StatusCode::TOO_MANY_REQUESTS, "Too many requests, try again later.\n".to_owned())
+}
+
+/// Create a response for a request timeout (408)
+pub fn request_timeout() -> hyper::Response<hyper::Body> {
+	from_template(hyper::StatusCode::REQUEST_TIMEOUT, "Timeout while reading the request body.\n".to_owned())
+}
+
 /// Create a text response for a template.
 fn from_template(status: hyper::StatusCode, body: String) -> hyper::Response<hyper::Body> {
 	hyper::Response::builder()
@@ -0,0 +1,81 @@
+use crate::module::RpcModule;
+use jsonrpsee_utils::server_utils::{MethodDispatcher, MethodFlags};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Methods served at a path of the server with their own limits, see [`Server::mount`](crate::HttpServer::mount).
+///
+/// The limits that are not set are the ones of the server.
+pub struct Route {
+	module: RpcModule,
+	method_flags: MethodFlags,
+	dispatcher: Option<Arc<dyn MethodDispatcher>>,
+	max_request_body_size: Option<u32>,
+	max_concurrent_requests: Option<usize>,
+}
+
+impl Route {
+	/// Create a route serving the methods of `module`.
+	pub fn new(module: RpcModule) -> Self {
+		Self {
+			module,
+			method_flags: MethodFlags::default(),
+			dispatcher: None,
+			max_request_body_size: None,
+			max_concurrent_requests: None,
+		}
+	}
+
+	/// Create a route routing the calls with `dispatcher`, the [`MethodFlags`] of the route are not applied.
+	pub fn from_dispatcher(dispatcher: impl MethodDispatcher) -> Self {
+		Self { dispatcher: Some(Arc::new(dispatcher)), ..Self::new(RpcModule::new()) }
+	}
+
+	/// Enable and disable the methods of the route through `flags`, independently of the methods of the
+	/// server and of the other routes.
+	pub fn method_flags(mut self, flags: MethodFlags) -> Self {
+		self.method_flags = flags;
+		self
+	}
+
+	/// Sets the maximum size of a request body in bytes.
+	pub fn max_request_body_size(mut self, size: u32) -> Self {
+		self.max_request_body_size = Some(size);
+		self
+	}
+
+	/// Sets the maximum number of requests to this route processed concurrently, further requests are
+	/// rejected with `429 Too Many Requests`.
+	///
+	/// The requests to this route don't count towards the limit of the server.
+	pub fn max_concurrent_requests(mut self, max: usize) -> Self {
+		self.max_concurrent_requests = Some(max);
+		self
+	}
+
+	pub(crate) fn into_endpoint(self, server: &Endpoint) -> Endpoint {
+		let dispatcher = match self.dispatcher {
+			Some(dispatcher) => dispatcher,
+			None => {
+				let (methods, wildcards) = self.module.into_parts();
+				Arc::new(wildcards.wrap(self.method_flags.wrap(methods)))
+			}
+		};
+		Endpoint {
+			dispatcher,
+			max_request_body_size: self.max_request_body_size.unwrap_or(server.max_request_body_size),
+			request_permits: match self.max_concurrent_requests {
+				Some(max) => Some(Arc::new(Semaphore::new(max))),
+				None => server.request_permits.clone(),
+			},
+		}
+	}
+}
+
+/// Methods and limits of a path, or of the server for the paths without a route.
+#[derive(Clone)]
+pub(crate) struct Endpoint {
+	pub(crate) dispatcher: Arc<dyn MethodDispatcher>,
+	pub(crate) max_request_body_size: u32,
+	pub(crate) request_permits: Option<Arc<Semaphore>>,
+}
@@ -26,6 +26,7 @@
 
 use crate::module::RpcModule;
 use crate::response;
+use crate::route::{Endpoint, Route};
 use anyhow::anyhow;
 use hyper::{
 	server::{conn::AddrIncoming, Builder as HyperBuilder},
@@ -33,29 +34,110 @@ use hyper::{
 	Error as HyperError,
 };
 use jsonrpsee_types::error::{Error, GenericTransportError};
-use jsonrpsee_types::v2::error::{
-	INVALID_REQUEST_CODE, INVALID_REQUEST_MSG, METHOD_NOT_FOUND_CODE, METHOD_NOT_FOUND_MSG, PARSE_ERROR_CODE,
-	PARSE_ERROR_MSG,
-};
+use jsonrpsee_types::redact::{LogRedactor, Redact};
+use jsonrpsee_types::v2::error::{INVALID_REQUEST_CODE, INVALID_REQUEST_MSG, PARSE_ERROR_CODE, PARSE_ERROR_MSG};
 use jsonrpsee_types::v2::{JsonRpcInvalidRequest, JsonRpcRequest, RpcError, RpcParams};
 use jsonrpsee_utils::http::{access_control::AccessControl, hyper_helpers::read_response_to_body};
-use jsonrpsee_utils::server_utils::send_error;
-use serde::Serialize;
+use jsonrpsee_utils::server_utils::{
+	send_error, ConfigIssue, ConfigReport, ConnectionId, FairScheduler, Idempotency, MethodDispatcher, MethodFlags,
+	MethodNormalization, Transformers, Turn,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
 use socket2::{Domain, Socket, Type};
 use std::{
+	collections::{hash_map::Entry, HashMap},
 	net::{SocketAddr, TcpListener},
-	sync::Arc,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+	time::Duration,
 };
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Semaphore};
 
 /// Builder to create JSON-RPC HTTP server.
 pub struct Builder {
 	access_control: AccessControl,
 	max_request_body_size: u32,
 	keep_alive: bool,
+	max_concurrent_requests: Option<usize>,
+	body_read_timeout: Option<Duration>,
+	batch_order: BatchOrder,
+	redactor: LogRedactor,
+}
+
+/// Order of the responses in a batch response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BatchOrder {
+	/// The calls are executed one after the other and the responses are in the order of the calls, for
+	/// clients that match batch responses positionally.
+	Received,
+	/// The calls are executed concurrently and the responses are in the order the calls completed.
+	Completion,
+}
+
+/// Options of a [`Builder`] as plain data, to load them with any serde format such as TOML, YAML or environment
+/// variables.
+///
+/// The fields are named after the methods of the builder, durations are given in milliseconds. Missing fields
+/// take the defaults of the builder and unknown fields are rejected. The access control is set on the builder
+/// returned by [`Builder::from`], as is the redaction of the logs.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+	/// See [`Builder::max_request_body_size`].
+	pub max_request_body_size: u32,
+	/// See [`Builder::keep_alive`].
+	pub keep_alive: bool,
+	/// See [`Builder::max_concurrent_requests`].
+	pub max_concurrent_requests: Option<usize>,
+	/// See [`Builder::body_read_timeout`].
+	pub body_read_timeout_ms: Option<u64>,
+	/// See [`Builder::batch_response_order`].
+	pub batch_response_order: BatchOrder,
+}
+
+impl Default for ServerConfig {
+	fn default() -> Self {
+		Builder::default().to_config()
+	}
+}
+
+/// Creates a builder with the options of `config`, the other options have their defaults.
+impl From<ServerConfig> for Builder {
+	fn from(config: ServerConfig) -> Self {
+		// NOTE: destructured without `..` such that new options can't be left out.
+		let ServerConfig {
+			max_request_body_size,
+			keep_alive,
+			max_concurrent_requests,
+			body_read_timeout_ms,
+			batch_response_order,
+		} = config;
+		Self {
+			max_request_body_size,
+			keep_alive,
+			max_concurrent_requests,
+			body_read_timeout: body_read_timeout_ms.map(Duration::from_millis),
+			batch_order: batch_response_order,
+			..Self::default()
+		}
+	}
 }
 
 impl Builder {
+	/// Returns the options of the builder that are plain data, see [`ServerConfig`].
+	pub fn to_config(&self) -> ServerConfig {
+		ServerConfig {
+			max_request_body_size: self.max_request_body_size,
+			keep_alive: self.keep_alive,
+			max_concurrent_requests: self.max_concurrent_requests,
+			body_read_timeout_ms: self.body_read_timeout.map(|timeout| timeout.as_millis() as u64),
+			batch_response_order: self.batch_order,
+		}
+	}
+
 	/// Sets the maximum size of a request body in bytes (default is 10 MiB).
 	pub fn max_request_body_size(mut self, size: u32) -> Self {
 		self.max_request_body_size = size;
@@ -76,6 +158,49 @@ impl Builder {
 		self
 	}
 
+	/// Sets the maximum number of requests processed concurrently, further requests are rejected
+	/// with `429 Too Many Requests`.
+	///
+	/// Default is unlimited.
+	pub fn max_concurrent_requests(mut self, max: usize) -> Self {
+		self.max_concurrent_requests = Some(max);
+		self
+	}
+
+	/// Sets the maximum time to read the body of a request, requests exceeding it are rejected
+	/// with `408 Request Timeout`.
+	///
+	/// Default is no timeout.
+	pub fn body_read_timeout(mut self, timeout: Duration) -> Self {
+		self.body_read_timeout = Some(timeout);
+		self
+	}
+
+	/// Sets the order of the responses in a batch response.
+	///
+	/// Default is [`BatchOrder::Received`].
+	pub fn batch_response_order(mut self, order: BatchOrder) -> Self {
+		self.batch_order = order;
+		self
+	}
+
+	/// Masks the params with `redact` before the received calls are logged, see
+	/// [`Redaction`](jsonrpsee_types::redact::Redaction) to mask params by position or object key.
+	///
+	/// By default, the params are logged as they are.
+	pub fn redact_logs(mut self, redact: impl Redact) -> Self {
+		self.redactor = LogRedactor::new(redact);
+		self
+	}
+
+	/// Checks the configured limits before binding, see [`Server::validate`] to check the registered methods
+	/// as well.
+	pub fn validate(&self) -> ConfigReport {
+		let mut report = ConfigReport::default();
+		check_limits(&mut report, self.max_request_body_size, self.max_concurrent_requests, self.body_read_timeout);
+		report
+	}
+
 	pub fn build(self, addr: SocketAddr) -> anyhow::Result<Server> {
 		let domain = Domain::for_address(addr);
 		let socket = Socket::new(domain, Type::STREAM, None)?;
@@ -97,13 +222,32 @@ impl Builder {
 			root: RpcModule::new(),
 			access_control: self.access_control,
 			max_request_body_size: self.max_request_body_size,
+			max_concurrent_requests: self.max_concurrent_requests,
+			body_read_timeout: self.body_read_timeout,
+			batch_order: self.batch_order,
+			redactor: self.redactor,
+			method_flags: MethodFlags::default(),
+			method_normalization: MethodNormalization::default(),
+			idempotency: None,
+			transformers: None,
+			dispatcher: None,
+			routes: HashMap::new(),
+			scheduler: None,
 		})
 	}
 }
 
 impl Default for Builder {
 	fn default() -> Self {
-		Self { max_request_body_size: 10 * 1024 * 1024, access_control: AccessControl::default(), keep_alive: true }
+		Self {
+			max_request_body_size: 10 * 1024 * 1024,
+			access_control: AccessControl::default(),
+			keep_alive: true,
+			max_concurrent_requests: None,
+			body_read_timeout: None,
+			batch_order: BatchOrder::Received,
+			redactor: LogRedactor::default(),
+		}
 	}
 }
 
@@ -118,6 +262,27 @@ pub struct Server {
 	max_request_body_size: u32,
 	/// Access control
 	access_control: AccessControl,
+	/// Max number of concurrently processed requests.
+	max_concurrent_requests: Option<usize>,
+	/// Max time to read the body of a request.
+	body_read_timeout: Option<Duration>,
+	/// Order of the responses in a batch response.
+	batch_order: BatchOrder,
+	/// Masking of the params in logs.
+	redactor: LogRedactor,
+	/// Enabled and disabled methods.
+	method_flags: MethodFlags,
+	/// How the names of the called methods are matched.
+	method_normalization: MethodNormalization,
+	/// Replays the responses to retried calls.
+	idempotency: Option<Idempotency>,
+	transformers: Option<Transformers>,
+	/// Router replacing the registered methods.
+	dispatcher: Option<Arc<dyn MethodDispatcher>>,
+	/// Methods mounted at paths, by path.
+	routes: HashMap<String, Route>,
+	/// Scheduler of the calls of the connections.
+	scheduler: Option<FairScheduler>,
 }
 
 impl Server {
@@ -130,6 +295,16 @@ impl Server {
 		self.root.register_method(method_name, callback)
 	}
 
+	/// Register a new RPC method for the method names matching `pattern`, such as `debug_*`, see
+	/// [`RpcModule::register_wildcard_method`].
+	pub fn register_wildcard_method<F, R>(&mut self, pattern: &'static str, callback: F) -> Result<(), Error>
+	where
+		R: Serialize,
+		F: Fn(&str, RpcParams) -> Result<R, RpcError> + Send + Sync + 'static,
+	{
+		self.root.register_wildcard_method(pattern, callback)
+	}
+
 	/// Register all methods from a module on this server.
 	pub fn register_module(&mut self, module: RpcModule) -> Result<(), Error> {
 		self.root.merge(module)
@@ -140,21 +315,151 @@ impl Server {
 		self.local_addr.ok_or_else(|| anyhow!("Local address not found"))
 	}
 
+	/// Returns a handle to enable and disable the methods of the server at runtime.
+	pub fn method_flags(&self) -> MethodFlags {
+		self.method_flags.clone()
+	}
+
+	/// Enable and disable the methods of the server through `flags`, for example to answer calls to disabled
+	/// methods with a custom error, replacing the handle returned by [`Server::method_flags`].
+	pub fn set_method_flags(&mut self, flags: MethodFlags) {
+		self.method_flags = flags;
+	}
+
+	/// Match the names of the called methods against the names of the registered methods according to
+	/// `normalization`, for example to accept `sayHello` for `say_hello`.
+	///
+	/// Default is [`MethodNormalization::Exact`].
+	pub fn set_method_normalization(&mut self, normalization: MethodNormalization) {
+		self.method_normalization = normalization;
+	}
+
+	/// Replay the responses to the calls retried with the same idempotency key, see [`Idempotency`].
+	pub fn set_idempotency(&mut self, idempotency: Idempotency) {
+		self.idempotency = Some(idempotency);
+	}
+
+	/// Rewrite the params and results of the calls to the registered methods with `transformers`, see
+	/// [`Transformers`].
+	pub fn set_transformers(&mut self, transformers: Transformers) {
+		self.transformers = Some(transformers);
+	}
+
+	/// Route the calls with `dispatcher` instead of the registered methods, which are not served then and
+	/// neither are the [`MethodFlags`] applied.
+	///
+	/// All calls are dispatched with the connection ID `0`.
+	pub fn set_dispatcher(&mut self, dispatcher: impl MethodDispatcher) {
+		self.dispatcher = Some(Arc::new(dispatcher));
+	}
+
+	/// Dispatch the calls of the connections in turn with `scheduler`, such that a connection submitting huge
+	/// batches doesn't starve the calls of the others, see [`FairScheduler`].
+	///
+	/// Every HTTP connection is scheduled with the default weight of `scheduler`.
+	pub fn set_fair_scheduler(&mut self, scheduler: FairScheduler) {
+		self.scheduler = Some(scheduler);
+	}
+
+	/// Serve the methods of `route` at `path`, such as `/admin`, instead of the methods of the server.
+	///
+	/// The paths are matched exactly, the requests to paths without a route are served the methods of the
+	/// server.
+	pub fn mount(&mut self, path: impl Into<String>, route: Route) -> Result<(), Error> {
+		match self.routes.entry(path.into()) {
+			Entry::Occupied(entry) => Err(Error::PathAlreadyMounted(entry.key().clone())),
+			Entry::Vacant(entry) => {
+				entry.insert(route);
+				Ok(())
+			}
+		}
+	}
+
+	/// Checks the configuration of the server before starting it: the limits, the methods whose names collide
+	/// once normalized, and the method flags, idempotency settings and transformers that refer to unregistered
+	/// methods.
+	///
+	/// Duplicate methods across the registered modules are rejected when registering them already.
+	pub fn validate(&self) -> ConfigReport {
+		let mut report = ConfigReport::default();
+		check_limits(&mut report, self.max_request_body_size, self.max_concurrent_requests, self.body_read_timeout);
+		if self.dispatcher.is_some() {
+			if self.root.method_names().next().is_some() {
+				report.issues.push(ConfigIssue::MethodsReplacedByDispatcher);
+			}
+			return report;
+		}
+		report.check_methods(
+			self.root.method_names(),
+			self.method_normalization,
+			&self.method_flags,
+			self.idempotency.as_ref(),
+			self.transformers.as_ref(),
+		);
+		report
+	}
+
 	/// Start the server.
 	pub async fn start(self) -> anyhow::Result<()> {
-		let methods = Arc::new(self.root.into_methods());
-		let max_request_body_size = self.max_request_body_size;
+		let dispatcher: Arc<dyn MethodDispatcher> = match self.dispatcher {
+			Some(dispatcher) => dispatcher,
+			None => {
+				let (mut methods, wildcards) = self.root.into_parts();
+				if let Some(transformers) = self.transformers.as_ref() {
+					methods = transformers.wrap(methods);
+				}
+				methods = self.method_flags.wrap(methods);
+				if let Some(idempotency) = self.idempotency.as_ref() {
+					methods = idempotency.wrap(methods);
+				}
+				match self.method_normalization {
+					MethodNormalization::Exact => Arc::new(wildcards.wrap(methods)),
+					normalization => Arc::new(wildcards.wrap(normalization.wrap(methods))),
+				}
+			}
+		};
+		let root = Endpoint {
+			dispatcher,
+			max_request_body_size: self.max_request_body_size,
+			request_permits: self.max_concurrent_requests.map(|max| Arc::new(Semaphore::new(max))),
+		};
+		let routes: Arc<HashMap<_, _>> =
+			Arc::new(self.routes.into_iter().map(|(path, route)| (path, route.into_endpoint(&root))).collect());
 		let access_control = self.access_control;
+		let body_read_timeout = self.body_read_timeout;
+		let batch_order = self.batch_order;
+		let redactor = self.redactor;
+		let scheduler = self.scheduler;
+		let next_conn_id = AtomicUsize::new(0);
 
 		let make_service = make_service_fn(move |_| {
-			let methods = methods.clone();
+			let root = root.clone();
+			let routes = routes.clone();
 			let access_control = access_control.clone();
+			let scheduler = scheduler.clone();
+			let redactor = redactor.clone();
+			// NOTE: `fetch_add` wraps on overflow which is intended.
+			let conn_id = next_conn_id.fetch_add(1, Ordering::Relaxed);
 
 			async move {
 				Ok::<_, HyperError>(service_fn(move |request| {
-					let methods = methods.clone();
+					let Endpoint { dispatcher, max_request_body_size, request_permits } =
+						routes.get(request.uri().path()).unwrap_or(&root).clone();
 					let access_control = access_control.clone();
+					let ctx = ConnectionContext {
+						dispatcher,
+						redactor: redactor.clone(),
+						scheduler: scheduler.clone(),
+						conn_id,
+					};
 					async move {
+						// NOTE: the permit is held until the response is returned.
+						let _permit = match request_permits.map(|permits| permits.try_acquire_owned()) {
+							Some(Ok(permit)) => Some(permit),
+							Some(Err(_)) => return Ok::<_, HyperError>(response::too_many_requests()),
+							None => None,
+						};
+
 						if let Err(e) = access_control_is_valid(&access_control, &request) {
 							return Ok::<_, HyperError>(e);
 						}
@@ -164,7 +469,15 @@ impl Server {
 						}
 
 						let (parts, body) = request.into_parts();
-						let body = match read_response_to_body(&parts.headers, body, max_request_body_size).await {
+						let read_body = read_response_to_body(&parts.headers, body, max_request_body_size);
+						let body = match body_read_timeout {
+							Some(timeout) => match tokio::time::timeout(timeout, read_body).await {
+								Ok(body) => body,
+								Err(_) => return Ok::<_, HyperError>(response::request_timeout()),
+							},
+							None => read_body.await,
+						};
+						let body = match body {
 							Ok(body) => body,
 							Err(GenericTransportError::TooLarge) => {
 								return Ok::<_, HyperError>(response::too_large("The request was too large"))
@@ -177,27 +490,21 @@ impl Server {
 						// NOTE(niklasad1): it's a channel because it's needed for batch requests.
 						let (tx, mut rx) = mpsc::unbounded_channel();
 
-						match serde_json::from_slice::<JsonRpcRequest>(&body) {
-							Ok(req) => {
-								log::debug!("recv: {:?}", req);
-								let params = RpcParams::new(req.params.map(|params| params.get()));
-								if let Some(method) = methods.get(&*req.method) {
-									// NOTE(niklasad1): connection ID is unused thus hardcoded to `0`.
-									if let Err(err) = (method)(req.id, params, &tx, 0) {
-										log::error!("method_call: {} failed: {:?}", req.method, err);
-									}
-								} else {
-									send_error(req.id, &tx, METHOD_NOT_FOUND_CODE, METHOD_NOT_FOUND_MSG);
-								}
+						let is_batch = body.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'[');
+						match serde_json::from_slice::<Vec<&RawValue>>(&body) {
+							Ok(batch) if !batch.is_empty() => {
+								execute_batch(&ctx, batch, batch_order, tx).await;
+								let response = collect_batch(&mut rx).await;
+								log::debug!("send: {:?}", response);
+								return Ok::<_, HyperError>(response::ok_response(response));
 							}
-							Err(_e) => {
-								let (id, code, msg) = match serde_json::from_slice::<JsonRpcInvalidRequest>(&body) {
-									Ok(req) => (req.id, INVALID_REQUEST_CODE, INVALID_REQUEST_MSG),
-									Err(_) => (None, PARSE_ERROR_CODE, PARSE_ERROR_MSG),
-								};
-								send_error(id, &tx, code, msg);
+							Ok(_) => send_error(None, &tx, INVALID_REQUEST_CODE, INVALID_REQUEST_MSG),
+							Err(_) if is_batch => send_error(None, &tx, PARSE_ERROR_CODE, PARSE_ERROR_MSG),
+							Err(_) => {
+								let _turn = ctx.turn().await;
+								ctx.execute(&tx, &body)
 							}
-						};
+						}
 
 						let response = rx.recv().await.expect("Sender is still alive managed by us above; qed");
 						log::debug!("send: {:?}", response);
@@ -212,6 +519,118 @@ impl Server {
 	}
 }
 
+/// Reports the limits rejecting every request.
+fn check_limits(
+	report: &mut ConfigReport,
+	max_request_body_size: u32,
+	max_concurrent_requests: Option<usize>,
+	body_read_timeout: Option<Duration>,
+) {
+	if max_request_body_size == 0 {
+		report.conflicting_limits("max request body size is 0, every request is rejected");
+	}
+	if max_concurrent_requests == Some(0) {
+		report.conflicting_limits("max concurrent requests is 0, every request is rejected");
+	}
+	if body_read_timeout == Some(Duration::ZERO) {
+		report.conflicting_limits("body read timeout is 0, every request times out");
+	}
+}
+
+/// State of a connection used to execute the calls of its requests.
+#[derive(Clone)]
+struct ConnectionContext {
+	/// Dispatcher of the calls to the requested path.
+	dispatcher: Arc<dyn MethodDispatcher>,
+	/// Masking of the params in logs.
+	redactor: LogRedactor,
+	/// Scheduler dispatching the calls of the connections in turn.
+	scheduler: Option<FairScheduler>,
+	/// ID of the connection.
+	conn_id: ConnectionId,
+}
+
+impl ConnectionContext {
+	/// Executes a single call and sends its response to `tx`.
+	fn execute(&self, tx: &mpsc::UnboundedSender<String>, body: &[u8]) {
+		match serde_json::from_slice::<JsonRpcRequest>(body) {
+			Ok(req) => {
+				log::debug!(
+					"recv: method={} params={} id={:?}",
+					req.method,
+					self.redactor.params(&req.method, &req.params),
+					req.id
+				);
+				let params = RpcParams::new(req.params.map(|params| params.get()));
+				// NOTE(niklasad1): connection ID is unused thus hardcoded to `0`.
+				if let Err(err) = self.dispatcher.dispatch(&req.method, req.id, params, tx, 0) {
+					log::error!("method_call: {} failed: {:?}", req.method, err);
+				}
+			}
+			Err(_e) => {
+				let (id, code, msg) = match serde_json::from_slice::<JsonRpcInvalidRequest>(body) {
+					Ok(req) => (req.id, INVALID_REQUEST_CODE, INVALID_REQUEST_MSG),
+					Err(_) => (None, PARSE_ERROR_CODE, PARSE_ERROR_MSG),
+				};
+				send_error(id, tx, code, msg);
+			}
+		};
+	}
+
+	/// Waits for the turn of a call of the connection, if the calls are scheduled.
+	async fn turn(&self) -> Option<Turn> {
+		match self.scheduler.as_ref() {
+			Some(scheduler) => Some(scheduler.acquire(self.conn_id).await),
+			None => None,
+		}
+	}
+}
+
+/// Executes the calls of a batch in `order`, each one in its turn if the calls are scheduled, the responses are
+/// sent to `tx` which is dropped once all calls were executed.
+async fn execute_batch(
+	ctx: &ConnectionContext,
+	batch: Vec<&RawValue>,
+	order: BatchOrder,
+	tx: mpsc::UnboundedSender<String>,
+) {
+	match order {
+		BatchOrder::Received => {
+			for call in batch {
+				let _turn = ctx.turn().await;
+				ctx.execute(&tx, call.get().as_bytes());
+			}
+		}
+		BatchOrder::Completion => {
+			for call in batch {
+				let ctx = ctx.clone();
+				let tx = tx.clone();
+				let call = call.get().to_owned();
+				tokio::spawn(async move {
+					let turn = ctx.turn().await;
+					tokio::task::spawn_blocking(move || {
+						ctx.execute(&tx, call.as_bytes());
+						drop(turn);
+					});
+				});
+			}
+		}
+	}
+}
+
+/// Joins the responses of a batch into a JSON array, in the order they are received.
+async fn collect_batch(rx: &mut mpsc::UnboundedReceiver<String>) -> String {
+	let mut response = String::from("[");
+	while let Some(call_response) = rx.recv().await {
+		if response.len() > 1 {
+			response.push(',');
+		}
+		response.push_str(&call_response);
+	}
+	response.push(']');
+	response
+}
+
 // Checks to that access control of the received request is the same as configured.
 fn access_control_is_valid(
 	access_control: &AccessControl,
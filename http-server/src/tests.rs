@@ -4,7 +4,7 @@ use std::net::SocketAddr;
 
 use crate::HttpServerBuilder;
 use jsonrpsee_test_utils::helpers::*;
-use jsonrpsee_test_utils::types::{Id, StatusCode};
+use jsonrpsee_test_utils::types::{Id, StatusCode, Uri};
 use jsonrpsee_types::jsonrpc::JsonValue;
 
 async fn server() -> SocketAddr {
@@ -82,3 +82,293 @@ async fn invalid_request_object() {
 	assert_eq!(response.status, StatusCode::OK);
 	assert_eq!(response.body, invalid_request(Id::Num(1)));
 }
+
+#[tokio::test]
+async fn slow_body_is_rejected_and_sheds_load() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	let mut server = HttpServerBuilder::default()
+		.max_concurrent_requests(1)
+		.body_read_timeout(std::time::Duration::from_millis(500))
+		.build("127.0.0.1:0".parse().unwrap())
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	server.register_method("say_hello", |_| Ok("lo")).unwrap();
+	tokio::spawn(async move { server.start().await.unwrap() });
+
+	// Send the headers and only a part of the body to hold the only request permit.
+	let mut slow_client = tokio::net::TcpStream::connect(addr).await.unwrap();
+	let slow_request = format!(
+		"POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: 100\r\n\r\n{{\"jsonrpc\"",
+		addr
+	);
+	slow_client.write_all(slow_request.as_bytes()).await.unwrap();
+	tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).await.unwrap();
+	assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+
+	let mut slow_response = vec![0; 1024];
+	let read = slow_client.read(&mut slow_response).await.unwrap();
+	assert!(String::from_utf8_lossy(&slow_response[..read]).starts_with("HTTP/1.1 408"));
+
+	let response = http_request(req.into(), to_http_uri(addr)).await.unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn batch_responses_follow_batch_order() {
+	use crate::BatchOrder;
+
+	async fn batch_server(order: BatchOrder) -> SocketAddr {
+		let mut server =
+			HttpServerBuilder::default().batch_response_order(order).build("127.0.0.1:0".parse().unwrap()).unwrap();
+		let addr = server.local_addr().unwrap();
+		server
+			.register_method("sleep_ms", |params| {
+				let ms: u64 = params.one()?;
+				std::thread::sleep(std::time::Duration::from_millis(ms));
+				Ok(ms)
+			})
+			.unwrap();
+		tokio::spawn(async move { server.start().await.unwrap() });
+		addr
+	}
+
+	let req = r#"[{"jsonrpc":"2.0","method":"sleep_ms","params":[300],"id":1},{"jsonrpc":"2.0","method":"sleep_ms","params":[0],"id":2},{"jsonrpc":"2.0","method":"bar","id":3}]"#;
+	let slow = ok_response(JsonValue::Number(300.into()), Id::Num(1));
+	let fast = ok_response(JsonValue::Number(0.into()), Id::Num(2));
+	let not_found = method_not_found(Id::Num(3));
+
+	let addr = batch_server(BatchOrder::Received).await;
+	let response = http_request(req.into(), to_http_uri(addr)).await.unwrap();
+	assert_eq!(response.body, format!("[{},{},{}]", slow, fast, not_found));
+
+	let addr = batch_server(BatchOrder::Completion).await;
+	let response = http_request(req.into(), to_http_uri(addr)).await.unwrap();
+	let responses: Vec<JsonValue> = serde_json::from_str(&response.body).unwrap();
+	assert_eq!(responses.len(), 3);
+	assert_eq!(responses[2], serde_json::from_str::<JsonValue>(&slow).unwrap());
+}
+
+#[tokio::test]
+async fn empty_batch_is_invalid_request() {
+	let addr = server().await;
+	let response = http_request("[]".into(), to_http_uri(addr)).await.unwrap();
+	assert_eq!(response.body, invalid_request(Id::Null));
+}
+
+#[tokio::test]
+async fn disabled_method_returns_configured_error() {
+	use crate::MethodFlags;
+
+	let mut server = HttpServerBuilder::default().build("127.0.0.1:0".parse().unwrap()).unwrap();
+	let addr = server.local_addr().unwrap();
+	let flags = MethodFlags::new().with_error(-32050, "Under maintenance");
+	server.set_method_flags(flags.clone());
+	server.register_method("say_hello", |_| Ok("lo")).unwrap();
+	flags.disable("say_hello");
+	tokio::spawn(async move { server.start().await.unwrap() });
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).await.unwrap();
+	assert_eq!(response.body, r#"{"jsonrpc":"2.0","error":{"code":-32050,"message":"Under maintenance"},"id":1}"#);
+
+	let req = r#"{"jsonrpc":"2.0","method":"bar","id":2}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).await.unwrap();
+	assert_eq!(response.body, method_not_found(Id::Num(2)));
+
+	assert_eq!(flags.disabled(), vec!["say_hello".to_owned()]);
+	flags.enable("say_hello");
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":3}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).await.unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(3)));
+}
+
+#[tokio::test]
+async fn method_names_are_normalized() {
+	use crate::MethodNormalization;
+
+	let mut server = HttpServerBuilder::default().build("127.0.0.1:0".parse().unwrap()).unwrap();
+	let addr = server.local_addr().unwrap();
+	server.set_method_normalization(MethodNormalization::SeparatorInsensitive);
+	server.register_method("say_hello", |_| Ok("lo")).unwrap();
+	tokio::spawn(async move { server.start().await.unwrap() });
+
+	let req = r#"{"jsonrpc":"2.0","method":"sayHello","id":1}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).await.unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+	let req = r#"{"jsonrpc":"2.0","method":"sayGoodbye","id":2}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).await.unwrap();
+	assert_eq!(response.body, method_not_found(Id::Num(2)));
+}
+
+#[tokio::test]
+async fn wildcard_methods_receive_the_method_name() {
+	use crate::MethodNormalization;
+
+	let mut server = HttpServerBuilder::default().build("127.0.0.1:0".parse().unwrap()).unwrap();
+	let addr = server.local_addr().unwrap();
+	server.set_method_normalization(MethodNormalization::SeparatorInsensitive);
+	server.register_method("say_hello", |_| Ok("lo")).unwrap();
+	server.register_wildcard_method("say*", |method, _| Ok(method.to_owned())).unwrap();
+	tokio::spawn(async move { server.start().await.unwrap() });
+
+	// The methods registered by name take precedence, including through their normalized names.
+	let req = r#"{"jsonrpc":"2.0","method":"sayHello","id":1}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).await.unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+	let req = r#"{"jsonrpc":"2.0","method":"sayGoodbye","id":2}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).await.unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("sayGoodbye".to_owned()), Id::Num(2)));
+}
+
+#[tokio::test]
+async fn custom_dispatcher_routes_calls() {
+	use crate::MethodDispatcher;
+	use jsonrpsee_types::v2::RpcParams;
+	use jsonrpsee_utils::server_utils::{send_response, ConnectionId, RpcId, RpcSender};
+
+	// Answers every call with the name of the called method.
+	struct Echo;
+
+	impl MethodDispatcher for Echo {
+		fn dispatch(
+			&self,
+			method: &str,
+			id: RpcId,
+			_: RpcParams,
+			tx: RpcSender,
+			_: ConnectionId,
+		) -> anyhow::Result<()> {
+			send_response(id, tx, method);
+			Ok(())
+		}
+	}
+
+	let mut server = HttpServerBuilder::default().build("127.0.0.1:0".parse().unwrap()).unwrap();
+	let addr = server.local_addr().unwrap();
+	server.set_dispatcher(Echo);
+	tokio::spawn(async move { server.start().await.unwrap() });
+
+	let req = r#"[{"jsonrpc":"2.0","method":"a","id":1},{"jsonrpc":"2.0","method":"b","id":2}]"#;
+	let response = http_request(req.into(), to_http_uri(addr)).await.unwrap();
+	assert_eq!(response.body, r#"[{"jsonrpc":"2.0","result":"a","id":1},{"jsonrpc":"2.0","result":"b","id":2}]"#);
+}
+
+#[tokio::test]
+async fn routes_are_served_at_their_path() {
+	use crate::{Route, RpcModule};
+	use jsonrpsee_types::error::Error;
+
+	let mut server = HttpServerBuilder::default().build("127.0.0.1:0".parse().unwrap()).unwrap();
+	let addr = server.local_addr().unwrap();
+	server.register_method("say_hello", |_| Ok("lo")).unwrap();
+	let mut admin = RpcModule::new();
+	admin.register_method("shutdown", |_| Ok(true)).unwrap();
+	server.mount("/admin", Route::new(admin).max_request_body_size(64)).unwrap();
+	assert!(matches!(server.mount("/admin", Route::new(RpcModule::new())), Err(Error::PathAlreadyMounted(_))));
+	tokio::spawn(async move { server.start().await.unwrap() });
+	let admin_uri: Uri = format!("http://{}/admin", addr).parse().unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"shutdown","id":1}"#;
+	let response = http_request(req.into(), admin_uri.clone()).await.unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::Bool(true), Id::Num(1)));
+	let response = http_request(req.into(), to_http_uri(addr)).await.unwrap();
+	assert_eq!(response.body, method_not_found(Id::Num(1)));
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":2}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).await.unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(2)));
+	let response = http_request(req.into(), admin_uri.clone()).await.unwrap();
+	assert_eq!(response.body, method_not_found(Id::Num(2)));
+
+	let req = format!(r#"{{"jsonrpc":"2.0","method":"shutdown","params":["{}"],"id":3}}"#, "a".repeat(64));
+	let response = http_request(req.into(), admin_uri).await.unwrap();
+	assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn validate_reports_configuration_issues() {
+	use jsonrpsee_utils::server_utils::{ConfigIssue, MethodFlags, MethodNormalization};
+
+	let builder = HttpServerBuilder::default().max_request_body_size(0).max_concurrent_requests(4);
+	assert_eq!(builder.validate().issues.len(), 1);
+	assert!(HttpServerBuilder::default().validate().is_ok());
+
+	let mut server = HttpServerBuilder::default().build("127.0.0.1:0".parse().unwrap()).unwrap();
+	server.register_method("say_hello", |_| Ok("lo")).unwrap();
+	server.register_method("sayHello", |_| Ok("lo")).unwrap();
+	assert!(server.validate().is_ok());
+
+	server.set_method_normalization(MethodNormalization::SeparatorInsensitive);
+	let flags = MethodFlags::new();
+	flags.disable("say_goodbye");
+	server.set_method_flags(flags);
+	assert_eq!(
+		server.validate().issues,
+		vec![
+			ConfigIssue::NormalizedNameCollision(vec!["sayHello".into(), "say_hello".into()]),
+			ConfigIssue::UnknownMethod { setting: "method flags", method: "say_goodbye".into() },
+		]
+	);
+}
+
+#[test]
+fn config_round_trips_through_builder() {
+	use crate::{BatchOrder, ServerConfig};
+
+	assert_eq!(serde_json::from_str::<ServerConfig>("{}").unwrap(), ServerConfig::default());
+	let config: ServerConfig =
+		serde_json::from_str(r#"{"body_read_timeout_ms":2000,"batch_response_order":"Completion"}"#).unwrap();
+	assert_eq!(config.batch_response_order, BatchOrder::Completion);
+	assert_eq!(HttpServerBuilder::from(config.clone()).to_config(), config);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn fair_scheduler_serves_other_connections_during_batches() {
+	use crate::FairScheduler;
+	use std::sync::{mpsc, Mutex};
+	use std::time::Duration;
+
+	// Calls to `slow` block until they are released, `block_in_place` lets the other connections run meanwhile.
+	let (entered_tx, mut entered_rx) = tokio::sync::mpsc::unbounded_channel();
+	let (release_tx, release_rx) = mpsc::channel::<()>();
+	let release_rx = Mutex::new(release_rx);
+	let scheduler = FairScheduler::new(1);
+	let mut server = HttpServerBuilder::default().build("127.0.0.1:0".parse().unwrap()).unwrap();
+	let addr = server.local_addr().unwrap();
+	server
+		.register_method("slow", move |_| {
+			entered_tx.send(()).unwrap();
+			tokio::task::block_in_place(|| release_rx.lock().unwrap().recv().unwrap());
+			Ok("done")
+		})
+		.unwrap();
+	server.register_method("say_hello", |_| Ok("lo")).unwrap();
+	server.set_fair_scheduler(scheduler.clone());
+	tokio::spawn(async move { server.start().await.unwrap() });
+	let uri = to_http_uri(addr);
+
+	let batch: Vec<_> = (0..3).map(|i| format!(r#"{{"jsonrpc":"2.0","method":"slow","id":{}}}"#, i)).collect();
+	let batch = tokio::spawn(http_request(format!("[{}]", batch.join(",")).into(), uri.clone()));
+	entered_rx.recv().await.unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let call = tokio::spawn(http_request(req.into(), uri));
+	while scheduler.waiting() == 0 {
+		tokio::time::sleep(Duration::from_millis(1)).await;
+	}
+
+	// The call gets the turn of the first call of the batch, the other calls of the batch are still blocked.
+	release_tx.send(()).unwrap();
+	let response = call.await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+	assert!(!batch.is_finished());
+
+	release_tx.send(()).unwrap();
+	release_tx.send(()).unwrap();
+	assert_eq!(batch.await.unwrap().unwrap().body.matches("done").count(), 3);
+}
@@ -27,6 +27,7 @@
 //! Contains implementation of the `syn::parse::Parse` trait. Allows parsing the input tokens
 //! stream in a structured way.
 
+use inflector::Inflector as _;
 use syn::spanned::Spanned as _;
 
 /// Multiple `ApiDefinition`s grouped into one struct.
@@ -40,6 +41,8 @@ pub struct ApiDefinitions {
 /// A single API defined by the user.
 #[derive(Debug)]
 pub struct ApiDefinition {
+	/// Attributes on the definition.
+	pub attributes: ApiAttrs,
 	/// Visibility of the definition (e.g. `pub`, `pub(crate)`, ...).
 	pub visibility: syn::Visibility,
 	/// Name of the API. For example `System`.
@@ -57,6 +60,66 @@ pub struct ApiMethod {
 	pub signature: syn::Signature,
 	/// Attributes on the method.
 	pub attributes: ApiMethodAttrs,
+	/// Doc comments of the method, copied to the generated functions.
+	pub docs: Vec<syn::Attribute>,
+}
+
+/// List of attributes applied to an API definition.
+#[derive(Debug, Default)]
+pub struct ApiAttrs {
+	/// Case convention of the RPC names of methods without a `method` attribute, if specified.
+	pub rename_all: Option<RenameAll>,
+}
+
+/// Case convention applied to method names, same as `#[serde(rename_all = "...")]`.
+#[derive(Debug, Clone, Copy)]
+pub enum RenameAll {
+	/// `lowercase`
+	Lower,
+	/// `UPPERCASE`
+	Upper,
+	/// `PascalCase`
+	Pascal,
+	/// `camelCase`
+	Camel,
+	/// `snake_case`
+	Snake,
+	/// `SCREAMING_SNAKE_CASE`
+	ScreamingSnake,
+	/// `kebab-case`
+	Kebab,
+	/// `SCREAMING-KEBAB-CASE`
+	ScreamingKebab,
+}
+
+impl RenameAll {
+	/// Applies the case convention to a snake case method name.
+	pub fn apply(self, name: &str) -> String {
+		match self {
+			RenameAll::Lower => name.replace('_', ""),
+			RenameAll::Upper => name.replace('_', "").to_uppercase(),
+			RenameAll::Pascal => name.to_pascal_case(),
+			RenameAll::Camel => name.to_camel_case(),
+			RenameAll::Snake => name.to_snake_case(),
+			RenameAll::ScreamingSnake => name.to_screaming_snake_case(),
+			RenameAll::Kebab => name.to_kebab_case(),
+			RenameAll::ScreamingKebab => name.to_kebab_case().to_uppercase(),
+		}
+	}
+
+	fn from_lit(lit: &syn::LitStr) -> syn::parse::Result<Self> {
+		match lit.value().as_str() {
+			"lowercase" => Ok(RenameAll::Lower),
+			"UPPERCASE" => Ok(RenameAll::Upper),
+			"PascalCase" => Ok(RenameAll::Pascal),
+			"camelCase" => Ok(RenameAll::Camel),
+			"snake_case" => Ok(RenameAll::Snake),
+			"SCREAMING_SNAKE_CASE" => Ok(RenameAll::ScreamingSnake),
+			"kebab-case" => Ok(RenameAll::Kebab),
+			"SCREAMING-KEBAB-CASE" => Ok(RenameAll::ScreamingKebab),
+			other => Err(syn::Error::new(lit.span(), format!("Unknown rename_all case convention: {}", other))),
+		}
+	}
 }
 
 /// List of attributes applied to a method.
@@ -115,6 +178,23 @@ impl syn::parse::Parse for ApiDefinitions {
 
 impl syn::parse::Parse for ApiDefinition {
 	fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+		let mut attributes = ApiAttrs::default();
+		for attribute in input.call(syn::Attribute::parse_outer)? {
+			if !attribute.path.is_ident("rpc") {
+				return Err(syn::Error::new(
+					attribute.span(),
+					"Only `rpc` attributes are allowed on the API definition",
+				));
+			}
+			let attrs: ApiAttrs = attribute.parse_args()?;
+			if attrs.rename_all.is_some() {
+				if attributes.rename_all.is_some() {
+					return Err(syn::Error::new(attribute.span(), "Duplicate rename_all attribute found"));
+				}
+				attributes.rename_all = attrs.rename_all;
+			}
+		}
+
 		let visibility = input.parse()?;
 		let name = input.parse()?;
 		let generics = input.parse()?;
@@ -122,7 +202,7 @@ impl syn::parse::Parse for ApiDefinition {
 		assert_eq!(group.delimiter(), proc_macro2::Delimiter::Brace);
 		let defs: ApiMethods = syn::parse2(group.stream())?;
 
-		Ok(ApiDefinition { visibility, name, generics, definitions: defs.definitions })
+		Ok(ApiDefinition { attributes, visibility, name, generics, definitions: defs.definitions })
 	}
 }
 
@@ -137,16 +217,19 @@ impl syn::parse::Parse for ApiMethod {
 		}
 
 		let mut attributes = ApiMethodAttrs::default();
-		for attribute in &item.attrs {
+		let mut docs = Vec::new();
+		for attribute in item.attrs {
 			if attribute.path.is_ident("rpc") {
 				let attrs = attribute.parse_args()?;
 				attributes.try_merge(attrs)?;
+			} else if attribute.path.is_ident("doc") {
+				docs.push(attribute);
 			} else {
 				// TODO: do we copy the attributes somewhere in the output?
 			}
 		}
 
-		Ok(ApiMethod { signature: item.sig, attributes })
+		Ok(ApiMethod { signature: item.sig, attributes, docs })
 	}
 }
 
@@ -170,6 +253,28 @@ impl ApiMethodAttrs {
 	}
 }
 
+impl syn::parse::Parse for ApiAttrs {
+	fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
+		let mut out = ApiAttrs::default();
+
+		let list = input.parse_terminated::<_, syn::token::Comma>(|input| {
+			let attr: syn::Ident = input.parse()?;
+			if attr != "rename_all" {
+				return Err(syn::Error::new(attr.span(), format!("Unknown attribute: {}", attr)));
+			}
+			let _: syn::token::Eq = input.parse()?;
+			input.parse::<syn::LitStr>()
+		})?;
+		for lit in list {
+			if out.rename_all.is_some() {
+				return Err(syn::Error::new(lit.span(), "Duplicate rename_all attribute found"));
+			}
+			out.rename_all = Some(RenameAll::from_lit(&lit)?);
+		}
+		Ok(out)
+	}
+}
+
 impl syn::parse::Parse for ApiMethodAttrs {
 	fn parse(input: syn::parse::ParseStream) -> syn::parse::Result<Self> {
 		let mut out = ApiMethodAttrs::default();
@@ -33,6 +33,7 @@ use std::collections::HashSet;
 use syn::spanned::Spanned as _;
 
 mod api_def;
+mod manifest;
 
 /// Wraps around one or more API definitions and generates an enum.
 ///
@@ -51,6 +52,10 @@ mod api_def;
 /// The content of the blocks is the same as the content of a trait definition, except that
 /// default implementations for methods are forbidden.
 ///
+/// The RPC method name is the name of the function unless renamed with `#[rpc(method = "...")]`.
+/// An API definition can be prefixed with `#[rpc(rename_all = "camelCase")]` to turn the names of all
+/// its functions into a case convention, using the same conventions as `#[serde(rename_all = "...")]`.
+///
 /// For each identifier (such as `Foo` and `Bar` in the example above), this macro will generate
 /// an enum where each variant corresponds to a function of the definition. Function names are
 /// turned into PascalCase to conform to the Rust style guide.
@@ -109,6 +114,68 @@ pub fn rpc_client_api(input_token_stream: TokenStream) -> TokenStream {
 	})
 }
 
+/// Generates the client and server of the APIs listed in a JSON manifest, to migrate large API surfaces
+/// that are described outside of Rust.
+///
+/// The path of the manifest is relative to the directory of the `Cargo.toml` of the crate, and the crate is
+/// rebuilt when the manifest changes:
+///
+/// ```ignore
+/// jsonrpsee_proc_macros::rpc_api_from_manifest!("api.json");
+/// ```
+///
+/// The manifest is an array of APIs, the fields mirror the syntax of [`rpc_client_api!`]; only `name`
+/// fields are required and a method without `returns` is a notification:
+///
+/// ```json
+/// [{
+///     "name": "Chain",
+///     "visibility": "pub",
+///     "rename_all": "camelCase",
+///     "methods": [{
+///         "name": "header",
+///         "method": "chain_getHeader",
+///         "docs": "Returns the header of the block with `hash`.",
+///         "positional_params": true,
+///         "params": [{ "name": "hash", "type": "u128" }],
+///         "returns": "Option<u128>"
+///     }]
+/// }]
+/// ```
+///
+/// Besides the client functions of [`rpc_client_api!`], every API `Foo` gets a `FooServer` trait with one
+/// function per method, and a `Foo::register_server` function registering an implementation of the trait
+/// with the `register_method` of a server:
+///
+/// ```ignore
+/// Chain::register_server(Arc::new(MyChain), |name, method| server.register_method(name, method))?;
+/// ```
+#[proc_macro]
+pub fn rpc_api_from_manifest(input_token_stream: TokenStream) -> TokenStream {
+	let path: syn::LitStr = match syn::parse(input_token_stream) {
+		Ok(path) => path,
+		Err(err) => return err.to_compile_error().into(),
+	};
+	let (defs, full_path) = match manifest::load(&path) {
+		Ok(loaded) => loaded,
+		Err(err) => return err.to_compile_error().into(),
+	};
+
+	let mut out = Vec::with_capacity(defs.apis.len() * 2);
+	for api in defs.apis {
+		match build_server_api(&api).and_then(|server| Ok((server, build_client_api(api)?))) {
+			Ok((server, client)) => out.extend([server, client]),
+			Err(err) => return err.to_compile_error().into(),
+		};
+	}
+
+	TokenStream::from(quote! {
+		// NOTE: makes the crate depend on the manifest.
+		const _: &[u8] = include_bytes!(#full_path);
+		#(#out)*
+	})
+}
+
 /// Generates the macro output token stream corresponding to a single API.
 fn build_client_api(api: api_def::ApiDefinition) -> Result<proc_macro2::TokenStream, syn::Error> {
 	let enum_name = &api.name;
@@ -196,8 +263,7 @@ fn build_client_functions(api: &api_def::ApiDefinition) -> Result<Vec<proc_macro
 			syn::ReturnType::Default => quote!(()),
 			syn::ReturnType::Type(_, ref ty) => quote_spanned!(ty.span()=> #ty),
 		};
-		let rpc_method_name =
-			function.attributes.method.clone().unwrap_or_else(|| function.signature.ident.to_string());
+		let rpc_method_name = rpc_method_name(api, function);
 
 		let mut params_list = Vec::new();
 		let mut params_to_json = Vec::new();
@@ -263,7 +329,9 @@ fn build_client_functions(api: &api_def::ApiDefinition) -> Result<Vec<proc_macro
 			)
 		};
 
+		let docs = &function.docs;
 		client_functions.push(quote_spanned!(function.signature.span()=>
+			#(#docs)*
 			#visibility async fn #f_name (client: &impl jsonrpsee_types::traits::Client #(, #params_list)*) -> core::result::Result<#ret_ty, jsonrpsee_types::error::Error>
 			where
 				#ret_ty: jsonrpsee_types::jsonrpc::DeserializeOwned
@@ -277,6 +345,112 @@ fn build_client_functions(api: &api_def::ApiDefinition) -> Result<Vec<proc_macro
 	Ok(client_functions)
 }
 
+/// Builds the server trait of an API and the `register_server` function registering its implementations.
+fn build_server_api(api: &api_def::ApiDefinition) -> Result<proc_macro2::TokenStream, syn::Error> {
+	let enum_name = &api.name;
+	let visibility = &api.visibility;
+	let trait_name = format_ident!("{}Server", enum_name);
+	if !api.generics.params.is_empty() {
+		return Err(syn::Error::new(api.generics.span(), "Servers of generic APIs are not supported"));
+	}
+
+	let mut trait_functions = Vec::new();
+	let mut registrations = Vec::new();
+	for function in &api.definitions {
+		let f_name = &function.signature.ident;
+		let docs = &function.docs;
+		let ret_ty = match function.signature.output {
+			syn::ReturnType::Default => quote!(()),
+			syn::ReturnType::Type(_, ref ty) => quote_spanned!(ty.span()=> #ty),
+		};
+		let rpc_method_name = rpc_method_name(api, function);
+
+		let mut params_list = Vec::new();
+		let mut params_names = Vec::new();
+		let mut params_tys = Vec::new();
+		for input in function.signature.inputs.iter() {
+			let (ty, pat) = match input {
+				syn::FnArg::Receiver(_) => {
+					return Err(syn::Error::new(
+						input.span(),
+						"Having `self` is not allowed in RPC queries definitions",
+					));
+				}
+				syn::FnArg::Typed(syn::PatType { ty, pat, .. }) => (ty, pat),
+			};
+			let name = param_variant_name(pat)?;
+			params_list.push(quote_spanned!(pat.span()=> #name: #ty));
+			params_names.push(name);
+			params_tys.push(ty);
+		}
+
+		let params_pat = if params_names.is_empty() { quote!(_) } else { quote!(params) };
+		let params_parsing = if params_names.is_empty() {
+			quote!()
+		} else if function.attributes.positional_params {
+			quote! {
+				let (#(#params_names,)*): (#(#params_tys,)*) = params.parse()?;
+			}
+		} else {
+			let rpc_params_names = params_names.iter().map(|name| name.to_string());
+			quote! {
+				let mut params: jsonrpsee_types::jsonrpc::JsonMap<String, jsonrpsee_types::jsonrpc::JsonValue> = params.parse()?;
+				#(
+					let #params_names: #params_tys = jsonrpsee_types::jsonrpc::from_value(params.remove(#rpc_params_names).unwrap_or_default())
+						.map_err(|_| jsonrpsee_types::v2::RpcError::InvalidParams)?;
+				)*
+			}
+		};
+
+		trait_functions.push(quote_spanned!(function.signature.span()=>
+			#(#docs)*
+			fn #f_name(&self #(, #params_list)*) -> core::result::Result<#ret_ty, jsonrpsee_types::v2::RpcError>;
+		));
+		registrations.push(quote_spanned!(function.signature.span()=>
+			let server = handler.clone();
+			register(#rpc_method_name, Box::new(move |#params_pat: jsonrpsee_types::v2::RpcParams| {
+				#params_parsing
+				let result = server.#f_name(#(#params_names),*)?;
+				jsonrpsee_types::jsonrpc::to_value(result).map_err(|_| jsonrpsee_types::v2::RpcError::Unknown)
+			}))?;
+		));
+	}
+
+	let trait_doc = format!(" Server of the `{}` API, see [`{}::register_server`].", enum_name, enum_name);
+	Ok(quote_spanned!(api.name.span()=>
+		#[doc = #trait_doc]
+		#visibility trait #trait_name: Send + Sync + 'static {
+			#(#trait_functions)*
+		}
+
+		impl #enum_name {
+			/// Registers the methods of `handler` with `register`, such as the `register_method` of a server.
+			#[allow(unused)]
+			#visibility fn register_server<S: #trait_name>(
+				handler: std::sync::Arc<S>,
+				mut register: impl FnMut(
+					&'static str,
+					Box<dyn Fn(jsonrpsee_types::v2::RpcParams) -> core::result::Result<jsonrpsee_types::jsonrpc::JsonValue, jsonrpsee_types::v2::RpcError> + Send + Sync>,
+				) -> core::result::Result<(), jsonrpsee_types::error::Error>,
+			) -> core::result::Result<(), jsonrpsee_types::error::Error> {
+				#(#registrations)*
+				Ok(())
+			}
+		}
+	))
+}
+
+/// Returns the RPC name of a method, renamed according to the conventions of the API.
+fn rpc_method_name(api: &api_def::ApiDefinition, function: &api_def::ApiMethod) -> String {
+	function.attributes.method.clone().unwrap_or_else(|| {
+		let name = function.signature.ident.to_string();
+		match api.attributes.rename_all {
+			Some(rename_all) => rename_all.apply(&name),
+			None => name,
+		}
+	})
+}
+
 /// Turns a snake case function name into an UpperCamelCase name suitable to be an enum variant.
 fn snake_case_to_camel_case(snake_case: &syn::Ident) -> syn::Ident {
 	syn::Ident::new(&snake_case.to_string().to_pascal_case(), snake_case.span())
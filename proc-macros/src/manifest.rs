@@ -0,0 +1,111 @@
+//! Turns a JSON manifest of APIs into the same definitions as the content of `rpc_client_api!`.
+
+use crate::api_def::ApiDefinitions;
+use quote::quote;
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+
+/// Reads the manifest at `path`, relative to the directory of the manifest of the crate being built.
+///
+/// Returns the definitions and the absolute path of the manifest.
+pub fn load(path: &syn::LitStr) -> syn::Result<(ApiDefinitions, String)> {
+	let mut full_path = std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap_or_default();
+	full_path.push(path.value());
+	let err = |msg: String| syn::Error::new(path.span(), msg);
+
+	let content = std::fs::read_to_string(&full_path)
+		.map_err(|e| err(format!("Failed to read {}: {}", full_path.display(), e)))?;
+	let manifest: Value =
+		serde_json::from_str(&content).map_err(|e| err(format!("Failed to parse {}: {}", full_path.display(), e)))?;
+	let apis = manifest.as_array().ok_or_else(|| err("The manifest must be an array of APIs".into()))?;
+
+	let mut tokens = Vec::with_capacity(apis.len());
+	for api in apis {
+		tokens.push(api_tokens(api).map_err(&err)?);
+	}
+	let defs = syn::parse2(quote!(#(#tokens)*))
+		.map_err(|e| err(format!("Invalid definition in {}: {}", full_path.display(), e)))?;
+	Ok((defs, full_path.display().to_string()))
+}
+
+/// Returns the tokens of a single API definition.
+fn api_tokens(api: &Value) -> Result<proc_macro2::TokenStream, String> {
+	let api = api.as_object().ok_or("Every API must be an object")?;
+	let name = ident(str_field(api, "name")?.ok_or("Missing API name")?)?;
+	let visibility: syn::Visibility = match str_field(api, "visibility")? {
+		Some(vis) => syn::parse_str(vis).map_err(|e| format!("Invalid visibility of {}: {}", name, e))?,
+		None => syn::Visibility::Inherited,
+	};
+	let rename_all = str_field(api, "rename_all")?.map(|case| quote!(#[rpc(rename_all = #case)]));
+
+	let mut methods = Vec::new();
+	let defs = api.get("methods").and_then(Value::as_array).ok_or_else(|| format!("Missing methods of {}", name))?;
+	for method in defs {
+		methods.push(method_tokens(method).map_err(|e| format!("{}: {}", name, e))?);
+	}
+
+	Ok(quote! {
+		#rename_all
+		#visibility #name {
+			#(#methods)*
+		}
+	})
+}
+
+/// Returns the tokens of a single method definition.
+fn method_tokens(method: &Value) -> Result<proc_macro2::TokenStream, String> {
+	let method = method.as_object().ok_or("Every method must be an object")?;
+	let name = ident(str_field(method, "name")?.ok_or("Missing method name")?)?;
+	let docs = str_field(method, "docs")?.unwrap_or_default().lines().map(|line| {
+		let line = format!(" {}", line);
+		quote!(#[doc = #line])
+	});
+
+	let mut rpc_attrs = Vec::new();
+	if let Some(rpc_method) = str_field(method, "method")? {
+		rpc_attrs.push(quote!(method = #rpc_method));
+	}
+	match method.get("positional_params") {
+		None | Some(Value::Bool(false)) => {}
+		Some(Value::Bool(true)) => rpc_attrs.push(quote!(positional_params)),
+		Some(_) => return Err(format!("positional_params of {} must be a boolean", name)),
+	}
+	let rpc_attrs = if rpc_attrs.is_empty() { None } else { Some(quote!(#[rpc(#(#rpc_attrs),*)])) };
+
+	let mut params = Vec::new();
+	for param in method.get("params").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default() {
+		let param = param.as_object().ok_or_else(|| format!("Every param of {} must be an object", name))?;
+		let param_name = ident(str_field(param, "name")?.ok_or_else(|| format!("Missing param name of {}", name))?)?;
+		let ty = ty(str_field(param, "type")?.ok_or_else(|| format!("Missing type of {}", param_name))?)?;
+		params.push(quote!(#param_name: #ty));
+	}
+	let output = match str_field(method, "returns")? {
+		Some(returns) => {
+			let returns = ty(returns)?;
+			quote!(-> #returns)
+		}
+		None => quote!(),
+	};
+
+	Ok(quote! {
+		#(#docs)*
+		#rpc_attrs
+		fn #name(#(#params),*) #output;
+	})
+}
+
+fn str_field<'a>(object: &'a Map<String, Value>, field: &str) -> Result<Option<&'a str>, String> {
+	match object.get(field) {
+		None => Ok(None),
+		Some(Value::String(value)) => Ok(Some(value)),
+		Some(_) => Err(format!("{} must be a string", field)),
+	}
+}
+
+fn ident(name: &str) -> Result<syn::Ident, String> {
+	syn::parse_str(name).map_err(|_| format!("Invalid name {:?}", name))
+}
+
+fn ty(ty: &str) -> Result<syn::Type, String> {
+	syn::parse_str(ty).map_err(|_| format!("Invalid type {:?}", ty))
+}
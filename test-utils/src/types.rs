@@ -70,6 +70,18 @@ impl WebSocketTestClient {
 		String::from_utf8(data).map_err(Into::into)
 	}
 
+	/// Sends `msg` without waiting for a response, such that several requests can be in flight.
+	pub async fn send_text(&mut self, msg: impl AsRef<str>) -> Result<(), Error> {
+		self.tx.send_text(msg).await?;
+		self.tx.flush().await.map_err(Into::into)
+	}
+
+	pub async fn receive(&mut self) -> Result<String, Error> {
+		let mut data = Vec::new();
+		self.rx.receive_data(&mut data).await?;
+		String::from_utf8(data).map_err(Into::into)
+	}
+
 	pub async fn close(&mut self) -> Result<(), Error> {
 		self.tx.close().await.map_err(Into::into)
 	}
@@ -197,19 +209,19 @@ async fn connection_task(socket: async_std::net::TcpStream, mode: ServerMode, mu
 			ws = next_ws => {
 				// Got a request on the connection but don't care about the contents.
 				// Just send out the pre-configured hardcoded responses.
-				if let Some(Ok(_)) = ws {
-					match &mode {
-						ServerMode::Response(r) => {
-							if let Err(e) = sender.send_text(&r).await {
-								log::warn!("send response to request error: {:?}", e);
-							}
+				match (ws, &mode) {
+					(Some(Ok(_)), ServerMode::Response(r)) => {
+						if let Err(e) = sender.send_text(&r).await {
+							log::warn!("send response to request error: {:?}", e);
 						}
-						ServerMode::Subscription { subscription_id, .. } => {
-							if let Err(e) = sender.send_text(&subscription_id).await {
-								log::warn!("send subscription id error: {:?}", e);
-							}
+					}
+					(Some(Ok(_)), ServerMode::Subscription { subscription_id, .. }) => {
+						if let Err(e) = sender.send_text(&subscription_id).await {
+							log::warn!("send subscription id error: {:?}", e);
 						}
 					}
+					// The connection was closed.
+					(Some(Err(_)), _) | (None, _) => break,
 				}
 			}
 			_ = next_exit => break,
@@ -70,6 +70,13 @@ pub async fn websocket_server() -> SocketAddr {
 		let mut server = rt.block_on(WsServer::new("127.0.0.1:0")).unwrap();
 
 		server.register_method("say_hello", |_| Ok("hello")).unwrap();
+		server.register_method("sayHello", |_| Ok("hello")).unwrap();
+		server
+			.register_method("sleep_ms", |params| {
+				std::thread::sleep(Duration::from_millis(params.one()?));
+				Ok("awake")
+			})
+			.unwrap();
 
 		rt.block_on(async move {
 			server_started_tx.send(server.local_addr().unwrap()).unwrap();
@@ -36,10 +36,25 @@ use helpers::{http_server, websocket_server, websocket_server_with_subscription}
 use jsonrpsee_http_client::HttpClientBuilder;
 use jsonrpsee_types::{
 	error::Error,
-	jsonrpc::{JsonValue, Params},
-	traits::{Client, SubscriptionClient},
+	jsonrpc::{self, DeserializeOwned, ErrorCode, JsonValue, Params, ParseError},
+	traits::{Client, SubscriptionClient, Value},
 };
 use jsonrpsee_ws_client::{WsClientBuilder, WsSubscription};
+use serde::{Deserialize, Serialize};
+
+/// Value type upper-casing the strings it decodes, to tell it apart from [`JsonValue`].
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+struct Shouting(JsonValue);
+
+impl Value for Shouting {
+	fn decode<T: DeserializeOwned>(self) -> Result<T, ParseError> {
+		match self.0 {
+			JsonValue::String(s) => jsonrpc::from_value(JsonValue::String(s.to_uppercase())),
+			value => jsonrpc::from_value(value),
+		}
+	}
+}
 
 #[tokio::test]
 async fn ws_subscription_works() {
@@ -59,6 +74,28 @@ async fn ws_subscription_works() {
 	}
 }
 
+#[tokio::test]
+async fn ws_client_shared_between_tasks() {
+	let server_addr = websocket_server_with_subscription().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = Arc::new(WsClientBuilder::default().build(&server_url).await.unwrap());
+
+	let tasks: Vec<_> = (0..4)
+		.map(|_| {
+			let client = client.clone();
+			tokio::spawn(async move {
+				let response: String = client.request("say_hello", Params::None).await.unwrap();
+				let mut sub: WsSubscription<u64> =
+					client.subscribe("subscribe_foo", Params::None, "unsubscribe_foo").await.unwrap();
+				(response, sub.next().await)
+			})
+		})
+		.collect();
+	for task in tasks {
+		assert_eq!(task.await.unwrap(), ("hello".to_owned(), Some(1337)));
+	}
+}
+
 #[tokio::test]
 async fn ws_method_call_works() {
 	let server_addr = websocket_server().await;
@@ -68,6 +105,390 @@ async fn ws_method_call_works() {
 	assert_eq!(response, JsonValue::String("hello".into()));
 }
 
+#[tokio::test]
+async fn ws_method_call_decodes_through_value_type() {
+	let server_addr = websocket_server().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default().value_type::<Shouting>().build(&server_url).await.unwrap();
+	let response: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(&response, "HELLO");
+}
+
+#[tokio::test]
+async fn ws_batch_is_split_when_server_rejects_batches() {
+	let server_addr = websocket_server().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default().split_rejected_batches(true).build(&server_url).await.unwrap();
+	let batch = vec![("say_hello", Params::None), ("unknown_method", Params::None), ("sayHello", Params::None)];
+
+	let results: Vec<Result<String, _>> = client.batch_request_results(batch).await.unwrap();
+	assert_eq!(results[0], Ok("hello".to_owned()));
+	assert_eq!(results[1].as_ref().unwrap_err().code, ErrorCode::MethodNotFound);
+	assert_eq!(results[2], Ok("hello".to_owned()));
+	assert_eq!(client.split_batches(), 1);
+
+	// Following batches are split right away.
+	let results: Vec<String> = client.batch_request(vec![("say_hello", Params::None)]).await.unwrap();
+	assert_eq!(results, vec!["hello".to_owned()]);
+	assert_eq!(client.split_batches(), 2);
+	let response: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(response, "hello");
+}
+
+#[tokio::test]
+async fn ws_preflight_fails_build_on_misconfigured_endpoint() {
+	use jsonrpsee_types::preflight::Preflight;
+	use jsonrpsee_ws_server::WsServer;
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server.register_method("rpc_methods", |_| Ok(vec!["rpc_methods", "chain_getHead"])).unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	tokio::spawn(server.start());
+
+	let preflight = Preflight::rpc_methods(vec!["chain_getHead"]);
+	assert!(WsClientBuilder::default().preflight(preflight).build(&server_url).await.is_ok());
+
+	let preflight = Preflight::rpc_methods(vec!["chain_getHead", "state_getStorage"]);
+	let err = WsClientBuilder::default().preflight(preflight).build(&server_url).await.unwrap_err();
+	assert!(matches!(err, Error::MissingMethods(ref methods) if methods == &["state_getStorage"]));
+
+	let preflight = Preflight::call("system_health", Params::None);
+	let err = WsClientBuilder::default().preflight(preflight).build(&server_url).await.unwrap_err();
+	assert!(matches!(err, Error::Request(ref err) if err.code == ErrorCode::MethodNotFound));
+}
+
+#[tokio::test]
+async fn ws_request_with_timeout_overrides_request_timeout() {
+	let server_addr = websocket_server().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client =
+		WsClientBuilder::default().request_timeout(Some(Duration::from_millis(50))).build(&server_url).await.unwrap();
+	let err = client.request::<String, _, _>("sleep_ms", Params::Array(vec![200.into()])).await.unwrap_err();
+	assert!(matches!(err, Error::WsRequestTimeout(ref call) if call.method == "sleep_ms"));
+	assert_eq!(err.to_string(), "Websocket request timeout waiting for response to sleep_ms");
+	let response: String =
+		client.request_with_timeout("sleep_ms", Params::Array(vec![200.into()]), Duration::from_secs(5)).await.unwrap();
+	assert_eq!(response, "awake");
+}
+
+#[tokio::test]
+async fn ws_retry_policy_retries_timeouts_only() {
+	use jsonrpsee_types::retry::RetryPolicy;
+
+	let server_addr = websocket_server().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default()
+		.request_timeout(Some(Duration::from_millis(50)))
+		.retry_policy(RetryPolicy::new(2, Duration::from_millis(100)))
+		.build(&server_url)
+		.await
+		.unwrap();
+
+	let started = std::time::Instant::now();
+	let err = client.request::<String, _, _>("unknown_method", Params::None).await.unwrap_err();
+	assert!(matches!(err, Error::Request(_)));
+	assert!(started.elapsed() < Duration::from_millis(100));
+
+	// Two retries after 100 and 200 ms.
+	let started = std::time::Instant::now();
+	let err = client.request::<String, _, _>("sleep_ms", Params::Array(vec![500.into()])).await.unwrap_err();
+	assert!(matches!(err, Error::WsRequestTimeout(_)));
+	assert!(started.elapsed() >= Duration::from_millis(450));
+}
+
+#[tokio::test]
+async fn ws_paused_subscription_buffers_notifications() {
+	use jsonrpsee_ws_server::WsServer;
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	let mut sink = server.register_subscription("subscribe_count", "unsubscribe_count").unwrap();
+	server.register_subscription_pausing(2).unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	tokio::spawn(server.start());
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let mut sub: WsSubscription<u64> =
+		client.subscribe("subscribe_count", Params::None, "unsubscribe_count").await.unwrap();
+	sink.send(&0).unwrap();
+	assert_eq!(sub.next().await, Some(0));
+
+	assert!(client.pause_subscription(&sub).await.unwrap());
+	for n in 1..=3 {
+		sink.send(&n).unwrap();
+	}
+	assert!(tokio::time::timeout(Duration::from_millis(100), sub.next()).await.is_err());
+
+	// The oldest buffered notification was dropped.
+	assert!(client.resume_subscription(&sub).await.unwrap());
+	assert_eq!(sub.next().await, Some(2));
+	assert_eq!(sub.next().await, Some(3));
+	sink.send(&4).unwrap();
+	assert_eq!(sub.next().await, Some(4));
+}
+
+#[tokio::test]
+async fn ws_compressed_fields_are_transparent() {
+	use jsonrpsee_types::codec::{CompressedFields, Deflate};
+	use jsonrpsee_ws_server::{Transformers, WsServer};
+
+	let fields = CompressedFields::new(Deflate::default()).param("code_upload", "/0").result("code_upload", "");
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server.register_method("code_upload", |params| params.one::<String>().map(|code| code.to_uppercase())).unwrap();
+	server.set_transformers(Transformers::new().compressed_fields(&fields));
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	tokio::spawn(server.start());
+
+	let code = "0061736d".repeat(256);
+	let client = WsClientBuilder::default().middleware(fields).build(&server_url).await.unwrap();
+	let uploaded: String = client.request("code_upload", Params::Array(vec![code.clone().into()])).await.unwrap();
+	assert_eq!(uploaded, code.to_uppercase());
+
+	// Without the middleware the result stays compressed, while uncompressed params are accepted.
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let uploaded: String = client.request("code_upload", Params::Array(vec![code.clone().into()])).await.unwrap();
+	assert!(uploaded.starts_with("deflate:") && uploaded.len() < code.len());
+}
+
+#[tokio::test]
+async fn ws_subscription_filter_discards_notifications_before_buffering() {
+	use jsonrpsee_ws_server::WsServer;
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	let mut sink = server.register_subscription("subscribe_numbers", "unsubscribe_numbers").unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	tokio::spawn(server.start());
+
+	// The buffer overflows, which closes the subscription, unless the odd numbers are discarded.
+	let client = WsClientBuilder::default().max_notifs_per_subscription(1).build(&server_url).await.unwrap();
+	let mut sub: WsSubscription<u64> = client
+		.subscribe_filtered("subscribe_numbers", Params::None, "unsubscribe_numbers", |notif| match notif.as_u64() {
+			Some(n) if n % 2 == 0 => Some((n * 10).into()),
+			_ => None,
+		})
+		.await
+		.unwrap();
+	for n in &[1_u64, 3, 5, 7, 9, 2] {
+		sink.send(n).unwrap();
+	}
+	assert_eq!(sub.next().await, Some(20));
+	sink.send(&4_u64).unwrap();
+	assert_eq!(sub.next().await, Some(40));
+}
+
+#[tokio::test]
+async fn ws_cancelled_requests_cancel_their_handler() {
+	use jsonrpsee_ws_server::WsServer;
+	use std::time::Instant;
+
+	let (cancelled_tx, mut cancelled_rx) = tokio::sync::mpsc::unbounded_channel();
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server
+		.register_cancellable_method("wait_for_cancel", move |_, token| {
+			let started = Instant::now();
+			while !token.is_cancelled() {
+				if started.elapsed() > Duration::from_secs(5) {
+					return Ok(false);
+				}
+				std::thread::sleep(Duration::from_millis(10));
+			}
+			cancelled_tx.send(()).unwrap();
+			Ok(true)
+		})
+		.unwrap();
+	server.register_cancellation().unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	tokio::spawn(server.start());
+
+	let client = WsClientBuilder::default().cancel_dropped_requests(true).build(&server_url).await.unwrap();
+	let (handle, response) = client.cancellable_request::<bool, _, _>("wait_for_cancel", Params::None);
+	let cancel = async {
+		tokio::time::sleep(Duration::from_millis(100)).await;
+		handle.cancel();
+	};
+	let (response, ()) = futures::join!(response, cancel);
+	assert!(matches!(response, Err(Error::RequestCancelled)));
+	tokio::time::timeout(Duration::from_secs(2), cancelled_rx.recv()).await.unwrap().unwrap();
+	assert!(client.is_connected());
+}
+
+#[tokio::test]
+async fn ws_negotiated_codec_serializes_messages() {
+	use jsonrpsee_types::wire::{Cbor, Codec};
+	use jsonrpsee_ws_server::WsServer;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	/// Counts the messages serialized with CBOR.
+	struct Counted(Arc<AtomicUsize>);
+
+	impl Codec for Counted {
+		fn subprotocol(&self) -> &str {
+			Cbor.subprotocol()
+		}
+
+		fn encode(&self, message: &JsonValue) -> Result<Vec<u8>, Error> {
+			self.0.fetch_add(1, Ordering::Relaxed);
+			Cbor.encode(message)
+		}
+
+		fn decode(&self, data: &[u8]) -> Result<JsonValue, Error> {
+			Cbor.decode(data)
+		}
+	}
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server.register_method("say_hello", |params| params.one::<String>().map(|name| format!("hello {}", name))).unwrap();
+	let mut sink = server.register_subscription("subscribe_hello", "unsubscribe_hello").unwrap();
+	server.register_codec(Cbor);
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	tokio::spawn(server.start());
+
+	let encoded = Arc::new(AtomicUsize::new(0));
+	let client = WsClientBuilder::default().codec(Counted(encoded.clone())).build(&server_url).await.unwrap();
+	let hello: String = client.request("say_hello", Params::Array(vec!["cbor".into()])).await.unwrap();
+	assert_eq!(hello, "hello cbor");
+	let mut sub: WsSubscription<Vec<u64>> =
+		client.subscribe("subscribe_hello", Params::None, "unsubscribe_hello").await.unwrap();
+	sink.send(&[1_u64, 2, 3]).unwrap();
+	assert_eq!(sub.next().await, Some(vec![1, 2, 3]));
+	assert_eq!(encoded.load(Ordering::Relaxed), 2);
+
+	// Clients that don't offer a codec exchange JSON.
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let hello: String = client.request("say_hello", Params::Array(vec!["json".into()])).await.unwrap();
+	assert_eq!(hello, "hello json");
+}
+
+#[tokio::test]
+async fn http_batch_responses_in_completion_order_are_matched_by_id() {
+	use jsonrpsee_http_server::{BatchOrder, HttpServerBuilder};
+
+	let mut server = HttpServerBuilder::default()
+		.batch_response_order(BatchOrder::Completion)
+		.build("127.0.0.1:0".parse().unwrap())
+		.unwrap();
+	server
+		.register_method("sleep", |params| {
+			let millis: u64 = params.one()?;
+			std::thread::sleep(Duration::from_millis(millis));
+			Ok(millis)
+		})
+		.unwrap();
+	let server_url = format!("http://{}", server.local_addr().unwrap());
+	tokio::spawn(async move { server.start().await.unwrap() });
+	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+
+	// The slowest call is first, thus its response is last.
+	let batch = vec![
+		("sleep", Params::Array(vec![300.into()])),
+		("sleep", Params::Array(vec![0.into()])),
+		("sleep", Params::Array(vec![150.into()])),
+	];
+	let results: Vec<u64> = client.batch_request(batch).await.unwrap();
+	assert_eq!(results, vec![300, 0, 150]);
+}
+
+#[tokio::test]
+async fn http_batch_calls_reference_earlier_results() {
+	use jsonrpsee_http_server::HttpServerBuilder;
+	use jsonrpsee_types::batch::BatchRequestBuilder;
+	use std::collections::HashMap;
+
+	let mut server = HttpServerBuilder::default().build("127.0.0.1:0".parse().unwrap()).unwrap();
+	server
+		.register_method("chain_getHead", |_| Ok(std::iter::once(("hash", "0xab")).collect::<HashMap<_, _>>()))
+		.unwrap();
+	server
+		.register_method("chain_getBlock", |params| {
+			let hash: String = params.one()?;
+			Ok(format!("block {}", hash))
+		})
+		.unwrap();
+	server.register_method("say_hello", |_| Ok("hello")).unwrap();
+	let server_url = format!("http://{}", server.local_addr().unwrap());
+	tokio::spawn(async move { server.start().await.unwrap() });
+	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+
+	let mut batch = BatchRequestBuilder::new();
+	let head = batch.insert::<JsonValue>("chain_getHead", Params::None);
+	let block = batch.insert::<String>("chain_getBlock", Params::Array(vec![head.result_at("/hash")]));
+	let hello = batch.insert::<String>("say_hello", Params::None);
+	let responses = batch.send(&client).await.unwrap();
+	assert_eq!(responses.get(block).unwrap(), "block 0xab");
+	assert_eq!(responses.get(hello).unwrap(), "hello");
+
+	let mut batch = BatchRequestBuilder::new();
+	let head = batch.insert::<JsonValue>("chain_getHead", Params::None);
+	batch.insert::<String>("chain_getBlock", Params::Array(vec![head.result_at("/parent")]));
+	assert!(matches!(batch.send(&client).await, Err(Error::Custom(_))));
+}
+
+/// Returns a path for a Unix domain socket that doesn't exist yet.
+#[cfg(unix)]
+fn unix_socket_path(name: &str) -> std::path::PathBuf {
+	let path = std::env::temp_dir().join(format!("jsonrpsee-{}-{}.sock", name, std::process::id()));
+	let _ = std::fs::remove_file(&path);
+	path
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn ws_over_unix_socket_works() {
+	use jsonrpsee_ws_server::{ConnectionHandler, RpcModule};
+	use tokio_util::compat::TokioAsyncReadCompatExt;
+
+	let mut module = RpcModule::new();
+	module.register_method("say_hello", |_| Ok("hello")).unwrap();
+	let handler = ConnectionHandler::new(module);
+	let path = unix_socket_path("ws");
+	let listener = tokio::net::UnixListener::bind(&path).unwrap();
+	tokio::spawn(async move {
+		let (socket, _) = listener.accept().await.unwrap();
+		handler.serve_connection(socket.compat(), "unix").await
+	});
+
+	let client = WsClientBuilder::default().build(&format!("unix://{}", path.display())).await.unwrap();
+	let response: JsonValue = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(response, JsonValue::String("hello".into()));
+	let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn newline_delimited_unix_socket_works() {
+	use jsonrpsee_types::jsonrpc;
+	use jsonrpsee_ws_client::UdsFraming;
+	use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+	// Answers every request with its method, one JSON-RPC message per line.
+	let path = unix_socket_path("lines");
+	let listener = tokio::net::UnixListener::bind(&path).unwrap();
+	tokio::spawn(async move {
+		let (socket, _) = listener.accept().await.unwrap();
+		let (reader, mut writer) = socket.into_split();
+		let mut lines = BufReader::new(reader).lines();
+		while let Some(line) = lines.next_line().await.unwrap() {
+			let request: JsonValue = jsonrpc::from_slice(line.as_bytes()).unwrap();
+			let response =
+				format!("\n{{\"jsonrpc\":\"2.0\",\"result\":{},\"id\":{}}}\n", request["method"], request["id"]);
+			writer.write_all(response.as_bytes()).await.unwrap();
+		}
+	});
+
+	let client = WsClientBuilder::default()
+		.uds_framing(UdsFraming::NewlineDelimited)
+		.ping_interval(Duration::from_millis(20), Duration::from_millis(10))
+		.build(&format!("unix://{}", path.display()))
+		.await
+		.unwrap();
+	let response: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(response, "say_hello");
+	// Pings aren't sent over newline-delimited connections, the connection isn't dropped for missing pongs.
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	let response: String = client.request("say_goodbye", Params::None).await.unwrap();
+	assert_eq!(response, "say_goodbye");
+	let _ = std::fs::remove_file(&path);
+}
+
 #[tokio::test]
 async fn http_method_call_works() {
 	let server_addr = http_server().await;
@@ -77,6 +498,15 @@ async fn http_method_call_works() {
 	assert_eq!(&response, "hello");
 }
 
+#[tokio::test]
+async fn http_method_call_decodes_through_value_type() {
+	let server_addr = http_server().await;
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().value_type::<Shouting>().build(&uri).unwrap();
+	let response: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(&response, "HELLO");
+}
+
 #[tokio::test]
 async fn ws_subscription_several_clients() {
 	let server_addr = websocket_server_with_subscription().await;
@@ -60,6 +60,36 @@ jsonrpsee_proc_macros::rpc_client_api! {
 	}
 }
 
+jsonrpsee_proc_macros::rpc_api_from_manifest!("src/api_manifest.json");
+
+struct MyCalculator;
+
+impl CalculatorServer for MyCalculator {
+	fn add(&self, a: u64, b: u64) -> Result<u64, jsonrpsee_types::v2::RpcError> {
+		Ok(a + b)
+	}
+
+	fn greet_user(&self, name: String, title: Option<String>) -> Result<String, jsonrpsee_types::v2::RpcError> {
+		match title {
+			Some(title) => Ok(format!("Hello {} {}", title, name)),
+			None => Ok(format!("Hello {}", name)),
+		}
+	}
+
+	fn answer(&self) -> Result<u64, jsonrpsee_types::v2::RpcError> {
+		Ok(42)
+	}
+}
+
+jsonrpsee_proc_macros::rpc_client_api! {
+	#[rpc(rename_all = "camelCase")]
+	Renamed {
+		fn say_hello() -> String;
+		#[rpc(method = "say_hello")]
+		fn say_hello_snake_case() -> String;
+	}
+}
+
 #[tokio::test]
 async fn proc_macros_generic_ws_client_api() {
 	let server_addr = helpers::websocket_server().await;
@@ -68,6 +98,8 @@ async fn proc_macros_generic_ws_client_api() {
 
 	assert_eq!(Test::<String>::say_hello(&client).await.unwrap(), "hello".to_string());
 	assert_eq!(Test2::<u16, String>::foo(&client, 99_u16).await.unwrap(), "hello".to_string());
+	assert_eq!(Renamed::say_hello(&client).await.unwrap(), "hello".to_string());
+	assert_eq!(Renamed::say_hello_snake_case(&client).await.unwrap(), "hello".to_string());
 	assert!(Registrar::register_para(&client, 99, "para").await.is_ok());
 }
 
@@ -82,3 +114,18 @@ async fn proc_macros_generic_http_client_api() {
 	// TODO: https://github.com/paritytech/jsonrpsee/issues/212
 	//assert!(Registrar::register_para(&client, 99, "para").await.is_ok());
 }
+
+#[tokio::test]
+async fn proc_macros_manifest_api() {
+	let mut server = jsonrpsee_ws_server::WsServer::new("127.0.0.1:0").await.unwrap();
+	Calculator::register_server(std::sync::Arc::new(MyCalculator), |name, method| server.register_method(name, method))
+		.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	tokio::spawn(server.start());
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	assert_eq!(Calculator::add(&client, 1_u64, 2_u64).await.unwrap(), 3);
+	assert_eq!(Calculator::greet_user(&client, "Ada", None).await.unwrap(), "Hello Ada");
+	assert_eq!(Calculator::greet_user(&client, "Ada", Some("Dr".to_string())).await.unwrap(), "Hello Dr Ada");
+	assert_eq!(Calculator::answer(&client).await.unwrap(), 42);
+}
@@ -0,0 +1,232 @@
+use crate::error::Error;
+use crate::jsonrpc::{self, DeserializeOwned, JsonValue, Params};
+use crate::traits::Client;
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt;
+use core::marker::PhantomData;
+
+/// Batch request whose calls are decoded into distinct types, unlike [`Client::batch_request`] which
+/// decodes all results into the same type.
+///
+/// Each call returns a [`BatchEntry`] used to get its typed result from the [`BatchResponses`].
+///
+/// The params of a call may reference the results of earlier calls with [`BatchEntry::result`], such as the
+/// hash of a block to get the block. The batch is then sent in several rounds, each one a batch of the calls
+/// whose referenced results are known, and the references are replaced by the results before sending. Servers
+/// don't need to support anything beyond JSON-RPC batches.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BatchRequestBuilder {
+	calls: Vec<(String, Params)>,
+}
+
+impl BatchRequestBuilder {
+	/// Create an empty batch.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a call to the batch whose result is decoded into `T`.
+	pub fn insert<T: DeserializeOwned>(
+		&mut self,
+		method: impl Into<String>,
+		params: impl Into<Params>,
+	) -> BatchEntry<T> {
+		self.calls.push((method.into(), params.into()));
+		BatchEntry { index: self.calls.len() - 1, marker: PhantomData }
+	}
+
+	/// Returns the number of calls in the batch.
+	pub fn len(&self) -> usize {
+		self.calls.len()
+	}
+
+	/// Returns `true` if the batch has no calls.
+	pub fn is_empty(&self) -> bool {
+		self.calls.is_empty()
+	}
+
+	/// Send the batch with `client`.
+	///
+	/// Fails like [`Client::batch_request`] if any of the calls fails, the results are decoded only when
+	/// they are read from the [`BatchResponses`].
+	///
+	/// Fails with [`Error::Custom`] if a call references the result of a call that isn't before it or a field
+	/// missing from a result.
+	pub async fn send<C: Client>(self, client: &C) -> Result<BatchResponses, Error> {
+		let mut dependencies = Vec::with_capacity(self.calls.len());
+		for (index, (_, params)) in self.calls.iter().enumerate() {
+			let mut call_dependencies = Vec::new();
+			params_values(params).for_each(|value| collect_references(value, &mut call_dependencies));
+			if let Some(dependency) = call_dependencies.iter().find(|dependency| **dependency >= index) {
+				return Err(Error::Custom(format!(
+					"Call {} references the result of call {} which isn't before it",
+					index, dependency
+				)));
+			}
+			dependencies.push(call_dependencies);
+		}
+		if dependencies.iter().all(Vec::is_empty) {
+			let values = client.batch_request::<JsonValue, _, _>(self.calls).await?;
+			return Ok(BatchResponses { values });
+		}
+
+		let mut results: Vec<Option<JsonValue>> = vec![None; self.calls.len()];
+		// NOTE: every call only references earlier calls, thus every round sends at least the first pending call.
+		while results.iter().any(Option::is_none) {
+			let mut round = Vec::new();
+			let mut calls = Vec::new();
+			for (index, (method, params)) in self.calls.iter().enumerate() {
+				if results[index].is_none()
+					&& dependencies[index].iter().all(|dependency| results[*dependency].is_some())
+				{
+					let mut params = params.clone();
+					params_values_mut(&mut params).try_for_each(|value| resolve_references(value, &results))?;
+					round.push(index);
+					calls.push((method.clone(), params));
+				}
+			}
+			let values = client.batch_request::<JsonValue, _, _>(calls).await?;
+			for (index, value) in round.into_iter().zip(values) {
+				results[index] = Some(value);
+			}
+		}
+		Ok(BatchResponses {
+			values: results.into_iter().map(|value| value.expect("All calls were sent; qed")).collect(),
+		})
+	}
+}
+
+/// Key of the placeholders of the results of earlier calls, see [`BatchEntry::result`].
+const RESULT_KEY: &str = "$batchResult";
+/// Key of the JSON pointer into the referenced result, see [`BatchEntry::result_at`].
+const POINTER_KEY: &str = "$pointer";
+
+fn params_values(params: &Params) -> impl Iterator<Item = &JsonValue> {
+	let (array, map) = match params {
+		Params::Array(array) => (Some(array), None),
+		Params::Map(map) => (None, Some(map)),
+		Params::None => (None, None),
+	};
+	array.into_iter().flatten().chain(map.into_iter().flat_map(|map| map.values()))
+}
+
+fn params_values_mut(params: &mut Params) -> impl Iterator<Item = &mut JsonValue> {
+	let (array, map) = match params {
+		Params::Array(array) => (Some(array), None),
+		Params::Map(map) => (None, Some(map)),
+		Params::None => (None, None),
+	};
+	array.into_iter().flatten().chain(map.into_iter().flat_map(|map| map.values_mut()))
+}
+
+/// Returns the index of the referenced call and the pointer into its result if `value` is a placeholder.
+fn as_reference(value: &JsonValue) -> Option<(usize, &str)> {
+	let object = value.as_object()?;
+	let index = object.get(RESULT_KEY)?.as_u64()? as usize;
+	match (object.len(), object.get(POINTER_KEY)) {
+		(1, None) => Some((index, "")),
+		(2, Some(JsonValue::String(pointer))) => Some((index, pointer)),
+		_ => None,
+	}
+}
+
+fn collect_references(value: &JsonValue, references: &mut Vec<usize>) {
+	if let Some((index, _)) = as_reference(value) {
+		references.push(index);
+		return;
+	}
+	match value {
+		JsonValue::Array(array) => array.iter().for_each(|value| collect_references(value, references)),
+		JsonValue::Object(map) => map.values().for_each(|value| collect_references(value, references)),
+		_ => {}
+	}
+}
+
+fn resolve_references(value: &mut JsonValue, results: &[Option<JsonValue>]) -> Result<(), Error> {
+	if let Some((index, pointer)) = as_reference(value) {
+		let result = results[index].as_ref().expect("References are resolved after the referenced calls; qed");
+		let resolved = result.pointer(pointer).ok_or_else(|| {
+			Error::Custom(format!(
+				"The result of call {} has no value at {:?} referenced by another call",
+				index, pointer
+			))
+		})?;
+		*value = resolved.clone();
+		return Ok(());
+	}
+	match value {
+		JsonValue::Array(array) => array.iter_mut().try_for_each(|value| resolve_references(value, results)),
+		JsonValue::Object(map) => map.values_mut().try_for_each(|value| resolve_references(value, results)),
+		_ => Ok(()),
+	}
+}
+
+/// Call of a [`BatchRequestBuilder`] whose result is decoded into `T`.
+pub struct BatchEntry<T> {
+	index: usize,
+	marker: PhantomData<fn() -> T>,
+}
+
+impl<T> BatchEntry<T> {
+	/// Returns the position of the call in the batch.
+	pub fn index(&self) -> usize {
+		self.index
+	}
+
+	/// Returns a placeholder of the result of the call, to use in the params of the calls inserted later in the
+	/// same batch.
+	///
+	/// The placeholder is the object `{"$batchResult": index}`, objects of this shape in the params are always
+	/// treated as placeholders.
+	pub fn result(&self) -> JsonValue {
+		let mut placeholder = jsonrpc::JsonMap::new();
+		placeholder.insert(RESULT_KEY.into(), self.index.into());
+		JsonValue::Object(placeholder)
+	}
+
+	/// Returns a placeholder of the value at the JSON `pointer` of the result of the call, such as `/hash`, see
+	/// [`BatchEntry::result`].
+	pub fn result_at(&self, pointer: &str) -> JsonValue {
+		let mut placeholder = jsonrpc::JsonMap::new();
+		placeholder.insert(RESULT_KEY.into(), self.index.into());
+		placeholder.insert(POINTER_KEY.into(), pointer.into());
+		JsonValue::Object(placeholder)
+	}
+}
+
+impl<T> Clone for BatchEntry<T> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<T> Copy for BatchEntry<T> {}
+
+impl<T> fmt::Debug for BatchEntry<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("BatchEntry").field("index", &self.index).finish()
+	}
+}
+
+/// Results of a batch sent with [`BatchRequestBuilder::send`], in the order the calls were inserted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchResponses {
+	values: Vec<JsonValue>,
+}
+
+impl BatchResponses {
+	/// Decode the result of `entry`.
+	///
+	/// # Panics
+	///
+	/// Panics if `entry` belongs to another batch with more calls.
+	pub fn get<T: DeserializeOwned>(&self, entry: BatchEntry<T>) -> Result<T, Error> {
+		let value = self.values.get(entry.index).expect("Entry belongs to the batch; qed").clone();
+		jsonrpc::from_value(value).map_err(Error::ParseError)
+	}
+
+	/// Returns the undecoded results.
+	pub fn into_values(self) -> Vec<JsonValue> {
+		self.values
+	}
+}
@@ -1,16 +1,22 @@
 use crate::error::Error;
-use crate::jsonrpc::{self, DeserializeOwned, JsonValue, Params, SubscriptionId};
-use alloc::{string::String, vec::Vec};
+use crate::jsonrpc::{self, DeserializeOwned, Id, JsonValue, Params, SubscriptionId};
+use crate::traits::Value;
+use alloc::{string::String, sync::Arc, vec::Vec};
 use core::marker::PhantomData;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::task::{Context, Poll};
 use futures::channel::{mpsc, oneshot};
 use futures::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
 
 /// Active subscription on a Client.
-pub struct Subscription<Notif> {
+pub struct Subscription<Notif, V = JsonValue> {
 	/// Channel to send requests to the background task.
-	pub to_back: mpsc::Sender<FrontToBack>,
-	/// Channel from which we receive notifications from the server, as encoded `JsonValue`s.
-	pub notifs_rx: mpsc::Receiver<JsonValue>,
+	pub to_back: mpsc::Sender<FrontToBack<V>>,
+	/// Channel from which we receive notifications from the server, as encoded `V`s.
+	pub notifs_rx: NotifReceiver<V>,
 	/// Subscription ID,
 	pub id: SubscriptionId,
 	/// Marker in order to pin the `Notif` parameter.
@@ -26,63 +32,377 @@ pub struct NotificationMessage {
 	pub params: Params,
 }
 
+/// Registers a handler of the notifications the server sends without a subscription.
+#[derive(Debug)]
+pub struct NotificationHandlerMessage {
+	/// Method of the handled notifications.
+	pub method: String,
+	/// Sink of the parameters of the notifications.
+	pub sink: mpsc::Sender<Params>,
+}
+
 /// Batch request message.
 #[derive(Debug)]
-pub struct BatchMessage {
+pub struct BatchMessage<V = JsonValue> {
 	/// Requests in the batch
 	pub requests: Vec<(String, Params)>,
-	/// One-shot channel over which we send back the result of this request.
-	pub send_back: oneshot::Sender<Result<Vec<JsonValue>, Error>>,
+	/// One-shot channel over which we send back the results of the requests, in the order of the requests.
+	pub send_back: oneshot::Sender<Result<Vec<Result<V, jsonrpc::Error>>, Error>>,
+	/// Span of the batch request.
+	pub span: RequestSpan,
 }
 
 /// Request message.
 #[derive(Debug)]
-pub struct RequestMessage {
+pub struct RequestMessage<V = JsonValue> {
 	/// Method for the request.
 	pub method: String,
 	/// Parameters of the request.
 	pub params: Params,
 	/// One-shot channel over which we send back the result of this request.
-	pub send_back: Option<oneshot::Sender<Result<JsonValue, Error>>>,
+	pub send_back: Option<oneshot::Sender<Result<V, Error>>>,
+	/// Span of the request.
+	pub span: RequestSpan,
 }
 
 /// Subscription message.
 #[derive(Debug)]
-pub struct SubscriptionMessage {
+pub struct SubscriptionMessage<V = JsonValue> {
 	/// Method for the subscription request.
 	pub subscribe_method: String,
 	/// Parameters to send for the subscription.
 	pub params: Params,
 	/// Method to use to unsubscribe later. Used if the channel unexpectedly closes.
 	pub unsubscribe_method: String,
+	/// What happens to notifications that arrive while the buffer of the subscription is full.
+	pub overflow_policy: OverflowPolicy,
+	/// Maps or discards the notifications before they are buffered.
+	pub filter: Option<NotifFilter<V>>,
 	/// If the subscription succeeds, we return a [`mpsc::Receiver`] that will receive notifications.
 	/// When we get a response from the server about that subscription, we send the result over
 	/// this channel.
-	pub send_back: oneshot::Sender<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>,
+	pub send_back: oneshot::Sender<Result<(NotifReceiver<V>, SubscriptionId), Error>>,
+	/// Span of the subscription request.
+	pub span: RequestSpan,
 }
 
 /// Message that the Client can send to the background task.
 #[derive(Debug)]
-pub enum FrontToBack {
+pub enum FrontToBack<V = JsonValue> {
 	/// Send a batch request to the server.
-	Batch(BatchMessage),
+	Batch(BatchMessage<V>),
 	/// Send a notification to the server.
 	Notification(NotificationMessage),
 	/// Send a request to the server.
-	StartRequest(RequestMessage),
+	StartRequest(RequestMessage<V>),
 	/// Send a subscription request to the server.
-	Subscribe(SubscriptionMessage),
+	Subscribe(SubscriptionMessage<V>),
+	/// Deliver the notifications of a method that the server sends without a subscription.
+	RegisterNotificationHandler(NotificationHandlerMessage),
 	/// When a subscription channel is closed, we send this message to the background
 	/// task to mark it ready for garbage collection.
-	// NOTE: It is not possible to cancel pending subscriptions or pending requests.
+	// NOTE: It is not possible to cancel pending subscriptions.
 	// Such operations will be blocked until a response is received or the background
 	// thread has been terminated.
 	SubscriptionClosed(SubscriptionId),
+	/// A pending request was dropped by the caller, the background task frees the slots of the requests
+	/// whose callers are gone and ignores their responses.
+	RequestCancelled,
+	/// Report the memory held by the background task.
+	MemoryStats(oneshot::Sender<MemoryStats>),
+	/// Report the subscriptions accepted by the server and not yet closed.
+	ActiveSubscriptions(oneshot::Sender<Vec<ActiveSubscription>>),
+}
+
+/// Memory held by the background task of a client, to diagnose leaks and size the buffers of long-running
+/// clients.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+	/// Calls, subscription requests and batches waiting for a response.
+	pub pending_requests: usize,
+	/// Slots left for new requests, out of the maximum number of concurrent requests.
+	pub free_request_slots: usize,
+	/// Active subscriptions.
+	pub active_subscriptions: usize,
+	/// Notifications buffered by the active subscriptions and not yet received.
+	pub buffered_notifications: usize,
+	/// Handlers of the notifications that the server sends without a subscription.
+	pub notification_handlers: usize,
+}
+
+/// Subscription accepted by the server and not yet closed, to debug clients and display the state of live
+/// streams.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActiveSubscription {
+	/// Method used to subscribe.
+	pub subscribe_method: String,
+	/// Parameters sent with the subscribe request.
+	pub params: Params,
+	/// ID of the subscription, as returned by the subscribe request even if the client resubscribed since.
+	pub subscription_id: SubscriptionId,
+	/// Notifications buffered and not yet received by the subscription.
+	pub buffered: usize,
+}
+
+/// Span of a request, carried to the background task of the client such that its events can be correlated
+/// with the caller, see the `tracing` feature.
+///
+/// Does nothing unless the `tracing` feature is enabled.
+#[derive(Clone, Debug)]
+pub struct RequestSpan(#[cfg(feature = "tracing")] tracing::Span);
+
+impl RequestSpan {
+	/// Create a span, child of the current span, of a call to `method`.
+	pub fn call(_method: &str) -> Self {
+		Self(
+			#[cfg(feature = "tracing")]
+			tracing::debug_span!("jsonrpc_call", method = _method, request_id = tracing::field::Empty),
+		)
+	}
+
+	/// Create a span, child of the current span, of a batch request of `len` calls.
+	pub fn batch(_len: usize) -> Self {
+		Self(
+			#[cfg(feature = "tracing")]
+			tracing::debug_span!("jsonrpc_batch", len = _len, request_id = tracing::field::Empty),
+		)
+	}
+
+	/// Create a span, child of the current span, of a subscription with `method`.
+	pub fn subscription(_method: &str) -> Self {
+		Self(
+			#[cfg(feature = "tracing")]
+			tracing::debug_span!("jsonrpc_subscribe", method = _method, request_id = tracing::field::Empty),
+		)
+	}
+
+	/// Returns `true` if the events of the span are recorded.
+	pub fn is_enabled(&self) -> bool {
+		#[cfg(feature = "tracing")]
+		return !self.0.is_disabled();
+		#[cfg(not(feature = "tracing"))]
+		return false;
+	}
+
+	/// Record the ID the request is sent with.
+	pub fn record_id(&self, _id: &Id) {
+		#[cfg(feature = "tracing")]
+		match _id {
+			Id::Num(id) => {
+				self.0.record("request_id", id);
+			}
+			Id::Str(id) => {
+				self.0.record("request_id", id.as_str());
+			}
+			Id::Null => {}
+		}
+	}
+
+	/// Emit a debug event in the span.
+	pub fn event(&self, _message: &str) {
+		#[cfg(feature = "tracing")]
+		self.0.in_scope(|| tracing::debug!("{}", _message));
+	}
+}
+
+/// What happens to a notification that arrives while the buffer of its subscription is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverflowPolicy {
+	/// Drop the oldest buffered notification to make room for the new one.
+	DropOldest,
+	/// Drop the new notification.
+	DropNewest,
+	/// Close the subscription, [`Subscription::next`] returns `None` once the buffered notifications are read.
+	#[default]
+	CloseSubscription,
+	/// Wait until the subscriber made room, which blocks all requests and subscriptions of the client meanwhile.
+	Block,
+}
+
+/// Maps the notifications of a subscription, encoded as `V`, or discards them by returning `None`.
+///
+/// It runs in the background task of the client before the notifications are buffered, thus discarded
+/// notifications never take room in the buffer. It must not block as it holds up all requests and subscriptions
+/// of the client meanwhile.
+pub struct NotifFilter<V>(Arc<dyn Fn(V) -> Option<V> + Send + Sync>);
+
+impl<V> NotifFilter<V> {
+	/// Create a filter calling `filter` with every notification.
+	pub fn new(filter: impl Fn(V) -> Option<V> + Send + Sync + 'static) -> Self {
+		Self(Arc::new(filter))
+	}
 }
 
-impl<Notif> Subscription<Notif>
+impl<V> Clone for NotifFilter<V> {
+	fn clone(&self) -> Self {
+		Self(self.0.clone())
+	}
+}
+
+impl<V> core::fmt::Debug for NotifFilter<V> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.write_str("NotifFilter")
+	}
+}
+
+/// Error when a notification couldn't be buffered.
+#[derive(Debug, PartialEq, Eq)]
+pub enum NotifSendError {
+	/// The buffer is full and the policy is [`OverflowPolicy::CloseSubscription`].
+	Full,
+	/// The subscription was dropped.
+	Closed,
+}
+
+/// Creates a buffer of `capacity` notifications handling overflows according to `policy`.
+pub fn notif_channel<V>(capacity: usize, policy: OverflowPolicy) -> (NotifSender<V>, NotifReceiver<V>) {
+	let (tx, rx) = mpsc::channel(capacity);
+	let rx = Arc::new(Mutex::new(rx));
+	let buffered = Arc::new(AtomicUsize::new(0));
+	let sender = NotifSender { tx, rx: rx.clone(), policy, filter: None, dropped: 0, buffered: buffered.clone() };
+	(sender, NotifReceiver { rx, buffered })
+}
+
+/// Sending end of the notification buffer of a subscription.
+#[derive(Debug)]
+pub struct NotifSender<V> {
+	tx: mpsc::Sender<V>,
+	// NOTE: shared with the receiver such that the oldest notification can be dropped.
+	rx: Arc<Mutex<mpsc::Receiver<V>>>,
+	policy: OverflowPolicy,
+	filter: Option<NotifFilter<V>>,
+	dropped: u64,
+	buffered: Arc<AtomicUsize>,
+}
+
+impl<V> NotifSender<V> {
+	/// Maps or discards the notifications with `filter` before they are buffered.
+	pub fn with_filter(mut self, filter: Option<NotifFilter<V>>) -> Self {
+		self.filter = filter;
+		self
+	}
+
+	/// Returns the number of buffered or new notifications dropped so far to handle overflows, the
+	/// notifications that [`NotifSender::send`] failed to buffer are not counted.
+	pub fn dropped(&self) -> u64 {
+		self.dropped
+	}
+
+	/// Returns the number of notifications buffered and not yet received by the subscription.
+	pub fn buffered(&self) -> usize {
+		self.buffered.load(Ordering::Relaxed)
+	}
+
+	/// Buffers `notif`, waits for room only if the policy is [`OverflowPolicy::Block`].
+	///
+	/// The notifications discarded by the filter are neither buffered nor counted as dropped.
+	pub async fn send(&mut self, notif: V) -> Result<(), NotifSendError> {
+		let notif = match self.filter.as_ref() {
+			Some(filter) => match (filter.0)(notif) {
+				Some(notif) => notif,
+				None => return Ok(()),
+			},
+			None => notif,
+		};
+		// NOTE: counted before it's buffered such that the receiver never takes more notifications than counted.
+		let counted = Counted::new(&self.buffered);
+		let res = self.buffer(notif).await;
+		if let Ok(true) = res {
+			counted.keep();
+		}
+		res.map(|_| ())
+	}
+
+	/// Returns `false` if `notif` was dropped.
+	async fn buffer(&mut self, notif: V) -> Result<bool, NotifSendError> {
+		let notif = match self.tx.try_send(notif) {
+			Ok(()) => return Ok(true),
+			Err(err) if err.is_disconnected() => return Err(NotifSendError::Closed),
+			Err(err) => err.into_inner(),
+		};
+		match self.policy {
+			OverflowPolicy::DropOldest => {
+				if lock(&self.rx).try_recv().is_ok() {
+					self.dropped += 1;
+					self.buffered.fetch_sub(1, Ordering::Relaxed);
+				}
+				self.tx.try_send(notif).map(|()| true).map_err(|err| {
+					if err.is_disconnected() {
+						NotifSendError::Closed
+					} else {
+						NotifSendError::Full
+					}
+				})
+			}
+			OverflowPolicy::DropNewest => {
+				log::debug!("Subscription buffer full, dropping notification");
+				self.dropped += 1;
+				Ok(false)
+			}
+			OverflowPolicy::CloseSubscription => Err(NotifSendError::Full),
+			OverflowPolicy::Block => self.tx.send(notif).await.map(|()| true).map_err(|_| NotifSendError::Closed),
+		}
+	}
+}
+
+/// Notification counted as buffered, uncounted when dropped unless it was buffered, such as when the send is
+/// cancelled.
+struct Counted(Option<Arc<AtomicUsize>>);
+
+impl Counted {
+	fn new(buffered: &Arc<AtomicUsize>) -> Self {
+		buffered.fetch_add(1, Ordering::Relaxed);
+		Self(Some(buffered.clone()))
+	}
+
+	fn keep(mut self) {
+		self.0 = None;
+	}
+}
+
+impl Drop for Counted {
+	fn drop(&mut self) {
+		if let Some(buffered) = self.0.take() {
+			buffered.fetch_sub(1, Ordering::Relaxed);
+		}
+	}
+}
+
+/// Receiving end of the notification buffer of a subscription.
+#[derive(Debug)]
+pub struct NotifReceiver<V> {
+	rx: Arc<Mutex<mpsc::Receiver<V>>>,
+	buffered: Arc<AtomicUsize>,
+}
+
+impl<V> Stream for NotifReceiver<V> {
+	type Item = V;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<V>> {
+		let next = lock(&self.rx).poll_next_unpin(cx);
+		if let Poll::Ready(Some(_)) = next {
+			self.buffered.fetch_sub(1, Ordering::Relaxed);
+		}
+		next
+	}
+}
+
+impl<V> Drop for NotifReceiver<V> {
+	fn drop(&mut self) {
+		// NOTE: the sender also holds the receiver, closing it makes the sender notice that the subscription
+		// was dropped.
+		lock(&self.rx).close();
+	}
+}
+
+fn lock<V>(rx: &Mutex<mpsc::Receiver<V>>) -> std::sync::MutexGuard<'_, mpsc::Receiver<V>> {
+	rx.lock().expect("The lock is never held across a panic; qed")
+}
+
+impl<Notif, V> Subscription<Notif, V>
 where
 	Notif: DeserializeOwned,
+	V: Value,
 {
 	/// Returns the next notification from the stream
 	/// This may return `None` if the subscription has been terminated,
@@ -90,19 +410,45 @@ where
 	///
 	/// Ignores any malformed packet.
 	pub async fn next(&mut self) -> Option<Notif> {
+		futures::StreamExt::next(self).await
+	}
+
+	/// Returns the next notification from the stream, or [`Error::ParseError`] if it can't be decoded into
+	/// `Notif`, the subscription remains usable after such an error.
+	///
+	/// Returns `None` if the subscription has been terminated, as [`Subscription::next`].
+	pub async fn try_next(&mut self) -> Option<Result<Notif, Error>> {
+		let notif = self.notifs_rx.next().await?;
+		Some(notif.decode().map_err(Error::ParseError))
+	}
+}
+
+// NOTE: no field is pinned, the stream only polls the receiver.
+impl<Notif, V> Unpin for Subscription<Notif, V> {}
+
+/// Yields the notifications as [`Subscription::next`], such that stalled subscriptions can be detected with
+/// timeouts and other stream combinators.
+impl<Notif, V> Stream for Subscription<Notif, V>
+where
+	Notif: DeserializeOwned,
+	V: Value,
+{
+	type Item = Notif;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Notif>> {
 		loop {
-			match self.notifs_rx.next().await {
-				Some(n) => match jsonrpc::from_value(n) {
-					Ok(parsed) => return Some(parsed),
+			match futures::ready!(self.notifs_rx.poll_next_unpin(cx)) {
+				Some(n) => match n.decode() {
+					Ok(parsed) => return Poll::Ready(Some(parsed)),
 					Err(e) => log::debug!("Subscription response error: {:?}", e),
 				},
-				None => return None,
+				None => return Poll::Ready(None),
 			}
 		}
 	}
 }
 
-impl<Notif> Drop for Subscription<Notif> {
+impl<Notif, V> Drop for Subscription<Notif, V> {
 	fn drop(&mut self) {
 		// We can't actually guarantee that this goes through. If the background task is busy, then
 		// the channel's buffer will be full, and our unsubscription request will never make it.
@@ -112,3 +458,43 @@ impl<Notif> Drop for Subscription<Notif> {
 		let _ = self.to_back.send(FrontToBack::SubscriptionClosed(id)).now_or_never();
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{notif_channel, NotifSendError, OverflowPolicy};
+	use futures::{FutureExt, StreamExt};
+
+	fn buffered(policy: OverflowPolicy) -> (Vec<u32>, Vec<Result<(), NotifSendError>>) {
+		// NOTE: the channel also has a slot per sender, thus it buffers two notifications.
+		let (mut tx, mut rx) = notif_channel(1, policy);
+		let sent: Vec<_> = (0..3).map(|n| tx.send(n).now_or_never().expect("Doesn't block with room; qed")).collect();
+		let dropped = if matches!(policy, OverflowPolicy::DropOldest | OverflowPolicy::DropNewest) { 1 } else { 0 };
+		assert_eq!(tx.dropped(), dropped);
+		assert_eq!(tx.buffered(), 2);
+		drop(tx);
+		let received = rx.by_ref().collect().now_or_never().expect("The sender is dropped; qed");
+		(received, sent)
+	}
+
+	#[test]
+	fn overflow_policies_work() {
+		assert_eq!(buffered(OverflowPolicy::DropOldest), (vec![1, 2], vec![Ok(()), Ok(()), Ok(())]));
+		assert_eq!(buffered(OverflowPolicy::DropNewest), (vec![0, 1], vec![Ok(()), Ok(()), Ok(())]));
+		assert_eq!(
+			buffered(OverflowPolicy::CloseSubscription),
+			(vec![0, 1], vec![Ok(()), Ok(()), Err(NotifSendError::Full)])
+		);
+	}
+
+	#[test]
+	fn block_waits_for_room() {
+		let (mut tx, mut rx) = notif_channel(0, OverflowPolicy::Block);
+		assert_eq!(tx.send(0).now_or_never(), Some(Ok(())));
+		assert!(tx.send(1).now_or_never().is_none());
+		assert_eq!(tx.buffered(), 1);
+		assert_eq!(rx.next().now_or_never(), Some(Some(0)));
+		assert_eq!(tx.buffered(), 0);
+		drop(rx);
+		assert_eq!(tx.send(2).now_or_never(), Some(Err(NotifSendError::Closed)));
+	}
+}
@@ -0,0 +1,200 @@
+use crate::error::Error;
+use crate::jsonrpc::{JsonValue, Params};
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+use std::io::{Read, Write};
+
+/// Compression of the values of string fields into strings, such as DEFLATE and base64, see
+/// [`CompressedFields`].
+pub trait FieldCodec: Send + Sync + 'static {
+	/// Name of the codec, prefixed to the compressed values such that uncompressed values are told apart.
+	fn name(&self) -> &str;
+
+	/// Compress `data` into a string.
+	fn encode(&self, data: &[u8]) -> String;
+
+	/// Decompress a string returned by [`FieldCodec::encode`].
+	fn decode(&self, encoded: &str) -> Result<Vec<u8>, Error>;
+}
+
+/// DEFLATE compression encoded in base64, named `deflate`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Deflate {
+	level: u32,
+}
+
+impl Deflate {
+	/// Compress with `level`, from 0 (no compression) to 9 (best compression).
+	pub fn new(level: u32) -> Self {
+		Self { level: level.min(9) }
+	}
+}
+
+impl Default for Deflate {
+	fn default() -> Self {
+		Self::new(6)
+	}
+}
+
+impl FieldCodec for Deflate {
+	fn name(&self) -> &str {
+		"deflate"
+	}
+
+	fn encode(&self, data: &[u8]) -> String {
+		let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::new(self.level));
+		encoder.write_all(data).expect("Writing to a Vec is infallible; qed");
+		base64::encode(encoder.finish().expect("Writing to a Vec is infallible; qed"))
+	}
+
+	fn decode(&self, encoded: &str) -> Result<Vec<u8>, Error> {
+		let compressed = base64::decode(encoded).map_err(|e| Error::Custom(format!("Invalid base64: {}", e)))?;
+		let mut data = Vec::new();
+		flate2::read::DeflateDecoder::new(&compressed[..])
+			.read_to_end(&mut data)
+			.map_err(|e| Error::Custom(format!("Invalid DEFLATE data: {}", e)))?;
+		Ok(data)
+	}
+}
+
+/// Large string fields of the params and results of methods compressed by a [`FieldCodec`], for transports
+/// without compression.
+///
+/// The fields are designated by JSON pointers, such as `/0` for the first positional param or `/code` for a
+/// field of a result. Compressed values are prefixed with the name of the codec and a `:`, values without
+/// the prefix are left as is when decompressing, such that peers that don't compress interoperate.
+///
+/// Clients compress the params and decompress the results, servers do the opposite, see the middlewares of
+/// the clients and the transformers of the servers.
+#[derive(Clone)]
+pub struct CompressedFields {
+	codec: Arc<dyn FieldCodec>,
+	methods: BTreeMap<String, Fields>,
+}
+
+/// Compressed fields of a method.
+#[derive(Clone, Debug, Default)]
+struct Fields {
+	params: Vec<String>,
+	result: Vec<String>,
+}
+
+impl CompressedFields {
+	/// Compress the fields with `codec`.
+	pub fn new(codec: impl FieldCodec) -> Self {
+		Self { codec: Arc::new(codec), methods: BTreeMap::new() }
+	}
+
+	/// Compress the param of `method` at the JSON `pointer`.
+	pub fn param(mut self, method: impl Into<String>, pointer: impl Into<String>) -> Self {
+		self.methods.entry(method.into()).or_default().params.push(pointer.into());
+		self
+	}
+
+	/// Compress the field of the result of `method` at the JSON `pointer`, the empty pointer designates the
+	/// whole result.
+	pub fn result(mut self, method: impl Into<String>, pointer: impl Into<String>) -> Self {
+		self.methods.entry(method.into()).or_default().result.push(pointer.into());
+		self
+	}
+
+	/// Returns the methods with compressed params or results.
+	pub fn methods(&self) -> impl Iterator<Item = &str> {
+		self.methods.keys().map(String::as_str)
+	}
+
+	/// Compress the designated params of a call to `method`.
+	pub fn compress_params(&self, method: &str, params: &mut Params) {
+		if let Some(fields) = self.methods.get(method).filter(|fields| !fields.params.is_empty()) {
+			let mut value = JsonValue::from(core::mem::replace(params, Params::None));
+			self.compress(&fields.params, &mut value);
+			*params = Params::serialized(&value).expect("Params remain an array or an object; qed");
+		}
+	}
+
+	/// Decompress the designated params of a call to `method`.
+	pub fn decompress_params(&self, method: &str, params: &mut JsonValue) -> Result<(), Error> {
+		match self.methods.get(method) {
+			Some(fields) => self.decompress(&fields.params, params),
+			None => Ok(()),
+		}
+	}
+
+	/// Compress the designated fields of the result of `method`.
+	pub fn compress_result(&self, method: &str, result: &mut JsonValue) {
+		if let Some(fields) = self.methods.get(method) {
+			self.compress(&fields.result, result);
+		}
+	}
+
+	/// Decompress the designated fields of the result of `method`.
+	pub fn decompress_result(&self, method: &str, result: &mut JsonValue) -> Result<(), Error> {
+		match self.methods.get(method) {
+			Some(fields) => self.decompress(&fields.result, result),
+			None => Ok(()),
+		}
+	}
+
+	fn compress(&self, pointers: &[String], value: &mut JsonValue) {
+		for pointer in pointers {
+			if let Some(JsonValue::String(field)) = value.pointer_mut(pointer) {
+				*field = format!("{}:{}", self.codec.name(), self.codec.encode(field.as_bytes()));
+			}
+		}
+	}
+
+	fn decompress(&self, pointers: &[String], value: &mut JsonValue) -> Result<(), Error> {
+		for pointer in pointers {
+			if let Some(JsonValue::String(field)) = value.pointer_mut(pointer) {
+				let encoded = match field.strip_prefix(self.codec.name()).and_then(|rest| rest.strip_prefix(':')) {
+					Some(encoded) => encoded,
+					None => continue,
+				};
+				let data = self.codec.decode(encoded)?;
+				*field = String::from_utf8(data)
+					.map_err(|_| Error::Custom(format!("Decompressed field {} isn't UTF-8", pointer)))?;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl fmt::Debug for CompressedFields {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("CompressedFields").field("codec", &self.codec.name()).field("methods", &self.methods).finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{CompressedFields, Deflate};
+	use crate::jsonrpc::{JsonValue, Params};
+
+	#[test]
+	fn fields_round_trip() {
+		let fields = CompressedFields::new(Deflate::default()).param("upload", "/0").result("download", "/code");
+		let code = "0061736d".repeat(64);
+
+		let mut params = Params::Array(vec![code.clone().into(), "small".into()]);
+		fields.compress_params("upload", &mut params);
+		let mut params = JsonValue::from(params);
+		let compressed = params[0].as_str().unwrap();
+		assert!(compressed.starts_with("deflate:") && compressed.len() < code.len());
+		assert_eq!(params[1], "small");
+		fields.decompress_params("upload", &mut params).unwrap();
+		assert_eq!(params[0], code.as_str());
+
+		let mut result = serde_json::json!({ "code": code });
+		fields.compress_result("download", &mut result);
+		assert_ne!(result["code"], code.as_str());
+		fields.decompress_result("download", &mut result).unwrap();
+		assert_eq!(result["code"], code.as_str());
+
+		// Values without the prefix of the codec are left as is.
+		let mut uncompressed = JsonValue::from(vec![JsonValue::from("plain")]);
+		fields.decompress_params("upload", &mut uncompressed).unwrap();
+		assert_eq!(uncompressed[0], "plain");
+	}
+}
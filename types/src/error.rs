@@ -1,5 +1,6 @@
 use crate::jsonrpc;
-use alloc::{boxed::Box, string::String};
+use crate::validation::InvalidField;
+use alloc::{boxed::Box, string::String, vec::Vec};
 use core::fmt;
 
 /// Convenience type for displaying errors.
@@ -17,6 +18,33 @@ impl<T: fmt::Display> fmt::Display for Mismatch<T> {
 	}
 }
 
+/// Call that an error is about.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallInfo {
+	/// Method of the call, or the methods of a batch between brackets.
+	pub method: String,
+	/// ID of the request, if known.
+	pub id: Option<jsonrpc::Id>,
+}
+
+impl CallInfo {
+	/// Create the info of a call to `method` with the request `id`, if known.
+	pub fn new(method: impl Into<String>, id: Option<jsonrpc::Id>) -> Self {
+		Self { method: method.into(), id }
+	}
+}
+
+impl fmt::Display for CallInfo {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(&self.method)?;
+		match &self.id {
+			Some(jsonrpc::Id::Num(id)) => write!(f, " (id={})", id),
+			Some(jsonrpc::Id::Str(id)) => write!(f, " (id={:?})", id),
+			Some(jsonrpc::Id::Null) | None => Ok(()),
+		}
+	}
+}
+
 /// Error type.
 pub enum Error {
 	/// Networking error or error on the low-level protocol layer.
@@ -27,10 +55,14 @@ pub enum Error {
 	Subscription(String, String),
 	/// Frontend/backend channel error.
 	Internal(futures::channel::mpsc::SendError),
-	/// Invalid response,
-	InvalidResponse(Mismatch<String>),
+	/// Invalid response to a call, if the call is known.
+	InvalidResponse(Option<CallInfo>, Mismatch<String>),
+	/// A field of the result was rejected by the [`ResponseValidator`](crate::validation::ResponseValidator).
+	InvalidResponseField(InvalidField),
 	/// The background task has been terminated.
-	RestartNeeded(String),
+	RestartNeeded(ShutdownReason),
+	/// The connection was lost while the request was in flight and the client is reconnecting.
+	ConnectionLost(ShutdownReason),
 	/// Failed to parse the data that the server sent back to us.
 	ParseError(jsonrpc::ParseError),
 	/// Invalid subscription ID.
@@ -43,8 +75,18 @@ pub enum Error {
 	MethodAlreadyRegistered(String),
 	/// Subscribe and unsubscribe method names are the same.
 	SubscriptionNameConflict(String),
-	/// Websocket request timeout
-	WsRequestTimeout,
+	/// Methods were already mounted at the path.
+	PathAlreadyMounted(String),
+	/// A method name pattern doesn't end with its single `*`.
+	InvalidMethodPattern(String),
+	/// Websocket request timeout.
+	WsRequestTimeout(CallInfo),
+	/// Request timeout.
+	RequestTimeout(CallInfo),
+	/// The server lacks the methods required by the [`Preflight`](crate::preflight::Preflight) call.
+	MissingMethods(Vec<String>),
+	/// The request was cancelled before a response arrived.
+	RequestCancelled,
 	/// Configured max number of request slots exceeded.
 	MaxSlotsExceeded,
 	/// Custom error.
@@ -61,10 +103,13 @@ impl fmt::Debug for Error {
 				write!(f, "Subscription failed, subscribe_method: {} unsubscribe_method: {}", elem0, elem1)
 			}
 			Self::Internal(ref elem) => write!(f, "Frontend/backend channel error: {}", elem),
-			Self::InvalidResponse(ref elem) => write!(f, "Invalid response: {}", elem),
+			Self::InvalidResponse(Some(ref call), ref elem) => write!(f, "Invalid response to {}: {}", call, elem),
+			Self::InvalidResponse(None, ref elem) => write!(f, "Invalid response: {}", elem),
+			Self::InvalidResponseField(ref elem) => write!(f, "Invalid response field: {}", elem),
 			Self::RestartNeeded(ref elem) => {
 				write!(f, "The background task been terminated because: {}; restart required", elem)
 			}
+			Self::ConnectionLost(ref elem) => write!(f, "The connection was lost because: {}; reconnecting", elem),
 			Self::ParseError(ref elem) => write!(f, "Parse error: {}", elem),
 			Self::InvalidSubscriptionId => write!(f, "Invalid subscription ID"),
 			Self::InvalidRequestId => write!(f, "Invalid request ID"),
@@ -73,7 +118,12 @@ impl fmt::Debug for Error {
 			Self::SubscriptionNameConflict(ref elem) => {
 				write!(f, "Cannot use the same method name for subscribe and unsubscribe, used: {}", elem)
 			}
-			Self::WsRequestTimeout => write!(f, "Websocket request timeout"),
+			Self::PathAlreadyMounted(ref elem) => write!(f, "Path: {} was already mounted", elem),
+			Self::InvalidMethodPattern(ref elem) => write!(f, "Method pattern: {} must end with its single *", elem),
+			Self::WsRequestTimeout(ref call) => write!(f, "Websocket request timeout waiting for response to {}", call),
+			Self::RequestTimeout(ref call) => write!(f, "Request timeout waiting for response to {}", call),
+			Self::MissingMethods(ref elem) => write!(f, "Methods missing on the server: {}", elem.join(", ")),
+			Self::RequestCancelled => write!(f, "Request cancelled"),
 			Self::MaxSlotsExceeded => write!(f, "Configured max number of request slots exceeded"),
 			Self::Custom(ref elem) => write!(f, "Custom error: {}", elem),
 		}
@@ -90,6 +140,55 @@ impl fmt::Display for Error {
 #[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
+/// Reason why the background task of a client was terminated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShutdownReason {
+	/// The server didn't answer a ping in time, the connection is considered dead.
+	Timeout,
+	/// The server closed the connection.
+	ConnectionClosedByServer {
+		/// Close code sent by the server, if known.
+		code: Option<u16>,
+		/// Close reason sent by the server, if known.
+		reason: Option<String>,
+	},
+	/// The server sent a message that violates the JSON-RPC protocol, such as a response with an unknown ID.
+	ProtocolViolation(String),
+	/// A message exceeded its maximum size, such as a message received from the server larger than the maximum
+	/// response size of the client.
+	MessageTooLarge,
+	/// Networking or low-level protocol error.
+	Transport(String),
+	/// The background task was dropped, for example because the scope it was spawned in ended.
+	Aborted,
+	/// Any other reason.
+	Other(String),
+}
+
+impl fmt::Display for ShutdownReason {
+	#[inline]
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match *self {
+			Self::Timeout => write!(f, "Timeout: the server didn't answer a ping in time"),
+			Self::ConnectionClosedByServer { ref code, ref reason } => {
+				write!(f, "Connection closed by server")?;
+				if let Some(code) = code {
+					write!(f, " with code {}", code)?;
+				}
+				if let Some(reason) = reason {
+					write!(f, ": {}", reason)?;
+				}
+				Ok(())
+			}
+			Self::ProtocolViolation(ref elem) => write!(f, "{}", elem),
+			Self::MessageTooLarge => write!(f, "The message was too large"),
+			Self::Transport(ref elem) => write!(f, "Networking or low-level protocol error: {}", elem),
+			Self::Aborted => write!(f, "Background task aborted"),
+			Self::Other(ref elem) => write!(f, "{}", elem),
+		}
+	}
+}
+
 /// Generic transport error.
 pub enum GenericTransportError<T> {
 	/// Request was too large.
@@ -0,0 +1,230 @@
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+
+/// Max size of the headers of a message framed with [`Framing::ContentLength`].
+const MAX_HEADERS_SIZE: usize = 8 * 1024;
+
+/// How JSON-RPC messages are delimited on a byte stream, such as a TCP connection, a Unix domain socket
+/// or the standard input and output of a process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+	/// Messages terminated by a newline, the blank lines between messages are skipped.
+	NewlineDelimited,
+	/// Messages preceded by headers with the `Content-Length` of the message, as in the language server
+	/// protocol.
+	ContentLength,
+}
+
+impl Framing {
+	/// Appends `message` with its framing to `out`.
+	///
+	/// With [`Framing::NewlineDelimited`], `message` must not contain a newline, which serialized JSON
+	/// never contains.
+	pub fn encode(self, message: &[u8], out: &mut Vec<u8>) {
+		match self {
+			Framing::NewlineDelimited => {
+				out.extend_from_slice(message);
+				out.push(b'\n');
+			}
+			Framing::ContentLength => {
+				out.extend_from_slice(format!("Content-Length: {}\r\n\r\n", message.len()).as_bytes());
+				out.extend_from_slice(message);
+			}
+		}
+	}
+}
+
+/// Error of a [`Decoder`], the stream can't be decoded further.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FramingError {
+	/// A message exceeded the maximum size.
+	TooLarge,
+	/// The headers of a message are invalid.
+	InvalidHeaders(String),
+}
+
+impl fmt::Display for FramingError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::TooLarge => write!(f, "The message was too large"),
+			Self::InvalidHeaders(reason) => write!(f, "Invalid message headers: {}", reason),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FramingError {}
+
+/// Splits the bytes read from a stream into messages, the bytes can be fed in chunks of any size.
+///
+/// ```
+/// use jsonrpsee_types::framing::{Decoder, Framing};
+///
+/// let mut decoder = Decoder::new(Framing::NewlineDelimited, 1024);
+/// decoder.extend(b"{\"id\":1}\n{\"id\"");
+/// assert_eq!(decoder.next_message(), Ok(Some(b"{\"id\":1}".to_vec())));
+/// assert_eq!(decoder.next_message(), Ok(None));
+/// decoder.extend(b":2}\n");
+/// assert_eq!(decoder.next_message(), Ok(Some(b"{\"id\":2}".to_vec())));
+/// ```
+#[derive(Debug)]
+pub struct Decoder {
+	framing: Framing,
+	max_message_size: usize,
+	buf: Vec<u8>,
+	/// Bytes of `buf` already searched for the end of the message or of the headers.
+	scanned: usize,
+	/// Length of the next message, once its headers are decoded with [`Framing::ContentLength`].
+	content_length: Option<usize>,
+}
+
+impl Decoder {
+	/// Create a decoder of messages up to `max_message_size` bytes.
+	pub fn new(framing: Framing, max_message_size: usize) -> Self {
+		Self { framing, max_message_size, buf: Vec::new(), scanned: 0, content_length: None }
+	}
+
+	/// Appends bytes read from the stream.
+	pub fn extend(&mut self, bytes: &[u8]) {
+		self.buf.extend_from_slice(bytes);
+	}
+
+	/// Returns `true` if no bytes of a partial message are buffered.
+	pub fn is_empty(&self) -> bool {
+		self.buf.is_empty()
+	}
+
+	/// Returns the next complete message, `None` if more bytes are needed.
+	pub fn next_message(&mut self) -> Result<Option<Vec<u8>>, FramingError> {
+		match self.framing {
+			Framing::NewlineDelimited => self.next_line(),
+			Framing::ContentLength => self.next_content(),
+		}
+	}
+
+	fn next_line(&mut self) -> Result<Option<Vec<u8>>, FramingError> {
+		loop {
+			let end = match self.buf[self.scanned..].iter().position(|b| *b == b'\n') {
+				Some(pos) => self.scanned + pos,
+				None => {
+					self.scanned = self.buf.len();
+					if self.buf.len() > self.max_message_size {
+						return Err(FramingError::TooLarge);
+					}
+					return Ok(None);
+				}
+			};
+			let mut line: Vec<u8> = self.buf.drain(..=end).collect();
+			self.scanned = 0;
+			line.pop();
+			if line.last() == Some(&b'\r') {
+				line.pop();
+			}
+			if line.len() > self.max_message_size {
+				return Err(FramingError::TooLarge);
+			}
+			if !line.iter().all(u8::is_ascii_whitespace) {
+				return Ok(Some(line));
+			}
+		}
+	}
+
+	fn next_content(&mut self) -> Result<Option<Vec<u8>>, FramingError> {
+		let len = match self.content_length {
+			Some(len) => len,
+			None => match self.decode_headers()? {
+				Some(len) => len,
+				None => return Ok(None),
+			},
+		};
+		if self.buf.len() < len {
+			return Ok(None);
+		}
+		self.content_length = None;
+		Ok(Some(self.buf.drain(..len).collect()))
+	}
+
+	/// Decodes and removes the headers from the buffer, returns the length of the message.
+	fn decode_headers(&mut self) -> Result<Option<usize>, FramingError> {
+		// NOTE: the terminator may straddle the bytes already scanned.
+		let start = self.scanned.saturating_sub(3);
+		let end = match self.buf[start..].windows(4).position(|w| w == b"\r\n\r\n") {
+			Some(pos) => start + pos,
+			None => {
+				self.scanned = self.buf.len();
+				if self.buf.len() > MAX_HEADERS_SIZE {
+					return Err(FramingError::InvalidHeaders("headers too large".into()));
+				}
+				return Ok(None);
+			}
+		};
+		let headers = core::str::from_utf8(&self.buf[..end])
+			.map_err(|_| FramingError::InvalidHeaders("headers are not UTF-8".into()))?;
+		let mut len = None;
+		for header in headers.split("\r\n") {
+			let (name, value) = header
+				.split_once(':')
+				.ok_or_else(|| FramingError::InvalidHeaders(format!("malformed header {:?}", header)))?;
+			if name.trim().eq_ignore_ascii_case("content-length") {
+				let value = value.trim();
+				len = Some(
+					value
+						.parse::<usize>()
+						.map_err(|_| FramingError::InvalidHeaders(format!("invalid Content-Length {:?}", value)))?,
+				);
+			}
+		}
+		let len = len.ok_or_else(|| FramingError::InvalidHeaders("missing Content-Length".into()))?;
+		if len > self.max_message_size {
+			return Err(FramingError::TooLarge);
+		}
+		self.buf.drain(..end + 4);
+		self.scanned = 0;
+		self.content_length = Some(len);
+		Ok(Some(len))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Decoder, Framing, FramingError};
+	use alloc::{vec, vec::Vec};
+
+	fn decode(framing: Framing, max: usize, chunks: &[&[u8]]) -> Result<Vec<Vec<u8>>, FramingError> {
+		let mut decoder = Decoder::new(framing, max);
+		let mut messages = Vec::new();
+		for chunk in chunks {
+			decoder.extend(chunk);
+			while let Some(message) = decoder.next_message()? {
+				messages.push(message);
+			}
+		}
+		Ok(messages)
+	}
+
+	#[test]
+	fn newline_delimited_messages_are_split() {
+		let messages = decode(Framing::NewlineDelimited, 16, &[b"{\"a\":1}\r\n\n  \n{\"b\"", b":2}", b"\n"]).unwrap();
+		assert_eq!(messages, vec![b"{\"a\":1}".to_vec(), b"{\"b\":2}".to_vec()]);
+		assert_eq!(decode(Framing::NewlineDelimited, 4, &[b"{\"a\"", b":1}"]), Err(FramingError::TooLarge));
+	}
+
+	#[test]
+	fn content_length_messages_are_split() {
+		let mut encoded = Vec::new();
+		Framing::ContentLength.encode(b"{\"a\":1}", &mut encoded);
+		encoded.extend_from_slice(b"Content-Type: application/json\r\ncontent-length: 7\r\n\r\n{\"b\":2}");
+		let chunks: Vec<&[u8]> = encoded.chunks(3).collect();
+		let messages = decode(Framing::ContentLength, 16, &chunks).unwrap();
+		assert_eq!(messages, vec![b"{\"a\":1}".to_vec(), b"{\"b\":2}".to_vec()]);
+	}
+
+	#[test]
+	fn content_length_is_checked() {
+		assert_eq!(decode(Framing::ContentLength, 4, &[b"Content-Length: 7\r\n\r\n"]), Err(FramingError::TooLarge));
+		assert!(matches!(
+			decode(Framing::ContentLength, 4, &[b"Content-Type: json\r\n\r\n"]),
+			Err(FramingError::InvalidHeaders(_))
+		));
+	}
+}
@@ -0,0 +1,57 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Serialization of the JSON-RPC types without panicking.
+
+use alloc::fmt;
+use serde::Serialize;
+
+/// Writes the serialization of `value` to `f`, directly with the `std` feature instead of serializing
+/// into a string first.
+pub(crate) fn fmt_json<T: Serialize + ?Sized>(value: &T, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+	#[cfg(feature = "std")]
+	return serde_json::to_writer(FmtWriter(f), value).map_err(|_| fmt::Error);
+	#[cfg(not(feature = "std"))]
+	return f.write_str(&serde_json::to_string(value).map_err(|_| fmt::Error)?);
+}
+
+/// Forwards the bytes written by `serde_json` to a formatter.
+#[cfg(feature = "std")]
+struct FmtWriter<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+#[cfg(feature = "std")]
+impl std::io::Write for FmtWriter<'_, '_> {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		// NOTE: `serde_json` writes whole UTF-8 sequences at once.
+		let s = core::str::from_utf8(buf).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+		self.0.write_str(s).map_err(std::io::Error::other)?;
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		Ok(())
+	}
+}
@@ -31,6 +31,8 @@
 
 mod error;
 mod id;
+mod json;
+mod number;
 mod params;
 mod request;
 mod response;
@@ -48,9 +50,11 @@ pub use serde_json::{from_slice, from_value, to_string, to_value, to_vec};
 
 pub use self::error::{Error, ErrorCode};
 pub use self::id::Id;
+pub use self::number::{NumberOptions, NumberPolicy, MAX_SAFE_INTEGER};
 pub use self::params::Params;
 pub use self::request::{Call, MethodCall, Notification, Request};
 pub use self::response::{
-	Failure, Output, Response, SubscriptionId, SubscriptionNotif, SubscriptionNotifParams, Success,
+	Failure, Output, Response, SubscriptionId, SubscriptionNotif, SubscriptionNotifParams, Success, CANCEL_METHOD,
+	SUBSCRIPTION_HEARTBEAT_METHOD, SUBSCRIPTION_PAUSE_METHOD, SUBSCRIPTION_RESUME_METHOD,
 };
 pub use self::version::Version;
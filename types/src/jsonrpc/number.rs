@@ -0,0 +1,464 @@
+use super::{from_value, to_value, JsonValue, Params, ParseError};
+use crate::traits::Value;
+
+use alloc::{format, string::ToString as _};
+use core::convert::TryFrom;
+use core::fmt;
+use serde::de::DeserializeOwned;
+use serde::ser::{self, Error as _, Serialize, Serializer};
+
+/// Largest integer that is exactly representable by an IEEE 754 double, `2^53 - 1`.
+///
+/// Peers parsing JSON numbers into doubles, such as JavaScript, silently round larger integers.
+pub const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// How numbers that can't be represented faithfully in JSON are handled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum NumberPolicy {
+	/// Use the closest JSON representation, which may lose information.
+	Lossy,
+	/// Represent the number as a string.
+	Stringify,
+	/// Fail to serialize or decode the value.
+	Error,
+}
+
+/// Handling of non-finite floating point numbers and integers larger than [`MAX_SAFE_INTEGER`].
+///
+/// The default is [`NumberPolicy::Lossy`] for both, such that values are serialized exactly like
+/// [`to_value`] does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct NumberOptions {
+	/// Handling of `NaN` and infinite floats.
+	///
+	/// Lossy serializes them as `null` and stringified they are `"NaN"`, `"Infinity"` or `"-Infinity"`.
+	pub non_finite: NumberPolicy,
+	/// Handling of integers whose absolute value is larger than [`MAX_SAFE_INTEGER`].
+	pub large_integers: NumberPolicy,
+}
+
+impl Default for NumberOptions {
+	fn default() -> Self {
+		Self { non_finite: NumberPolicy::Lossy, large_integers: NumberPolicy::Lossy }
+	}
+}
+
+impl NumberOptions {
+	/// Serialize `value` into a [`JsonValue`], applying the options to every number.
+	pub fn to_value<T: Serialize + ?Sized>(&self, value: &T) -> Result<JsonValue, ParseError> {
+		to_value(Checked { value, options: *self })
+	}
+
+	/// Apply the large integer option to a value that is already serialized.
+	///
+	/// Non-finite floats are serialized as `null` by [`to_value`], so only [`NumberOptions::to_value`] can
+	/// handle them.
+	pub fn apply(&self, value: JsonValue) -> Result<JsonValue, ParseError> {
+		match value {
+			JsonValue::Number(n) if !is_safe_number(&n) => match self.large_integers {
+				NumberPolicy::Lossy => Ok(JsonValue::Number(n)),
+				NumberPolicy::Stringify => Ok(JsonValue::String(n.to_string())),
+				NumberPolicy::Error => Err(large_integer_error(n)),
+			},
+			JsonValue::Array(items) => items.into_iter().map(|item| self.apply(item)).collect(),
+			JsonValue::Object(map) => {
+				map.into_iter().map(|(key, value)| self.apply(value).map(|value| (key, value))).collect()
+			}
+			value => Ok(value),
+		}
+	}
+
+	/// Apply the large integer option to all parameters, see [`NumberOptions::apply`].
+	pub fn apply_params(&self, params: Params) -> Result<Params, ParseError> {
+		match params {
+			Params::None => Ok(Params::None),
+			Params::Array(items) => {
+				items.into_iter().map(|item| self.apply(item)).collect::<Result<_, _>>().map(Params::Array)
+			}
+			Params::Map(map) => map
+				.into_iter()
+				.map(|(key, value)| self.apply(value).map(|value| (key, value)))
+				.collect::<Result<_, _>>()
+				.map(Params::Map),
+		}
+	}
+
+	/// Decode a received value into `T` after applying the large integer option to it.
+	pub fn decode<V: Value, T: DeserializeOwned>(&self, value: V) -> Result<T, ParseError> {
+		from_value(self.apply(to_value(value)?)?)
+	}
+}
+
+fn is_safe_number(n: &super::JsonNumber) -> bool {
+	match (n.as_u64(), n.as_i64()) {
+		(Some(n), _) => n <= MAX_SAFE_INTEGER,
+		(_, Some(n)) => is_safe_integer(n.into()),
+		// Floats are exactly what the peer parses.
+		_ => true,
+	}
+}
+
+fn is_safe_integer(n: i128) -> bool {
+	n.unsigned_abs() <= MAX_SAFE_INTEGER.into()
+}
+
+fn large_integer_error<T: ser::Error>(n: impl fmt::Display) -> T {
+	T::custom(format!("Integer {} is larger than 2^53 - 1 and can't be represented exactly", n))
+}
+
+/// Serializes the wrapped value with [`NumberSerializer`].
+struct Checked<'a, T: ?Sized> {
+	value: &'a T,
+	options: NumberOptions,
+}
+
+impl<T: Serialize + ?Sized> Serialize for Checked<'_, T> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		self.value.serialize(NumberSerializer { inner: serializer, options: self.options })
+	}
+}
+
+/// Serializer applying [`NumberOptions`] to the numbers and forwarding everything to the inner serializer.
+struct NumberSerializer<S> {
+	inner: S,
+	options: NumberOptions,
+}
+
+impl<S: Serializer> NumberSerializer<S> {
+	fn integer<N>(self, n: N, serialize: impl FnOnce(S, N) -> Result<S::Ok, S::Error>) -> Result<S::Ok, S::Error>
+	where
+		N: Into<i128> + fmt::Display + Copy,
+	{
+		if is_safe_integer(n.into()) {
+			return serialize(self.inner, n);
+		}
+		match self.options.large_integers {
+			NumberPolicy::Lossy => serialize(self.inner, n),
+			NumberPolicy::Stringify => self.inner.collect_str(&n),
+			NumberPolicy::Error => Err(large_integer_error(n)),
+		}
+	}
+
+	fn float(self, n: f64, serialize: impl FnOnce(S) -> Result<S::Ok, S::Error>) -> Result<S::Ok, S::Error> {
+		if n.is_finite() {
+			return serialize(self.inner);
+		}
+		match self.options.non_finite {
+			NumberPolicy::Lossy => serialize(self.inner),
+			NumberPolicy::Stringify if n.is_nan() => self.inner.serialize_str("NaN"),
+			NumberPolicy::Stringify if n > 0.0 => self.inner.serialize_str("Infinity"),
+			NumberPolicy::Stringify => self.inner.serialize_str("-Infinity"),
+			NumberPolicy::Error => Err(S::Error::custom(format!("Float {} is not finite", n))),
+		}
+	}
+}
+
+macro_rules! forward {
+	($($method:ident($ty:ty)),*) => {
+		$(
+			fn $method(self, v: $ty) -> Result<S::Ok, S::Error> {
+				self.inner.$method(v)
+			}
+		)*
+	};
+}
+
+impl<S: Serializer> Serializer for NumberSerializer<S> {
+	type Ok = S::Ok;
+	type Error = S::Error;
+	type SerializeSeq = Compound<S::SerializeSeq>;
+	type SerializeTuple = Compound<S::SerializeTuple>;
+	type SerializeTupleStruct = Compound<S::SerializeTupleStruct>;
+	type SerializeTupleVariant = Compound<S::SerializeTupleVariant>;
+	type SerializeMap = Compound<S::SerializeMap>;
+	type SerializeStruct = Compound<S::SerializeStruct>;
+	type SerializeStructVariant = Compound<S::SerializeStructVariant>;
+
+	forward!(
+		serialize_bool(bool),
+		serialize_i8(i8),
+		serialize_i16(i16),
+		serialize_i32(i32),
+		serialize_u8(u8),
+		serialize_u16(u16),
+		serialize_u32(u32),
+		serialize_char(char),
+		serialize_str(&str),
+		serialize_bytes(&[u8]),
+		serialize_unit_struct(&'static str)
+	);
+
+	fn serialize_i64(self, v: i64) -> Result<S::Ok, S::Error> {
+		self.integer(v, S::serialize_i64)
+	}
+
+	fn serialize_u64(self, v: u64) -> Result<S::Ok, S::Error> {
+		self.integer(v, S::serialize_u64)
+	}
+
+	fn serialize_i128(self, v: i128) -> Result<S::Ok, S::Error> {
+		self.integer(v, S::serialize_i128)
+	}
+
+	fn serialize_u128(self, v: u128) -> Result<S::Ok, S::Error> {
+		// NOTE: `u128` doesn't fit in `i128`, values that don't are large anyway.
+		match i128::try_from(v) {
+			Ok(n) if is_safe_integer(n) => self.inner.serialize_u128(v),
+			_ => match self.options.large_integers {
+				NumberPolicy::Lossy => self.inner.serialize_u128(v),
+				NumberPolicy::Stringify => self.inner.collect_str(&v),
+				NumberPolicy::Error => Err(large_integer_error(v)),
+			},
+		}
+	}
+
+	fn serialize_f32(self, v: f32) -> Result<S::Ok, S::Error> {
+		self.float(v.into(), |inner| inner.serialize_f32(v))
+	}
+
+	fn serialize_f64(self, v: f64) -> Result<S::Ok, S::Error> {
+		self.float(v, |inner| inner.serialize_f64(v))
+	}
+
+	fn serialize_none(self) -> Result<S::Ok, S::Error> {
+		self.inner.serialize_none()
+	}
+
+	fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+		self.inner.serialize_some(&Checked { value, options: self.options })
+	}
+
+	fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+		self.inner.serialize_unit()
+	}
+
+	fn serialize_unit_variant(
+		self,
+		name: &'static str,
+		variant_index: u32,
+		variant: &'static str,
+	) -> Result<S::Ok, S::Error> {
+		self.inner.serialize_unit_variant(name, variant_index, variant)
+	}
+
+	fn serialize_newtype_struct<T: Serialize + ?Sized>(self, name: &'static str, value: &T) -> Result<S::Ok, S::Error> {
+		self.inner.serialize_newtype_struct(name, &Checked { value, options: self.options })
+	}
+
+	fn serialize_newtype_variant<T: Serialize + ?Sized>(
+		self,
+		name: &'static str,
+		variant_index: u32,
+		variant: &'static str,
+		value: &T,
+	) -> Result<S::Ok, S::Error> {
+		let value = Checked { value, options: self.options };
+		self.inner.serialize_newtype_variant(name, variant_index, variant, &value)
+	}
+
+	fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+		Ok(Compound { inner: self.inner.serialize_seq(len)?, options: self.options })
+	}
+
+	fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+		Ok(Compound { inner: self.inner.serialize_tuple(len)?, options: self.options })
+	}
+
+	fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct, S::Error> {
+		Ok(Compound { inner: self.inner.serialize_tuple_struct(name, len)?, options: self.options })
+	}
+
+	fn serialize_tuple_variant(
+		self,
+		name: &'static str,
+		variant_index: u32,
+		variant: &'static str,
+		len: usize,
+	) -> Result<Self::SerializeTupleVariant, S::Error> {
+		Ok(Compound {
+			inner: self.inner.serialize_tuple_variant(name, variant_index, variant, len)?,
+			options: self.options,
+		})
+	}
+
+	fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+		Ok(Compound { inner: self.inner.serialize_map(len)?, options: self.options })
+	}
+
+	fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct, S::Error> {
+		Ok(Compound { inner: self.inner.serialize_struct(name, len)?, options: self.options })
+	}
+
+	fn serialize_struct_variant(
+		self,
+		name: &'static str,
+		variant_index: u32,
+		variant: &'static str,
+		len: usize,
+	) -> Result<Self::SerializeStructVariant, S::Error> {
+		Ok(Compound {
+			inner: self.inner.serialize_struct_variant(name, variant_index, variant, len)?,
+			options: self.options,
+		})
+	}
+
+	fn is_human_readable(&self) -> bool {
+		self.inner.is_human_readable()
+	}
+}
+
+/// Compound serializer applying [`NumberOptions`] to its elements.
+struct Compound<C> {
+	inner: C,
+	options: NumberOptions,
+}
+
+impl<C: ser::SerializeSeq> ser::SerializeSeq for Compound<C> {
+	type Ok = C::Ok;
+	type Error = C::Error;
+
+	fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+		self.inner.serialize_element(&Checked { value, options: self.options })
+	}
+
+	fn end(self) -> Result<C::Ok, C::Error> {
+		self.inner.end()
+	}
+}
+
+impl<C: ser::SerializeTuple> ser::SerializeTuple for Compound<C> {
+	type Ok = C::Ok;
+	type Error = C::Error;
+
+	fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+		self.inner.serialize_element(&Checked { value, options: self.options })
+	}
+
+	fn end(self) -> Result<C::Ok, C::Error> {
+		self.inner.end()
+	}
+}
+
+impl<C: ser::SerializeTupleStruct> ser::SerializeTupleStruct for Compound<C> {
+	type Ok = C::Ok;
+	type Error = C::Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+		self.inner.serialize_field(&Checked { value, options: self.options })
+	}
+
+	fn end(self) -> Result<C::Ok, C::Error> {
+		self.inner.end()
+	}
+}
+
+impl<C: ser::SerializeTupleVariant> ser::SerializeTupleVariant for Compound<C> {
+	type Ok = C::Ok;
+	type Error = C::Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+		self.inner.serialize_field(&Checked { value, options: self.options })
+	}
+
+	fn end(self) -> Result<C::Ok, C::Error> {
+		self.inner.end()
+	}
+}
+
+impl<C: ser::SerializeMap> ser::SerializeMap for Compound<C> {
+	type Ok = C::Ok;
+	type Error = C::Error;
+
+	fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), C::Error> {
+		self.inner.serialize_key(&Checked { value: key, options: self.options })
+	}
+
+	fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+		self.inner.serialize_value(&Checked { value, options: self.options })
+	}
+
+	fn end(self) -> Result<C::Ok, C::Error> {
+		self.inner.end()
+	}
+}
+
+impl<C: ser::SerializeStruct> ser::SerializeStruct for Compound<C> {
+	type Ok = C::Ok;
+	type Error = C::Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), C::Error> {
+		self.inner.serialize_field(key, &Checked { value, options: self.options })
+	}
+
+	fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+		self.inner.skip_field(key)
+	}
+
+	fn end(self) -> Result<C::Ok, C::Error> {
+		self.inner.end()
+	}
+}
+
+impl<C: ser::SerializeStructVariant> ser::SerializeStructVariant for Compound<C> {
+	type Ok = C::Ok;
+	type Error = C::Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), C::Error> {
+		self.inner.serialize_field(key, &Checked { value, options: self.options })
+	}
+
+	fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+		self.inner.skip_field(key)
+	}
+
+	fn end(self) -> Result<C::Ok, C::Error> {
+		self.inner.end()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{NumberOptions, NumberPolicy, MAX_SAFE_INTEGER};
+	use crate::jsonrpc::{JsonValue, Params};
+
+	fn options(policy: NumberPolicy) -> NumberOptions {
+		NumberOptions { non_finite: policy, large_integers: policy }
+	}
+
+	#[test]
+	fn to_value_applies_policy() {
+		let value = (f64::NAN, f64::NEG_INFINITY, MAX_SAFE_INTEGER + 1, Some(-(1_i128 << 60)), MAX_SAFE_INTEGER);
+
+		let lossy = NumberOptions::default().to_value(&value).unwrap();
+		assert_eq!(lossy, serde_json::to_value(value).unwrap());
+
+		let stringified = options(NumberPolicy::Stringify).to_value(&value).unwrap();
+		assert_eq!(
+			stringified,
+			serde_json::json!(["NaN", "-Infinity", "9007199254740992", "-1152921504606846976", 9007199254740991_u64])
+		);
+
+		assert!(options(NumberPolicy::Error).to_value(&[f64::INFINITY]).is_err());
+		assert!(options(NumberPolicy::Error).to_value(&[u64::MAX]).is_err());
+		assert!(options(NumberPolicy::Error).to_value(&[1.5_f64, 2.0]).is_ok());
+	}
+
+	#[test]
+	fn apply_handles_nested_large_integers() {
+		let value = serde_json::json!({ "balance": u64::MAX, "entries": [1, -(1_i64 << 62)], "rate": 1e300 });
+
+		assert_eq!(options(NumberPolicy::Lossy).apply(value.clone()).unwrap(), value);
+		assert_eq!(
+			options(NumberPolicy::Stringify).apply(value.clone()).unwrap(),
+			serde_json::json!({ "balance": "18446744073709551615", "entries": [1, "-4611686018427387904"], "rate": 1e300 })
+		);
+		assert!(options(NumberPolicy::Error).apply(value).is_err());
+
+		let params = Params::Array(vec![JsonValue::from(u64::MAX)]);
+		assert_eq!(
+			options(NumberPolicy::Stringify).apply_params(params).unwrap(),
+			Params::Array(vec!["18446744073709551615".into()])
+		);
+	}
+}
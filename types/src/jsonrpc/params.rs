@@ -26,10 +26,12 @@
 
 use alloc::{format, string::String, vec::Vec};
 use serde::de::DeserializeOwned;
+use serde::ser::Error as _;
 use serde::{Deserialize, Serialize};
-use serde_json::value::from_value;
+use serde_json::value::{from_value, to_value};
 
 use super::{Error, JsonValue};
+use crate::validation;
 
 /// Request parameters
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -45,6 +47,91 @@ pub enum Params {
 }
 
 impl Params {
+	/// Create named parameters from `(name, value)` pairs.
+	///
+	/// ```
+	/// use jsonrpsee_types::jsonrpc::{JsonValue, Params};
+	///
+	/// let params = Params::from_named(vec![("foo", JsonValue::from(1)), ("bar", "baz".into())]);
+	/// assert_eq!(params.get::<u64>("foo"), Ok(1));
+	/// ```
+	pub fn from_named<I, K, V>(params: I) -> Self
+	where
+		I: IntoIterator<Item = (K, V)>,
+		K: Into<String>,
+		V: Into<JsonValue>,
+	{
+		Params::Map(params.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+	}
+
+	/// Create parameters from the serialization of `params`, for example positional parameters from a tuple
+	/// or named parameters from a struct.
+	///
+	/// Fails unless `params` is serialized into an array, an object or `null`.
+	///
+	/// ```
+	/// use jsonrpsee_types::jsonrpc::{JsonValue, Params};
+	///
+	/// let params = Params::serialized(&(1, "foo")).unwrap();
+	/// assert_eq!(params, Params::Array(vec![JsonValue::from(1), "foo".into()]));
+	/// ```
+	pub fn serialized<S: Serialize>(params: &S) -> Result<Self, serde_json::Error> {
+		match to_value(params)? {
+			JsonValue::Array(vec) => Ok(Params::Array(vec)),
+			JsonValue::Object(map) => Ok(Params::Map(map)),
+			JsonValue::Null => Ok(Params::None),
+			_ => Err(serde_json::Error::custom("Parameters must be serialized into an array or an object")),
+		}
+	}
+
+	/// Create named parameters from the serialization of `params`, such as a struct, see
+	/// [`Params::from_named`] for `(name, value)` pairs.
+	///
+	/// Fails unless `params` is serialized into an object.
+	///
+	/// ```
+	/// use jsonrpsee_types::jsonrpc::Params;
+	///
+	/// #[derive(serde::Serialize)]
+	/// struct Transfer<'a> {
+	///     to: &'a str,
+	///     amount: u64,
+	/// }
+	///
+	/// let params = Params::from_struct(&Transfer { to: "alice", amount: 10 }).unwrap();
+	/// assert_eq!(params.get::<u64>("amount"), Ok(10));
+	/// assert!(Params::from_struct(&(1, 2)).is_err());
+	/// ```
+	pub fn from_struct<S: Serialize>(params: &S) -> Result<Self, serde_json::Error> {
+		match to_value(params)? {
+			JsonValue::Object(map) => Ok(Params::Map(map)),
+			other => Err(serde_json::Error::custom(format!(
+				"Named parameters must be serialized into an object, got {}",
+				validation::kind(&other)
+			))),
+		}
+	}
+
+	/// Parse the named parameter `key` into expected type.
+	///
+	/// A missing parameter is treated as `null`, thus parsing into an `Option` yields `None`.
+	pub fn get<D>(&self, key: &str) -> Result<D, Error>
+	where
+		D: DeserializeOwned,
+	{
+		let map = match self {
+			Params::Map(map) => map,
+			p => return Err(Error::invalid_params_with_details("Named parameters were expected", p)),
+		};
+
+		match map.get(key) {
+			Some(value) => D::deserialize(value)
+				.map_err(|e| Error::invalid_params(format!("Invalid params: parameter `{}`: {}.", key, e))),
+			None => from_value(JsonValue::Null)
+				.map_err(|_| Error::invalid_params(format!("Invalid params: missing parameter `{}`.", key))),
+		}
+	}
+
 	/// Parse incoming `Params` into expected common.
 	pub fn parse<D>(self) -> Result<D, Error>
 	where
@@ -74,6 +161,31 @@ impl From<Params> for JsonValue {
 	}
 }
 
+/// Create positional parameters from values of any serializable types, `rpc_params![]` creates
+/// [`Params::None`].
+///
+/// ```
+/// use jsonrpsee_types::{jsonrpc::{JsonValue, Params}, rpc_params};
+///
+/// let params = rpc_params![1, "foo", vec![true]];
+/// assert_eq!(params, Params::Array(vec![JsonValue::from(1), "foo".into(), JsonValue::from(vec![true])]));
+/// ```
+///
+/// # Panics
+///
+/// Panics if a value fails to serialize, such as a map with non-string keys.
+#[macro_export]
+macro_rules! rpc_params {
+	() => {
+		$crate::jsonrpc::Params::None
+	};
+	($($param:expr),+ $(,)?) => {
+		$crate::jsonrpc::Params::Array(
+			[$($crate::jsonrpc::to_value(&$param).expect("Parameters of rpc_params! must serialize")),+].into(),
+		)
+	};
+}
+
 #[cfg(test)]
 mod tests {
 	use super::Params;
@@ -125,6 +237,76 @@ mod tests {
 		assert_eq!(err2.data, None);
 	}
 
+	#[test]
+	fn named_params_get_works() {
+		let params = Params::from_named(vec![("foo", JsonValue::from(1)), ("bar", JsonValue::from("baz"))]);
+
+		assert_eq!(params.get::<u8>("foo"), Ok(1));
+		assert_eq!(params.get::<String>("bar"), Ok("baz".to_string()));
+		assert_eq!(params.get::<Option<u8>>("qux"), Ok(None));
+
+		let err = params.get::<u8>("qux").unwrap_err();
+		assert_eq!(err.code, ErrorCode::InvalidParams);
+		assert_eq!(err.message, "Invalid params: missing parameter `qux`.");
+
+		let err = params.get::<u8>("bar").unwrap_err();
+		assert_eq!(err.code, ErrorCode::InvalidParams);
+		assert_eq!(err.message, "Invalid params: parameter `bar`: invalid type: string \"baz\", expected u8.");
+
+		let err = Params::Array(vec![JsonValue::from(1)]).get::<u8>("foo").unwrap_err();
+		assert_eq!(err.code, ErrorCode::InvalidParams);
+	}
+
+	#[test]
+	fn serialized_params_must_be_array_or_object() {
+		#[derive(serde::Serialize)]
+		struct Named {
+			foo: u8,
+		}
+
+		assert_eq!(
+			Params::serialized(&(1, true)).unwrap(),
+			Params::Array(vec![JsonValue::from(1), JsonValue::from(true)])
+		);
+		assert_eq!(Params::serialized(&Named { foo: 1 }).unwrap(), Params::from_named(vec![("foo", 1)]));
+		assert_eq!(Params::serialized(&()).unwrap(), Params::None);
+		assert!(Params::serialized(&1).is_err());
+	}
+
+	#[test]
+	fn struct_params_must_be_object() {
+		#[derive(serde::Serialize)]
+		struct Named {
+			foo: u8,
+		}
+
+		assert_eq!(Params::from_struct(&Named { foo: 1 }).unwrap(), Params::from_named(vec![("foo", 1)]));
+		assert_eq!(
+			Params::from_struct(&[1, 2]).unwrap_err().to_string(),
+			"Named parameters must be serialized into an object, got array"
+		);
+		assert!(Params::from_struct(&()).is_err());
+	}
+
+	#[test]
+	fn rpc_params_serializes_heterogeneous_values() {
+		#[derive(serde::Serialize)]
+		struct Named {
+			foo: u8,
+		}
+
+		assert_eq!(rpc_params![], Params::None);
+		assert_eq!(
+			rpc_params![1u64, "bar", Named { foo: 2 }, None::<u8>,],
+			Params::Array(vec![
+				JsonValue::from(1),
+				JsonValue::from("bar"),
+				serde_json::json!({ "foo": 2 }),
+				JsonValue::Null
+			])
+		);
+	}
+
 	#[test]
 	fn single_param_parsed_as_tuple() {
 		let params: (u64,) = Params::Array(vec![JsonValue::from(1)]).parse().unwrap();
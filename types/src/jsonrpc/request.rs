@@ -108,9 +108,35 @@ pub enum Request {
 	Batch(Vec<Call>),
 }
 
+impl Call {
+	/// Serializes the call into a string.
+	pub fn to_json_string(&self) -> Result<String, serde_json::Error> {
+		serde_json::to_string(self)
+	}
+
+	/// Serializes the call into `writer`, for example to reuse a buffer.
+	#[cfg(feature = "std")]
+	pub fn to_writer<W: std::io::Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+		serde_json::to_writer(writer, self)
+	}
+}
+
+impl Request {
+	/// Serializes the request into a string.
+	pub fn to_json_string(&self) -> Result<String, serde_json::Error> {
+		serde_json::to_string(self)
+	}
+
+	/// Serializes the request into `writer`, for example to reuse a buffer.
+	#[cfg(feature = "std")]
+	pub fn to_writer<W: std::io::Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+		serde_json::to_writer(writer, self)
+	}
+}
+
 impl fmt::Display for Request {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		write!(f, "{}", serde_json::to_string(self).expect("Request valid JSON; qed"))
+		super::json::fmt_json(self, f)
 	}
 }
 
@@ -180,6 +206,23 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn request_display_and_writer_match_serialization() {
+		let request = Request::Single(Call::MethodCall(MethodCall {
+			jsonrpc: Version::V2,
+			method: "upd\u{e4}te \"now\"".to_owned(),
+			params: Params::Array(vec![Value::from(1), Value::from("\u{1f600}")]),
+			id: Id::Num(1),
+		}));
+
+		let serialized = request.to_json_string().unwrap();
+		assert_eq!(serialized, serde_json::to_string(&request).unwrap());
+		assert_eq!(alloc::format!("{}", request), serialized);
+		let mut buf = b"prefix ".to_vec();
+		request.to_writer(&mut buf).unwrap();
+		assert_eq!(buf, [&b"prefix "[..], serialized.as_bytes()].concat());
+	}
+
 	#[test]
 	fn notification_deserialize() {
 		use serde_json;
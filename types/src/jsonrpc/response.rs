@@ -24,7 +24,7 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use super::{Error, Id, JsonValue, Version};
+use super::{Error, Id, JsonValue, Notification, Version};
 
 use alloc::{
 	fmt,
@@ -33,35 +33,50 @@ use alloc::{
 	vec::Vec,
 };
 use core::convert::TryFrom;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 /// JSONRPC response.
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 #[serde(untagged)]
-pub enum Response {
+pub enum Response<V = JsonValue> {
 	/// Single response
-	Single(Output),
+	Single(Output<V>),
 	/// Response to batch request (batch of responses)
-	Batch(Vec<Output>),
+	Batch(Vec<Output<V>>),
 	/// Notification to an active subscription.
-	Notif(SubscriptionNotif),
+	Notif(SubscriptionNotif<V>),
+	/// Notification sent by the server without a subscription.
+	Notification(Notification),
 }
 
-impl fmt::Display for Response {
+impl<V: Serialize> Response<V> {
+	/// Serializes the response into a string.
+	pub fn to_json_string(&self) -> Result<String, serde_json::Error> {
+		serde_json::to_string(self)
+	}
+
+	/// Serializes the response into `writer`, for example to reuse a buffer.
+	#[cfg(feature = "std")]
+	pub fn to_writer<W: std::io::Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+		serde_json::to_writer(writer, self)
+	}
+}
+
+impl<V: Serialize> fmt::Display for Response<V> {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		write!(f, "{}", serde_json::to_string(self).expect("Response valid JSON; qed"))
+		super::json::fmt_json(self, f)
 	}
 }
 
 /// Successful response
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
-pub struct Success {
+pub struct Success<V = JsonValue> {
 	/// Protocol version
 	pub jsonrpc: Version,
 	/// Result
-	pub result: JsonValue,
+	pub result: V,
 	/// Correlation id
 	pub id: Id,
 }
@@ -82,33 +97,56 @@ pub struct Failure {
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 #[serde(untagged)]
-pub enum Output {
+pub enum Output<V = JsonValue> {
 	/// Success
-	Success(Success),
+	Success(Success<V>),
 	/// Failure
 	Failure(Failure),
 }
 
+/// Method of the heartbeat notifications that a server sends on quiet subscriptions, with a `null` result.
+///
+/// Clients don't deliver heartbeats to the subscriptions, they only tell that the subscription is still alive.
+pub const SUBSCRIPTION_HEARTBEAT_METHOD: &str = "rpc_subscriptionHeartbeat";
+
+/// Method that asks a server to stop sending the notifications of a subscription until it's resumed with
+/// [`SUBSCRIPTION_RESUME_METHOD`].
+///
+/// Optional extension of the protocol: the single parameter is the ID of the subscription and the result is
+/// `false` if the server doesn't know the subscription. Servers may buffer the notifications while paused.
+pub const SUBSCRIPTION_PAUSE_METHOD: &str = "rpc_pauseSubscription";
+
+/// Method that asks a server to resume a subscription paused with [`SUBSCRIPTION_PAUSE_METHOD`], sending the
+/// buffered notifications first.
+pub const SUBSCRIPTION_RESUME_METHOD: &str = "rpc_resumeSubscription";
+
+/// Method of the notifications that tell a server that the client is no longer interested in the response to a
+/// call, such that it can stop the handler of the call.
+///
+/// Optional extension of the protocol: the single parameter is the ID of the call. Servers may still answer the
+/// call, the client ignores the response.
+pub const CANCEL_METHOD: &str = "rpc_cancel";
+
 /// Server notification about something the client is subscribed to.
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
-pub struct SubscriptionNotif {
+pub struct SubscriptionNotif<V = JsonValue> {
 	/// Protocol version
 	pub jsonrpc: Version,
 	/// A String containing the name of the method that was used for the subscription.
 	pub method: String,
 	/// Parameters of the notification.
-	pub params: SubscriptionNotifParams,
+	pub params: SubscriptionNotifParams<V>,
 }
 
 /// Field of a [`SubscriptionNotif`].
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
-pub struct SubscriptionNotifParams {
+pub struct SubscriptionNotifParams<V = JsonValue> {
 	/// Subscription id, as communicated during the subscription.
 	pub subscription: SubscriptionId,
 	/// Actual data that the server wants to communicate to us.
-	pub result: JsonValue,
+	pub result: V,
 }
 
 /// Id of a subscription, communicated by the server.
@@ -122,9 +160,9 @@ pub enum SubscriptionId {
 	Str(String),
 }
 
-impl Output {
+impl<V> Output<V> {
 	/// Creates new output given `Result`, `Id` and `Version`.
-	pub fn from(result: Result<JsonValue, Error>, id: Id, jsonrpc: Version) -> Self {
+	pub fn from(result: Result<V, Error>, id: Id, jsonrpc: Version) -> Self {
 		match result {
 			Ok(result) => Output::Success(Success { id, jsonrpc, result }),
 			Err(error) => Output::Failure(Failure { id, jsonrpc, error }),
@@ -146,20 +184,38 @@ impl Output {
 			Output::Failure(ref f) => &f.id,
 		}
 	}
+
+	/// Turns the output into the result of the call.
+	pub fn into_result(self) -> Result<V, Error> {
+		match self {
+			Output::Success(s) => Ok(s.result),
+			Output::Failure(f) => Err(f.error),
+		}
+	}
+}
+
+impl<V: Serialize> Output<V> {
+	/// Serializes the output into a string.
+	pub fn to_json_string(&self) -> Result<String, serde_json::Error> {
+		serde_json::to_string(self)
+	}
+
+	/// Serializes the output into `writer`, for example to reuse a buffer.
+	#[cfg(feature = "std")]
+	pub fn to_writer<W: std::io::Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+		serde_json::to_writer(writer, self)
+	}
 }
 
 impl TryFrom<Output> for JsonValue {
 	type Error = Error;
 
 	fn try_from(output: Output) -> Result<JsonValue, Error> {
-		match output {
-			Output::Success(s) => Ok(s.result),
-			Output::Failure(f) => Err(f.error),
-		}
+		output.into_result()
 	}
 }
 
-impl Response {
+impl<V: DeserializeOwned> Response<V> {
 	/// Creates new `Response` with given error and `Version`
 	pub fn from(error: impl Into<Error>, jsonrpc: Version) -> Self {
 		Failure { id: Id::Null, jsonrpc, error: error.into() }.into()
@@ -177,14 +233,14 @@ impl Response {
 	}
 }
 
-impl From<Failure> for Response {
+impl<V> From<Failure> for Response<V> {
 	fn from(failure: Failure) -> Self {
 		Response::Single(Output::Failure(failure))
 	}
 }
 
-impl From<Success> for Response {
-	fn from(success: Success) -> Self {
+impl<V> From<Success<V>> for Response<V> {
+	fn from(success: Success<V>) -> Self {
 		Response::Single(Output::Success(success))
 	}
 }
@@ -201,7 +257,8 @@ impl SubscriptionId {
 
 #[cfg(test)]
 mod tests {
-	use super::{Error, Failure, Id, Output, Response, Success, Version};
+	use super::{Error, Failure, Id, Notification, Output, Response, Success, Version};
+	use crate::jsonrpc::Params;
 	use alloc::vec;
 	use serde_json::Value;
 
@@ -226,12 +283,23 @@ mod tests {
 
 	#[test]
 	fn failure_output_serialize() {
-		let fo = Output::Failure(Failure { jsonrpc: Version::V2, error: Error::parse_error(), id: Id::Num(1) });
+		let fo: Output = Output::Failure(Failure { jsonrpc: Version::V2, error: Error::parse_error(), id: Id::Num(1) });
 
 		let serialized = serde_json::to_string(&fo).unwrap();
 		assert_eq!(serialized, r#"{"jsonrpc":"2.0","error":{"code":-32700,"message":"Parse error"},"id":1}"#);
 	}
 
+	#[test]
+	fn output_with_custom_value_type_deserialize() {
+		let dso = r#"{"jsonrpc":"2.0","result":1,"id":1}"#;
+		let deserialized: Output<u64> = serde_json::from_str(dso).unwrap();
+		assert_eq!(deserialized.into_result(), Ok(1));
+
+		let dfo = r#"{"jsonrpc":"2.0","error":{"code":-32700,"message":"Parse error"},"id":1}"#;
+		let deserialized: Output<u64> = serde_json::from_str(dfo).unwrap();
+		assert_eq!(deserialized.into_result(), Err(Error::parse_error()));
+	}
+
 	#[test]
 	fn failure_output_deserialize() {
 		let dfo = r#"{"jsonrpc":"2.0","error":{"code":-32700,"message":"Parse error"},"id":1}"#;
@@ -295,4 +363,18 @@ mod tests {
 		assert!(deserialized1.is_err(), "Empty string is not valid JSON, so we should get an error.");
 		assert_eq!(deserialized2.unwrap(), Response::Batch(vec![]));
 	}
+
+	#[test]
+	fn should_parse_plain_notification() {
+		let dsr = r#"{"jsonrpc":"2.0","method":"chain_newHead","params":[1]}"#;
+		let deserialized: Response = serde_json::from_str(dsr).unwrap();
+		assert_eq!(
+			deserialized,
+			Response::Notification(Notification {
+				jsonrpc: Version::V2,
+				method: "chain_newHead".into(),
+				params: Params::Array(vec![1.into()]),
+			})
+		);
+	}
 }
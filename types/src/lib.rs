@@ -18,5 +18,30 @@ pub mod error;
 /// Shared client types.
 pub mod client;
 
+/// Batch requests with typed results.
+pub mod batch;
+
 /// Traits
 pub mod traits;
+
+/// Validation of responses.
+pub mod validation;
+
+/// Checks of the endpoints when building clients.
+pub mod preflight;
+
+/// Retries of failed method calls.
+pub mod retry;
+
+/// Framing of messages over byte streams.
+pub mod framing;
+
+/// Masking of sensitive params in logs.
+pub mod redact;
+
+/// Serialization of the messages exchanged over WebSocket connections.
+pub mod wire;
+
+/// Compression of large fields of params and results.
+#[cfg(feature = "compression")]
+pub mod codec;
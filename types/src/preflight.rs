@@ -0,0 +1,64 @@
+use crate::error::Error;
+use crate::jsonrpc::{JsonValue, Params};
+use crate::traits::Client;
+use alloc::{string::String, vec::Vec};
+
+/// Call issued by a client right after connecting, such that building the client fails on misconfigured
+/// endpoints instead of the first calls.
+///
+/// The preflight fails with the error of the call, or with [`Error::MissingMethods`] if the server lacks the
+/// required methods. Clients that don't connect ahead of the calls, such as HTTP clients, run it with
+/// [`Preflight::run`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preflight {
+	method: String,
+	params: Params,
+	required_methods: Vec<String>,
+}
+
+impl Preflight {
+	/// Call `method` with `params`, such as `system_health`, and only check that the call succeeds.
+	pub fn call(method: impl Into<String>, params: Params) -> Self {
+		Self { method: method.into(), params, required_methods: Vec::new() }
+	}
+
+	/// Call `rpc_methods` and check that the methods of the server include `required`.
+	pub fn rpc_methods<I, S>(required: I) -> Self
+	where
+		I: IntoIterator<Item = S>,
+		S: Into<String>,
+	{
+		Self::call("rpc_methods", Params::None).require_methods(required)
+	}
+
+	/// Check that the result of the call lists `methods`, the result is either an array of method names or an
+	/// object with a `methods` array, such as the result of `rpc_methods`.
+	pub fn require_methods<I, S>(mut self, methods: I) -> Self
+	where
+		I: IntoIterator<Item = S>,
+		S: Into<String>,
+	{
+		self.required_methods.extend(methods.into_iter().map(Into::into));
+		self
+	}
+
+	/// Issues the call with `client` and checks its result.
+	pub async fn run<C: Client + Sync + ?Sized>(&self, client: &C) -> Result<(), Error> {
+		let result: JsonValue = client.request(self.method.as_str(), self.params.clone()).await?;
+		if self.required_methods.is_empty() {
+			return Ok(());
+		}
+		let methods = match &result {
+			JsonValue::Object(object) => object.get("methods").and_then(JsonValue::as_array),
+			result => result.as_array(),
+		};
+		let methods: Vec<&str> = methods.into_iter().flatten().filter_map(JsonValue::as_str).collect();
+		let missing: Vec<String> =
+			self.required_methods.iter().filter(|method| !methods.contains(&method.as_str())).cloned().collect();
+		if missing.is_empty() {
+			Ok(())
+		} else {
+			Err(Error::MissingMethods(missing))
+		}
+	}
+}
@@ -0,0 +1,202 @@
+use crate::jsonrpc::{self, JsonValue};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::fmt;
+use serde::Serialize;
+
+/// Masking of sensitive params, such as private keys or passwords, before they are written to logs or audit
+/// records.
+pub trait Redact: Send + Sync + 'static {
+	/// Masks the sensitive values of the `params` of a call to `method` in place.
+	fn redact(&self, method: &str, params: &mut JsonValue);
+}
+
+impl<F: Fn(&str, &mut JsonValue) + Send + Sync + 'static> Redact for F {
+	fn redact(&self, method: &str, params: &mut JsonValue) {
+		self(method, params)
+	}
+}
+
+/// Masks the params at configured positions and the values of configured object keys.
+///
+/// ```
+/// use jsonrpsee_types::redact::{Redact, Redaction};
+///
+/// let redaction = Redaction::new().position("account_unlock", 1).key("password");
+/// let mut params = serde_json::json!(["alice", "hunter2", { "password": "hunter2", "ttl": 60 }]);
+/// redaction.redact("account_unlock", &mut params);
+/// assert_eq!(params, serde_json::json!(["alice", "[REDACTED]", { "password": "[REDACTED]", "ttl": 60 }]));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Redaction {
+	positions: BTreeMap<String, Vec<usize>>,
+	keys: BTreeSet<String>,
+	mask: Option<String>,
+}
+
+impl Redaction {
+	/// Masks nothing.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Masks the param at `index` of the calls to `method` with positional params.
+	pub fn position(mut self, method: impl Into<String>, index: usize) -> Self {
+		self.positions.entry(method.into()).or_default().push(index);
+		self
+	}
+
+	/// Masks the values of the object keys named `key` at any depth of the params of every method.
+	pub fn key(mut self, key: impl Into<String>) -> Self {
+		self.keys.insert(key.into());
+		self
+	}
+
+	/// Replaces the masked values by the string `mask`.
+	///
+	/// The default mask is `[REDACTED]`.
+	pub fn mask(mut self, mask: impl Into<String>) -> Self {
+		self.mask = Some(mask.into());
+		self
+	}
+
+	fn masked(&self) -> JsonValue {
+		JsonValue::String(self.mask.clone().unwrap_or_else(|| "[REDACTED]".into()))
+	}
+
+	fn redact_keys(&self, value: &mut JsonValue) {
+		match value {
+			JsonValue::Object(object) => {
+				for (key, value) in object.iter_mut() {
+					if self.keys.contains(key) {
+						*value = self.masked();
+					} else {
+						self.redact_keys(value);
+					}
+				}
+			}
+			JsonValue::Array(values) => values.iter_mut().for_each(|value| self.redact_keys(value)),
+			_ => (),
+		}
+	}
+}
+
+impl Redact for Redaction {
+	fn redact(&self, method: &str, params: &mut JsonValue) {
+		if let (Some(positions), JsonValue::Array(values)) = (self.positions.get(method), &mut *params) {
+			for index in positions {
+				if let Some(value) = values.get_mut(*index) {
+					*value = self.masked();
+				}
+			}
+		}
+		if !self.keys.is_empty() {
+			self.redact_keys(params);
+		}
+	}
+}
+
+/// Formats messages and params for logs, with the params masked by a [`Redact`] if one is set.
+///
+/// The messages are only serialized and masked when they are formatted, thus when their log level is enabled.
+#[derive(Clone, Default)]
+pub struct LogRedactor(Option<Arc<dyn Redact>>);
+
+impl LogRedactor {
+	/// Masks the params with `redact`.
+	pub fn new(redact: impl Redact) -> Self {
+		Self(Some(Arc::new(redact)))
+	}
+
+	/// Formats `message` as JSON, with the params of its calls and notifications masked, including those of
+	/// batches.
+	pub fn message<'a, T: Serialize>(&'a self, message: &'a T) -> Redacted<'a, T> {
+		Redacted { redact: self.0.as_deref(), method: None, value: message }
+	}
+
+	/// Formats the `params` of a call to `method` as JSON, masked.
+	pub fn params<'a, T: Serialize>(&'a self, method: &'a str, params: &'a T) -> Redacted<'a, T> {
+		Redacted { redact: self.0.as_deref(), method: Some(method), value: params }
+	}
+}
+
+impl fmt::Debug for LogRedactor {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_tuple("LogRedactor").field(&self.0.is_some()).finish()
+	}
+}
+
+/// Message or params formatted by a [`LogRedactor`].
+pub struct Redacted<'a, T> {
+	redact: Option<&'a dyn Redact>,
+	/// Method of the params, `None` if `value` is a message.
+	method: Option<&'a str>,
+	value: &'a T,
+}
+
+impl<T: Serialize> fmt::Display for Redacted<'_, T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		// NOTE: written instead of failing such that logging never panics.
+		let mut value = match jsonrpc::to_value(self.value) {
+			Ok(value) => value,
+			Err(err) => return write!(f, "<invalid JSON: {}>", err),
+		};
+		let redact = match self.redact {
+			Some(redact) => redact,
+			None => return fmt::Display::fmt(&value, f),
+		};
+		match self.method {
+			Some(method) => redact.redact(method, &mut value),
+			None => redact_message(redact, &mut value),
+		}
+		fmt::Display::fmt(&value, f)
+	}
+}
+
+/// Masks the params of the calls and notifications of `message`, a single message or a batch.
+fn redact_message(redact: &dyn Redact, message: &mut JsonValue) {
+	match message {
+		JsonValue::Array(batch) => batch.iter_mut().for_each(|message| redact_message(redact, message)),
+		JsonValue::Object(object) => {
+			let method = match object.get("method") {
+				Some(JsonValue::String(method)) => method.clone(),
+				_ => return,
+			};
+			if let Some(params) = object.get_mut("params") {
+				redact.redact(&method, params);
+			}
+		}
+		_ => (),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{LogRedactor, Redaction};
+	use crate::jsonrpc::{self, Params};
+	use serde_json::json;
+
+	#[test]
+	fn messages_are_redacted_when_formatted() {
+		let redactor = LogRedactor::new(Redaction::new().position("sign", 0).key("secret").mask("***"));
+		let batch = json!([
+			{ "jsonrpc": "2.0", "method": "sign", "params": ["0xkey", "payload"], "id": 1 },
+			{ "jsonrpc": "2.0", "method": "login", "params": { "user": "bob", "secret": "pw" } },
+			{ "jsonrpc": "2.0", "result": "0xkey", "id": 1 },
+		]);
+		assert_eq!(
+			redactor.message(&batch).to_string(),
+			jsonrpc::to_string(&json!([
+				{ "jsonrpc": "2.0", "method": "sign", "params": ["***", "payload"], "id": 1 },
+				{ "jsonrpc": "2.0", "method": "login", "params": { "user": "bob", "secret": "***" } },
+				{ "jsonrpc": "2.0", "result": "0xkey", "id": 1 },
+			]))
+			.unwrap()
+		);
+
+		let params = Params::Array(vec!["0xkey".into(), "payload".into()]);
+		assert_eq!(redactor.params("sign", &params).to_string(), r#"["***","payload"]"#);
+		assert_eq!(redactor.params("verify", &params).to_string(), r#"["0xkey","payload"]"#);
+		assert_eq!(LogRedactor::default().params("sign", &params).to_string(), r#"["0xkey","payload"]"#);
+	}
+}
@@ -0,0 +1,111 @@
+use crate::error::Error;
+use alloc::sync::Arc;
+use core::fmt;
+use core::time::Duration;
+
+type Classifier = dyn Fn(&Error) -> bool + Send + Sync;
+
+/// Re-issues the method calls that failed before the server answered them, such as on transport errors or
+/// timeouts, waiting with an exponential backoff between the attempts.
+///
+/// Calls answered with a JSON-RPC error, [`Error::Request`], are never retried: the server processed them.
+/// Other calls may have reached the server before failing, only retry methods that are safe to execute
+/// twice.
+#[derive(Clone)]
+pub struct RetryPolicy {
+	max_retries: usize,
+	backoff: Duration,
+	max_backoff: Duration,
+	retry_on: Arc<Classifier>,
+}
+
+impl RetryPolicy {
+	/// Retry at most `max_retries` times, waiting `backoff` before the first retry and doubling the delay after
+	/// every failed retry.
+	///
+	/// By default the errors classified as transient by [`RetryPolicy::is_transient`] are retried.
+	pub fn new(max_retries: usize, backoff: Duration) -> Self {
+		Self { max_retries, backoff, max_backoff: Duration::MAX, retry_on: Arc::new(Self::is_transient) }
+	}
+
+	/// Cap the delay between two attempts to `max`.
+	///
+	/// Uncapped by default.
+	pub fn max_backoff(mut self, max: Duration) -> Self {
+		self.max_backoff = max;
+		self
+	}
+
+	/// Only retry the calls whose error `retry_on` returns `true` for, instead of the transient errors.
+	///
+	/// `retry_on` isn't called with [`Error::Request`].
+	pub fn retry_on<F>(mut self, retry_on: F) -> Self
+	where
+		F: Fn(&Error) -> bool + Send + Sync + 'static,
+	{
+		self.retry_on = Arc::new(retry_on);
+		self
+	}
+
+	/// Returns the delay before the retry of a call that failed with `error` after `retries` retries, `None`
+	/// if the call must not be retried.
+	pub fn next_retry(&self, retries: usize, error: &Error) -> Option<Duration> {
+		if retries >= self.max_retries || matches!(error, Error::Request(_)) || !(self.retry_on)(error) {
+			return None;
+		}
+		let factor = 1u32.checked_shl(retries as u32).unwrap_or(u32::MAX);
+		Some(self.backoff.checked_mul(factor).unwrap_or(Duration::MAX).min(self.max_backoff))
+	}
+
+	/// Returns `true` for the errors of calls that may succeed if issued again: transport errors, timeouts and
+	/// connections lost while reconnecting.
+	pub fn is_transient(error: &Error) -> bool {
+		matches!(
+			error,
+			Error::TransportError(_) | Error::RequestTimeout(_) | Error::WsRequestTimeout(_) | Error::ConnectionLost(_)
+		)
+	}
+}
+
+impl fmt::Debug for RetryPolicy {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("RetryPolicy")
+			.field("max_retries", &self.max_retries)
+			.field("backoff", &self.backoff)
+			.field("max_backoff", &self.max_backoff)
+			.finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::RetryPolicy;
+	use crate::error::{CallInfo, Error};
+	use crate::jsonrpc;
+	use core::time::Duration;
+
+	#[test]
+	fn backoff_doubles_up_to_the_cap() {
+		let policy = RetryPolicy::new(4, Duration::from_millis(100)).max_backoff(Duration::from_millis(300));
+		let timeout = Error::RequestTimeout(CallInfo::new("foo", None));
+		let delays: Vec<_> = (0..5).map(|retries| policy.next_retry(retries, &timeout)).collect();
+		assert_eq!(
+			delays,
+			vec![
+				Some(Duration::from_millis(100)),
+				Some(Duration::from_millis(200)),
+				Some(Duration::from_millis(300)),
+				Some(Duration::from_millis(300)),
+				None
+			]
+		);
+	}
+
+	#[test]
+	fn json_rpc_errors_are_never_retried() {
+		let policy = RetryPolicy::new(3, Duration::from_millis(1)).retry_on(|_| true);
+		assert_eq!(policy.next_retry(0, &Error::Request(jsonrpc::Error::internal_error())), None);
+		assert!(policy.next_retry(0, &Error::InvalidRequestId).is_some());
+		assert_eq!(RetryPolicy::new(3, Duration::from_millis(1)).next_retry(0, &Error::InvalidRequestId), None);
+	}
+}
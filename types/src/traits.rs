@@ -1,8 +1,27 @@
-use crate::client::Subscription;
+use crate::client::{OverflowPolicy, Subscription};
 use crate::error::Error;
-use crate::jsonrpc::{DeserializeOwned, Params};
-use alloc::{boxed::Box, string::String, vec::Vec};
+use crate::jsonrpc::{self, DeserializeOwned, JsonValue, Params, ParseError, Serialize};
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
 use async_trait::async_trait;
+use core::fmt;
+use core::time::Duration;
+
+/// JSON value that results and notifications are deserialized into before they are handed over to the caller.
+///
+/// [`JsonValue`] is used by default, implement this trait to plug in an alternative representation
+/// such as a more compact or arena-backed value.
+///
+/// Only the clients are generic over this trait, the servers always parse params and results as [`JsonValue`].
+pub trait Value: DeserializeOwned + Serialize + fmt::Debug + Send + 'static {
+	/// Decode the value into a concrete type.
+	fn decode<T: DeserializeOwned>(self) -> Result<T, ParseError>;
+}
+
+impl Value for JsonValue {
+	fn decode<T: DeserializeOwned>(self) -> Result<T, ParseError> {
+		jsonrpc::from_value(self)
+	}
+}
 
 /// [JSON-RPC](https://www.jsonrpc.org/specification) client interface that can make requests and notifications.
 #[async_trait]
@@ -20,6 +39,17 @@ pub trait Client {
 		M: Into<String> + Send,
 		P: Into<Params> + Send;
 
+	/// Send a [method call request](https://www.jsonrpc.org/specification#request_object) that fails if
+	/// no response arrives within `timeout`, overriding the request timeout of the client.
+	///
+	/// The error returned on timeout depends on the client, [`Error::WsRequestTimeout`] for WebSocket
+	/// clients and [`Error::RequestTimeout`] for HTTP clients.
+	async fn request_with_timeout<T, M, P>(&self, method: M, params: P, timeout: Duration) -> Result<T, Error>
+	where
+		T: DeserializeOwned,
+		M: Into<String> + Send,
+		P: Into<Params> + Send;
+
 	/// Send a [batch request](https://www.jsonrpc.org/specification#batch).
 	///
 	/// The response to batch are returned in the same order as it was inserted in the batch.
@@ -31,11 +61,24 @@ pub trait Client {
 		T: DeserializeOwned + Default + Clone,
 		M: Into<String> + Send,
 		P: Into<Params> + Send;
+
+	/// Send a [batch request](https://www.jsonrpc.org/specification#batch) and return the result of every
+	/// request, in the same order as they were inserted in the batch, such that the batch can partially
+	/// succeed.
+	///
+	/// Returns `Error` if the batch itself fails or if a successful result can't be decoded into `T`.
+	async fn batch_request_results<T, M, P>(&self, batch: Vec<(M, P)>) -> Result<Vec<Result<T, jsonrpc::Error>>, Error>
+	where
+		T: DeserializeOwned,
+		M: Into<String> + Send,
+		P: Into<Params> + Send;
 }
 
 /// [JSON-RPC](https://www.jsonrpc.org/specification) client interface that can make requests, notifications and subscriptions.
+///
+/// The `V` param is the [`Value`] that notifications are deserialized into before being decoded into `Notif`.
 #[async_trait]
-pub trait SubscriptionClient: Client {
+pub trait SubscriptionClient<V: Value = JsonValue>: Client {
 	/// Send a subscription request to the server, technically not part of the [JSON-RPC specification](https://www.jsonrpc.org/specification)
 	///
 	/// The `subscribe_method` and `params` are used to ask for the subscription towards the
@@ -49,10 +92,108 @@ pub trait SubscriptionClient: Client {
 		subscribe_method: SM,
 		params: P,
 		unsubscribe_method: UM,
-	) -> Result<Subscription<Notif>, Error>
+	) -> Result<Subscription<Notif, V>, Error>
+	where
+		SM: Into<String> + Send,
+		UM: Into<String> + Send,
+		P: Into<Params> + Send,
+		Notif: DeserializeOwned;
+
+	/// Send a subscription request to the server like [`SubscriptionClient::subscribe`], handling notifications
+	/// that arrive while the buffer of the subscription is full according to `policy` instead of the default
+	/// policy of the client.
+	async fn subscribe_with_policy<SM, UM, P, Notif>(
+		&self,
+		subscribe_method: SM,
+		params: P,
+		unsubscribe_method: UM,
+		policy: OverflowPolicy,
+	) -> Result<Subscription<Notif, V>, Error>
 	where
 		SM: Into<String> + Send,
 		UM: Into<String> + Send,
 		P: Into<Params> + Send,
 		Notif: DeserializeOwned;
 }
+
+/// Shares a client between tasks without wrapping it, every clone of the [`Arc`] uses the same connection.
+#[async_trait]
+impl<C: Client + Send + Sync + ?Sized> Client for Arc<C> {
+	async fn notification<M, P>(&self, method: M, params: P) -> Result<(), Error>
+	where
+		M: Into<String> + Send,
+		P: Into<Params> + Send,
+	{
+		(**self).notification(method, params).await
+	}
+
+	async fn request<T, M, P>(&self, method: M, params: P) -> Result<T, Error>
+	where
+		T: DeserializeOwned,
+		M: Into<String> + Send,
+		P: Into<Params> + Send,
+	{
+		(**self).request(method, params).await
+	}
+
+	async fn request_with_timeout<T, M, P>(&self, method: M, params: P, timeout: Duration) -> Result<T, Error>
+	where
+		T: DeserializeOwned,
+		M: Into<String> + Send,
+		P: Into<Params> + Send,
+	{
+		(**self).request_with_timeout(method, params, timeout).await
+	}
+
+	async fn batch_request<T, M, P>(&self, batch: Vec<(M, P)>) -> Result<Vec<T>, Error>
+	where
+		T: DeserializeOwned + Default + Clone,
+		M: Into<String> + Send,
+		P: Into<Params> + Send,
+	{
+		(**self).batch_request(batch).await
+	}
+
+	async fn batch_request_results<T, M, P>(&self, batch: Vec<(M, P)>) -> Result<Vec<Result<T, jsonrpc::Error>>, Error>
+	where
+		T: DeserializeOwned,
+		M: Into<String> + Send,
+		P: Into<Params> + Send,
+	{
+		(**self).batch_request_results(batch).await
+	}
+}
+
+#[async_trait]
+impl<V: Value, C: SubscriptionClient<V> + Send + Sync + ?Sized> SubscriptionClient<V> for Arc<C> {
+	async fn subscribe<SM, UM, P, Notif>(
+		&self,
+		subscribe_method: SM,
+		params: P,
+		unsubscribe_method: UM,
+	) -> Result<Subscription<Notif, V>, Error>
+	where
+		SM: Into<String> + Send,
+		UM: Into<String> + Send,
+		P: Into<Params> + Send,
+		Notif: DeserializeOwned,
+	{
+		(**self).subscribe(subscribe_method, params, unsubscribe_method).await
+	}
+
+	async fn subscribe_with_policy<SM, UM, P, Notif>(
+		&self,
+		subscribe_method: SM,
+		params: P,
+		unsubscribe_method: UM,
+		policy: OverflowPolicy,
+	) -> Result<Subscription<Notif, V>, Error>
+	where
+		SM: Into<String> + Send,
+		UM: Into<String> + Send,
+		P: Into<Params> + Send,
+		Notif: DeserializeOwned,
+	{
+		(**self).subscribe_with_policy(subscribe_method, params, unsubscribe_method, policy).await
+	}
+}
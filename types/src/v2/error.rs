@@ -38,6 +38,8 @@ pub const INVALID_PARAMS_CODE: i32 = -32602;
 pub const INVALID_REQUEST_CODE: i32 = -32600;
 /// Method not found error code.
 pub const METHOD_NOT_FOUND_CODE: i32 = -32601;
+/// Method disabled error code, in the range reserved for implementation-defined server errors.
+pub const METHOD_DISABLED_CODE: i32 = -32001;
 
 /// Parse error message
 pub const PARSE_ERROR_MSG: &str = "Parse error";
@@ -49,3 +51,5 @@ pub const INVALID_PARAMS_MSG: &str = "Invalid params";
 pub const INVALID_REQUEST_MSG: &str = "Invalid request";
 /// Method not found error message.
 pub const METHOD_NOT_FOUND_MSG: &str = "Method not found";
+/// Method disabled error message.
+pub const METHOD_DISABLED_MSG: &str = "Method disabled";
@@ -0,0 +1,207 @@
+use crate::error::{Error, Mismatch};
+use crate::jsonrpc::{self, JsonValue};
+use crate::traits::Value;
+use alloc::{
+	boxed::Box,
+	collections::BTreeMap,
+	format,
+	string::{String, ToString as _},
+	sync::Arc,
+	vec::Vec,
+};
+use core::fmt;
+
+/// Field of a result that failed validation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidField {
+	/// Path of the field, where `$` is the result, for example `$.blocks[0].hash`.
+	pub path: String,
+	/// Expected and actual field.
+	pub mismatch: Mismatch<String>,
+}
+
+impl InvalidField {
+	/// Create a new invalid field at `path`.
+	pub fn new(path: impl Into<String>, expected: impl Into<String>, got: impl Into<String>) -> Self {
+		Self { path: path.into(), mismatch: Mismatch { expected: expected.into(), got: got.into() } }
+	}
+}
+
+impl fmt::Display for InvalidField {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}: {}", self.path, self.mismatch)
+	}
+}
+
+/// Expected shape of a JSON value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Schema {
+	/// Any value.
+	Any,
+	/// `null`.
+	Null,
+	/// A boolean.
+	Bool,
+	/// A number.
+	Number,
+	/// A string.
+	String,
+	/// An array whose items all match the schema.
+	Array(Box<Schema>),
+	/// An object with at least the given fields, additional fields are allowed.
+	Object(Vec<(String, Schema)>),
+	/// `null`, a missing field or a value matching the schema.
+	Optional(Box<Schema>),
+}
+
+impl Schema {
+	/// Check that `value` matches the schema, returns the first field that doesn't.
+	pub fn check(&self, value: &JsonValue) -> Result<(), InvalidField> {
+		self.check_at("$", Some(value))
+	}
+
+	fn check_at(&self, path: &str, value: Option<&JsonValue>) -> Result<(), InvalidField> {
+		let value = match (self, value) {
+			(Schema::Optional(_), None) | (Schema::Optional(_), Some(JsonValue::Null)) => return Ok(()),
+			(Schema::Optional(schema), value) => return schema.check_at(path, value),
+			(_, None) => return Err(InvalidField::new(path, self.to_string(), "missing")),
+			(_, Some(value)) => value,
+		};
+
+		match (self, value) {
+			(Schema::Any, _)
+			| (Schema::Null, JsonValue::Null)
+			| (Schema::Bool, JsonValue::Bool(_))
+			| (Schema::Number, JsonValue::Number(_))
+			| (Schema::String, JsonValue::String(_)) => Ok(()),
+			(Schema::Array(schema), JsonValue::Array(items)) => {
+				for (i, item) in items.iter().enumerate() {
+					schema.check_at(&format!("{}[{}]", path, i), Some(item))?;
+				}
+				Ok(())
+			}
+			(Schema::Object(fields), JsonValue::Object(map)) => {
+				for (name, schema) in fields {
+					schema.check_at(&format!("{}.{}", path, name), map.get(name))?;
+				}
+				Ok(())
+			}
+			_ => Err(InvalidField::new(path, self.to_string(), kind(value))),
+		}
+	}
+}
+
+impl fmt::Display for Schema {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Schema::Any => write!(f, "any"),
+			Schema::Null => write!(f, "null"),
+			Schema::Bool => write!(f, "boolean"),
+			Schema::Number => write!(f, "number"),
+			Schema::String => write!(f, "string"),
+			Schema::Array(_) => write!(f, "array"),
+			Schema::Object(_) => write!(f, "object"),
+			Schema::Optional(schema) => write!(f, "optional {}", schema),
+		}
+	}
+}
+
+/// Returns the JSON type of `value`.
+pub(crate) fn kind(value: &JsonValue) -> &'static str {
+	match value {
+		JsonValue::Null => "null",
+		JsonValue::Bool(_) => "boolean",
+		JsonValue::Number(_) => "number",
+		JsonValue::String(_) => "string",
+		JsonValue::Array(_) => "array",
+		JsonValue::Object(_) => "object",
+	}
+}
+
+type Invariant = dyn Fn(&str, &JsonValue) -> Result<(), InvalidField> + Send + Sync;
+
+/// Checks the results of method calls against per-method [`Schema`]s and an invariant callback before
+/// they are decoded, rejecting violations with [`Error::InvalidResponseField`].
+///
+/// Notifications are not validated.
+#[derive(Clone, Default)]
+pub struct ResponseValidator {
+	schemas: BTreeMap<String, Schema>,
+	invariant: Option<Arc<Invariant>>,
+}
+
+impl ResponseValidator {
+	/// Create a validator accepting all results.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Check the results of `method` against `schema`.
+	pub fn schema(mut self, method: impl Into<String>, schema: Schema) -> Self {
+		self.schemas.insert(method.into(), schema);
+		self
+	}
+
+	/// Check all results with `invariant`, called with the method name and the result once it matches the
+	/// schema of the method, if any.
+	pub fn invariant<F>(mut self, invariant: F) -> Self
+	where
+		F: Fn(&str, &JsonValue) -> Result<(), InvalidField> + Send + Sync + 'static,
+	{
+		self.invariant = Some(Arc::new(invariant));
+		self
+	}
+
+	/// Validate the result of a call to `method`.
+	///
+	/// Results that aren't [`JsonValue`]s are converted into one first.
+	pub fn validate<V: Value>(&self, method: &str, value: &V) -> Result<(), Error> {
+		let schema = self.schemas.get(method);
+		if schema.is_none() && self.invariant.is_none() {
+			return Ok(());
+		}
+		let value = jsonrpc::to_value(value).map_err(Error::ParseError)?;
+		if let Some(schema) = schema {
+			schema.check(&value).map_err(Error::InvalidResponseField)?;
+		}
+		if let Some(invariant) = self.invariant.as_ref() {
+			invariant(method, &value).map_err(Error::InvalidResponseField)?;
+		}
+		Ok(())
+	}
+}
+
+impl fmt::Debug for ResponseValidator {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("ResponseValidator")
+			.field("schemas", &self.schemas)
+			.field("invariant", &self.invariant.is_some())
+			.finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{InvalidField, Schema};
+	use crate::jsonrpc::JsonValue;
+
+	#[test]
+	fn schema_reports_path_of_first_mismatch() {
+		let schema = Schema::Object(vec![
+			("number".into(), Schema::Number),
+			("parent".into(), Schema::Optional(Box::new(Schema::String))),
+			("extrinsics".into(), Schema::Array(Box::new(Schema::String))),
+		]);
+
+		let ok: JsonValue = serde_json::from_str(r#"{"number":1,"extrinsics":["a","b"],"extra":null}"#).unwrap();
+		assert_eq!(schema.check(&ok), Ok(()));
+
+		let bad_item: JsonValue = serde_json::from_str(r#"{"number":1,"parent":"a","extrinsics":["a",2]}"#).unwrap();
+		assert_eq!(schema.check(&bad_item), Err(InvalidField::new("$.extrinsics[1]", "string", "number")));
+
+		let missing: JsonValue = serde_json::from_str(r#"{"extrinsics":[]}"#).unwrap();
+		assert_eq!(schema.check(&missing), Err(InvalidField::new("$.number", "number", "missing")));
+
+		assert_eq!(schema.check(&JsonValue::Null), Err(InvalidField::new("$", "object", "null")));
+	}
+}
@@ -0,0 +1,80 @@
+use crate::error::Error;
+use crate::jsonrpc::{self, JsonValue};
+use alloc::{format, vec::Vec};
+
+/// Serialization of the JSON-RPC messages exchanged over a WebSocket connection, such as CBOR for
+/// bandwidth-sensitive deployments.
+///
+/// Only JSON and the `Cbor` codec of the `cbor` feature are provided, other formats such as MessagePack
+/// can be plugged in by implementing this trait.
+///
+/// The client offers its codecs as subprotocols in the `Sec-WebSocket-Protocol` header of the handshake and the
+/// server accepts the first one it supports. Messages are exchanged as JSON text if the server accepts none.
+pub trait Codec: Send + Sync + 'static {
+	/// Subprotocol naming the codec during the handshake.
+	fn subprotocol(&self) -> &str;
+
+	/// Serialize `message` into a binary frame.
+	fn encode(&self, message: &JsonValue) -> Result<Vec<u8>, Error>;
+
+	/// Deserialize a frame serialized by [`Codec::encode`].
+	fn decode(&self, data: &[u8]) -> Result<JsonValue, Error>;
+}
+
+/// JSON text, named `json`, the default if no codec is negotiated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Json;
+
+impl Codec for Json {
+	fn subprotocol(&self) -> &str {
+		"json"
+	}
+
+	fn encode(&self, message: &JsonValue) -> Result<Vec<u8>, Error> {
+		jsonrpc::to_vec(message).map_err(|e| Error::Custom(format!("Invalid JSON: {}", e)))
+	}
+
+	fn decode(&self, data: &[u8]) -> Result<JsonValue, Error> {
+		jsonrpc::from_slice(data).map_err(|e| Error::Custom(format!("Invalid JSON: {}", e)))
+	}
+}
+
+/// [CBOR](https://www.rfc-editor.org/rfc/rfc8949), named `cbor`.
+#[cfg(feature = "cbor")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl Codec for Cbor {
+	fn subprotocol(&self) -> &str {
+		"cbor"
+	}
+
+	fn encode(&self, message: &JsonValue) -> Result<Vec<u8>, Error> {
+		serde_cbor::to_vec(message).map_err(|e| Error::Custom(format!("Invalid CBOR: {}", e)))
+	}
+
+	fn decode(&self, data: &[u8]) -> Result<JsonValue, Error> {
+		serde_cbor::from_slice(data).map_err(|e| Error::Custom(format!("Invalid CBOR: {}", e)))
+	}
+}
+
+#[cfg(all(test, feature = "cbor"))]
+mod tests {
+	use super::{Cbor, Codec, Json};
+	use crate::jsonrpc::JsonValue;
+
+	#[test]
+	fn codecs_round_trip() {
+		let message: JsonValue = serde_json::from_str(
+			r#"{"jsonrpc":"2.0","method":"say_hello","params":[1,-2.5,"three",null,{"four":[true]}],"id":7}"#,
+		)
+		.unwrap();
+		let json = Json.encode(&message).unwrap();
+		let cbor = Cbor.encode(&message).unwrap();
+		assert!(cbor.len() < json.len());
+		assert_eq!(Json.decode(&json).unwrap(), message);
+		assert_eq!(Cbor.decode(&cbor).unwrap(), message);
+		assert!(Cbor.decode(&json).is_err());
+	}
+}
@@ -0,0 +1,238 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Client address behind proxies, from the `Forwarded` (RFC 7239) and `X-Forwarded-For` headers.
+
+use crate::http::hyper_helpers;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Range of addresses, such as `10.0.0.0/8`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct IpRange {
+	addr: IpAddr,
+	prefix_len: u8,
+}
+
+impl IpRange {
+	fn contains(&self, addr: IpAddr) -> bool {
+		match (self.addr, addr) {
+			(IpAddr::V4(range), IpAddr::V4(addr)) => {
+				let mask = u32::MAX.checked_shl(32 - u32::from(self.prefix_len)).unwrap_or(0);
+				u32::from(range) & mask == u32::from(addr) & mask
+			}
+			(IpAddr::V6(range), IpAddr::V6(addr)) => {
+				let mask = u128::MAX.checked_shl(128 - u32::from(self.prefix_len)).unwrap_or(0);
+				u128::from(range) & mask == u128::from(addr) & mask
+			}
+			_ => false,
+		}
+	}
+}
+
+/// Proxies trusted to report the address of the clients whose requests they forward, such as load balancers.
+///
+/// The addresses reported by untrusted peers are ignored, otherwise any client could pick the address that is
+/// rate limited or logged by setting the headers itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrustedProxies {
+	ranges: Vec<IpRange>,
+}
+
+impl TrustedProxies {
+	/// Create an empty list, the address of the peer is always the address of the client.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Trust the proxies whose address starts with the first `prefix_len` bits of `addr`, for example
+	/// `10.0.0.0` and `8`. Use a `prefix_len` of 32 for IPv4 and 128 for IPv6 to trust a single address.
+	///
+	/// # Panics
+	///
+	/// Panics if `prefix_len` is longer than `addr`.
+	pub fn trust(mut self, addr: IpAddr, prefix_len: u8) -> Self {
+		let max = if addr.is_ipv4() { 32 } else { 128 };
+		assert!(prefix_len <= max, "prefix length of {} is longer than {} bits", addr, max);
+		self.ranges.push(IpRange { addr: canonical(addr), prefix_len });
+		self
+	}
+
+	/// Returns `true` if `addr` is a trusted proxy.
+	pub fn is_trusted(&self, addr: IpAddr) -> bool {
+		let addr = canonical(addr);
+		self.ranges.iter().any(|range| range.contains(addr))
+	}
+
+	/// Returns the address of the client whose request was received from `remote_addr` with `headers`.
+	///
+	/// The addresses of the `Forwarded` header, or of the `X-Forwarded-For` header if the request has no
+	/// `Forwarded` header, are walked from the closest proxy to the client. The first address that isn't a
+	/// trusted proxy is the address of the client. If an address is obfuscated or invalid, the address of the
+	/// proxy that reported it is returned instead.
+	pub fn client_ip(&self, remote_addr: IpAddr, headers: &hyper::HeaderMap) -> IpAddr {
+		let mut client = canonical(remote_addr);
+		if !self.is_trusted(client) {
+			return client;
+		}
+		let forwarded: Vec<_> = hyper_helpers::read_header_values(headers, "forwarded")
+			.filter_map(|value| value.to_str().ok())
+			.flat_map(|value| value.split(','))
+			.map(forwarded_for)
+			.collect();
+		let chain = if forwarded.is_empty() {
+			hyper_helpers::read_header_values(headers, "x-forwarded-for")
+				.filter_map(|value| value.to_str().ok())
+				.flat_map(|value| value.split(','))
+				.map(|node| parse_node(node.trim()))
+				.collect()
+		} else {
+			forwarded
+		};
+		for addr in chain.into_iter().rev() {
+			match addr {
+				Some(addr) => {
+					client = canonical(addr);
+					if !self.is_trusted(client) {
+						break;
+					}
+				}
+				None => break,
+			}
+		}
+		client
+	}
+}
+
+/// Returns the `for` parameter of an element of the `Forwarded` header, `None` if it is missing or
+/// not an address.
+fn forwarded_for(element: &str) -> Option<IpAddr> {
+	element.split(';').find_map(|pair| {
+		let mut pair = pair.splitn(2, '=');
+		let name = pair.next()?.trim();
+		let value = pair.next()?.trim();
+		if name.eq_ignore_ascii_case("for") {
+			parse_node(value.trim_matches('"'))
+		} else {
+			None
+		}
+	})
+}
+
+/// Parses an IPv4 or IPv6 address with an optional port, such as `192.0.2.60:4711` or
+/// `[2001:db8:cafe::17]:4711`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+	if let Some(rest) = node.strip_prefix('[') {
+		return rest.split(']').next()?.parse::<Ipv6Addr>().ok().map(IpAddr::V6);
+	}
+	if let Ok(addr) = node.parse::<IpAddr>() {
+		return Some(addr);
+	}
+	node.split(':').next()?.parse::<Ipv4Addr>().ok().map(IpAddr::V4)
+}
+
+/// Maps IPv4-mapped IPv6 addresses, such as peers of dual-stack sockets, to IPv4 addresses.
+fn canonical(addr: IpAddr) -> IpAddr {
+	match addr {
+		IpAddr::V6(v6) => match v6.segments() {
+			[0, 0, 0, 0, 0, 0xffff, hi, lo] => IpAddr::V4(Ipv4Addr::from((u32::from(hi) << 16) | u32::from(lo))),
+			_ => addr,
+		},
+		addr => addr,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse_node, TrustedProxies};
+	use hyper::header::{HeaderMap, HeaderValue};
+	use std::net::IpAddr;
+
+	fn ip(addr: &str) -> IpAddr {
+		addr.parse().unwrap()
+	}
+
+	fn header_map(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+		let mut headers = HeaderMap::new();
+		for (name, value) in pairs {
+			headers.append(*name, HeaderValue::from_static(value));
+		}
+		headers
+	}
+
+	#[test]
+	fn parse_node_works() {
+		assert_eq!(parse_node("192.0.2.60"), Some(ip("192.0.2.60")));
+		assert_eq!(parse_node("192.0.2.60:4711"), Some(ip("192.0.2.60")));
+		assert_eq!(parse_node("2001:db8::17"), Some(ip("2001:db8::17")));
+		assert_eq!(parse_node("[2001:db8::17]:4711"), Some(ip("2001:db8::17")));
+		assert_eq!(parse_node("unknown"), None);
+		assert_eq!(parse_node("_hidden"), None);
+	}
+
+	#[test]
+	fn trusted_ranges_work() {
+		let trusted = TrustedProxies::new().trust(ip("10.0.0.0"), 8).trust(ip("2001:db8::"), 32);
+		assert!(trusted.is_trusted(ip("10.1.2.3")));
+		assert!(trusted.is_trusted(ip("::ffff:10.1.2.3")));
+		assert!(trusted.is_trusted(ip("2001:db8:1::1")));
+		assert!(!trusted.is_trusted(ip("11.0.0.1")));
+		assert!(!trusted.is_trusted(ip("2001:db9::1")));
+		assert!(TrustedProxies::new().trust(ip("0.0.0.0"), 0).is_trusted(ip("203.0.113.1")));
+	}
+
+	#[test]
+	fn untrusted_peers_are_the_client() {
+		let trusted = TrustedProxies::new().trust(ip("10.0.0.0"), 8);
+		let headers = header_map(&[("x-forwarded-for", "198.51.100.1")]);
+		assert_eq!(trusted.client_ip(ip("203.0.113.7"), &headers), ip("203.0.113.7"));
+	}
+
+	#[test]
+	fn x_forwarded_for_is_walked_to_first_untrusted_address() {
+		let trusted = TrustedProxies::new().trust(ip("10.0.0.0"), 8);
+		// The client spoofs the first address, the load balancers append the ones they received from.
+		let headers = header_map(&[("x-forwarded-for", "1.2.3.4, 198.51.100.1"), ("x-forwarded-for", "10.0.0.2")]);
+		assert_eq!(trusted.client_ip(ip("10.0.0.1"), &headers), ip("198.51.100.1"));
+
+		let headers = header_map(&[("x-forwarded-for", "10.0.0.3, 10.0.0.2")]);
+		assert_eq!(trusted.client_ip(ip("10.0.0.1"), &headers), ip("10.0.0.3"));
+		assert_eq!(trusted.client_ip(ip("10.0.0.1"), &HeaderMap::new()), ip("10.0.0.1"));
+	}
+
+	#[test]
+	fn forwarded_takes_precedence() {
+		let trusted = TrustedProxies::new().trust(ip("10.0.0.0"), 8);
+		let headers = header_map(&[
+			("forwarded", r#"for=192.0.2.60;proto=http;by=203.0.113.43, For="[2001:db8:cafe::17]:4711""#),
+			("x-forwarded-for", "198.51.100.1"),
+		]);
+		assert_eq!(trusted.client_ip(ip("10.0.0.1"), &headers), ip("2001:db8:cafe::17"));
+
+		// Obfuscated addresses stop the walk at the proxy that reported them.
+		let headers = header_map(&[("forwarded", "for=198.51.100.1, for=_hidden, for=10.0.0.2")]);
+		assert_eq!(trusted.client_ip(ip("10.0.0.1"), &headers), ip("10.0.0.2"));
+	}
+}
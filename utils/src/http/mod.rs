@@ -28,6 +28,7 @@
 
 pub mod access_control;
 pub mod cors;
+pub mod forwarded;
 pub mod hosts;
 pub mod hyper_helpers;
 
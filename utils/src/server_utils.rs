@@ -1,11 +1,29 @@
 //! Shared helpers for JSON-RPC Servers.
 
-use jsonrpsee_types::v2::error::{INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG};
+use jsonrpsee_types::error::Error;
+use jsonrpsee_types::v2::error::{
+	INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, METHOD_DISABLED_CODE, METHOD_DISABLED_MSG, METHOD_NOT_FOUND_CODE,
+	METHOD_NOT_FOUND_MSG,
+};
+use jsonrpsee_types::v2::traits::RpcMethod;
 use jsonrpsee_types::v2::{JsonRpcError, JsonRpcErrorParams, JsonRpcResponse, RpcParams, TwoPointZero};
-use rustc_hash::FxHashMap;
-use serde::Serialize;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
 use serde_json::value::RawValue;
-use tokio::sync::mpsc;
+use serde_json::{Map, Value};
+use smallvec::SmallVec;
+use std::borrow::Cow;
+use std::collections::{hash_map::Entry, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+
+/// Responses up to this size in bytes are serialized into a buffer on the stack.
+const SMALL_RESPONSE_LEN: usize = 128;
+
+/// Named param carrying the idempotency key of a call, see [`Idempotency`].
+pub const IDEMPOTENCY_KEY_PARAM: &str = "idempotencyKey";
 
 /// Connection ID.
 pub type ConnectionId = usize;
@@ -17,10 +35,150 @@ pub type RpcId<'a> = Option<&'a RawValue>;
 pub type Method = Box<dyn Send + Sync + Fn(RpcId, RpcParams, RpcSender, ConnectionId) -> anyhow::Result<()>>;
 /// Methods registered in the Server.
 pub type Methods = FxHashMap<&'static str, Method>;
+/// Method registered for a name pattern in the server, called with the name of the called method.
+pub type WildcardMethod =
+	Box<dyn Send + Sync + Fn(&str, RpcId, RpcParams, RpcSender, ConnectionId) -> anyhow::Result<()>>;
+
+/// Routes the calls received by a server to their implementation, replacing the [`Methods`] of the
+/// registered modules, for example to match method names against patterns or per-tenant tables.
+pub trait MethodDispatcher: Send + Sync + 'static {
+	/// Handle a call to `method` received on connection `conn_id`, the response must be sent to `tx`.
+	///
+	/// Returning an error closes the connection of WebSocket servers and is logged by HTTP servers.
+	fn dispatch(
+		&self,
+		method: &str,
+		id: RpcId,
+		params: RpcParams,
+		tx: RpcSender,
+		conn_id: ConnectionId,
+	) -> anyhow::Result<()>;
+
+	/// Returns `true` if the calls to `method` are routed to an implementation, the calls to the other methods
+	/// are served by the [`WildcardMethods`] matching them.
+	///
+	/// Returns `true` by default.
+	fn contains(&self, _method: &str) -> bool {
+		true
+	}
+}
+
+/// The built-in router, answering calls to unknown methods with [`METHOD_NOT_FOUND_CODE`].
+impl MethodDispatcher for Methods {
+	fn dispatch(
+		&self,
+		method: &str,
+		id: RpcId,
+		params: RpcParams,
+		tx: RpcSender,
+		conn_id: ConnectionId,
+	) -> anyhow::Result<()> {
+		match self.get(method) {
+			Some(method) => method(id, params, tx, conn_id),
+			None => {
+				send_error(id, tx, METHOD_NOT_FOUND_CODE, METHOD_NOT_FOUND_MSG);
+				Ok(())
+			}
+		}
+	}
+
+	fn contains(&self, method: &str) -> bool {
+		self.contains_key(method)
+	}
+}
+
+/// Methods registered for name patterns such as `debug_*`, serving the calls to the methods whose names start
+/// with the text before the `*` and that aren't registered by name, for example to forward a namespace to a
+/// plugin.
+///
+/// The longest matching pattern is used.
+#[derive(Default)]
+pub struct WildcardMethods {
+	/// Methods by the prefix of their pattern.
+	methods: Vec<(&'static str, WildcardMethod)>,
+}
+
+impl WildcardMethods {
+	/// Register `method` for `pattern`, which must end with its single `*`.
+	pub fn insert(&mut self, pattern: &'static str, method: WildcardMethod) -> Result<(), Error> {
+		let prefix = match pattern.strip_suffix('*') {
+			Some(prefix) if !prefix.contains('*') => prefix,
+			_ => return Err(Error::InvalidMethodPattern(pattern.into())),
+		};
+		if self.methods.iter().any(|(registered, _)| *registered == prefix) {
+			return Err(Error::MethodAlreadyRegistered(pattern.into()));
+		}
+		self.methods.push((prefix, method));
+		Ok(())
+	}
+
+	/// Register the methods of `other`, fails without registering any if a pattern is already registered.
+	pub fn merge(&mut self, other: WildcardMethods) -> Result<(), Error> {
+		for (prefix, _) in &other.methods {
+			if self.methods.iter().any(|(registered, _)| registered == prefix) {
+				return Err(Error::MethodAlreadyRegistered(format!("{}*", prefix)));
+			}
+		}
+		self.methods.extend(other.methods);
+		Ok(())
+	}
+
+	/// Returns `true` if no method is registered.
+	pub fn is_empty(&self) -> bool {
+		self.methods.is_empty()
+	}
+
+	/// Serve the calls to the methods that `dispatcher` doesn't contain with these methods.
+	pub fn wrap<D: MethodDispatcher>(self, dispatcher: D) -> WithWildcards<D> {
+		WithWildcards { dispatcher, wildcards: self }
+	}
+
+	fn get(&self, method: &str) -> Option<&WildcardMethod> {
+		self.methods
+			.iter()
+			.filter(|(prefix, _)| method.starts_with(prefix))
+			.max_by_key(|(prefix, _)| prefix.len())
+			.map(|(_, method)| method)
+	}
+}
+
+impl fmt::Debug for WildcardMethods {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_set().entries(self.methods.iter().map(|(prefix, _)| format!("{}*", prefix))).finish()
+	}
+}
+
+/// Methods registered by name and for name patterns, see [`WildcardMethods::wrap`].
+pub struct WithWildcards<D> {
+	dispatcher: D,
+	wildcards: WildcardMethods,
+}
+
+impl<D: MethodDispatcher> MethodDispatcher for WithWildcards<D> {
+	fn dispatch(
+		&self,
+		method: &str,
+		id: RpcId,
+		params: RpcParams,
+		tx: RpcSender,
+		conn_id: ConnectionId,
+	) -> anyhow::Result<()> {
+		if !self.dispatcher.contains(method) {
+			if let Some(wildcard) = self.wildcards.get(method) {
+				return wildcard(method, id, params, tx, conn_id);
+			}
+		}
+		self.dispatcher.dispatch(method, id, params, tx, conn_id)
+	}
+
+	fn contains(&self, method: &str) -> bool {
+		self.dispatcher.contains(method) || self.wildcards.get(method).is_some()
+	}
+}
 
 /// Helper for sending JSON-RPC responses to the client
 pub fn send_response(id: RpcId, tx: RpcSender, result: impl Serialize) {
-	let json = match serde_json::to_string(&JsonRpcResponse { jsonrpc: TwoPointZero, id, result }) {
+	let json = match to_json_string(&JsonRpcResponse { jsonrpc: TwoPointZero, id, result }) {
 		Ok(json) => json,
 		Err(err) => {
 			log::error!("Error serializing response: {:?}", err);
@@ -36,7 +194,7 @@ pub fn send_response(id: RpcId, tx: RpcSender, result: impl Serialize) {
 
 /// Helper for sending JSON-RPC errors to the client
 pub fn send_error(id: RpcId, tx: RpcSender, code: i32, message: &str) {
-	let json = match serde_json::to_string(&JsonRpcError {
+	let json = match to_json_string(&JsonRpcError {
 		jsonrpc: TwoPointZero,
 		error: JsonRpcErrorParams { code, message },
 		id,
@@ -53,3 +211,1019 @@ pub fn send_error(id: RpcId, tx: RpcSender, code: i32, message: &str) {
 		log::error!("Error sending response to the client: {:?}", err)
 	}
 }
+
+/// Serialize `value` to a JSON string.
+///
+/// Small values, such as responses carrying booleans, numbers or short strings, are serialized on the stack,
+/// the only heap allocation being the exactly sized string handed to the connection.
+/// Handle to enable and disable registered methods at runtime, for example for maintenance or staged rollouts.
+///
+/// Calls to disabled methods are answered with a configurable error instead of being executed. All methods are
+/// enabled initially.
+#[derive(Clone, Debug)]
+pub struct MethodFlags {
+	disabled: Arc<RwLock<FxHashSet<String>>>,
+	code: i32,
+	message: Arc<str>,
+}
+
+impl Default for MethodFlags {
+	fn default() -> Self {
+		Self { disabled: Default::default(), code: METHOD_DISABLED_CODE, message: METHOD_DISABLED_MSG.into() }
+	}
+}
+
+impl MethodFlags {
+	/// Create flags answering calls to disabled methods with [`METHOD_DISABLED_CODE`].
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Answer calls to disabled methods with `code` and `message`.
+	pub fn with_error(mut self, code: i32, message: impl Into<String>) -> Self {
+		self.code = code;
+		self.message = message.into().into();
+		self
+	}
+
+	/// Disable `method`, returns `false` if it was already disabled.
+	pub fn disable(&self, method: impl Into<String>) -> bool {
+		self.disabled.write().expect("The lock is never held across a panic; qed").insert(method.into())
+	}
+
+	/// Enable `method`, returns `false` if it was already enabled.
+	pub fn enable(&self, method: &str) -> bool {
+		self.disabled.write().expect("The lock is never held across a panic; qed").remove(method)
+	}
+
+	/// Returns `true` if `method` is enabled.
+	pub fn is_enabled(&self, method: &str) -> bool {
+		!self.disabled.read().expect("The lock is never held across a panic; qed").contains(method)
+	}
+
+	/// Returns the disabled methods, in no particular order.
+	pub fn disabled(&self) -> Vec<String> {
+		self.disabled.read().expect("The lock is never held across a panic; qed").iter().cloned().collect()
+	}
+
+	/// Wrap `methods` such that they answer with the configured error while they are disabled.
+	pub fn wrap(&self, methods: Methods) -> Methods {
+		methods
+			.into_iter()
+			.map(|(name, method)| {
+				let flags = self.clone();
+				let method: Method = Box::new(move |id, params, tx, conn| {
+					if flags.is_enabled(name) {
+						method(id, params, tx, conn)
+					} else {
+						send_error(id, tx, flags.code, &flags.message);
+						Ok(())
+					}
+				});
+				(name, method)
+			})
+			.collect()
+	}
+
+	/// Admin method taking the name of a method as single parameter and enabling it, see
+	/// [`MethodFlags::enable`].
+	pub fn enable_method(&self) -> impl RpcMethod<bool> {
+		let flags = self.clone();
+		move |params: RpcParams| {
+			let method: String = params.one()?;
+			Ok(flags.enable(&method))
+		}
+	}
+
+	/// Admin method taking the name of a method as single parameter and disabling it, see
+	/// [`MethodFlags::disable`].
+	///
+	/// **Note**: the admin methods can be disabled as well, which can't be undone over RPC.
+	pub fn disable_method(&self) -> impl RpcMethod<bool> {
+		let flags = self.clone();
+		move |params: RpcParams| {
+			let method: String = params.one()?;
+			Ok(flags.disable(method))
+		}
+	}
+}
+
+/// Replays the response of a call to the same method with the same idempotency key within a TTL instead of
+/// executing the method again, such that clients can safely retry write-type methods over flaky networks.
+///
+/// The key is the [`IDEMPOTENCY_KEY_PARAM`] member of the named params of a call, a string which is removed
+/// before the method parses the params. Calls without a key and calls to the methods that weren't selected are
+/// executed as usual. Only successful responses are replayed, and the keys are shared by all connections.
+///
+/// **Note**: calls with the same key that are executed concurrently are all executed, the responses of later
+/// calls are replayed from the first one to complete. Subscriptions can't be deduplicated and must not be
+/// selected, nor can the methods responding after they return, see [`ConfigIssue::DeferredResponse`].
+#[derive(Clone, Debug)]
+pub struct Idempotency {
+	methods: Arc<FxHashSet<String>>,
+	ttl: Duration,
+	cache: Arc<Mutex<ResponseCache>>,
+}
+
+/// Successful responses by method and idempotency key.
+#[derive(Debug, Default)]
+struct ResponseCache {
+	results: FxHashMap<(String, String), (Instant, Box<RawValue>)>,
+	/// Keys in insertion order, which is also their expiration order.
+	expirations: VecDeque<(Instant, (String, String))>,
+}
+
+impl ResponseCache {
+	fn remove_expired(&mut self, now: Instant) {
+		while let Some((expires, _)) = self.expirations.front() {
+			if *expires > now {
+				break;
+			}
+			let (expires, key) = self.expirations.pop_front().expect("Checked above; qed");
+			// NOTE: the key may have been cached again by a concurrent call.
+			if let Entry::Occupied(entry) = self.results.entry(key) {
+				if entry.get().0 == expires {
+					entry.remove();
+				}
+			}
+		}
+	}
+}
+
+#[derive(Deserialize)]
+struct SuccessResponse<'a> {
+	#[serde(borrow)]
+	result: &'a RawValue,
+}
+
+impl Idempotency {
+	/// Deduplicate the calls to `methods` carrying the same key within `ttl`.
+	pub fn new(methods: impl IntoIterator<Item = impl Into<String>>, ttl: Duration) -> Self {
+		Self {
+			methods: Arc::new(methods.into_iter().map(Into::into).collect()),
+			ttl,
+			cache: Arc::new(Mutex::new(ResponseCache::default())),
+		}
+	}
+
+	/// Returns how long the responses are replayed.
+	pub fn ttl(&self) -> Duration {
+		self.ttl
+	}
+
+	/// Returns the number of cached responses, including the expired ones that weren't evicted yet.
+	pub fn len(&self) -> usize {
+		self.cache.lock().expect("The lock is never held across a panic; qed").results.len()
+	}
+
+	/// Returns `true` if no responses are cached.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns the deduplicated methods, in no particular order.
+	pub fn methods(&self) -> impl Iterator<Item = &str> {
+		self.methods.iter().map(String::as_str)
+	}
+
+	/// Wrap the selected `methods` such that their responses are replayed to the calls with the same key.
+	pub fn wrap(&self, methods: Methods) -> Methods {
+		methods
+			.into_iter()
+			.map(|(name, method)| {
+				if !self.methods.contains(name) {
+					return (name, method);
+				}
+				let idempotency = self.clone();
+				let method: Method =
+					Box::new(move |id, params, tx, conn| idempotency.call(name, &method, id, params, tx, conn));
+				(name, method)
+			})
+			.collect()
+	}
+
+	fn call(
+		&self,
+		name: &str,
+		method: &Method,
+		id: RpcId,
+		params: RpcParams,
+		tx: RpcSender,
+		conn: ConnectionId,
+	) -> anyhow::Result<()> {
+		let mut map: Map<String, Value> = match params.parse() {
+			Ok(map) => map,
+			Err(_) => return method(id, params, tx, conn),
+		};
+		let key = match map.remove(IDEMPOTENCY_KEY_PARAM) {
+			Some(Value::String(key)) => (name.to_owned(), key),
+			_ => return method(id, params, tx, conn),
+		};
+
+		let now = Instant::now();
+		let cached = {
+			let mut cache = self.cache.lock().expect("The lock is never held across a panic; qed");
+			cache.remove_expired(now);
+			cache.results.get(&key).map(|(_, result)| result.clone())
+		};
+		if let Some(result) = cached {
+			log::debug!("Replaying the response to {} with idempotency key {:?}", name, key.1);
+			send_response(id, tx, &*result);
+			return Ok(());
+		}
+
+		let params = serde_json::to_string(&map)?;
+		let (response_tx, mut response_rx) = mpsc::unbounded_channel();
+		let res = method(id, RpcParams::new(Some(&params)), &response_tx, conn);
+		while let Ok(response) = response_rx.try_recv() {
+			if let Ok(success) = serde_json::from_str::<SuccessResponse>(&response) {
+				let expires = now + self.ttl;
+				let mut cache = self.cache.lock().expect("The lock is never held across a panic; qed");
+				cache.results.insert(key.clone(), (expires, success.result.to_owned()));
+				cache.expirations.push_back((expires, key.clone()));
+			}
+			if let Err(err) = tx.send(response) {
+				log::error!("Error sending response to the client: {:?}", err)
+			}
+		}
+		res
+	}
+}
+
+type Transform = dyn Fn(Value) -> Value + Send + Sync;
+
+/// Rewrites the params of the calls to selected methods before they are dispatched and their results before
+/// they are sent, such that compatibility shims for legacy clients, like unit conversions or renamed fields,
+/// don't pollute the handlers.
+///
+/// Params are passed as [`Value::Null`] if the call has none, and returning [`Value::Null`] removes them. Error
+/// responses are sent unchanged. The transformers of a method are applied in the order they were added.
+///
+/// **Note**: the results of subscriptions can't be transformed, only their params, adding a result transformer
+/// to a subscription breaks it. Methods responding after they return can't be transformed at all, see
+/// [`ConfigIssue::DeferredResponse`].
+#[derive(Clone, Default)]
+pub struct Transformers {
+	methods: FxHashMap<String, MethodTransformers>,
+}
+
+#[derive(Clone, Default)]
+struct MethodTransformers {
+	params: Vec<Arc<Transform>>,
+	results: Vec<Arc<Transform>>,
+}
+
+impl Transformers {
+	/// Create transformers leaving all calls unchanged.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Rewrite the params of the calls to `method` with `transform`.
+	pub fn params<F>(mut self, method: impl Into<String>, transform: F) -> Self
+	where
+		F: Fn(Value) -> Value + Send + Sync + 'static,
+	{
+		self.methods.entry(method.into()).or_default().params.push(Arc::new(transform));
+		self
+	}
+
+	/// Rewrite the results of the calls to `method` with `transform`.
+	pub fn result<F>(mut self, method: impl Into<String>, transform: F) -> Self
+	where
+		F: Fn(Value) -> Value + Send + Sync + 'static,
+	{
+		self.methods.entry(method.into()).or_default().results.push(Arc::new(transform));
+		self
+	}
+
+	/// Decompress the designated params and compress the designated results of `fields`.
+	///
+	/// Params that fail to decompress are passed to the method unchanged.
+	#[cfg(feature = "compression")]
+	pub fn compressed_fields(mut self, fields: &jsonrpsee_types::codec::CompressedFields) -> Self {
+		for method in fields.methods() {
+			let (name, params_fields) = (method.to_owned(), fields.clone());
+			self = self.params(method, move |mut params| {
+				if let Err(err) = params_fields.decompress_params(&name, &mut params) {
+					log::debug!("Failed to decompress the params of {}: {:?}", name, err);
+				}
+				params
+			});
+			let (name, result_fields) = (method.to_owned(), fields.clone());
+			self = self.result(method, move |mut result| {
+				result_fields.compress_result(&name, &mut result);
+				result
+			});
+		}
+		self
+	}
+
+	/// Returns `true` if no method is transformed.
+	pub fn is_empty(&self) -> bool {
+		self.methods.is_empty()
+	}
+
+	/// Returns the transformed methods, in no particular order.
+	pub fn methods(&self) -> impl Iterator<Item = &str> {
+		self.methods.keys().map(String::as_str)
+	}
+
+	/// Wrap the transformed `methods` such that their params and results are rewritten.
+	pub fn wrap(&self, methods: Methods) -> Methods {
+		methods
+			.into_iter()
+			.map(|(name, method)| {
+				let transformers = match self.methods.get(name) {
+					Some(transformers) => transformers.clone(),
+					None => return (name, method),
+				};
+				let method: Method =
+					Box::new(move |id, params, tx, conn| transformers.call(&method, id, params, tx, conn));
+				(name, method)
+			})
+			.collect()
+	}
+}
+
+impl fmt::Debug for Transformers {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_set().entries(self.methods.keys()).finish()
+	}
+}
+
+impl MethodTransformers {
+	fn call(
+		&self,
+		method: &Method,
+		id: RpcId,
+		params: RpcParams,
+		tx: RpcSender,
+		conn: ConnectionId,
+	) -> anyhow::Result<()> {
+		let transformed;
+		let params = if self.params.is_empty() {
+			params
+		} else {
+			let mut value = params.parse().unwrap_or(Value::Null);
+			for transform in &self.params {
+				value = transform(value);
+			}
+			transformed = if value.is_null() { None } else { Some(serde_json::to_string(&value)?) };
+			RpcParams::new(transformed.as_deref())
+		};
+		if self.results.is_empty() {
+			return method(id, params, tx, conn);
+		}
+
+		let (response_tx, mut response_rx) = mpsc::unbounded_channel();
+		let res = method(id, params, &response_tx, conn);
+		while let Ok(response) = response_rx.try_recv() {
+			let result = serde_json::from_str::<SuccessResponse>(&response)
+				.and_then(|success| serde_json::from_str::<Value>(success.result.get()));
+			match result {
+				Ok(mut result) => {
+					for transform in &self.results {
+						result = transform(result);
+					}
+					send_response(id, tx, result);
+				}
+				Err(_) => {
+					if let Err(err) = tx.send(response) {
+						log::error!("Error sending response to the client: {:?}", err)
+					}
+				}
+			}
+		}
+		res
+	}
+}
+
+/// How the names of the called methods are matched against the names of the registered methods, to tolerate
+/// clients from ecosystems with different naming conventions.
+///
+/// The exact name is always tried first, thus methods whose names only differ by case or separators remain
+/// callable by their exact names, but are not matched otherwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MethodNormalization {
+	/// Match the names exactly.
+	#[default]
+	Exact,
+	/// Ignore the case of the names, `Say_Hello` matches `say_hello`.
+	CaseInsensitive,
+	/// Ignore the case of the names and the `_` and `-` separators, `sayHello` matches `say_hello`.
+	SeparatorInsensitive,
+}
+
+impl MethodNormalization {
+	/// Returns the normalized form of `method`, two names match if their normalized forms are equal.
+	pub fn normalize<'a>(&self, method: &'a str) -> Cow<'a, str> {
+		match self {
+			Self::Exact => Cow::Borrowed(method),
+			Self::CaseInsensitive => Cow::Owned(method.to_lowercase()),
+			Self::SeparatorInsensitive => {
+				Cow::Owned(method.chars().filter(|c| *c != '_' && *c != '-').flat_map(char::to_lowercase).collect())
+			}
+		}
+	}
+
+	/// Wrap `methods` such that they are called by the names matching theirs.
+	pub fn wrap(self, methods: Methods) -> NormalizedMethods {
+		let mut aliases = FxHashMap::default();
+		let mut ambiguous = FxHashSet::default();
+		for name in methods.keys() {
+			let normalized = self.normalize(name).into_owned();
+			match aliases.entry(normalized) {
+				Entry::Vacant(entry) => {
+					entry.insert(*name);
+				}
+				Entry::Occupied(entry) => {
+					log::warn!("Methods {} and {} have the same normalized name", entry.get(), name);
+					ambiguous.insert(entry.key().clone());
+				}
+			}
+		}
+		for normalized in ambiguous {
+			aliases.remove(&normalized);
+		}
+		NormalizedMethods { methods, aliases, normalization: self }
+	}
+}
+
+/// Methods called by the names matching theirs, see [`MethodNormalization::wrap`].
+pub struct NormalizedMethods {
+	methods: Methods,
+	/// Registered names by normalized name.
+	aliases: FxHashMap<String, &'static str>,
+	normalization: MethodNormalization,
+}
+
+impl MethodDispatcher for NormalizedMethods {
+	fn dispatch(
+		&self,
+		method: &str,
+		id: RpcId,
+		params: RpcParams,
+		tx: RpcSender,
+		conn_id: ConnectionId,
+	) -> anyhow::Result<()> {
+		let name = match self.methods.contains_key(method) {
+			true => method,
+			false => self.aliases.get(&*self.normalization.normalize(method)).copied().unwrap_or(method),
+		};
+		self.methods.dispatch(name, id, params, tx, conn_id)
+	}
+
+	fn contains(&self, method: &str) -> bool {
+		self.methods.contains_key(method) || self.aliases.contains_key(&*self.normalization.normalize(method))
+	}
+}
+
+/// Round-robin scheduling of the calls of the connections of a server, such that a connection submitting many
+/// calls at once, such as huge batches, doesn't starve the calls of the other connections.
+///
+/// At most `max_concurrent` calls are dispatched at the same time. Once all slots are taken, the calls wait for
+/// their turn and the freed slots are handed to the waiting connections in turn, a connection getting as many
+/// consecutive turns as its weight. The weights default to `1` and are adjusted at runtime with
+/// [`FairScheduler::set_weight`], for example to favor trusted clients.
+#[derive(Clone, Debug)]
+pub struct FairScheduler {
+	inner: Arc<Mutex<SchedulerState>>,
+}
+
+#[derive(Debug)]
+struct SchedulerState {
+	max_concurrent: usize,
+	/// Slots not held by a [`Turn`].
+	free: usize,
+	default_weight: u32,
+	weights: FxHashMap<ConnectionId, u32>,
+	/// Calls waiting for their turn, by connection.
+	waiting: FxHashMap<ConnectionId, VecDeque<oneshot::Sender<Turn>>>,
+	/// Connections with waiting calls, the front one is served next.
+	ring: VecDeque<ConnectionId>,
+	/// Consecutive turns left to the front connection of the ring.
+	credit: u32,
+}
+
+impl SchedulerState {
+	fn weight(&self, conn_id: ConnectionId) -> u32 {
+		self.weights.get(&conn_id).copied().unwrap_or(self.default_weight)
+	}
+
+	/// Pops the next waiting call in round-robin order.
+	fn next_waiting(&mut self) -> Option<oneshot::Sender<Turn>> {
+		let conn_id = *self.ring.front()?;
+		let queue = self.waiting.get_mut(&conn_id).expect("Connections in the ring have waiting calls; qed");
+		let waiting = queue.pop_front().expect("Connections in the ring have waiting calls; qed");
+		self.credit = self.credit.saturating_sub(1);
+		if queue.is_empty() {
+			self.waiting.remove(&conn_id);
+			self.ring.pop_front();
+			self.credit = self.ring.front().map_or(0, |next| self.weight(*next));
+		} else if self.credit == 0 {
+			self.ring.rotate_left(1);
+			self.credit = self.ring.front().map_or(0, |next| self.weight(*next));
+		}
+		Some(waiting)
+	}
+}
+
+impl FairScheduler {
+	/// Create a scheduler dispatching at most `max_concurrent` calls at the same time.
+	///
+	/// # Panics
+	///
+	/// Panics if `max_concurrent` is `0`.
+	pub fn new(max_concurrent: usize) -> Self {
+		assert!(max_concurrent > 0, "max_concurrent has to be greater than 0");
+		let state = SchedulerState {
+			max_concurrent,
+			free: max_concurrent,
+			default_weight: 1,
+			weights: FxHashMap::default(),
+			waiting: FxHashMap::default(),
+			ring: VecDeque::new(),
+			credit: 0,
+		};
+		Self { inner: Arc::new(Mutex::new(state)) }
+	}
+
+	/// Give `weight` consecutive turns to the connections without a weight set by [`FairScheduler::set_weight`].
+	///
+	/// Default is `1`, weights of `0` are treated as `1`.
+	pub fn with_default_weight(self, weight: u32) -> Self {
+		self.lock().default_weight = weight.max(1);
+		self
+	}
+
+	/// Returns the max number of calls dispatched at the same time.
+	pub fn max_concurrent(&self) -> usize {
+		self.lock().max_concurrent
+	}
+
+	/// Returns the weight of the connections without a weight set by [`FairScheduler::set_weight`].
+	pub fn default_weight(&self) -> u32 {
+		self.lock().default_weight
+	}
+
+	/// Give `weight` consecutive turns to the connection `conn_id`, weights of `0` are treated as `1`.
+	pub fn set_weight(&self, conn_id: ConnectionId, weight: u32) {
+		self.lock().weights.insert(conn_id, weight.max(1));
+	}
+
+	/// Forget the weight of the connection `conn_id`, once it's closed.
+	pub fn remove(&self, conn_id: ConnectionId) {
+		self.lock().weights.remove(&conn_id);
+	}
+
+	/// Wait for the turn of a call of the connection `conn_id`, the slot is held until the returned [`Turn`] is
+	/// dropped.
+	pub async fn acquire(&self, conn_id: ConnectionId) -> Turn {
+		let rx = {
+			let mut state = self.lock();
+			if state.free > 0 && state.ring.is_empty() {
+				state.free -= 1;
+				return Turn { scheduler: Some(self.clone()) };
+			}
+			let (tx, rx) = oneshot::channel();
+			let queue = state.waiting.entry(conn_id).or_default();
+			queue.push_back(tx);
+			if queue.len() == 1 {
+				state.ring.push_back(conn_id);
+				if state.ring.len() == 1 {
+					state.credit = state.weight(conn_id);
+				}
+			}
+			rx
+		};
+		// NOTE: the sender is only dropped with the scheduler, which `self` keeps alive.
+		rx.await.expect("Waiting calls are handed a turn; qed")
+	}
+
+	/// Returns the number of calls waiting for their turn.
+	pub fn waiting(&self) -> usize {
+		self.lock().waiting.values().map(VecDeque::len).sum()
+	}
+
+	/// Hands the slot of a dropped [`Turn`] to the next waiting call.
+	fn release(&self) {
+		let mut state = self.lock();
+		while let Some(waiting) = state.next_waiting() {
+			// NOTE: the turn of a call that stopped waiting is returned, the slot goes to the next waiting call.
+			match waiting.send(Turn { scheduler: Some(self.clone()) }) {
+				Ok(()) => return,
+				Err(mut turn) => turn.scheduler = None,
+			}
+		}
+		state.free += 1;
+	}
+
+	fn lock(&self) -> std::sync::MutexGuard<'_, SchedulerState> {
+		self.inner.lock().expect("The lock is never held across a panic; qed")
+	}
+}
+
+/// Slot of a call scheduled by a [`FairScheduler`], handed to the next waiting call when dropped.
+#[derive(Debug)]
+pub struct Turn {
+	scheduler: Option<FairScheduler>,
+}
+
+impl Drop for Turn {
+	fn drop(&mut self) {
+		if let Some(scheduler) = self.scheduler.take() {
+			scheduler.release();
+		}
+	}
+}
+
+/// Issue in the configuration of a server, see [`ConfigReport`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigIssue {
+	/// Limits that contradict each other or reject every request, such as a max request body size of `0`.
+	ConflictingLimits(String),
+	/// Methods whose names have the same normalized form, they're only callable by their exact names, see
+	/// [`MethodNormalization`].
+	NormalizedNameCollision(Vec<String>),
+	/// A setting, such as `"transformers"`, refers to a method that isn't registered.
+	UnknownMethod {
+		/// Name of the setting.
+		setting: &'static str,
+		/// Method that isn't registered.
+		method: String,
+	},
+	/// The registered methods are not served because a [`MethodDispatcher`] replaces them.
+	MethodsReplacedByDispatcher,
+	/// A setting, such as `"transformers"`, wraps a method that responds after it returns, such as the
+	/// cancellable methods of the WebSocket server. The setting only sees the responses sent before the method
+	/// returns, the later response would be lost.
+	DeferredResponse {
+		/// Name of the setting.
+		setting: &'static str,
+		/// Method responding after it returns.
+		method: String,
+	},
+}
+
+impl fmt::Display for ConfigIssue {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::ConflictingLimits(limits) => write!(f, "Conflicting limits: {}", limits),
+			Self::NormalizedNameCollision(methods) => {
+				write!(f, "Methods {} have the same normalized name", methods.join(", "))
+			}
+			Self::UnknownMethod { setting, method } => write!(f, "The {} refer to unknown method: {}", setting, method),
+			Self::MethodsReplacedByDispatcher => write!(f, "The registered methods are replaced by a dispatcher"),
+			Self::DeferredResponse { setting, method } => {
+				write!(f, "The {} can't wrap method {}, which responds after it returns", setting, method)
+			}
+		}
+	}
+}
+
+/// Issues in the configuration of a server, reported before it starts rather than failing or being ignored
+/// while it serves the calls.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConfigReport {
+	/// Issues found, in the order they were checked.
+	pub issues: Vec<ConfigIssue>,
+}
+
+impl ConfigReport {
+	/// Returns `true` if no issue was found.
+	pub fn is_ok(&self) -> bool {
+		self.issues.is_empty()
+	}
+
+	/// Adds a [`ConfigIssue::ConflictingLimits`] described by `limits`.
+	pub fn conflicting_limits(&mut self, limits: impl Into<String>) {
+		self.issues.push(ConfigIssue::ConflictingLimits(limits.into()));
+	}
+
+	/// Checks the settings of the `methods` registered on a server.
+	pub fn check_methods<'a>(
+		&mut self,
+		methods: impl IntoIterator<Item = &'a str>,
+		normalization: MethodNormalization,
+		flags: &MethodFlags,
+		idempotency: Option<&Idempotency>,
+		transformers: Option<&Transformers>,
+	) {
+		let methods: FxHashSet<&str> = methods.into_iter().collect();
+		let mut normalized: FxHashMap<Cow<str>, Vec<&str>> = FxHashMap::default();
+		for method in &methods {
+			normalized.entry(normalization.normalize(method)).or_default().push(method);
+		}
+		let mut collisions: Vec<Vec<String>> = normalized
+			.into_iter()
+			.filter(|(_, names)| names.len() > 1)
+			.map(|(_, names)| {
+				let mut names: Vec<String> = names.into_iter().map(Into::into).collect();
+				names.sort();
+				names
+			})
+			.collect();
+		collisions.sort();
+		self.issues.extend(collisions.into_iter().map(ConfigIssue::NormalizedNameCollision));
+
+		let mut unknown = |setting: &'static str, mut names: Vec<String>| {
+			names.retain(|name| !methods.contains(name.as_str()));
+			names.sort();
+			self.issues.extend(names.into_iter().map(|method| ConfigIssue::UnknownMethod { setting, method }));
+		};
+		unknown("method flags", flags.disabled());
+		if let Some(idempotency) = idempotency {
+			unknown("idempotency settings", idempotency.methods().map(Into::into).collect());
+		}
+		if let Some(transformers) = transformers {
+			unknown("transformers", transformers.methods().map(Into::into).collect());
+		}
+	}
+
+	/// Checks that neither the `idempotency` settings nor the `transformers` wrap any of the `deferred` methods,
+	/// which respond after they return.
+	pub fn check_deferred_methods<'a>(
+		&mut self,
+		deferred: impl IntoIterator<Item = &'a str>,
+		idempotency: Option<&Idempotency>,
+		transformers: Option<&Transformers>,
+	) {
+		let mut deferred: Vec<&str> = deferred.into_iter().collect();
+		deferred.sort_unstable();
+		let mut wrapped = |setting: &'static str, wraps: &dyn Fn(&str) -> bool| {
+			self.issues.extend(
+				deferred
+					.iter()
+					.filter(|method| wraps(method))
+					.map(|method| ConfigIssue::DeferredResponse { setting, method: (*method).into() }),
+			);
+		};
+		if let Some(idempotency) = idempotency {
+			wrapped("idempotency settings", &|method| idempotency.methods.contains(method));
+		}
+		if let Some(transformers) = transformers {
+			wrapped("transformers", &|method| transformers.methods.contains_key(method));
+		}
+	}
+}
+
+impl fmt::Display for ConfigReport {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.is_ok() {
+			return write!(f, "No configuration issues");
+		}
+		for (i, issue) in self.issues.iter().enumerate() {
+			if i > 0 {
+				writeln!(f)?;
+			}
+			write!(f, "{}", issue)?;
+		}
+		Ok(())
+	}
+}
+
+fn to_json_string<T: Serialize>(value: &T) -> Result<String, serde_json::Error> {
+	let mut buf = SmallVec::<[u8; SMALL_RESPONSE_LEN]>::new();
+	serde_json::to_writer(&mut buf, value)?;
+	Ok(String::from_utf8(buf.into_vec()).expect("serde_json emits valid UTF-8; qed"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		send_error, send_response, to_json_string, ConfigIssue, ConfigReport, FairScheduler, Idempotency,
+		MethodDispatcher, MethodFlags, MethodNormalization, Methods, Transformers, WildcardMethods, SMALL_RESPONSE_LEN,
+	};
+	use jsonrpsee_types::error::Error;
+	use jsonrpsee_types::v2::{JsonRpcResponse, RpcParams, TwoPointZero};
+	use serde_json::value::RawValue;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+	use std::time::Duration;
+	use tokio::sync::mpsc;
+
+	#[test]
+	fn to_json_string_matches_serde_json() {
+		let id = RawValue::from_string("1".to_owned()).unwrap();
+		let small = JsonRpcResponse { jsonrpc: TwoPointZero, id: Some(&*id), result: true };
+		let small_json = to_json_string(&small).unwrap();
+		assert_eq!(small_json, serde_json::to_string(&small).unwrap());
+		assert_eq!(small_json.capacity(), small_json.len());
+
+		let large = JsonRpcResponse { jsonrpc: TwoPointZero, id: Some(&*id), result: "a".repeat(SMALL_RESPONSE_LEN) };
+		assert_eq!(to_json_string(&large).unwrap(), serde_json::to_string(&large).unwrap());
+	}
+
+	#[test]
+	fn normalized_methods_match_other_conventions() {
+		let mut methods = Methods::default();
+		for name in ["eth_getBalance", "say_hello", "sayHello", "SAY_HELLO"] {
+			methods.insert(name, Box::new(move |_, _, tx, _| Ok(tx.send(name.to_owned())?)));
+		}
+		let methods = MethodNormalization::SeparatorInsensitive.wrap(methods);
+		let (tx, mut rx) = mpsc::unbounded_channel();
+		let mut call = |method: &str| {
+			methods.dispatch(method, None, RpcParams::new(None), &tx, 0).unwrap();
+			rx.try_recv().unwrap()
+		};
+
+		assert_eq!(call("eth_get_balance"), "eth_getBalance");
+		assert_eq!(call("ETH-GETBALANCE"), "eth_getBalance");
+		// Ambiguous names are only matched exactly.
+		assert_eq!(call("sayHello"), "sayHello");
+		assert_eq!(call("SAY_HELLO"), "SAY_HELLO");
+		assert!(call("say-hello").contains("Method not found"));
+	}
+
+	#[test]
+	fn idempotency_replays_successful_responses() {
+		let calls = Arc::new(AtomicUsize::new(0));
+		let mut methods = Methods::default();
+		let counter = calls.clone();
+		methods.insert(
+			"transfer",
+			Box::new(move |id, params, tx, _| {
+				let params: serde_json::Map<String, serde_json::Value> = params.parse()?;
+				match params.get("amount") {
+					Some(amount) if !params.contains_key("idempotencyKey") => {
+						send_response(id, tx, (counter.fetch_add(1, Ordering::Relaxed), amount))
+					}
+					_ => send_error(id, tx, -32602, "Invalid params"),
+				}
+				Ok(())
+			}),
+		);
+		let methods = Idempotency::new(["transfer"], Duration::from_secs(60)).wrap(methods);
+		let (tx, mut rx) = mpsc::unbounded_channel();
+		let mut call = |id: &str, params: &str| {
+			let id = RawValue::from_string(id.to_owned()).unwrap();
+			methods.dispatch("transfer", Some(&id), RpcParams::new(Some(params)), &tx, 0).unwrap();
+			rx.try_recv().unwrap()
+		};
+
+		let first = call("1", r#"{"amount":5,"idempotencyKey":"a"}"#);
+		assert_eq!(first, r#"{"jsonrpc":"2.0","result":[0,5],"id":1}"#);
+		assert_eq!(call("2", r#"{"amount":5,"idempotencyKey":"a"}"#), r#"{"jsonrpc":"2.0","result":[0,5],"id":2}"#);
+		assert_eq!(call("3", r#"{"amount":5,"idempotencyKey":"b"}"#), r#"{"jsonrpc":"2.0","result":[1,5],"id":3}"#);
+		assert_eq!(call("4", r#"{"amount":5}"#), r#"{"jsonrpc":"2.0","result":[2,5],"id":4}"#);
+		// Errors are not replayed.
+		assert!(call("5", r#"{"idempotencyKey":"c"}"#).contains("error"));
+		assert_eq!(call("6", r#"{"amount":5,"idempotencyKey":"c"}"#), r#"{"jsonrpc":"2.0","result":[3,5],"id":6}"#);
+		assert_eq!(calls.load(Ordering::Relaxed), 4);
+	}
+
+	#[test]
+	fn transformers_rewrite_params_and_results() {
+		let mut methods = Methods::default();
+		methods.insert(
+			"balance",
+			Box::new(|id, params, tx, _| {
+				let params: serde_json::Value = params.parse().unwrap_or_default();
+				match params["account"].as_str() {
+					Some(_) => send_response(id, tx, serde_json::json!({ "balance_wei": 2_000_000_000_u64 })),
+					None => send_error(id, tx, -32602, "Invalid params"),
+				}
+				Ok(())
+			}),
+		);
+		let transformers = Transformers::new()
+			.params("balance", |mut params| {
+				if let Some(address) = params.get_mut("address").map(serde_json::Value::take) {
+					params["account"] = address;
+				}
+				params
+			})
+			.result(
+				"balance",
+				|result| serde_json::json!({ "balance_gwei": result["balance_wei"].as_u64().unwrap() / 1_000_000_000 }),
+			);
+		let methods = transformers.wrap(methods);
+		let (tx, mut rx) = mpsc::unbounded_channel();
+		let id = RawValue::from_string("1".to_owned()).unwrap();
+
+		methods.dispatch("balance", Some(&id), RpcParams::new(Some(r#"{"address":"0xab"}"#)), &tx, 0).unwrap();
+		assert_eq!(rx.try_recv().unwrap(), r#"{"jsonrpc":"2.0","result":{"balance_gwei":2},"id":1}"#);
+		// Errors are sent unchanged.
+		methods.dispatch("balance", Some(&id), RpcParams::new(None), &tx, 0).unwrap();
+		assert!(rx.try_recv().unwrap().contains("Invalid params"));
+	}
+
+	#[test]
+	fn fair_scheduler_round_robins_connections() {
+		use futures::FutureExt;
+
+		fn grant_order(scheduler: FairScheduler) -> Vec<&'static str> {
+			futures::executor::block_on(async {
+				let held = scheduler.acquire(0).now_or_never().unwrap();
+				let mut calls: Vec<_> = [("a1", 0), ("a2", 0), ("a3", 0), ("b1", 1)]
+					.iter()
+					.map(|&(name, conn_id)| (name, scheduler.acquire(conn_id).boxed()))
+					.collect();
+				for (_, call) in calls.iter_mut() {
+					assert!(futures::poll!(call).is_pending());
+				}
+				assert_eq!(scheduler.waiting(), 4);
+
+				let mut order = Vec::new();
+				let mut turn = held;
+				while !calls.is_empty() {
+					drop(turn);
+					let granted: Vec<_> =
+						calls.iter_mut().filter_map(|(name, call)| Some((*name, call.now_or_never()?))).collect();
+					assert_eq!(granted.len(), 1);
+					let (name, granted) = granted.into_iter().next().unwrap();
+					calls.retain(|(other, _)| *other != name);
+					order.push(name);
+					turn = granted;
+				}
+				order
+			})
+		}
+
+		assert_eq!(grant_order(FairScheduler::new(1)), ["a1", "b1", "a2", "a3"]);
+		let scheduler = FairScheduler::new(1);
+		scheduler.set_weight(0, 2);
+		assert_eq!(grant_order(scheduler), ["a1", "a2", "b1", "a3"]);
+	}
+
+	#[test]
+	fn config_report_lists_method_issues() {
+		let flags = MethodFlags::new();
+		flags.disable("say_hello");
+		flags.disable("removed");
+		let idempotency = Idempotency::new(["transfer"], Duration::from_secs(60));
+		let transformers = Transformers::new().params("sayHello", |params| params);
+		let mut report = ConfigReport::default();
+		report.check_methods(
+			["sayHello", "say_hello", "get-block", "get_block", "transfer"],
+			MethodNormalization::SeparatorInsensitive,
+			&flags,
+			Some(&idempotency),
+			Some(&transformers),
+		);
+		assert_eq!(
+			report.issues,
+			vec![
+				ConfigIssue::NormalizedNameCollision(vec!["get-block".into(), "get_block".into()]),
+				ConfigIssue::NormalizedNameCollision(vec!["sayHello".into(), "say_hello".into()]),
+				ConfigIssue::UnknownMethod { setting: "method flags", method: "removed".into() },
+			]
+		);
+
+		let mut report = ConfigReport::default();
+		report.check_methods(["say_hello"], MethodNormalization::Exact, &MethodFlags::new(), None, None);
+		assert!(report.is_ok());
+	}
+
+	#[test]
+	fn wildcard_methods_serve_unregistered_names() {
+		let mut methods = Methods::default();
+		methods.insert(
+			"debug_version",
+			Box::new(|id, _, tx, _| {
+				send_response(id, tx, "exact");
+				Ok(())
+			}),
+		);
+		let mut wildcards = WildcardMethods::default();
+		wildcards
+			.insert(
+				"debug_*",
+				Box::new(|method, id, _, tx, _| {
+					send_response(id, tx, method);
+					Ok(())
+				}),
+			)
+			.unwrap();
+		wildcards
+			.insert(
+				"debug_trace*",
+				Box::new(|_, id, _, tx, _| {
+					send_response(id, tx, "trace");
+					Ok(())
+				}),
+			)
+			.unwrap();
+		assert!(matches!(
+			wildcards.insert("*_foo", Box::new(|_, _, _, _, _| Ok(()))),
+			Err(Error::InvalidMethodPattern(_))
+		));
+		assert!(matches!(
+			wildcards.insert("debug_*", Box::new(|_, _, _, _, _| Ok(()))),
+			Err(Error::MethodAlreadyRegistered(_))
+		));
+		let methods = wildcards.wrap(methods);
+		let (tx, mut rx) = mpsc::unbounded_channel();
+		let id = RawValue::from_string("1".to_owned()).unwrap();
+
+		for (method, result) in
+			[("debug_version", "exact"), ("debug_blocks", "debug_blocks"), ("debug_traceCall", "trace")]
+		{
+			methods.dispatch(method, Some(&id), RpcParams::new(None), &tx, 0).unwrap();
+			assert_eq!(rx.try_recv().unwrap(), format!(r#"{{"jsonrpc":"2.0","result":"{}","id":1}}"#, result));
+		}
+		methods.dispatch("eth_blocks", Some(&id), RpcParams::new(None), &tx, 0).unwrap();
+		assert!(rx.try_recv().unwrap().contains("Method not found"));
+	}
+}
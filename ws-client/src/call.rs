@@ -0,0 +1,42 @@
+use crate::client::WsClient;
+use jsonrpc::DeserializeOwned;
+use jsonrpsee_types::{error::Error, jsonrpc, traits::Value};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Method call with its options, created by [`WsClient::call`].
+#[derive(Debug)]
+pub struct CallBuilder<'a, V> {
+	client: &'a WsClient<V>,
+	method: String,
+	params: Result<jsonrpc::Params, Error>,
+	timeout: Option<Duration>,
+}
+
+impl<'a, V: Value> CallBuilder<'a, V> {
+	pub(crate) fn new(client: &'a WsClient<V>, method: String) -> Self {
+		Self { client, method, params: Ok(jsonrpc::Params::None), timeout: None }
+	}
+
+	/// Sets the parameters of the call, a tuple or an array for positional parameters, a struct or a map for
+	/// named parameters.
+	///
+	/// The call fails if `params` is serialized into anything else.
+	pub fn params(mut self, params: impl Serialize) -> Self {
+		self.params = jsonrpc::Params::serialized(&params).map_err(|e| Error::Custom(e.to_string()));
+		self
+	}
+
+	/// Fails the call with [`Error::WsRequestTimeout`] if no response arrives within `timeout`, instead of
+	/// the [`WsClientBuilder::request_timeout`](crate::WsClientBuilder::request_timeout) of the client.
+	pub fn timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = Some(timeout);
+		self
+	}
+
+	/// Sends the call and waits for the result.
+	pub async fn send<T: DeserializeOwned>(self) -> Result<T, Error> {
+		let timeout = self.timeout.or(self.client.request_timeout);
+		self.client.send_request(self.method, self.params?, timeout).await
+	}
+}
@@ -24,54 +24,90 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use crate::manager::{RequestManager, RequestStatus};
-use crate::transport::WsTransportClientBuilder;
-use crate::{jsonrpc_transport, transport::parse_url};
+use crate::call::CallBuilder;
+use crate::config::{
+	BatchingWindowConfig, ClientConfig, InvalidConfig, PingConfig, ReconnectConfig, RequestQueueConfig,
+};
+use crate::diagnostics::{BackgroundError, ErrorHook};
+use crate::heartbeat::HeartbeatMonitor;
+use crate::id::{IdProvider, SharedIdProvider};
+use crate::jsonrpc_transport::{self, Coalesced};
+use crate::keepalive::Keepalive;
+use crate::lazy::LazyConnection;
+use crate::manager::{BatchState, RequestManager, RequestStatus};
+use crate::metrics::{ClientMetrics, SharedMetrics};
+use crate::middleware::{CallContext, ClientMiddleware, Middlewares};
+use crate::proxy::{HttpProxy, Proxy};
+use crate::queue::RequestQueue;
+use crate::socks::Socks5Proxy;
+#[cfg(all(unix, feature = "uds"))]
+use crate::transport::UdsFraming;
+use crate::transport::{
+	self, parse_url, parse_url_unresolved, Authorization, Codecs, Direction, Host, Mode, RawTap, TlsConfig,
+	WsConnectError, WsHandshakeError, WsStream, WsTransportClientBuilder,
+};
 use async_std::sync::Mutex;
 use async_trait::async_trait;
+use fnv::FnvHashMap;
 use futures::{
 	channel::{mpsc, oneshot},
-	future::Either,
+	future::{BoxFuture, Either},
 	prelude::*,
 	sink::SinkExt,
 };
 use jsonrpc::DeserializeOwned;
 use jsonrpsee_types::{
-	client::{BatchMessage, FrontToBack, NotificationMessage, RequestMessage, Subscription, SubscriptionMessage},
-	error::Error,
-	jsonrpc::{self, JsonValue, SubscriptionId},
-	traits::{Client, SubscriptionClient},
+	client::{
+		notif_channel, ActiveSubscription, BatchMessage, FrontToBack, MemoryStats, NotifFilter,
+		NotificationHandlerMessage, NotificationMessage, OverflowPolicy, RequestMessage, RequestSpan, Subscription,
+		SubscriptionMessage,
+	},
+	error::{CallInfo, Error, ShutdownReason},
+	jsonrpc::{self, JsonValue, NumberOptions, SubscriptionId},
+	preflight::Preflight,
+	redact::{LogRedactor, Redact},
+	retry::RetryPolicy,
+	traits::{Client, SubscriptionClient, Value},
+	validation::ResponseValidator,
+	wire::Codec,
 };
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::convert::TryFrom;
 use std::marker::PhantomData;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
-use std::{borrow::Cow, convert::TryInto};
 
 /// Wrapper over a [`oneshot::Receiver`](futures::channel::oneshot::Receiver) that reads
-/// the underlying channel once and then stores the result in String.
+/// the underlying channel once and then stores the result as a [`ShutdownReason`].
 /// It is possible that the error is read more than once if several calls are made
 /// when the background thread has been terminated.
 #[derive(Debug)]
 enum ErrorFromBack {
 	/// Error message is already read.
-	Read(String),
+	Read(ShutdownReason),
 	/// Error message is unread.
-	Unread(oneshot::Receiver<Error>),
+	Unread(oneshot::Receiver<ShutdownReason>),
 }
 
 impl ErrorFromBack {
 	async fn read_error(self) -> (Self, Error) {
 		match self {
 			Self::Unread(rx) => {
-				let msg = match rx.await {
-					Ok(msg) => msg.to_string(),
+				let reason = match rx.await {
+					Ok(reason) => reason,
 					// This should never happen because the receiving end is still alive.
 					// Would be a bug in the logic of the background task.
-					Err(_) => "Error reason could not be found. This is a bug. Please open an issue.".to_string(),
+					Err(_) => ShutdownReason::Other(
+						"Error reason could not be found. This is a bug. Please open an issue.".to_string(),
+					),
 				};
-				let err = Error::RestartNeeded(msg.clone());
-				(Self::Read(msg), err)
+				let err = Error::RestartNeeded(reason.clone());
+				(Self::Read(reason), err)
 			}
-			Self::Read(msg) => (Self::Read(msg.clone()), Error::RestartNeeded(msg)),
+			Self::Read(reason) => (Self::Read(reason.clone()), Error::RestartNeeded(reason)),
 		}
 	}
 }
@@ -81,50 +117,401 @@ impl ErrorFromBack {
 /// It's possible that the background thread is terminated and this makes the client unusable.
 /// An error [`Error::RestartNeeded`] is returned if this happens and users has to manually
 /// handle dropping and restarting a new client.
+///
+/// Responses are decoded into the intermediate value type `V` before being converted
+/// to the types requested by the user, see [`Value`].
+///
+/// The client is `Send + Sync`, share it between tasks as an `Arc<WsClient>`, which implements [`Client`] and
+/// [`SubscriptionClient`] as well.
 #[derive(Debug)]
-pub struct WsClient {
+pub struct WsClient<V = JsonValue> {
 	/// Channel to send requests to the background task.
-	to_back: mpsc::Sender<FrontToBack>,
+	to_back: mpsc::Sender<FrontToBack<V>>,
+	/// Sender shared by all calls to the background task.
+	// NOTE: every clone of a `mpsc::Sender` has a guaranteed slot in the channel, thus all calls have to go
+	// through the same sender to wait for capacity in the channel.
+	shared_to_back: Mutex<mpsc::Sender<FrontToBack<V>>>,
 	/// If the background thread terminates the error is sent to this channel.
 	// NOTE(niklasad1): This is a Mutex to circumvent that the async fns takes immutable references.
 	error: Mutex<ErrorFromBack>,
 	/// Request timeout
-	request_timeout: Option<Duration>,
+	pub(crate) request_timeout: Option<Duration>,
+	/// Subscriptions made by this client.
+	subscriptions: SubscriptionRegistry,
+	/// Listeners of the state of the connection.
+	connection_listeners: ConnectionListeners,
+	/// Validator of the results of method calls.
+	validator: Option<ResponseValidator>,
+	/// Handling of numbers in parameters and results.
+	number_options: Option<NumberOptions>,
+	/// Retries of the method calls.
+	retry_policy: Option<RetryPolicy>,
+	/// Overflow policy of subscriptions that don't set one.
+	overflow_policy: OverflowPolicy,
+	/// Capacity of the buffers of subscriptions and notification handlers.
+	max_notifs_per_subscription: usize,
+	/// Hooks around method calls.
+	middlewares: Middlewares<V>,
+	/// Send the calls of batch requests one by one once the server rejected a batch.
+	split_rejected_batches: bool,
+	/// Number of batch requests sent as single calls.
+	split_batches: AtomicU64,
+	/// Masking of the params in logs.
+	redactor: LogRedactor,
+}
+
+/// Coalescing of the messages sent in quick succession, see [`WsClientBuilder::batching_window`].
+#[derive(Clone, Copy, Debug)]
+struct BatchingWindow {
+	window: Duration,
+	max_messages: usize,
+	/// Coalesce the requests along with the notifications.
+	requests: bool,
+}
+
+/// Connection of the background task, established before spawning it or dialed on first use.
+enum Connection {
+	Established(transport::Sender, transport::Receiver),
+	Lazy(LazyConnection),
+}
+
+/// How the background task reconnects, see [`WsClientBuilder::reconnect`].
+#[derive(Debug)]
+struct Reconnect {
+	/// Endpoints tried in order on every attempt, see [`WsClientBuilder::build_with_fallbacks`].
+	transports: Vec<WsTransportClientBuilder<'static>>,
+	max_retries: usize,
+	backoff: Duration,
+}
+
+/// Description of a subscription, sufficient to create it again on another client.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionDescriptor {
+	/// Method used to subscribe.
+	pub subscribe_method: String,
+	/// Parameters sent with the subscribe request.
+	pub params: jsonrpc::Params,
+	/// Method used to unsubscribe.
+	pub unsubscribe_method: String,
+}
+
+/// Subscriptions of a client, shared by the frontend and the background task such that they can
+/// still be exported after the background task terminated.
+#[derive(Clone, Debug, Default)]
+struct SubscriptionRegistry {
+	/// Subscriptions by request ID, the flag is set once the server accepted the subscription.
+	inner: Arc<std::sync::Mutex<FnvHashMap<u64, (SubscriptionDescriptor, bool)>>>,
+}
+
+impl SubscriptionRegistry {
+	fn insert_pending(&self, request_id: u64, descriptor: SubscriptionDescriptor) {
+		self.lock().insert(request_id, (descriptor, false));
+	}
+
+	fn activate(&self, request_id: u64) {
+		if let Some((_, active)) = self.lock().get_mut(&request_id) {
+			*active = true;
+		}
+	}
+
+	fn remove(&self, request_id: u64) {
+		self.lock().remove(&request_id);
+	}
+
+	fn take(&self, request_id: u64) -> Option<SubscriptionDescriptor> {
+		self.lock().remove(&request_id).map(|(descriptor, _)| descriptor)
+	}
+
+	fn get(&self, request_id: u64) -> Option<SubscriptionDescriptor> {
+		self.lock().get(&request_id).map(|(descriptor, _)| descriptor.clone())
+	}
+
+	fn clear(&self) {
+		self.lock().clear();
+	}
+
+	fn active(&self) -> Vec<SubscriptionDescriptor> {
+		self.lock().values().filter(|(_, active)| *active).map(|(descriptor, _)| descriptor.clone()).collect()
+	}
+
+	fn lock(&self) -> std::sync::MutexGuard<'_, FnvHashMap<u64, (SubscriptionDescriptor, bool)>> {
+		self.inner.lock().expect("The lock is never held across a panic; qed")
+	}
+}
+
+/// Change of the state of the connection of a client, see [`WsClient::connection_events`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionEvent {
+	/// The client reconnected after the connection was lost.
+	Connected,
+	/// The connection was lost, the client terminates unless it reconnects, see [`WsClientBuilder::reconnect`].
+	Disconnected {
+		/// Why the connection was lost.
+		reason: ShutdownReason,
+	},
+}
+
+/// Listeners of the [`ConnectionEvent`]s of a client, shared by the frontend and the background task.
+///
+/// `None` once the background task terminated.
+#[derive(Clone, Debug)]
+struct ConnectionListeners {
+	inner: Arc<std::sync::Mutex<Option<Listeners>>>,
+}
+
+#[derive(Debug, Default)]
+struct Listeners {
+	senders: Vec<mpsc::UnboundedSender<ConnectionEvent>>,
+	/// Set while the client tries to reconnect.
+	disconnected: bool,
+}
+
+impl ConnectionListeners {
+	fn new() -> Self {
+		Self { inner: Arc::new(std::sync::Mutex::new(Some(Listeners::default()))) }
+	}
+
+	/// Returns the events sent from now on, the stream ends once the background task terminated.
+	fn listen(&self) -> mpsc::UnboundedReceiver<ConnectionEvent> {
+		let (tx, rx) = mpsc::unbounded();
+		if let Some(listeners) = self.lock().as_mut() {
+			listeners.senders.push(tx);
+		}
+		rx
+	}
+
+	fn notify(&self, event: ConnectionEvent) {
+		if let Some(listeners) = self.lock().as_mut() {
+			listeners.disconnected = matches!(event, ConnectionEvent::Disconnected { .. });
+			listeners.senders.retain(|tx| tx.unbounded_send(event.clone()).is_ok());
+		}
+	}
+
+	/// Ends the streams of the listeners, the background task terminated because of `reason`.
+	///
+	/// The listeners aren't notified again if the connection was lost before trying to reconnect.
+	fn close(&self, reason: ShutdownReason) {
+		if let Some(listeners) = self.lock().take() {
+			if !listeners.disconnected {
+				let event = ConnectionEvent::Disconnected { reason };
+				for tx in listeners.senders {
+					let _ = tx.unbounded_send(event.clone());
+				}
+			}
+		}
+	}
+
+	fn lock(&self) -> std::sync::MutexGuard<'_, Option<Listeners>> {
+		self.inner.lock().expect("The lock is never held across a panic; qed")
+	}
 }
 
 /// Configuration.
 #[derive(Clone, Debug)]
-pub struct WsClientBuilder<'a> {
+pub struct WsClientBuilder<'a, V = JsonValue> {
 	max_request_body_size: usize,
+	max_response_size: usize,
 	request_timeout: Option<Duration>,
 	connection_timeout: Duration,
+	connection_attempt_delay: Duration,
 	origin: Option<Cow<'a, str>>,
+	authorization: Option<Authorization>,
 	handshake_url: Cow<'a, str>,
 	max_concurrent_requests: usize,
+	request_queue: Option<(usize, Option<Duration>)>,
 	max_notifs_per_subscription: usize,
+	overflow_policy: OverflowPolicy,
+	batching_window: Option<BatchingWindow>,
+	tls_session_resumption: bool,
+	tls_config: Option<TlsConfig>,
+	raw_tap: Option<RawTap>,
+	codecs: Codecs,
+	redactor: LogRedactor,
+	compression: Option<u8>,
+	proxy: Option<Proxy>,
+	proxy_from_env: bool,
+	#[cfg(all(unix, feature = "uds"))]
+	uds_framing: UdsFraming,
+	reconnect: Option<(usize, Duration)>,
+	max_redirections: usize,
+	write_queue: Option<usize>,
+	ping: Option<(Duration, Duration)>,
+	subscription_heartbeat: Option<Duration>,
+	response_validator: Option<ResponseValidator>,
+	preflight: Option<Preflight>,
+	number_options: Option<NumberOptions>,
+	retry_policy: Option<RetryPolicy>,
+	id_provider: Option<SharedIdProvider>,
+	metrics: Option<SharedMetrics>,
+	error_hook: Option<ErrorHook>,
+	lenient_batches: bool,
+	/// Send the calls of batch requests one by one once the server rejected a batch.
+	split_rejected_batches: bool,
+	strict_subscription_ids: bool,
+	cancel_dropped_requests: bool,
+	middlewares: Middlewares<V>,
+	marker: PhantomData<V>,
+}
+
+/// Creates a builder with the options of `config`, the other options have their defaults.
+///
+/// Fails if an option is rejected by its builder method, such as compression window bits that are not within
+/// `9..=15`.
+impl<'a> TryFrom<ClientConfig> for WsClientBuilder<'a> {
+	type Error = InvalidConfig;
+
+	fn try_from(config: ClientConfig) -> Result<Self, InvalidConfig> {
+		// NOTE: destructured without `..` such that new options can't be left out.
+		let ClientConfig {
+			max_request_body_size,
+			max_response_size,
+			request_timeout_ms,
+			connection_timeout_ms,
+			connection_attempt_delay_ms,
+			origin_header,
+			handshake_url,
+			max_concurrent_requests,
+			request_queue,
+			max_notifs_per_subscription,
+			subscription_overflow_policy,
+			batching_window,
+			tls_session_resumption,
+			compression,
+			proxy_from_env,
+			#[cfg(all(unix, feature = "uds"))]
+			uds_framing,
+			reconnect,
+			max_redirections,
+			write_queue,
+			ping_interval,
+			subscription_heartbeat_ms,
+			number_options,
+			lenient_batch_responses,
+			split_rejected_batches,
+			strict_subscription_ids,
+			cancel_dropped_requests,
+		} = config;
+		if matches!(compression, Some(bits) if !(9..=15).contains(&bits)) {
+			return Err(InvalidConfig { option: "compression", reason: "max window bits have to be within 9..=15" });
+		}
+
+		let mut builder = Self::default()
+			.max_request_body_size(max_request_body_size)
+			.max_response_size(max_response_size)
+			.request_timeout(request_timeout_ms.map(Duration::from_millis))
+			.connection_timeout(Duration::from_millis(connection_timeout_ms))
+			.connection_attempt_delay(Duration::from_millis(connection_attempt_delay_ms))
+			.origin_header(origin_header.map(Cow::Owned))
+			.handshake_url(Cow::Owned(handshake_url))
+			.max_concurrent_requests(max_concurrent_requests)
+			.max_notifs_per_subscription(max_notifs_per_subscription)
+			.subscription_overflow_policy(subscription_overflow_policy)
+			.tls_session_resumption(tls_session_resumption)
+			.compression(compression)
+			.proxy_from_env(proxy_from_env)
+			.max_redirections(max_redirections)
+			.write_queue(write_queue)
+			.lenient_batch_responses(lenient_batch_responses)
+			.split_rejected_batches(split_rejected_batches)
+			.strict_subscription_ids(strict_subscription_ids)
+			.cancel_dropped_requests(cancel_dropped_requests);
+		#[cfg(all(unix, feature = "uds"))]
+		{
+			builder = builder.uds_framing(uds_framing);
+		}
+		if let Some(queue) = request_queue {
+			builder = builder.request_queue(queue.capacity, queue.timeout_ms.map(Duration::from_millis));
+		}
+		if let Some(batching) = batching_window {
+			let window = Duration::from_millis(batching.window_ms);
+			builder = match batching.notifications_only {
+				true => builder.notification_batching_window(window, batching.max_messages),
+				false => builder.batching_window(window, batching.max_messages),
+			};
+		}
+		if let Some(reconnect) = reconnect {
+			builder = builder.reconnect(reconnect.max_retries, Duration::from_millis(reconnect.backoff_ms));
+		}
+		if let Some(ping) = ping_interval {
+			builder = builder
+				.ping_interval(Duration::from_millis(ping.interval_ms), Duration::from_millis(ping.pong_timeout_ms));
+		}
+		if let Some(timeout) = subscription_heartbeat_ms {
+			builder = builder.subscription_heartbeat(Duration::from_millis(timeout));
+		}
+		if let Some(options) = number_options {
+			builder = builder.number_options(options);
+		}
+		Ok(builder)
+	}
 }
 
 impl<'a> Default for WsClientBuilder<'a> {
 	fn default() -> Self {
 		Self {
 			max_request_body_size: 10 * 1024 * 1024,
+			max_response_size: 10 * 1024 * 1024,
 			request_timeout: None,
 			connection_timeout: Duration::from_secs(10),
+			connection_attempt_delay: Duration::from_millis(250),
 			origin: None,
+			authorization: None,
 			handshake_url: From::from("/"),
 			max_concurrent_requests: 256,
+			request_queue: None,
 			max_notifs_per_subscription: 4,
+			overflow_policy: OverflowPolicy::CloseSubscription,
+			batching_window: None,
+			tls_session_resumption: true,
+			tls_config: None,
+			raw_tap: None,
+			codecs: Codecs::default(),
+			redactor: LogRedactor::default(),
+			compression: None,
+			proxy: None,
+			proxy_from_env: false,
+			#[cfg(all(unix, feature = "uds"))]
+			uds_framing: UdsFraming::default(),
+			reconnect: None,
+			max_redirections: 0,
+			write_queue: None,
+			ping: None,
+			subscription_heartbeat: None,
+			response_validator: None,
+			preflight: None,
+			number_options: None,
+			retry_policy: None,
+			id_provider: None,
+			metrics: None,
+			error_hook: None,
+			lenient_batches: false,
+			split_rejected_batches: false,
+			strict_subscription_ids: false,
+			cancel_dropped_requests: false,
+			middlewares: Middlewares::default(),
+			marker: PhantomData,
 		}
 	}
 }
 
-impl<'a> WsClientBuilder<'a> {
-	/// Set max request body size.
+impl<'a, V: Value> WsClientBuilder<'a, V> {
+	/// Set max request body size, larger requests fail without being sent.
+	///
+	/// Default is 10 MiB.
 	pub fn max_request_body_size(mut self, size: usize) -> Self {
 		self.max_request_body_size = size;
 		self
 	}
 
+	/// Set max size of the messages received from the server, the connection is dropped with
+	/// [`ShutdownReason::MessageTooLarge`] when the server announces a larger message, before it's buffered.
+	///
+	/// Default is 10 MiB.
+	pub fn max_response_size(mut self, size: usize) -> Self {
+		self.max_response_size = size;
+		self
+	}
+
 	/// Set request timeout.
 	pub fn request_timeout(mut self, timeout: Option<Duration>) -> Self {
 		self.request_timeout = timeout;
@@ -137,12 +524,41 @@ impl<'a> WsClientBuilder<'a> {
 		self
 	}
 
+	/// Set the delay before connecting to the next address of the server while the earlier attempts are still
+	/// pending, see [`WsTransportClientBuilder::with_attempt_delay`].
+	///
+	/// The default delay is 250 milliseconds.
+	pub fn connection_attempt_delay(mut self, delay: Duration) -> Self {
+		self.connection_attempt_delay = delay;
+		self
+	}
+
 	/// Set origin header to pass during the handshake.
 	pub fn origin_header(mut self, origin: Option<Cow<'a, str>>) -> Self {
 		self.origin = origin;
 		self
 	}
 
+	/// Authenticate with `username` and `password` through the `Authorization` header of the handshake,
+	/// with the `Basic` scheme.
+	///
+	/// The credentials are not sent to another host that the handshake is redirected to and are redacted
+	/// when the builder is formatted with `Debug`.
+	pub fn basic_auth(mut self, username: &str, password: &str) -> Self {
+		self.authorization = Some(Authorization::basic(username, password));
+		self
+	}
+
+	/// Authenticate with `token` through the `Authorization` header of the handshake, with the `Bearer`
+	/// scheme.
+	///
+	/// The token is not sent to another host that the handshake is redirected to and is redacted when
+	/// the builder is formatted with `Debug`.
+	pub fn bearer_token(mut self, token: &str) -> Self {
+		self.authorization = Some(Authorization::bearer(token));
+		self
+	}
+
 	/// Set URL to send during the handshake.
 	pub fn handshake_url(mut self, url: Cow<'a, str>) -> Self {
 		self.handshake_url = url;
@@ -155,7 +571,22 @@ impl<'a> WsClientBuilder<'a> {
 		self
 	}
 
-	/// Set max concurrent notification capacity for each subscription; when the capacity is exceeded the subscription will be dropped.
+	/// Wait for a free request slot once [`WsClientBuilder::max_concurrent_requests`] are in flight instead of
+	/// failing with [`Error::MaxSlotsExceeded`], queueing at most `capacity` calls, subscription requests and
+	/// batch requests which get the slots in the order they were made.
+	///
+	/// The requests made while the queue is full, or that waited longer than `timeout`, fail with
+	/// [`Error::MaxSlotsExceeded`]. The requests collected by the [`WsClientBuilder::batching_window`] after
+	/// the first one of a batch aren't queued.
+	///
+	/// Disabled by default.
+	pub fn request_queue(mut self, capacity: usize, timeout: Option<Duration>) -> Self {
+		self.request_queue = Some((capacity, timeout));
+		self
+	}
+
+	/// Set max concurrent notification capacity for each subscription; when the capacity is exceeded the
+	/// notification is handled according to the [`WsClientBuilder::subscription_overflow_policy`].
 	///
 	/// You can also prevent the subscription being dropped by calling [`WsSubscription::next()`](jsonrpsee_types::client::Subscription) frequently enough
 	/// such that the buffer capacity doesn't exceeds.
@@ -168,6 +599,447 @@ impl<'a> WsClientBuilder<'a> {
 		self
 	}
 
+	/// Set what happens to notifications that arrive while the buffer of a subscription is full, see
+	/// [`WsClientBuilder::max_notifs_per_subscription`].
+	///
+	/// Subscriptions can override it with [`SubscriptionClient::subscribe_with_policy`].
+	///
+	/// Default is [`OverflowPolicy::CloseSubscription`].
+	pub fn subscription_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+		self.overflow_policy = policy;
+		self
+	}
+
+	/// Set a window to coalesce notifications and requests sent in quick succession into batches.
+	///
+	/// Once a notification or request is sent, the following ones are collected for at most `window`
+	/// or until `max_messages` are collected and then sent to the server as a single batch. This trades
+	/// a bounded latency increase for fewer frames under bursty load.
+	///
+	/// Disabled by default.
+	pub fn batching_window(mut self, window: Duration, max_messages: usize) -> Self {
+		self.batching_window = Some(BatchingWindow { window, max_messages, requests: true });
+		self
+	}
+
+	/// Set a window to coalesce notifications sent in quick succession into batches, like
+	/// [`WsClientBuilder::batching_window`] but without delaying the requests, for example for high-frequency
+	/// telemetry.
+	///
+	/// A request sent within the window ends it, the collected notifications are sent before the request.
+	///
+	/// Disabled by default.
+	pub fn notification_batching_window(mut self, window: Duration, max_messages: usize) -> Self {
+		self.batching_window = Some(BatchingWindow { window, max_messages, requests: false });
+		self
+	}
+
+	/// Enable or disable TLS session resumption for `wss://` connections.
+	///
+	/// When enabled, sessions are cached in memory and shared by all clients of the process so that
+	/// reconnecting to the same endpoint skips the full TLS handshake.
+	///
+	/// Enabled by default.
+	pub fn tls_session_resumption(mut self, enabled: bool) -> Self {
+		self.tls_session_resumption = enabled;
+		self
+	}
+
+	/// Use `config` to establish `wss://` connections, for example to trust private CAs, authenticate with
+	/// client certificates or negotiate ALPN protocols.
+	///
+	/// Sessions are stored by the session storage of `config`, which overrides
+	/// [`WsClientBuilder::tls_session_resumption`].
+	///
+	/// By default, the Mozilla root certificates are trusted.
+	pub fn tls_config(mut self, config: rustls::ClientConfig) -> Self {
+		self.tls_config = Some(TlsConfig(Arc::new(config)));
+		self
+	}
+
+	/// Pass every JSON payload sent to and received from the server to `hook`, as it is on the wire, for
+	/// example to capture the traffic for bug reports without a packet sniffer and the TLS keys.
+	///
+	/// The payloads are passed before compression and encryption, pings and pongs are not passed. The hook is
+	/// called from the background task of the client and must not block.
+	///
+	/// Disabled by default.
+	pub fn on_raw_message<F>(mut self, hook: F) -> Self
+	where
+		F: Fn(Direction, &str) + Send + Sync + 'static,
+	{
+		self.raw_tap = Some(RawTap(Arc::new(hook)));
+		self
+	}
+
+	/// Mask the params with `redact` before the requests, notifications and responses are logged, see
+	/// [`Redaction`](jsonrpsee_types::redact::Redaction) to mask params by position or object key.
+	///
+	/// The payloads passed to [`WsClientBuilder::on_raw_message`] are not masked.
+	///
+	/// By default, the params are logged as they are.
+	pub fn redact_logs(mut self, redact: impl Redact) -> Self {
+		self.redactor = LogRedactor::new(redact);
+		self
+	}
+
+	/// Offer `codec` to serialize the messages if the server supports it, see [`Codec`].
+	///
+	/// The codecs are offered in the order they are added and the messages are exchanged as JSON if the server
+	/// accepts none, the payloads passed to [`WsClientBuilder::on_raw_message`] are JSON regardless.
+	///
+	/// By default, no codec is offered.
+	pub fn codec(mut self, codec: impl Codec) -> Self {
+		self.codecs.0.push(Arc::new(codec));
+		self
+	}
+
+	/// Compress messages with the `permessage-deflate` extension if the server supports it, using a LZ77
+	/// window of at most `2^max_window_bits` bytes, or don't offer compression if `None`.
+	///
+	/// Disabled by default.
+	///
+	/// # Panics
+	///
+	/// Panics if `max_window_bits` is not within `9..=15`.
+	pub fn compression(mut self, max_window_bits: Option<u8>) -> Self {
+		if let Some(bits) = max_window_bits {
+			assert!((9..=15).contains(&bits), "max window bits have to be within 9..=15");
+		}
+		self.compression = max_window_bits;
+		self
+	}
+
+	/// Tunnel the connection through the SOCKS5 proxy at `addr`, authenticating with a username and password
+	/// if `auth` is set, for example to connect through Tor.
+	///
+	/// The host of the URL is resolved by the proxy and the TLS stream of `wss://` connections is established
+	/// through the tunnel.
+	///
+	/// Disabled by default.
+	pub fn socks5_proxy(mut self, addr: SocketAddr, auth: Option<(String, String)>) -> Self {
+		self.proxy = Some(Proxy::Socks5(Socks5Proxy { addr, auth }));
+		self
+	}
+
+	/// Tunnel the connection through the HTTP proxy at `addr` with the `CONNECT` method, authenticating with
+	/// the `Basic` scheme if `auth` is set.
+	///
+	/// The host of the URL is resolved by the proxy and the TLS stream of `wss://` connections is established
+	/// through the tunnel. Overrides [`WsClientBuilder::socks5_proxy`] and the reverse.
+	///
+	/// Disabled by default.
+	pub fn http_proxy(mut self, addr: SocketAddr, auth: Option<(String, String)>) -> Self {
+		self.proxy = Some(Proxy::Http(HttpProxy { addr, auth }));
+		self
+	}
+
+	/// Read the proxy from the environment if no proxy is set: `HTTPS_PROXY` for `wss://` URLs and `HTTP_PROXY`
+	/// for `ws://` URLs, hosts matching `NO_PROXY` are connected to directly.
+	///
+	/// The variables are read when the client is built, `http://` and `socks5://` proxy URLs are supported.
+	///
+	/// Disabled by default.
+	pub fn proxy_from_env(mut self, enabled: bool) -> Self {
+		self.proxy_from_env = enabled;
+		self
+	}
+
+	/// Set the framing of the messages exchanged over `unix://` URLs.
+	///
+	/// WebSocket frames by default.
+	#[cfg(all(unix, feature = "uds"))]
+	pub fn uds_framing(mut self, framing: UdsFraming) -> Self {
+		self.uds_framing = framing;
+		self
+	}
+
+	/// Reconnect when the connection is lost, retrying at most `max_retries` times and waiting `backoff`
+	/// before the first retry, doubling the delay after every failed retry.
+	///
+	/// Active subscriptions are re-issued on the new connection and keep delivering notifications through
+	/// their existing [`WsSubscription`](crate::WsSubscription), requests in flight when the connection was
+	/// lost fail with [`Error::ConnectionLost`]. Clients built with [`WsClientBuilder::build_with_stream`] don't
+	/// reconnect.
+	///
+	/// Disabled by default.
+	pub fn reconnect(mut self, max_retries: usize, backoff: Duration) -> Self {
+		self.reconnect = Some((max_retries, backoff));
+		self
+	}
+
+	/// Follow up to `max` redirects of the HTTP handshake, such as the `301` or `307` responses of a gateway,
+	/// to the host or path of their `Location` header.
+	///
+	/// Disabled by default.
+	pub fn max_redirections(mut self, max: usize) -> Self {
+		self.max_redirections = max;
+		self
+	}
+
+	/// Write the messages to the server from a separate task, which the background task hands them over to
+	/// through a queue of `capacity` messages, such that writing large requests or batches doesn't delay the
+	/// processing of the responses and notifications received meanwhile.
+	///
+	/// The background task waits for room in the queue once it is full. If `None`, the background task
+	/// writes the messages itself.
+	///
+	/// Default is `None`.
+	pub fn write_queue(mut self, capacity: Option<usize>) -> Self {
+		self.write_queue = capacity;
+		self
+	}
+
+	/// Send a WebSocket ping every `interval` to keep idle connections open, the connection is lost with
+	/// [`ShutdownReason::Timeout`] if no pong was received within `pong_timeout` of a ping.
+	///
+	/// `pong_timeout` is capped to `interval`.
+	///
+	/// Disabled by default.
+	pub fn ping_interval(mut self, interval: Duration, pong_timeout: Duration) -> Self {
+		self.ping = Some((interval, pong_timeout.min(interval)));
+		self
+	}
+
+	/// Close the subscriptions that received neither notifications nor heartbeats within `timeout`, to detect
+	/// subscriptions whose stream silently died on the server. [`WsSubscription::next`] of a closed subscription
+	/// returns `None` once the buffered notifications are read.
+	///
+	/// The server has to send heartbeats on quiet subscriptions, at an interval well below `timeout`, otherwise
+	/// subscriptions that merely have nothing to report are closed too. Heartbeats, notifications with the
+	/// [`SUBSCRIPTION_HEARTBEAT_METHOD`] method, are never delivered to the subscriptions.
+	///
+	/// Disabled by default.
+	///
+	/// [`SUBSCRIPTION_HEARTBEAT_METHOD`]: jsonrpc::SUBSCRIPTION_HEARTBEAT_METHOD
+	/// [`WsSubscription::next`]: jsonrpsee_types::client::Subscription::next
+	pub fn subscription_heartbeat(mut self, timeout: Duration) -> Self {
+		self.subscription_heartbeat = Some(timeout);
+		self
+	}
+
+	/// Validate the results of method calls with `validator` before decoding them, results it rejects fail
+	/// with [`Error::InvalidResponseField`].
+	///
+	/// Disabled by default.
+	pub fn response_validator(mut self, validator: ResponseValidator) -> Self {
+		self.response_validator = Some(validator);
+		self
+	}
+
+	/// Issue the `preflight` call once connected, building the client fails with its error, such that
+	/// misconfigured endpoints are caught at startup.
+	///
+	/// The preflight isn't issued by [`WsClientBuilder::build_lazy`], nor again after reconnecting.
+	///
+	/// Disabled by default.
+	pub fn preflight(mut self, preflight: Preflight) -> Self {
+		self.preflight = Some(preflight);
+		self
+	}
+
+	/// Apply `options` to the parameters of all calls and to the results of method calls, see [`NumberOptions`].
+	///
+	/// Parameters are already serialized when passed to the client, serialize them with
+	/// [`NumberOptions::to_value`] to also handle non-finite floats.
+	///
+	/// Disabled by default.
+	pub fn number_options(mut self, options: NumberOptions) -> Self {
+		self.number_options = Some(options);
+		self
+	}
+
+	/// Re-issue the method calls that failed with a timeout or because the connection was lost according to
+	/// `policy`, calls answered with a JSON-RPC error are never retried.
+	///
+	/// Calls only fail with [`Error::ConnectionLost`] if the client [reconnects](WsClientBuilder::reconnect).
+	/// Notifications, batch requests and subscription requests are not retried.
+	///
+	/// Disabled by default.
+	pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+		self.retry_policy = Some(policy);
+		self
+	}
+
+	/// Generate the IDs of the requests with `provider` instead of numbering them, see [`IdProvider`].
+	///
+	/// Disabled by default.
+	pub fn id_provider(mut self, provider: impl IdProvider) -> Self {
+		self.id_provider = Some(SharedIdProvider(Arc::new(provider)));
+		self
+	}
+
+	/// Report the method calls, batch requests and subscription notifications of the client to `metrics`.
+	///
+	/// Disabled by default.
+	pub fn metrics(mut self, metrics: impl ClientMetrics) -> Self {
+		self.metrics = Some(SharedMetrics(Arc::new(metrics)));
+		self
+	}
+
+	/// Call `hook` when the background task stops because of an error or a panic, with the context of the
+	/// failure such as the last message received from the server, for postmortems of
+	/// [`Error::RestartNeeded`].
+	///
+	/// The hook is called from the background task and must not block. The last message is only recorded
+	/// if a hook is set.
+	pub fn on_background_error<F>(mut self, hook: F) -> Self
+	where
+		F: Fn(&BackgroundError) + Send + Sync + 'static,
+	{
+		self.error_hook = Some(ErrorHook(Arc::new(hook)));
+		self
+	}
+
+	/// Accept the responses to batch requests split across several frames, or sent one by one, for
+	/// compatibility with servers that don't answer a batch with a single array.
+	///
+	/// A response whose ID matches both a pending call and a request of a pending batch is matched to the
+	/// call, use [`WsClientBuilder::id_provider`] to generate distinct IDs.
+	///
+	/// Disabled by default.
+	pub fn lenient_batch_responses(mut self, lenient: bool) -> Self {
+		self.lenient_batches = lenient;
+		self
+	}
+
+	/// Send the calls of a batch request one by one if the server rejects the batch, for compatibility with
+	/// servers without batch support.
+	///
+	/// A batch is rejected by an error without ID, a parse error or an invalid request error, answering the
+	/// oldest pending batch. Once a batch was rejected the following batches are split right away, see
+	/// [`WsClient::split_batches`]. The results are returned in the order of the calls as for a batch.
+	///
+	/// Disabled by default.
+	pub fn split_rejected_batches(mut self, split: bool) -> Self {
+		self.split_rejected_batches = split;
+		self
+	}
+
+	/// Reject the subscriptions that the server acknowledges with an empty subscription ID, or one containing
+	/// whitespace or control characters, with [`Error::InvalidSubscriptionId`].
+	///
+	/// Disabled by default.
+	pub fn strict_subscription_ids(mut self, strict: bool) -> Self {
+		self.strict_subscription_ids = strict;
+		self
+	}
+
+	/// Send a [`CANCEL_METHOD`] notification with the ID of every call whose request is dropped, or cancelled
+	/// with a [`CancelHandle`], before it's answered, such that the server can stop its handler.
+	///
+	/// The server must support the optional [`CANCEL_METHOD`] extension.
+	///
+	/// Disabled by default.
+	///
+	/// [`CANCEL_METHOD`]: jsonrpc::CANCEL_METHOD
+	pub fn cancel_dropped_requests(mut self, cancel: bool) -> Self {
+		self.cancel_dropped_requests = cancel;
+		self
+	}
+
+	/// Pass the method calls of the client through `middleware`, after the middlewares registered before.
+	///
+	/// Middlewares are specific to the value type, thus [`WsClientBuilder::value_type`] removes them.
+	pub fn middleware(mut self, middleware: impl ClientMiddleware<V>) -> Self {
+		self.middlewares.0.push(Arc::new(middleware));
+		self
+	}
+
+	/// Returns the options of the builder that are plain data, see [`ClientConfig`].
+	pub fn to_config(&self) -> ClientConfig {
+		let millis = |duration: Duration| duration.as_millis() as u64;
+		ClientConfig {
+			max_request_body_size: self.max_request_body_size,
+			max_response_size: self.max_response_size,
+			request_timeout_ms: self.request_timeout.map(millis),
+			connection_timeout_ms: millis(self.connection_timeout),
+			connection_attempt_delay_ms: millis(self.connection_attempt_delay),
+			origin_header: self.origin.as_ref().map(|origin| origin.to_string()),
+			handshake_url: self.handshake_url.to_string(),
+			max_concurrent_requests: self.max_concurrent_requests,
+			request_queue: self
+				.request_queue
+				.map(|(capacity, timeout)| RequestQueueConfig { capacity, timeout_ms: timeout.map(millis) }),
+			max_notifs_per_subscription: self.max_notifs_per_subscription,
+			subscription_overflow_policy: self.overflow_policy,
+			batching_window: self.batching_window.map(|batching| BatchingWindowConfig {
+				window_ms: millis(batching.window),
+				max_messages: batching.max_messages,
+				notifications_only: !batching.requests,
+			}),
+			tls_session_resumption: self.tls_session_resumption,
+			compression: self.compression,
+			proxy_from_env: self.proxy_from_env,
+			#[cfg(all(unix, feature = "uds"))]
+			uds_framing: self.uds_framing,
+			reconnect: self
+				.reconnect
+				.map(|(max_retries, backoff)| ReconnectConfig { max_retries, backoff_ms: millis(backoff) }),
+			max_redirections: self.max_redirections,
+			write_queue: self.write_queue,
+			ping_interval: self.ping.map(|(interval, pong_timeout)| PingConfig {
+				interval_ms: millis(interval),
+				pong_timeout_ms: millis(pong_timeout),
+			}),
+			subscription_heartbeat_ms: self.subscription_heartbeat.map(millis),
+			number_options: self.number_options,
+			lenient_batch_responses: self.lenient_batches,
+			split_rejected_batches: self.split_rejected_batches,
+			strict_subscription_ids: self.strict_subscription_ids,
+			cancel_dropped_requests: self.cancel_dropped_requests,
+		}
+	}
+
+	/// Set the intermediate value type responses are decoded into, defaults to [`JsonValue`].
+	///
+	/// The middlewares registered before are removed.
+	pub fn value_type<T: Value>(self) -> WsClientBuilder<'a, T> {
+		WsClientBuilder {
+			max_request_body_size: self.max_request_body_size,
+			max_response_size: self.max_response_size,
+			request_timeout: self.request_timeout,
+			connection_timeout: self.connection_timeout,
+			connection_attempt_delay: self.connection_attempt_delay,
+			origin: self.origin,
+			authorization: self.authorization,
+			handshake_url: self.handshake_url,
+			max_concurrent_requests: self.max_concurrent_requests,
+			request_queue: self.request_queue,
+			max_notifs_per_subscription: self.max_notifs_per_subscription,
+			overflow_policy: self.overflow_policy,
+			batching_window: self.batching_window,
+			tls_session_resumption: self.tls_session_resumption,
+			tls_config: self.tls_config,
+			raw_tap: self.raw_tap,
+			codecs: self.codecs,
+			redactor: self.redactor,
+			compression: self.compression,
+			proxy: self.proxy,
+			proxy_from_env: self.proxy_from_env,
+			#[cfg(all(unix, feature = "uds"))]
+			uds_framing: self.uds_framing,
+			reconnect: self.reconnect,
+			max_redirections: self.max_redirections,
+			write_queue: self.write_queue,
+			ping: self.ping,
+			subscription_heartbeat: self.subscription_heartbeat,
+			response_validator: self.response_validator,
+			preflight: self.preflight,
+			number_options: self.number_options,
+			retry_policy: self.retry_policy,
+			id_provider: self.id_provider,
+			metrics: self.metrics,
+			error_hook: self.error_hook,
+			lenient_batches: self.lenient_batches,
+			split_rejected_batches: self.split_rejected_batches,
+			strict_subscription_ids: self.strict_subscription_ids,
+			cancel_dropped_requests: self.cancel_dropped_requests,
+			middlewares: Middlewares::default(),
+			marker: PhantomData,
+		}
+	}
+
 	/// Build the client with specified URL to connect to.
 	/// If the port number is missing from the URL, the default port number is used.
 	///
@@ -175,52 +1047,675 @@ impl<'a> WsClientBuilder<'a> {
 	/// `ws://host` - port 80 is used
 	///
 	/// `wss://host` - port 443 is used
-	pub async fn build(self, url: &'a str) -> Result<WsClient, Error> {
-		let max_capacity_per_subscription = self.max_notifs_per_subscription;
-		let max_concurrent_requests = self.max_concurrent_requests;
-		let request_timeout = self.request_timeout;
-		let (to_back, from_front) = mpsc::channel(self.max_concurrent_requests);
-		let (err_tx, err_rx) = oneshot::channel();
+	///
+	/// `unix:///path/to.sock` - connects to the Unix domain socket at `/path/to.sock`, see
+	/// [`WsClientBuilder::uds_framing`], requires the `uds` feature
+	pub async fn build(self, url: &'a str) -> Result<WsClient<V>, Error> {
+		self.build_scoped(url, spawn_detached).await
+	}
+
+	/// Build the client like [`WsClientBuilder::build`] but hand its background task over to `spawn`, for
+	/// example to spawn it in a `tokio::task::JoinSet` or another task scope.
+	///
+	/// The client is shut down when the task is dropped, such as when the scope ends: pending and later
+	/// calls fail with [`Error::RestartNeeded`] and [`ShutdownReason::Aborted`].
+	pub async fn build_scoped<F>(self, url: &'a str, spawn: F) -> Result<WsClient<V>, Error>
+	where
+		F: FnOnce(BoxFuture<'static, ()>),
+	{
+		let transport = self.transport(url)?;
+		let reconnect = self.reconnect.map(|(max_retries, backoff)| Reconnect {
+			transports: vec![transport.clone().into_owned()],
+			max_retries,
+			backoff,
+		});
+		let (sender, receiver) = transport.build().await.map_err(|e| Error::TransportError(Box::new(e)))?;
+		let preflight = self.preflight.clone();
+		check(self.spawn(Connection::Established(sender, receiver), reconnect, spawn), preflight).await
+	}
+
+	/// Build the client like [`WsClientBuilder::build`] connecting to the first of `urls` that accepts the
+	/// connection, such that the client survives its primary endpoint going down.
+	///
+	/// The endpoints are tried in order, when connecting and on every attempt to reconnect if
+	/// [`WsClientBuilder::reconnect`] is enabled, thus the client goes back to the primary endpoint once it's
+	/// up again when the connection is lost. Fails with the error of the last endpoint if none accepts the
+	/// connection.
+	pub async fn build_with_fallbacks(self, urls: &[&'a str]) -> Result<WsClient<V>, Error> {
+		let transports = urls.iter().map(|url| Ok(self.transport(url)?.into_owned())).collect::<Result<Vec<_>, _>>()?;
+		let (sender, receiver) = connect_any(&transports).await.map_err(|e| Error::TransportError(Box::new(e)))?;
+		let reconnect = self.reconnect.map(|(max_retries, backoff)| Reconnect { transports, max_retries, backoff });
+		let preflight = self.preflight.clone();
+		check(self.spawn(Connection::Established(sender, receiver), reconnect, spawn_detached), preflight).await
+	}
+
+	/// Build the client like [`WsClientBuilder::build`] but without connecting, the server is dialed on the
+	/// first call or subscription, for example when the server starts after the client.
+	///
+	/// Up to `max_queued` calls wait for the connection, further calls fail with [`Error::MaxSlotsExceeded`].
+	/// If dialing fails, the waiting calls fail with [`Error::ConnectionLost`] and the server is dialed again
+	/// on the next call. [`WsClient::is_connected`] returns `true` until the client is shut down, whether the
+	/// server was dialed or not.
+	pub fn build_lazy(self, url: &'a str, max_queued: usize) -> Result<WsClient<V>, Error> {
+		let transport = self.transport(url)?.into_owned();
+		let reconnect = self.reconnect.map(|(max_retries, backoff)| Reconnect {
+			transports: vec![transport.clone()],
+			max_retries,
+			backoff,
+		});
+		Ok(self.spawn(Connection::Lazy(LazyConnection::new(transport, max_queued)), reconnect, spawn_detached))
+	}
+
+	/// Returns the transport connecting to `url`.
+	fn transport(&self, url: &'a str) -> Result<WsTransportClientBuilder<'a>, Error> {
+		#[cfg(all(unix, feature = "uds"))]
+		if let Some(path) = url.strip_prefix("unix://") {
+			return Ok(self
+				.transport_builder(Vec::new(), Host::from("localhost"), Mode::Plain)
+				.with_unix_socket(path, self.uds_framing));
+		}
+		self.tcp_transport(url)
+	}
+
+	/// Returns the transport connecting to the TCP socket of `url`, directly or through a proxy.
+	fn tcp_transport(&self, url: &'a str) -> Result<WsTransportClientBuilder<'a>, Error> {
+		let (host, port, mode) = parse_url_unresolved(url).map_err(|e| Error::TransportError(Box::new(e)))?;
+		let proxy = match self.proxy.clone() {
+			Some(proxy) => Some(proxy),
+			None if self.proxy_from_env => {
+				Proxy::from_env(&host, mode).map_err(|e| Error::TransportError(Box::new(e)))?
+			}
+			None => None,
+		};
+		let transport = match proxy {
+			// The proxy resolves the host, it may not be resolvable locally such as onion addresses.
+			Some(proxy) => self.transport_builder(Vec::new(), host, mode).with_proxy(proxy, port),
+			None => {
+				let (sockaddrs, host, mode) = parse_url(url).map_err(|e| Error::TransportError(Box::new(e)))?;
+				self.transport_builder(sockaddrs, host, mode)
+			}
+		};
+		Ok(transport)
+	}
 
-		let (sockaddrs, host, mode) = parse_url(url).map_err(|e| Error::TransportError(Box::new(e)))?;
+	/// Build the client over an already established stream, such as a TLS tunnel or a multiplexed substream.
+	///
+	/// Only the WebSocket handshake is performed on the stream and `host` is passed in the `Host` header.
+	pub async fn build_with_stream(self, host: &str, stream: impl WsStream) -> Result<WsClient<V>, Error> {
+		let (sender, receiver) = self
+			.transport_builder(Vec::new(), Host::from(host), Mode::Plain)
+			.build_with_stream(stream)
+			.await
+			.map_err(|e| Error::TransportError(Box::new(e)))?;
+		let preflight = self.preflight.clone();
+		check(self.spawn(Connection::Established(sender, receiver), None, spawn_detached), preflight).await
+	}
 
-		let builder = WsTransportClientBuilder {
+	fn transport_builder(&self, sockaddrs: Vec<SocketAddr>, host: Host, mode: Mode) -> WsTransportClientBuilder<'a> {
+		WsTransportClientBuilder {
 			sockaddrs,
 			mode,
 			host,
-			handshake_url: self.handshake_url,
+			handshake_url: self.handshake_url.clone(),
 			timeout: self.connection_timeout,
+			attempt_delay: self.connection_attempt_delay,
 			origin: None,
+			authorization: self.authorization.clone(),
 			max_request_body_size: self.max_request_body_size,
+			max_response_size: self.max_response_size,
+			tls_session_resumption: self.tls_session_resumption,
+			tls_config: self.tls_config.clone(),
+			compression: self.compression,
+			proxy: None,
+			max_redirections: self.max_redirections,
+			write_queue: self.write_queue,
+			raw_tap: self.raw_tap.clone(),
+			codecs: self.codecs.clone(),
+			redactor: self.redactor.clone(),
+			#[cfg(all(unix, feature = "uds"))]
+			unix_socket: None,
+		}
+	}
+
+	fn spawn(
+		self,
+		connection: Connection,
+		reconnect: Option<Reconnect>,
+		spawn: impl FnOnce(BoxFuture<'static, ()>),
+	) -> WsClient<V> {
+		let max_capacity_per_subscription = self.max_notifs_per_subscription;
+		let batching_window = self.batching_window;
+		let mut manager = RequestManager::new(self.max_concurrent_requests)
+			.with_id_provider(self.id_provider)
+			.with_metrics(self.metrics)
+			.with_lenient_batches(self.lenient_batches)
+			.with_split_rejected_batches(self.split_rejected_batches)
+			.with_strict_subscription_ids(self.strict_subscription_ids)
+			.with_cancel_notifications(self.cancel_dropped_requests);
+		let queue = self.request_queue.map(|(capacity, timeout)| RequestQueue::new(capacity, timeout));
+		let ping = self.ping;
+		let subscription_heartbeat = self.subscription_heartbeat;
+		let error_hook = self.error_hook.clone();
+		let (to_back, from_front) = mpsc::channel(self.max_concurrent_requests);
+		let (err_tx, err_rx) = oneshot::channel();
+		let subscriptions = SubscriptionRegistry::default();
+		let back_subscriptions = subscriptions.clone();
+		let connection_listeners = ConnectionListeners::new();
+		let back_connection_listeners = connection_listeners.clone();
+
+		spawn(Box::pin(async move {
+			let mut from_front = from_front;
+			let (sender, receiver, pending) = match connection {
+				Connection::Established(sender, receiver) => (sender, receiver, Vec::new()),
+				Connection::Lazy(lazy) => match lazy.connect(&mut from_front, &mut manager).await {
+					Some(connected) => connected,
+					None => return,
+				},
+			};
+			let ctx = BackgroundContext {
+				front_error: err_tx,
+				max_notifs_per_subscription: max_capacity_per_subscription,
+				batching_window,
+				subscriptions: back_subscriptions,
+				connection_listeners: back_connection_listeners,
+				reconnect,
+				manager,
+				queue,
+				ping,
+				subscription_heartbeat,
+				error_hook,
+			};
+			let (sender, receiver) =
+				(jsonrpc_transport::Sender::new(sender), jsonrpc_transport::Receiver::new(receiver));
+			background_task(sender, receiver, from_front, pending, ctx).await;
+		}));
+		WsClient {
+			shared_to_back: Mutex::new(to_back.clone()),
+			to_back,
+			request_timeout: self.request_timeout,
+			error: Mutex::new(ErrorFromBack::Unread(err_rx)),
+			subscriptions,
+			connection_listeners,
+			validator: self.response_validator,
+			number_options: self.number_options,
+			retry_policy: self.retry_policy,
+			overflow_policy: self.overflow_policy,
+			max_notifs_per_subscription: self.max_notifs_per_subscription,
+			middlewares: self.middlewares,
+			split_rejected_batches: self.split_rejected_batches,
+			split_batches: AtomicU64::new(0),
+			redactor: self.redactor,
+		}
+	}
+}
+
+/// Issues the `preflight` call with the newly built `client`, see [`WsClientBuilder::preflight`].
+async fn check<V: Value>(client: WsClient<V>, preflight: Option<Preflight>) -> Result<WsClient<V>, Error> {
+	if let Some(preflight) = preflight {
+		preflight.run(&client).await?;
+	}
+	Ok(client)
+}
+
+/// Handle cancelling a request of [`WsClient::cancellable_request`].
+#[derive(Debug)]
+pub struct CancelHandle(oneshot::Sender<()>);
+
+impl CancelHandle {
+	/// Cancel the request, which fails with [`Error::RequestCancelled`] unless it was already answered.
+	///
+	/// Dropping the handle doesn't cancel the request.
+	pub fn cancel(self) {
+		let _ = self.0.send(());
+	}
+}
+
+/// Frees the slot of a request in the background task if the request is dropped before it's answered.
+struct CancelOnDrop<V>(Option<mpsc::Sender<FrontToBack<V>>>);
+
+impl<V> CancelOnDrop<V> {
+	fn disarm(mut self) {
+		self.0 = None;
+	}
+}
+
+impl<V> Drop for CancelOnDrop<V> {
+	fn drop(&mut self) {
+		// NOTE: every clone of a `mpsc::Sender` has a guaranteed slot in the channel, thus this only fails if
+		// the background task terminated.
+		if let Some(mut to_back) = self.0.take() {
+			let _ = to_back.try_send(FrontToBack::RequestCancelled);
+		}
+	}
+}
+
+impl<V: Value> WsClient<V> {
+	/// Checks if the client is connected to the target.
+	pub fn is_connected(&self) -> bool {
+		!self.to_back.is_closed()
+	}
+
+	/// Returns the active subscriptions of the client, in no particular order.
+	///
+	/// The subscriptions remain available after the connection was lost, such that they can be
+	/// re-created on a new client with [`WsClient::restore_subscriptions`].
+	pub fn subscriptions(&self) -> Vec<SubscriptionDescriptor> {
+		self.subscriptions.active()
+	}
+
+	/// Subscribes again to the subscriptions exported by [`WsClient::subscriptions`] of another client.
+	///
+	/// Returns the result of each subscription in the order of `descriptors`.
+	pub async fn restore_subscriptions<N>(
+		&self,
+		descriptors: impl IntoIterator<Item = SubscriptionDescriptor>,
+	) -> Vec<Result<Subscription<N, V>, Error>>
+	where
+		N: DeserializeOwned,
+	{
+		let mut subscriptions = Vec::new();
+		for descriptor in descriptors {
+			let subscription =
+				self.subscribe(descriptor.subscribe_method, descriptor.params, descriptor.unsubscribe_method).await;
+			subscriptions.push(subscription);
+		}
+		subscriptions
+	}
+
+	/// Prepare a call to `method`, the options of the call are set on the returned builder.
+	///
+	/// ```no_run
+	/// # async fn call(client: jsonrpsee_ws_client::WsClient) -> Result<(), jsonrpsee_types::error::Error> {
+	/// use std::time::Duration;
+	///
+	/// let sum: u64 = client.call("add").params((1, 2)).timeout(Duration::from_secs(5)).send().await?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn call(&self, method: impl Into<String>) -> CallBuilder<'_, V> {
+		CallBuilder::new(self, method.into())
+	}
+
+	/// Perform a request towards the server that can be cancelled with the returned [`CancelHandle`] while
+	/// the response is awaited, for example by another task.
+	pub fn cancellable_request<'a, T, M, P>(
+		&'a self,
+		method: M,
+		params: P,
+	) -> (CancelHandle, impl Future<Output = Result<T, Error>> + 'a)
+	where
+		T: DeserializeOwned + 'a,
+		M: Into<String>,
+		P: Into<jsonrpc::Params>,
+	{
+		let (cancel_tx, cancel_rx) = oneshot::channel();
+		let request = self.send_request(method.into(), params.into(), self.request_timeout);
+		let response = async move {
+			futures::pin_mut!(request);
+			match future::select(request, cancel_rx).await {
+				Either::Left((response, _)) => response,
+				Either::Right((Ok(()), _)) => Err(Error::RequestCancelled),
+				// The handle was dropped.
+				Either::Right((Err(_), request)) => request.await,
+			}
 		};
+		(CancelHandle(cancel_tx), response)
+	}
+
+	/// Returns the changes of the state of the connection from now on.
+	///
+	/// The stream ends once the background task terminated, after a [`ConnectionEvent::Disconnected`] with
+	/// the reason unless the client was dropped.
+	pub fn connection_events(&self) -> mpsc::UnboundedReceiver<ConnectionEvent> {
+		self.connection_listeners.listen()
+	}
+
+	/// Returns the number of batch requests whose calls were sent one by one because the server rejected
+	/// batches, see [`WsClientBuilder::split_rejected_batches`].
+	pub fn split_batches(&self) -> u64 {
+		self.split_batches.load(Ordering::Relaxed)
+	}
+
+	/// Returns the requests, subscriptions and buffered notifications held by the background task, to
+	/// diagnose leaks and size the buffers of long-running clients.
+	///
+	/// Fails with [`Error::RestartNeeded`] if the background task terminated.
+	pub async fn memory_stats(&self) -> Result<MemoryStats, Error> {
+		let (send_back_tx, send_back_rx) = oneshot::channel();
+		self.send_to_back(FrontToBack::MemoryStats(send_back_tx)).await?;
+		match send_back_rx.await {
+			Ok(stats) => Ok(stats),
+			Err(_) => Err(self.read_error_from_backend().await),
+		}
+	}
+
+	/// Returns the subscriptions accepted by the server and not yet closed, with their buffered notifications,
+	/// to debug the client or display the state of live streams.
+	///
+	/// Fails with [`Error::RestartNeeded`] if the background task terminated, see
+	/// [`WsClient::subscriptions`] to export the subscriptions regardless.
+	pub async fn active_subscriptions(&self) -> Result<Vec<ActiveSubscription>, Error> {
+		let (send_back_tx, send_back_rx) = oneshot::channel();
+		self.send_to_back(FrontToBack::ActiveSubscriptions(send_back_tx)).await?;
+		match send_back_rx.await {
+			Ok(active) => Ok(active),
+			Err(_) => Err(self.read_error_from_backend().await),
+		}
+	}
+
+	/// Waits until the background task terminated and returns the reason, such that the client can be
+	/// replaced without waiting for a call to fail.
+	///
+	/// Returns immediately if the background task already terminated.
+	pub async fn on_disconnect(&self) -> ShutdownReason {
+		let mut events = self.connection_events();
+		while events.next().await.is_some() {}
+		match self.read_error_from_backend().await {
+			Error::RestartNeeded(reason) => reason,
+			err => ShutdownReason::Other(err.to_string()),
+		}
+	}
+
+	/// Ask the server to stop sending the notifications of `subscription` until it's resumed with
+	/// [`WsClient::resume_subscription`], for example while the UI showing them is hidden.
+	///
+	/// The server must support the optional [`SUBSCRIPTION_PAUSE_METHOD`] extension, it may send the
+	/// notifications that it buffered while paused when resuming. Returns `false` if the server doesn't know the
+	/// subscription.
+	///
+	/// [`SUBSCRIPTION_PAUSE_METHOD`]: jsonrpc::SUBSCRIPTION_PAUSE_METHOD
+	pub async fn pause_subscription<N>(&self, subscription: &Subscription<N, V>) -> Result<bool, Error> {
+		self.subscription_control(jsonrpc::SUBSCRIPTION_PAUSE_METHOD, subscription).await
+	}
+
+	/// Ask the server to resume a subscription paused with [`WsClient::pause_subscription`].
+	///
+	/// Returns `false` if the server doesn't know the subscription.
+	pub async fn resume_subscription<N>(&self, subscription: &Subscription<N, V>) -> Result<bool, Error> {
+		self.subscription_control(jsonrpc::SUBSCRIPTION_RESUME_METHOD, subscription).await
+	}
+
+	/// Send a subscription request to the server like [`SubscriptionClient::subscribe`], with its notifications
+	/// mapped or discarded by `filter` before they are buffered, see [`NotifFilter`].
+	///
+	/// The filter is called with the notifications encoded as `V`, that is as JSON by default, and is kept when
+	/// the subscription is restored after a reconnect.
+	pub async fn subscribe_filtered<SM, UM, P, N>(
+		&self,
+		subscribe_method: SM,
+		params: P,
+		unsubscribe_method: UM,
+		filter: impl Fn(V) -> Option<V> + Send + Sync + 'static,
+	) -> Result<Subscription<N, V>, Error>
+	where
+		SM: Into<String> + Send,
+		UM: Into<String> + Send,
+		P: Into<jsonrpc::Params> + Send,
+		N: DeserializeOwned,
+	{
+		let filter = Some(NotifFilter::new(filter));
+		self.start_subscription(
+			subscribe_method.into(),
+			params.into(),
+			unsubscribe_method.into(),
+			self.overflow_policy,
+			filter,
+		)
+		.await
+	}
+
+	async fn start_subscription<N>(
+		&self,
+		subscribe_method: String,
+		params: jsonrpc::Params,
+		unsubscribe_method: String,
+		overflow_policy: OverflowPolicy,
+		filter: Option<NotifFilter<V>>,
+	) -> Result<Subscription<N, V>, Error> {
+		let params = self.encode_params(params)?;
+
+		if subscribe_method == unsubscribe_method {
+			return Err(Error::Subscription(subscribe_method, unsubscribe_method));
+		}
+
+		log::trace!("[frontend]: subscribe: {:?}, unsubscribe: {:?}", subscribe_method, unsubscribe_method);
+		let (send_back_tx, send_back_rx) = oneshot::channel();
+		let span = RequestSpan::subscription(&subscribe_method);
+		self.send_to_back(FrontToBack::Subscribe(SubscriptionMessage {
+			subscribe_method,
+			unsubscribe_method,
+			params,
+			send_back: send_back_tx,
+			overflow_policy,
+			filter,
+			span,
+		}))
+		.await?;
+
+		let (notifs_rx, id) = match send_back_rx.await {
+			Ok(Ok(val)) => val,
+			Ok(Err(err)) => return Err(err),
+			Err(_) => return Err(self.read_error_from_backend().await),
+		};
+		Ok(Subscription { to_back: self.to_back.clone(), notifs_rx, marker: PhantomData, id })
+	}
+
+	async fn subscription_control<N>(&self, method: &str, subscription: &Subscription<N, V>) -> Result<bool, Error> {
+		let id = serde_json::to_value(&subscription.id).map_err(Error::ParseError)?;
+		self.send_request(method.into(), jsonrpc::Params::Array(vec![id]), self.request_timeout).await
+	}
+
+	/// Returns the parameters of the notifications of `method` that the server sends without a subscription.
+	///
+	/// Notifications without a handler are dropped, notifications arriving while the stream holds
+	/// [`WsClientBuilder::max_notifs_per_subscription`] notifications are dropped as well.
+	pub async fn on_notification(&self, method: impl Into<String>) -> Result<mpsc::Receiver<jsonrpc::Params>, Error> {
+		let (sink, notifs) = mpsc::channel(self.max_notifs_per_subscription);
+		let method = method.into();
+		log::trace!("[frontend]: register notification handler: {:?}", method);
+		self.send_to_back(FrontToBack::RegisterNotificationHandler(NotificationHandlerMessage { method, sink }))
+			.await?;
+		Ok(notifs)
+	}
+
+	/// Sends a message to the background task and waits for capacity in the channel if it's full.
+	///
+	/// Waiting for capacity is bounded by the request timeout, if configured.
+	async fn send_to_back(&self, msg: FrontToBack<V>) -> Result<(), Error> {
+		let res = {
+			let mut to_back = self.shared_to_back.lock().await;
+			if let Some(duration) = self.request_timeout {
+				let call = call_info(&msg);
+				let send = to_back.send(msg);
+				let timeout = async_std::task::sleep(duration);
+				futures::pin_mut!(send, timeout);
+				match future::select(send, timeout).await {
+					future::Either::Left((res, _)) => res,
+					future::Either::Right((_, _)) => return Err(Error::WsRequestTimeout(call)),
+				}
+			} else {
+				to_back.send(msg).await
+			}
+		};
+
+		match res {
+			Ok(()) => Ok(()),
+			Err(_) => Err(self.read_error_from_backend().await),
+		}
+	}
+
+	pub(crate) async fn send_request<T: DeserializeOwned>(
+		&self,
+		method: String,
+		params: jsonrpc::Params,
+		timeout: Option<Duration>,
+	) -> Result<T, Error> {
+		if self.middlewares.0.is_empty() {
+			let value = self.send_call(method, params, timeout).await?;
+			return self.decode(value);
+		}
+
+		let mut call = CallContext::new(method, params);
+		let mut response = match self.middlewares.0.iter().find_map(|middleware| middleware.on_request(&mut call)) {
+			Some(value) => Ok(value),
+			None => self.send_call(call.method.clone(), call.params.clone(), timeout).await,
+		};
+		for middleware in &self.middlewares.0 {
+			middleware.on_response(&call, &mut response);
+		}
+		self.decode(response?)
+	}
+
+	/// Sends a call to `method` and retries it according to the retry policy.
+	async fn send_call(&self, method: String, params: jsonrpc::Params, timeout: Option<Duration>) -> Result<V, Error> {
+		let policy = match self.retry_policy.as_ref() {
+			Some(policy) => policy,
+			None => return self.send_call_once(method, params, timeout).await,
+		};
+		let mut retries = 0;
+		loop {
+			let err = match self.send_call_once(method.clone(), params.clone(), timeout).await {
+				Ok(value) => return Ok(value),
+				Err(err) => err,
+			};
+			match policy.next_retry(retries, &err) {
+				Some(delay) => {
+					log::debug!("[frontend]: retry call to {} in {:?} after: {}", method, delay, err);
+					async_std::task::sleep(delay).await;
+					retries += 1;
+				}
+				None => return Err(err),
+			}
+		}
+	}
+
+	async fn send_call_once(
+		&self,
+		method: String,
+		params: jsonrpc::Params,
+		timeout: Option<Duration>,
+	) -> Result<V, Error> {
+		let params = self.encode_params(params)?;
+		log::trace!("[frontend]: send request: method={:?}, params={}", method, self.redactor.params(&method, &params));
+		let (send_back_tx, send_back_rx) = oneshot::channel();
+		let call = CallInfo::new(method.clone(), None);
+
+		let span = RequestSpan::call(&method);
+		self.send_to_back(FrontToBack::StartRequest(RequestMessage {
+			method,
+			params,
+			send_back: Some(send_back_tx),
+			span,
+		}))
+		.await?;
+		let cancel = CancelOnDrop(Some(self.to_back.clone()));
+
+		let send_back_rx_out = if let Some(duration) = timeout {
+			let timeout = async_std::task::sleep(duration);
+			futures::pin_mut!(send_back_rx, timeout);
+			match future::select(send_back_rx, timeout).await {
+				future::Either::Left((send_back_rx_out, _)) => send_back_rx_out,
+				future::Either::Right((_, _)) => return Err(Error::WsRequestTimeout(call)),
+			}
+		} else {
+			send_back_rx.await
+		};
+		cancel.disarm();
+
+		let value = match send_back_rx_out {
+			Ok(Ok(v)) => v,
+			Ok(Err(err)) => return Err(err),
+			Err(_) => return Err(self.read_error_from_backend().await),
+		};
+		if let Some(validator) = self.validator.as_ref() {
+			validator.validate(&call.method, &value)?;
+		}
+		Ok(value)
+	}
+
+	/// Sends a batch request and returns the results of its calls, in the order of the calls.
+	async fn send_batch<M, P>(&self, batch: Vec<(M, P)>) -> Result<Vec<Result<V, jsonrpc::Error>>, Error>
+	where
+		M: Into<String>,
+		P: Into<jsonrpc::Params>,
+	{
+		let calls: Vec<(String, jsonrpc::Params)> = batch.into_iter().map(|(r, p)| (r.into(), p.into())).collect();
+		if !self.split_rejected_batches {
+			return self.send_batch_once(calls).await;
+		}
+		if self.split_batches() == 0 {
+			match self.send_batch_once(calls.clone()).await {
+				Err(Error::Request(err)) => {
+					log::warn!("[frontend]: batch rejected by the server, sending its calls one by one: {:?}", err)
+				}
+				res => return res,
+			}
+		}
+		self.split_batches.fetch_add(1, Ordering::Relaxed);
+		let mut values = Vec::with_capacity(calls.len());
+		for (method, params) in calls {
+			match self.send_call_once(method, params, self.request_timeout).await {
+				Ok(value) => values.push(Ok(value)),
+				Err(Error::Request(err)) => values.push(Err(err)),
+				Err(err) => return Err(err),
+			}
+		}
+		Ok(values)
+	}
+
+	async fn send_batch_once(
+		&self,
+		calls: Vec<(String, jsonrpc::Params)>,
+	) -> Result<Vec<Result<V, jsonrpc::Error>>, Error> {
+		let (send_back_tx, send_back_rx) = oneshot::channel();
+		let requests: Vec<(String, jsonrpc::Params)> = calls
+			.into_iter()
+			.map(|(method, params)| Ok((method, self.encode_params(params)?)))
+			.collect::<Result<_, Error>>()?;
+		log::trace!(
+			"[frontend]: send batch request: {:?}",
+			requests.iter().map(|(method, _)| method).collect::<Vec<_>>()
+		);
+		let validated_methods: Option<Vec<String>> =
+			self.validator.as_ref().map(|_| requests.iter().map(|(method, _)| method.clone()).collect());
+		let span = RequestSpan::batch(requests.len());
+		self.send_to_back(FrontToBack::Batch(BatchMessage { requests, send_back: send_back_tx, span })).await?;
+
+		let values = match send_back_rx.await {
+			Ok(Ok(v)) => v,
+			Ok(Err(err)) => return Err(err),
+			Err(_) => return Err(self.read_error_from_backend().await),
+		};
+
+		if let (Some(validator), Some(methods)) = (self.validator.as_ref(), validated_methods) {
+			for (method, value) in methods.iter().zip(&values) {
+				if let Ok(value) = value {
+					validator.validate(method, value)?;
+				}
+			}
+		}
+		Ok(values)
+	}
 
-		let (sender, receiver) = builder.build().await.map_err(|e| Error::TransportError(Box::new(e)))?;
-
-		async_std::task::spawn(async move {
-			background_task(
-				jsonrpc_transport::Sender::new(sender),
-				jsonrpc_transport::Receiver::new(receiver),
-				from_front,
-				err_tx,
-				max_capacity_per_subscription,
-				max_concurrent_requests,
-			)
-			.await;
-		});
-		Ok(WsClient { to_back, request_timeout, error: Mutex::new(ErrorFromBack::Unread(err_rx)) })
+	fn encode_params(&self, params: jsonrpc::Params) -> Result<jsonrpc::Params, Error> {
+		match self.number_options {
+			Some(options) => options.apply_params(params).map_err(Error::ParseError),
+			None => Ok(params),
+		}
 	}
-}
 
-impl WsClient {
-	/// Checks if the client is connected to the target.
-	pub fn is_connected(&self) -> bool {
-		!self.to_back.is_closed()
+	fn decode<T: DeserializeOwned>(&self, value: V) -> Result<T, Error> {
+		match self.number_options {
+			Some(options) => options.decode(value),
+			None => value.decode(),
+		}
+		.map_err(Error::ParseError)
 	}
 
 	// Reads the error message from the backend thread.
 	async fn read_error_from_backend(&self) -> Error {
 		let mut err_lock = self.error.lock().await;
-		let from_back = std::mem::replace(&mut *err_lock, ErrorFromBack::Read(String::new()));
+		let from_back = std::mem::replace(&mut *err_lock, ErrorFromBack::Read(ShutdownReason::Other(String::new())));
 		let (next_state, err) = from_back.read_error().await;
 		*err_lock = next_state;
 		err
@@ -228,7 +1723,7 @@ impl WsClient {
 }
 
 #[async_trait]
-impl Client for WsClient {
+impl<V: Value> Client for WsClient<V> {
 	/// Send a notification to the server.
 	async fn notification<M, P>(&self, method: M, params: P) -> Result<(), Error>
 	where
@@ -236,12 +1731,13 @@ impl Client for WsClient {
 		P: Into<jsonrpc::Params> + Send,
 	{
 		let method = method.into();
-		let params = params.into();
-		log::trace!("[frontend]: send notification: method={:?}, params={:?}", method, params);
-		match self.to_back.clone().send(FrontToBack::Notification(NotificationMessage { method, params })).await {
-			Ok(()) => Ok(()),
-			Err(_) => Err(self.read_error_from_backend().await),
-		}
+		let params = self.encode_params(params.into())?;
+		log::trace!(
+			"[frontend]: send notification: method={:?}, params={}",
+			method,
+			self.redactor.params(&method, &params)
+		);
+		self.send_to_back(FrontToBack::Notification(NotificationMessage { method, params })).await
 	}
 
 	/// Perform a request towards the server.
@@ -251,38 +1747,18 @@ impl Client for WsClient {
 		M: Into<String> + Send,
 		P: Into<jsonrpc::Params> + Send,
 	{
-		let method = method.into();
-		let params = params.into();
-		log::trace!("[frontend]: send request: method={:?}, params={:?}", method, params);
-		let (send_back_tx, send_back_rx) = oneshot::channel();
-
-		if self
-			.to_back
-			.clone()
-			.send(FrontToBack::StartRequest(RequestMessage { method, params, send_back: Some(send_back_tx) }))
-			.await
-			.is_err()
-		{
-			return Err(self.read_error_from_backend().await);
-		}
-
-		let send_back_rx_out = if let Some(duration) = self.request_timeout {
-			let timeout = async_std::task::sleep(duration);
-			futures::pin_mut!(send_back_rx, timeout);
-			match future::select(send_back_rx, timeout).await {
-				future::Either::Left((send_back_rx_out, _)) => send_back_rx_out,
-				future::Either::Right((_, _)) => return Err(Error::WsRequestTimeout),
-			}
-		} else {
-			send_back_rx.await
-		};
+		self.send_request(method.into(), params.into(), self.request_timeout).await
+	}
 
-		let json_value = match send_back_rx_out {
-			Ok(Ok(v)) => v,
-			Ok(Err(err)) => return Err(err),
-			Err(_) => return Err(self.read_error_from_backend().await),
-		};
-		jsonrpc::from_value(json_value).map_err(Error::ParseError)
+	/// Perform a request towards the server, failing with [`Error::WsRequestTimeout`] if no response
+	/// arrives within `timeout`.
+	async fn request_with_timeout<T, M, P>(&self, method: M, params: P, timeout: Duration) -> Result<T, Error>
+	where
+		T: DeserializeOwned,
+		M: Into<String> + Send,
+		P: Into<jsonrpc::Params> + Send,
+	{
+		self.send_request(method.into(), params.into(), Some(timeout)).await
 	}
 
 	async fn batch_request<T, M, P>(&self, batch: Vec<(M, P)>) -> Result<Vec<T>, Error>
@@ -291,33 +1767,29 @@ impl Client for WsClient {
 		M: Into<String> + Send,
 		P: Into<jsonrpc::Params> + Send,
 	{
-		let (send_back_tx, send_back_rx) = oneshot::channel();
-		let requests: Vec<(String, jsonrpc::Params)> = batch.into_iter().map(|(r, p)| (r.into(), p.into())).collect();
-		log::trace!("[frontend]: send batch request: {:?}", requests);
-		if self
-			.to_back
-			.clone()
-			.send(FrontToBack::Batch(BatchMessage { requests, send_back: send_back_tx }))
-			.await
-			.is_err()
-		{
-			return Err(self.read_error_from_backend().await);
-		}
-
-		let json_values = match send_back_rx.await {
-			Ok(Ok(v)) => v,
-			Ok(Err(err)) => return Err(err),
-			Err(_) => return Err(self.read_error_from_backend().await),
-		};
+		let values = self.send_batch(batch).await?;
+		values.into_iter().map(|val| self.decode(val.map_err(Error::Request)?)).collect()
+	}
 
-		let values: Result<_, _> =
-			json_values.into_iter().map(|val| jsonrpc::from_value(val).map_err(Error::ParseError)).collect();
-		Ok(values?)
+	async fn batch_request_results<T, M, P>(&self, batch: Vec<(M, P)>) -> Result<Vec<Result<T, jsonrpc::Error>>, Error>
+	where
+		T: DeserializeOwned,
+		M: Into<String> + Send,
+		P: Into<jsonrpc::Params> + Send,
+	{
+		let values = self.send_batch(batch).await?;
+		values
+			.into_iter()
+			.map(|val| match val {
+				Ok(val) => self.decode(val).map(Ok),
+				Err(err) => Ok(Err(err)),
+			})
+			.collect()
 	}
 }
 
 #[async_trait]
-impl SubscriptionClient for WsClient {
+impl<V: Value> SubscriptionClient<V> for WsClient<V> {
 	/// Send a subscription request to the server.
 	///
 	/// The `subscribe_method` and `params` are used to ask for the subscription towards the
@@ -327,178 +1799,348 @@ impl SubscriptionClient for WsClient {
 		subscribe_method: SM,
 		params: P,
 		unsubscribe_method: UM,
-	) -> Result<Subscription<N>, Error>
+	) -> Result<Subscription<N, V>, Error>
 	where
 		SM: Into<String> + Send,
 		UM: Into<String> + Send,
 		P: Into<jsonrpc::Params> + Send,
 		N: DeserializeOwned,
 	{
-		let subscribe_method = subscribe_method.into();
-		let unsubscribe_method = unsubscribe_method.into();
-		let params = params.into();
+		self.subscribe_with_policy(subscribe_method, params, unsubscribe_method, self.overflow_policy).await
+	}
 
-		if subscribe_method == unsubscribe_method {
-			return Err(Error::Subscription(subscribe_method, unsubscribe_method));
+	async fn subscribe_with_policy<SM, UM, P, N>(
+		&self,
+		subscribe_method: SM,
+		params: P,
+		unsubscribe_method: UM,
+		overflow_policy: OverflowPolicy,
+	) -> Result<Subscription<N, V>, Error>
+	where
+		SM: Into<String> + Send,
+		UM: Into<String> + Send,
+		P: Into<jsonrpc::Params> + Send,
+		N: DeserializeOwned,
+	{
+		self.start_subscription(
+			subscribe_method.into(),
+			params.into(),
+			unsubscribe_method.into(),
+			overflow_policy,
+			None,
+		)
+		.await
+	}
+}
+
+/// Returns the call that `msg` starts, for the errors of the call.
+fn call_info<V>(msg: &FrontToBack<V>) -> CallInfo {
+	let method = match msg {
+		FrontToBack::Batch(batch) => {
+			let methods: Vec<&str> = batch.requests.iter().map(|(method, _)| method.as_str()).collect();
+			format!("[{}]", methods.join(", "))
 		}
+		FrontToBack::Notification(notif) => notif.method.clone(),
+		FrontToBack::StartRequest(request) => request.method.clone(),
+		FrontToBack::Subscribe(subscription) => subscription.subscribe_method.clone(),
+		FrontToBack::RegisterNotificationHandler(handler) => handler.method.clone(),
+		FrontToBack::SubscriptionClosed(_)
+		| FrontToBack::RequestCancelled
+		| FrontToBack::MemoryStats(_)
+		| FrontToBack::ActiveSubscriptions(_) => String::new(),
+	};
+	CallInfo::new(method, None)
+}
 
-		log::trace!("[frontend]: subscribe: {:?}, unsubscribe: {:?}", subscribe_method, unsubscribe_method);
-		let (send_back_tx, send_back_rx) = oneshot::channel();
-		if self
-			.to_back
-			.clone()
-			.send(FrontToBack::Subscribe(SubscriptionMessage {
-				subscribe_method,
-				unsubscribe_method,
-				params,
-				send_back: send_back_tx,
-			}))
-			.await
-			.is_err()
-		{
-			return Err(self.read_error_from_backend().await);
+/// Spawns the background task of a client that isn't scoped.
+fn spawn_detached(task: BoxFuture<'static, ()>) {
+	async_std::task::spawn(task);
+}
+
+/// Sends the reason of the shutdown to the frontend and to the listeners of the connection events,
+/// [`ShutdownReason::Aborted`] if the background task is dropped before it sent one.
+///
+/// The error hook is called with the reason, and with the context recorded by [`ShutdownNotifier::record`].
+struct ShutdownNotifier {
+	tx: Option<oneshot::Sender<ShutdownReason>>,
+	listeners: ConnectionListeners,
+	error_hook: Option<ErrorHook>,
+	last_frame: Option<String>,
+	pending_requests: usize,
+}
+
+impl ShutdownNotifier {
+	fn new(tx: oneshot::Sender<ShutdownReason>, listeners: ConnectionListeners, error_hook: Option<ErrorHook>) -> Self {
+		Self { tx: Some(tx), listeners, error_hook, last_frame: None, pending_requests: 0 }
+	}
+
+	/// Records the message received from the server for the error hook, does nothing without hook.
+	fn record<V: Value>(&mut self, response: &jsonrpc::Response<V>, manager: &RequestManager<V>) {
+		if self.error_hook.is_some() {
+			self.last_frame = Some(response.to_string());
+			self.pending_requests = manager.pending_requests();
+		}
+	}
+
+	fn call_error_hook(&self, reason: &ShutdownReason, panicked: bool) {
+		if let Some(hook) = self.error_hook.as_ref() {
+			(hook.0)(&BackgroundError {
+				reason: reason.clone(),
+				panicked,
+				last_frame: self.last_frame.clone(),
+				pending_requests: self.pending_requests,
+			});
 		}
+	}
 
-		let (notifs_rx, id) = match send_back_rx.await {
-			Ok(Ok(val)) => val,
-			Ok(Err(err)) => return Err(err),
-			Err(_) => return Err(self.read_error_from_backend().await),
-		};
-		Ok(Subscription { to_back: self.to_back.clone(), notifs_rx, marker: PhantomData, id })
+	fn send(mut self, reason: ShutdownReason) -> Result<(), ShutdownReason> {
+		self.call_error_hook(&reason, false);
+		self.listeners.close(reason.clone());
+		self.tx.take().expect("Only taken by `send` which consumes the notifier; qed").send(reason)
+	}
+}
+
+impl Drop for ShutdownNotifier {
+	fn drop(&mut self) {
+		if let Some(tx) = self.tx.take() {
+			let reason = if std::thread::panicking() {
+				let reason = ShutdownReason::Other("The background task panicked".into());
+				self.call_error_hook(&reason, true);
+				reason
+			} else {
+				ShutdownReason::Aborted
+			};
+			self.listeners.close(reason.clone());
+			let _ = tx.send(reason);
+		}
 	}
 }
 
+/// Options and state of the [`background_task`] of a client, kept across reconnections.
+struct BackgroundContext<V> {
+	/// Notifies the frontend of the reason the background task terminated.
+	front_error: oneshot::Sender<ShutdownReason>,
+	/// Capacity of the buffer of every subscription.
+	max_notifs_per_subscription: usize,
+	/// Coalescing of the outgoing messages.
+	batching_window: Option<BatchingWindow>,
+	/// Subscriptions shared with the frontend.
+	subscriptions: SubscriptionRegistry,
+	/// Listeners of the connection lifecycle events.
+	connection_listeners: ConnectionListeners,
+	/// How the background task reconnects when the connection is lost.
+	reconnect: Option<Reconnect>,
+	/// Requests and subscriptions in progress.
+	manager: RequestManager<V>,
+	/// Requests waiting for a free slot.
+	queue: Option<RequestQueue<V>>,
+	/// Ping interval and pong timeout.
+	ping: Option<(Duration, Duration)>,
+	/// Timeout after which silent subscriptions are closed, see [`WsClientBuilder::subscription_heartbeat`].
+	subscription_heartbeat: Option<Duration>,
+	/// Called with the reason the background task terminated.
+	error_hook: Option<ErrorHook>,
+}
+
 /// Function being run in the background that processes messages from the frontend.
-async fn background_task(
+async fn background_task<V: Value>(
 	mut sender: jsonrpc_transport::Sender,
 	receiver: jsonrpc_transport::Receiver,
-	mut frontend: mpsc::Receiver<FrontToBack>,
-	front_error: oneshot::Sender<Error>,
-	max_notifs_per_subscription: usize,
-	max_concurrent_requests: usize,
+	mut frontend: mpsc::Receiver<FrontToBack<V>>,
+	pending: Vec<FrontToBack<V>>,
+	ctx: BackgroundContext<V>,
 ) {
-	let mut manager = RequestManager::new(max_concurrent_requests);
+	let BackgroundContext {
+		front_error,
+		max_notifs_per_subscription,
+		batching_window,
+		subscriptions,
+		connection_listeners,
+		reconnect,
+		mut manager,
+		mut queue,
+		ping,
+		subscription_heartbeat,
+		error_hook,
+	} = ctx;
+	let mut front_error = ShutdownNotifier::new(front_error, connection_listeners.clone(), error_hook);
+	let keepalive_for = |receiver: &jsonrpc_transport::Receiver| {
+		ping.filter(|_| receiver.supports_ping())
+			.map(|(interval, pong_timeout)| Keepalive::new(interval, pong_timeout, receiver.pong_flag()))
+	};
+	let mut keepalive = keepalive_for(&receiver);
+	let mut heartbeats = subscription_heartbeat.map(HeartbeatMonitor::new);
+	let mut backend_event = Box::pin(backend_events::<V>(receiver));
+	// IDs of the subscriptions that were re-issued after reconnecting, by the ID known to the frontend.
+	let mut aliases = FnvHashMap::default();
 
-	let backend_event = futures::stream::unfold(receiver, |mut receiver| async {
-		let res = receiver.next_response().await;
-		Some((res, receiver))
-	});
-
-	futures::pin_mut!(backend_event);
+	// Messages received before a lazy connection was established.
+	for msg in pending {
+		let msg = match queue.as_mut() {
+			Some(queue) => match queue.admit(msg, &manager) {
+				Some(msg) => msg,
+				None => continue,
+			},
+			None => msg,
+		};
+		process_frontend_message(&mut sender, &mut manager, &subscriptions, &mut aliases, msg).await;
+	}
 
 	loop {
-		let next_frontend = frontend.next();
-		let next_backend = backend_event.next();
-		futures::pin_mut!(next_frontend, next_backend);
+		if let Some(queue) = queue.as_mut() {
+			while let Some(msg) = queue.pop(&manager) {
+				process_frontend_message(&mut sender, &mut manager, &subscriptions, &mut aliases, msg).await;
+			}
+		}
+		let (event, check_heartbeats, expire_queued) = {
+			let next_frontend = frontend.next();
+			let next_backend = backend_event.next();
+			let next_ping = Keepalive::timer(&mut keepalive);
+			let next_heartbeat_check = HeartbeatMonitor::timer(&mut heartbeats);
+			let next_queue_expiry = RequestQueue::timer(&queue);
+			futures::pin_mut!(next_frontend, next_backend, next_ping, next_heartbeat_check, next_queue_expiry);
+			let timers = future::select(future::select(next_ping, next_heartbeat_check), next_queue_expiry);
+			match future::select(future::select(next_frontend, next_backend), timers).await {
+				Either::Left((Either::Left((msg, _)), _)) => (Some(Either::Left(msg)), false, false),
+				Either::Left((Either::Right((response, _)), _)) => (Some(Either::Right(response)), false, false),
+				Either::Right((Either::Left((Either::Left(_), _)), _)) => (None, false, false),
+				Either::Right((Either::Left((Either::Right(_), _)), _)) => (None, true, false),
+				Either::Right((Either::Right(_), _)) => (None, false, true),
+			}
+		};
+		if expire_queued {
+			queue.as_mut().expect("The timer never resolves without queue; qed").remove_expired();
+			continue;
+		}
+		if check_heartbeats {
+			let monitor = heartbeats.as_mut().expect("The timer never resolves without monitor; qed");
+			close_silent_subscriptions(&mut sender, &mut manager, &subscriptions, monitor).await;
+			continue;
+		}
+		let event = match (event, keepalive.as_mut()) {
+			(Some(event), _) => event,
+			(None, Some(keepalive)) => match keepalive.on_timer(&mut sender).await {
+				Ok(()) => continue,
+				// The connection is dead, handled like a receive error.
+				Err(err) => Either::Right(Some(Err(err))),
+			},
+			(None, None) => unreachable!("The timer never resolves without keepalive; qed"),
+		};
 
-		match future::select(next_frontend, next_backend).await {
+		if let Either::Right(Some(Ok(response))) = &event {
+			front_error.record(response, &manager);
+		}
+		match event {
 			// User dropped the sender side of the channel.
 			// There is nothing to do just terminate.
-			Either::Left((None, _)) => {
+			Either::Left(None) => {
 				log::trace!("[backend]: frontend dropped; terminate client");
 				return;
 			}
 
-			Either::Left((Some(FrontToBack::Batch(batch)), _)) => {
-				log::trace!("[backend]: client prepares to send batch request: {:?}", batch);
-				if let Err(e) = sender.start_batch_request(batch, &mut manager).await {
-					log::warn!("[backend]: client batch request failed: {:?}", e);
-				}
-			}
-
-			// User called `notification` on the front-end
-			Either::Left((Some(FrontToBack::Notification(notif)), _)) => {
-				log::trace!("[backend]: client prepares to send notification: {:?}", notif);
-				if let Err(e) = sender.send_notification(notif).await {
-					log::warn!("[backend]: client notif failed: {:?}", e);
-				}
-			}
-
-			// User called `request` on the front-end
-			Either::Left((Some(FrontToBack::StartRequest(request)), _)) => {
-				log::trace!("[backend]: client prepares to send request={:?}", request);
-				if let Err(e) = sender.start_request(request, &mut manager).await {
-					log::warn!("[backend]: client request failed: {:?}", e);
+			Either::Left(Some(mut msg)) => {
+				resolve_subscription_alias(&mut msg, &aliases);
+				let msg = match queue.as_mut() {
+					Some(queue) => match queue.admit(msg, &manager) {
+						Some(msg) => msg,
+						None => continue,
+					},
+					None => msg,
+				};
+				let msg = match (batching_window, msg) {
+					(Some(batching), FrontToBack::Notification(notif)) => {
+						coalesce_and_send(
+							&mut sender,
+							&mut manager,
+							&mut frontend,
+							Coalesced::Notification(notif),
+							batching,
+						)
+						.await
+					}
+					(Some(batching), FrontToBack::StartRequest(request)) if batching.requests => {
+						coalesce_and_send(
+							&mut sender,
+							&mut manager,
+							&mut frontend,
+							Coalesced::Request(request),
+							batching,
+						)
+						.await
+					}
+					(_, msg) => Some(msg),
+				};
+				if let Some(msg) = msg {
+					process_frontend_message(&mut sender, &mut manager, &subscriptions, &mut aliases, msg).await;
 				}
 			}
-			// User called `subscribe` on the front-end.
-			Either::Left((Some(FrontToBack::Subscribe(subscribe)), _)) => {
-				log::trace!("[backend]: client prepares to start subscription: {:?}", subscribe);
-				if let Err(e) = sender.start_subscription(subscribe, &mut manager).await {
-					log::warn!("[backend]: client subscription failed: {:?}", e);
+			Either::Right(Some(Ok(jsonrpc::Response::Single(response)))) => {
+				if let Some(batch_state) = manager.take_rejected_batch(&response) {
+					reject_batch(&mut manager, batch_state, response);
+					continue;
 				}
-			}
-			// User dropped a subscription.
-			Either::Left((Some(FrontToBack::SubscriptionClosed(sub_id)), _)) => {
-				log::trace!("Closing subscription: {:?}", sub_id);
-				// NOTE: The subscription may have been closed earlier if
-				// the channel was full or disconnected.
-				if let Some(unsub) = manager
-					.get_request_id_by_subscription_id(&sub_id)
-					.and_then(|req_id| build_unsubscribe_message(&mut manager, req_id, sub_id))
-				{
-					stop_subscription(&mut sender, &mut manager, unsub).await;
+				if manager.is_split_batch_response(response.id()) {
+					process_split_batch_response(&mut manager, response);
+					continue;
 				}
-			}
-			Either::Right((Some(Ok(jsonrpc::Response::Single(response))), _)) => {
-				match process_response(&mut manager, response, max_notifs_per_subscription) {
+				match process_response(&mut manager, &subscriptions, response, max_notifs_per_subscription) {
 					Ok(Some(unsub)) => {
 						stop_subscription(&mut sender, &mut manager, unsub).await;
 					}
 					Ok(None) => (),
 					Err(err) => {
-						let _ = front_error.send(err);
+						let _ = front_error.send(ShutdownReason::ProtocolViolation(err.to_string()));
 						return;
 					}
 				}
 			}
-			Either::Right((Some(Ok(jsonrpc::Response::Batch(batch))), _)) => {
-				let mut digest = Vec::with_capacity(batch.len());
-				let mut ordered_responses = vec![JsonValue::Null; batch.len()];
-				let mut rps_unordered: Vec<_> = Vec::with_capacity(batch.len());
-
-				for rp in batch {
-					let id = match rp.id().as_number().copied() {
-						Some(id) => id,
-						None => {
-							let _ = front_error.send(Error::InvalidRequestId);
-							return;
-						}
-					};
-					let rp: Result<JsonValue, Error> = rp.try_into().map_err(Error::Request);
-					let rp = match rp {
-						Ok(rp) => rp,
-						Err(err) => {
-							let _ = front_error.send(err);
-							return;
-						}
-					};
-					digest.push(id);
-					rps_unordered.push((id, rp));
-				}
-
+			Either::Right(Some(Ok(jsonrpc::Response::Batch(batch)))) => {
+				let mut digest: Vec<_> = batch.iter().map(|rp| rp.id().clone()).collect();
 				digest.sort_unstable();
 				let batch_state = match manager.complete_pending_batch(digest) {
 					Some(state) => state,
+					// Responses to coalesced requests, see `coalesce_and_send`, or split batch responses.
 					None => {
-						log::warn!("Received unknown batch response");
+						for response in batch {
+							if manager.is_split_batch_response(response.id()) {
+								process_split_batch_response(&mut manager, response);
+								continue;
+							}
+							match process_response(&mut manager, &subscriptions, response, max_notifs_per_subscription)
+							{
+								Ok(Some(unsub)) => {
+									stop_subscription(&mut sender, &mut manager, unsub).await;
+								}
+								Ok(None) => (),
+								Err(err) => {
+									let _ = front_error.send(ShutdownReason::ProtocolViolation(err.to_string()));
+									return;
+								}
+							}
+						}
 						continue;
 					}
 				};
 
-				for (id, rp) in rps_unordered {
+				let mut batch_state = batch_state;
+				for rp in batch {
 					let pos = batch_state
 						.order
-						.get(&id)
+						.get(rp.id())
 						.copied()
 						.expect("All request IDs valid checked by RequestManager above; qed");
-					ordered_responses[pos] = rp;
+					batch_state.responses[pos] = Some(rp.into_result());
 				}
-				manager.reclaim_request_id(batch_state.request_id);
-				let _ = batch_state.send_back.send(Ok(ordered_responses));
+				complete_batch(&mut manager, batch_state);
 			}
-			Either::Right((Some(Ok(jsonrpc::Response::Notif(notif))), _)) => {
+			Either::Right(Some(Ok(jsonrpc::Response::Notif(notif)))) => {
 				let sub_id = notif.params.subscription;
+				if let Some(monitor) = heartbeats.as_mut() {
+					monitor.on_notification(&sub_id);
+				}
+				if notif.method == jsonrpc::SUBSCRIPTION_HEARTBEAT_METHOD {
+					continue;
+				}
 				let request_id = match manager.get_request_id_by_subscription_id(&sub_id) {
 					Some(r) => r,
 					None => {
@@ -507,11 +2149,11 @@ async fn background_task(
 					}
 				};
 
-				match manager.as_subscription_mut(&request_id) {
-					Some(send_back_sink) => {
-						if let Err(e) = send_back_sink.try_send(notif.params.result) {
+				match manager.send_notification(&request_id, &notif.method, notif.params.result).await {
+					Some(res) => {
+						if let Err(e) = res {
 							log::error!("Dropping subscription {:?} error: {:?}", sub_id, e);
-							let unsub_req = build_unsubscribe_message(&mut manager, request_id, sub_id)
+							let unsub_req = build_unsubscribe_message(&mut manager, &subscriptions, request_id, sub_id)
 								.expect("request ID and subscription ID valid checked above; qed");
 							stop_subscription(&mut sender, &mut manager, unsub_req).await;
 						}
@@ -521,18 +2163,411 @@ async fn background_task(
 					}
 				}
 			}
-			Either::Right((Some(Err(e)), _)) => {
-				log::error!("Error: {:?} terminating client", e);
-				let _ = front_error.send(Error::TransportError(Box::new(e)));
-				return;
+			Either::Right(Some(Ok(jsonrpc::Response::Notification(notif)))) => {
+				if !manager.send_to_notification_handlers(&notif.method, notif.params) {
+					log::debug!("[backend]: dropping notification without handler: {:?}", notif.method);
+				}
 			}
-			Either::Right((None, _)) => {
-				log::error!("[backend]: WebSocket receiver dropped; terminate client");
-				let _ = front_error.send(Error::Custom("WebSocket receiver dropped".into()));
-				return;
+			Either::Right(response) => {
+				let reason = match response {
+					Some(Err(e)) => {
+						log::error!("Error: {:?}", e);
+						e.into()
+					}
+					_ => {
+						log::error!("[backend]: WebSocket receiver dropped");
+						ShutdownReason::Other("WebSocket receiver dropped".into())
+					}
+				};
+
+				let reconnected = match reconnect.as_ref() {
+					Some(reconnect) => {
+						connection_listeners.notify(ConnectionEvent::Disconnected { reason: reason.clone() });
+						reconnect_and_resubscribe(
+							reconnect,
+							&mut manager,
+							&subscriptions,
+							&mut aliases,
+							&reason,
+							max_notifs_per_subscription,
+						)
+						.await
+					}
+					None => None,
+				};
+				match reconnected {
+					Some((new_sender, new_receiver)) => {
+						connection_listeners.notify(ConnectionEvent::Connected);
+						sender = new_sender;
+						keepalive = keepalive_for(&new_receiver);
+						backend_event = Box::pin(backend_events(new_receiver));
+					}
+					None => {
+						log::error!("[backend]: terminate client");
+						let _ = front_error.send(reason);
+						return;
+					}
+				}
+			}
+		}
+	}
+}
+
+/// Stream of the responses received from the server.
+fn backend_events<V: Value>(
+	receiver: jsonrpc_transport::Receiver,
+) -> impl Stream<Item = Result<jsonrpc::Response<V>, WsConnectError>> {
+	futures::stream::unfold(receiver, |mut receiver| async {
+		let res = receiver.next_response().await;
+		Some((res, receiver))
+	})
+}
+
+/// Connects to the first of `transports` that accepts the connection, fails with the error of the last one.
+async fn connect_any(
+	transports: &[WsTransportClientBuilder<'_>],
+) -> Result<(transport::Sender, transport::Receiver), WsHandshakeError> {
+	let mut last_error = None;
+	for transport in transports {
+		match transport.clone().build().await {
+			Ok(connection) => return Ok(connection),
+			Err(e) => {
+				log::debug!("[backend]: failed to connect to {:?}: {:?}", transport.host, e);
+				last_error = Some(e);
+			}
+		}
+	}
+	Err(last_error.unwrap_or(WsHandshakeError::NoAddressFound))
+}
+
+/// Re-establishes the connection after it was lost and re-issues the active subscriptions.
+///
+/// Requests in flight are failed with [`Error::ConnectionLost`], returns `None` if no retry succeeded.
+async fn reconnect_and_resubscribe<V: Value>(
+	reconnect: &Reconnect,
+	manager: &mut RequestManager<V>,
+	subscriptions: &SubscriptionRegistry,
+	aliases: &mut FnvHashMap<SubscriptionId, SubscriptionId>,
+	reason: &ShutdownReason,
+	max_notifs_per_subscription: usize,
+) -> Option<(jsonrpc_transport::Sender, jsonrpc_transport::Receiver)> {
+	let active: Vec<_> = manager
+		.reset(|| Error::ConnectionLost(reason.clone()))
+		.into_iter()
+		.filter_map(|(request_id, sub_id, sink, _)| Some((subscriptions.take(request_id)?, sub_id, sink)))
+		.collect();
+	subscriptions.clear();
+
+	let mut backoff = reconnect.backoff;
+	let mut connection = None;
+	for attempt in 1..=reconnect.max_retries {
+		async_std::task::sleep(backoff).await;
+		match connect_any(&reconnect.transports).await {
+			Ok(established) => {
+				connection = Some(established);
+				break;
+			}
+			Err(e) => log::debug!("[backend]: reconnect attempt {} failed: {:?}", attempt, e),
+		}
+		backoff = backoff.checked_mul(2).unwrap_or(backoff);
+	}
+	let (sender, receiver) = connection?;
+	log::debug!("[backend]: reconnected, re-issuing {} subscriptions", active.len());
+
+	let mut sender = jsonrpc_transport::Sender::new(sender);
+	let mut receiver = jsonrpc_transport::Receiver::new(receiver);
+	for (descriptor, old_sub_id, sink) in active {
+		let front_id = aliases
+			.iter()
+			.find(|(_, server_id)| **server_id == old_sub_id)
+			.map(|(front_id, _)| front_id.clone())
+			.unwrap_or(old_sub_id);
+		aliases.remove(&front_id);
+
+		let subscribe_method = descriptor.subscribe_method.clone();
+		let resubscribed = resubscribe(
+			&mut sender,
+			&mut receiver,
+			manager,
+			subscriptions,
+			descriptor,
+			// NOTE: all endpoints share the timeout of the builder.
+			reconnect.transports[0].timeout,
+			max_notifs_per_subscription,
+		)
+		.await;
+		match resubscribed {
+			Ok((request_id, sub_id)) => {
+				if let Some(new_sink) = manager.as_subscription_mut(&request_id) {
+					*new_sink = sink;
+				}
+				if sub_id != front_id {
+					aliases.insert(front_id, sub_id);
+				}
+			}
+			// NOTE: dropping `sink` terminates the subscription in the frontend.
+			Err(e) => log::warn!("[backend]: failed to re-issue subscription {:?}: {:?}", subscribe_method, e),
+		}
+	}
+	Some((sender, receiver))
+}
+
+/// Re-issues a single subscription on a new connection and waits for the server to accept it.
+///
+/// Returns the request ID and the new subscription ID.
+async fn resubscribe<V: Value>(
+	sender: &mut jsonrpc_transport::Sender,
+	receiver: &mut jsonrpc_transport::Receiver,
+	manager: &mut RequestManager<V>,
+	subscriptions: &SubscriptionRegistry,
+	descriptor: SubscriptionDescriptor,
+	timeout: Duration,
+	max_notifs_per_subscription: usize,
+) -> Result<(u64, SubscriptionId), Error> {
+	let (send_back, mut accepted) = oneshot::channel();
+	let request_id = sender
+		.start_subscription(
+			SubscriptionMessage {
+				subscribe_method: descriptor.subscribe_method.clone(),
+				params: descriptor.params.clone(),
+				unsubscribe_method: descriptor.unsubscribe_method.clone(),
+				send_back,
+				// NOTE: the sink of the previous subscription, and thus its policy and filter, is reused.
+				overflow_policy: OverflowPolicy::default(),
+				filter: None,
+				span: RequestSpan::subscription(&descriptor.subscribe_method),
+			},
+			manager,
+		)
+		.await?;
+	let call = CallInfo::new(descriptor.subscribe_method.clone(), Some(manager.sent_id(request_id)));
+	subscriptions.insert_pending(request_id, descriptor);
+
+	loop {
+		let response = async_std::future::timeout(timeout, receiver.next_response())
+			.await
+			.map_err(|_| Error::WsRequestTimeout(call.clone()))?
+			.map_err(|e| Error::TransportError(Box::new(e)))?;
+		match response {
+			jsonrpc::Response::Single(response) => {
+				// NOTE: unsubscribes the subscriptions whose frontend was dropped meanwhile.
+				if let Some(unsub) = process_response(manager, subscriptions, response, max_notifs_per_subscription)? {
+					stop_subscription(sender, manager, unsub).await;
+				}
+			}
+			jsonrpc::Response::Notif(notif) if notif.method == jsonrpc::SUBSCRIPTION_HEARTBEAT_METHOD => (),
+			jsonrpc::Response::Notif(notif) => {
+				// Notification of a subscription that was already re-issued.
+				let sub_id = notif.params.subscription;
+				if let Some(request_id) = manager.get_request_id_by_subscription_id(&sub_id) {
+					let _ = manager.send_notification(&request_id, &notif.method, notif.params.result).await;
+				}
+			}
+			jsonrpc::Response::Notification(notif) => {
+				manager.send_to_notification_handlers(&notif.method, notif.params);
+			}
+			jsonrpc::Response::Batch(_) => log::debug!("[backend]: unexpected batch response while resubscribing"),
+		}
+
+		if let Ok(Some(accepted)) = accepted.try_recv() {
+			return accepted.map(|(_, sub_id)| (request_id, sub_id));
+		}
+	}
+}
+
+/// Adds a response to a batch request that the server split across frames, see
+/// [`WsClientBuilder::lenient_batch_responses`].
+fn process_split_batch_response<V: Value>(manager: &mut RequestManager<V>, response: jsonrpc::Output<V>) {
+	let id = response.id().clone();
+	if let Some(batch_state) = manager.add_batch_response(&id, response.into_result()) {
+		complete_batch(manager, batch_state);
+	}
+}
+
+/// Sends the responses of a batch request back to the frontend.
+fn complete_batch<V>(manager: &mut RequestManager<V>, batch_state: BatchState<V>) {
+	manager.request_answered(batch_state.request_id, "Response received");
+	manager.reclaim_request_id(batch_state.request_id);
+	let (send_back, responses) = batch_state.into_responses();
+	let _ = send_back.send(Ok(responses));
+}
+
+/// Answers a batch request that the server rejected with the error `response`, see
+/// [`RequestManager::take_rejected_batch`].
+fn reject_batch<V>(manager: &mut RequestManager<V>, batch_state: BatchState<V>, response: jsonrpc::Output<V>) {
+	manager.request_answered(batch_state.request_id, "Batch rejected");
+	manager.reclaim_request_id(batch_state.request_id);
+	let err = match response.into_result() {
+		Err(err) => err,
+		Ok(_) => unreachable!("Rejections are errors checked by RequestManager; qed"),
+	};
+	let _ = batch_state.send_back.send(Err(Error::Request(err)));
+}
+
+/// Describes the active subscriptions by the IDs known to the frontend.
+fn active_subscriptions<V>(
+	manager: &RequestManager<V>,
+	subscriptions: &SubscriptionRegistry,
+	aliases: &FnvHashMap<SubscriptionId, SubscriptionId>,
+) -> Vec<ActiveSubscription> {
+	manager
+		.subscription_buffers()
+		.filter_map(|(request_id, sub_id, buffered)| {
+			let descriptor = subscriptions.get(request_id)?;
+			let subscription_id = aliases
+				.iter()
+				.find(|(_, server_id)| *server_id == sub_id)
+				.map_or_else(|| sub_id.clone(), |(front_id, _)| front_id.clone());
+			Some(ActiveSubscription {
+				subscribe_method: descriptor.subscribe_method,
+				params: descriptor.params,
+				subscription_id,
+				buffered,
+			})
+		})
+		.collect()
+}
+
+/// Replaces the subscription ID passed to the pause and resume methods by the ID that the server gave to the
+/// subscription when it was re-issued after reconnecting.
+fn resolve_subscription_alias<V>(msg: &mut FrontToBack<V>, aliases: &FnvHashMap<SubscriptionId, SubscriptionId>) {
+	let request = match msg {
+		FrontToBack::StartRequest(request)
+			if request.method == jsonrpc::SUBSCRIPTION_PAUSE_METHOD
+				|| request.method == jsonrpc::SUBSCRIPTION_RESUME_METHOD =>
+		{
+			request
+		}
+		_ => return,
+	};
+	if let jsonrpc::Params::Array(params) = &mut request.params {
+		if let Some(param) = params.first_mut() {
+			let server_id = serde_json::from_value(param.clone()).ok().and_then(|id| aliases.get(&id));
+			if let Some(server_id) = server_id {
+				*param = serde_json::to_value(server_id).expect("Subscription IDs serialize to JSON; qed");
+			}
+		}
+	}
+}
+
+/// Process a message from the frontend.
+async fn process_frontend_message<V: Value>(
+	sender: &mut jsonrpc_transport::Sender,
+	manager: &mut RequestManager<V>,
+	subscriptions: &SubscriptionRegistry,
+	aliases: &mut FnvHashMap<SubscriptionId, SubscriptionId>,
+	msg: FrontToBack<V>,
+) {
+	match msg {
+		FrontToBack::Batch(batch) => {
+			log::trace!("[backend]: client prepares to send batch request of {} calls", batch.requests.len());
+			if let Err(e) = sender.start_batch_request(batch, manager).await {
+				log::warn!("[backend]: client batch request failed: {:?}", e);
+			}
+		}
+
+		// User called `notification` on the front-end
+		FrontToBack::Notification(notif) => {
+			log::trace!("[backend]: client prepares to send notification: method={:?}", notif.method);
+			if let Err(e) = sender.send_notification(notif).await {
+				log::warn!("[backend]: client notif failed: {:?}", e);
+			}
+		}
+
+		// User called `request` on the front-end
+		FrontToBack::StartRequest(request) => {
+			log::trace!("[backend]: client prepares to send request: method={:?}", request.method);
+			if let Err(e) = sender.start_request(request, manager).await {
+				log::warn!("[backend]: client request failed: {:?}", e);
+			}
+		}
+		// User called `subscribe` on the front-end.
+		FrontToBack::Subscribe(subscribe) => {
+			log::trace!("[backend]: client prepares to start subscription: method={:?}", subscribe.subscribe_method);
+			let descriptor = SubscriptionDescriptor {
+				subscribe_method: subscribe.subscribe_method.clone(),
+				params: subscribe.params.clone(),
+				unsubscribe_method: subscribe.unsubscribe_method.clone(),
+			};
+			match sender.start_subscription(subscribe, manager).await {
+				Ok(request_id) => subscriptions.insert_pending(request_id, descriptor),
+				Err(e) => log::warn!("[backend]: client subscription failed: {:?}", e),
+			}
+		}
+		FrontToBack::RegisterNotificationHandler(handler) => {
+			manager.insert_notification_handler(handler.method, handler.sink);
+		}
+		FrontToBack::MemoryStats(send_back) => {
+			let _ = send_back.send(manager.memory_stats());
+		}
+		FrontToBack::ActiveSubscriptions(send_back) => {
+			let _ = send_back.send(active_subscriptions(manager, subscriptions, aliases));
+		}
+		// User dropped a pending request.
+		FrontToBack::RequestCancelled => {
+			log::trace!("[backend]: client cancels dropped requests");
+			for id in manager.cancel_dropped_calls() {
+				let id = jsonrpc::to_value(id).expect("Id to JSON is infallible; qed");
+				let notif = NotificationMessage {
+					method: jsonrpc::CANCEL_METHOD.into(),
+					params: jsonrpc::Params::Array(vec![id]),
+				};
+				if let Err(e) = sender.send_notification(notif).await {
+					log::warn!("[backend]: client cancel notification failed: {:?}", e);
+				}
+			}
+		}
+		// User dropped a subscription.
+		FrontToBack::SubscriptionClosed(sub_id) => {
+			log::trace!("Closing subscription: {:?}", sub_id);
+			let sub_id = aliases.remove(&sub_id).unwrap_or(sub_id);
+			// NOTE: The subscription may have been closed earlier if
+			// the channel was full or disconnected.
+			if let Some(unsub) = manager
+				.get_request_id_by_subscription_id(&sub_id)
+				.and_then(|req_id| build_unsubscribe_message(manager, subscriptions, req_id, sub_id))
+			{
+				stop_subscription(sender, manager, unsub).await;
+			}
+		}
+	}
+}
+
+/// Collects the notifications and requests sent by the frontend within `window`, up to `max_messages`,
+/// and sends them to the server as a single batch.
+///
+/// Returns the message that ended the window if it can't be sent as part of the batch.
+async fn coalesce_and_send<V: Value>(
+	sender: &mut jsonrpc_transport::Sender,
+	manager: &mut RequestManager<V>,
+	frontend: &mut mpsc::Receiver<FrontToBack<V>>,
+	first: Coalesced<V>,
+	batching: BatchingWindow,
+) -> Option<FrontToBack<V>> {
+	let mut batch = vec![first];
+	let mut rest = None;
+	let timeout = async_std::task::sleep(batching.window);
+	futures::pin_mut!(timeout);
+
+	while batch.len() < batching.max_messages {
+		match future::select(frontend.next(), &mut timeout).await {
+			Either::Left((Some(FrontToBack::Notification(notif)), _)) => batch.push(Coalesced::Notification(notif)),
+			Either::Left((Some(FrontToBack::StartRequest(request)), _)) if batching.requests => {
+				batch.push(Coalesced::Request(request))
+			}
+			Either::Left((msg, _)) => {
+				rest = msg;
+				break;
 			}
+			Either::Right(_) => break,
 		}
 	}
+
+	log::trace!("[backend]: client prepares to send {} coalesced messages", batch.len());
+	if let Err(e) = sender.send_coalesced(batch, manager).await {
+		log::warn!("[backend]: client coalesced messages failed: {:?}", e);
+	}
+	rest
 }
 
 /// Process a response from the server.
@@ -540,15 +2575,23 @@ async fn background_task(
 /// Returns `Ok(None)` if the response was successful
 /// Returns `Ok(Some(_))` if the response got an error but could be handled.
 /// Returns `Err(_)` if the response couldn't be handled.
-fn process_response(
-	manager: &mut RequestManager,
-	response: jsonrpc::Output,
+fn process_response<V: Value>(
+	manager: &mut RequestManager<V>,
+	subscriptions: &SubscriptionRegistry,
+	response: jsonrpc::Output<V>,
 	max_capacity_per_subscription: usize,
-) -> Result<Option<RequestMessage>, Error> {
-	let response_id: u64 = *response.id().as_number().ok_or(Error::InvalidRequestId)?;
+) -> Result<Option<RequestMessage<V>>, Error> {
+	if manager.take_cancelled(response.id()) {
+		log::trace!("[backend]: ignore response to cancelled request: {:?}", response.id());
+		return Ok(None);
+	}
+	let response_id = manager.request_id_of(response.id()).ok_or(Error::InvalidRequestId)?;
 
 	match manager.request_status(&response_id) {
 		RequestStatus::PendingMethodCall => {
+			let success = matches!(response, jsonrpc::Output::Success(_));
+			manager.request_ended(response_id, success);
+			manager.request_answered(response_id, if success { "Response received" } else { "Error received" });
 			let send_back_oneshot = match manager.complete_pending_call(response_id) {
 				Some(Some(send)) => send,
 				Some(None) => return Ok(None),
@@ -556,36 +2599,48 @@ fn process_response(
 			};
 
 			manager.reclaim_request_id(response_id);
-			let response = response.try_into().map_err(Error::Request);
+			let response = response.into_result().map_err(Error::Request);
 			let _ = send_back_oneshot.send(response);
 			Ok(None)
 		}
 		RequestStatus::PendingSubscription => {
-			let (send_back_oneshot, unsubscribe_method) =
+			let (send_back_oneshot, unsubscribe_method, (overflow_policy, filter)) =
 				manager.complete_pending_subscription(response_id).ok_or(Error::InvalidRequestId)?;
-			let json_sub_id: JsonValue = match response.try_into() {
+			manager.request_answered(response_id, "Response received");
+			let sub_id = match response.into_result() {
 				Ok(response) => response,
 				Err(e) => {
+					subscriptions.remove(response_id);
 					let _ = send_back_oneshot.send(Err(Error::Request(e)));
 					return Ok(None);
 				}
 			};
 
-			let sub_id: SubscriptionId = match jsonrpc::from_value(json_sub_id) {
-				Ok(sub_id) => sub_id,
+			let sub_id: SubscriptionId = match sub_id.decode() {
+				Ok(sub_id) if manager.accepts_subscription_id(&sub_id) => sub_id,
+				Ok(sub_id) => {
+					let method = subscriptions.take(response_id).map(|descriptor| descriptor.subscribe_method);
+					log::warn!("[backend]: malformed subscription ID {:?} in response to {:?}", sub_id, method);
+					let _ = send_back_oneshot.send(Err(Error::InvalidSubscriptionId));
+					return Ok(None);
+				}
 				Err(_) => {
+					subscriptions.remove(response_id);
 					let _ = send_back_oneshot.send(Err(Error::InvalidSubscriptionId));
 					return Ok(None);
 				}
 			};
 
-			let (subscribe_tx, subscribe_rx) = mpsc::channel(max_capacity_per_subscription);
+			let (subscribe_tx, subscribe_rx) = notif_channel(max_capacity_per_subscription, overflow_policy);
+			let subscribe_tx = subscribe_tx.with_filter(filter);
 			if manager.insert_subscription(response_id, sub_id.clone(), subscribe_tx, unsubscribe_method).is_ok() {
+				subscriptions.activate(response_id);
 				match send_back_oneshot.send(Ok((subscribe_rx, sub_id.clone()))) {
 					Ok(_) => Ok(None),
-					Err(_) => Ok(build_unsubscribe_message(manager, response_id, sub_id)),
+					Err(_) => Ok(build_unsubscribe_message(manager, subscriptions, response_id, sub_id)),
 				}
 			} else {
+				subscriptions.remove(response_id);
 				let _ = send_back_oneshot.send(Err(Error::InvalidSubscriptionId));
 				Ok(None)
 			}
@@ -594,12 +2649,31 @@ fn process_response(
 	}
 }
 
+/// Unsubscribes the subscriptions that received neither notifications nor heartbeats within the timeout of
+/// `monitor`, which terminates them in the frontend.
+async fn close_silent_subscriptions<V>(
+	sender: &mut jsonrpc_transport::Sender,
+	manager: &mut RequestManager<V>,
+	subscriptions: &SubscriptionRegistry,
+	monitor: &mut HeartbeatMonitor,
+) {
+	for sub_id in monitor.on_timer(manager.active_subscriptions()) {
+		log::warn!("[backend]: no heartbeat of subscription {:?} within {:?}, closing it", sub_id, monitor.timeout());
+		let unsub = manager
+			.get_request_id_by_subscription_id(&sub_id)
+			.and_then(|request_id| build_unsubscribe_message(manager, subscriptions, request_id, sub_id));
+		if let Some(unsub) = unsub {
+			stop_subscription(sender, manager, unsub).await;
+		}
+	}
+}
+
 /// Sends an unsubscribe to request to server to indicate
 /// that the client is not interested in the subscription anymore.
-async fn stop_subscription(
+async fn stop_subscription<V>(
 	sender: &mut jsonrpc_transport::Sender,
-	manager: &mut RequestManager,
-	unsub: RequestMessage,
+	manager: &mut RequestManager<V>,
+	unsub: RequestMessage<V>,
 ) {
 	if let Err(e) = sender.start_request(unsub, manager).await {
 		log::error!("Send unsubscribe request failed: {:?}", e);
@@ -607,13 +2681,20 @@ async fn stop_subscription(
 }
 
 /// Builds an unsubscription message, semantically the same as an ordinary request.
-fn build_unsubscribe_message(
-	manager: &mut RequestManager,
+fn build_unsubscribe_message<V>(
+	manager: &mut RequestManager<V>,
+	subscriptions: &SubscriptionRegistry,
 	req_id: u64,
 	sub_id: SubscriptionId,
-) -> Option<RequestMessage> {
+) -> Option<RequestMessage<V>> {
 	let (_, unsub, sub_id) = manager.remove_subscription(req_id, sub_id)?;
+	subscriptions.remove(req_id);
 	manager.reclaim_request_id(req_id);
 	let json_sub_id = jsonrpc::to_value(sub_id).expect("SubscriptionId to JSON is infallible; qed");
-	Some(RequestMessage { method: unsub, params: jsonrpc::Params::Array(vec![json_sub_id]), send_back: None })
+	Some(RequestMessage {
+		span: RequestSpan::call(&unsub),
+		method: unsub,
+		params: jsonrpc::Params::Array(vec![json_sub_id]),
+		send_back: None,
+	})
 }
@@ -0,0 +1,134 @@
+#[cfg(all(unix, feature = "uds"))]
+use crate::transport::UdsFraming;
+use crate::WsClientBuilder;
+use jsonrpsee_types::client::OverflowPolicy;
+use jsonrpsee_types::jsonrpc::NumberOptions;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Options of a [`WsClientBuilder`] as plain data, to load them with any serde format such as TOML, YAML or
+/// environment variables.
+///
+/// The fields are named after the methods of the builder, durations are given in milliseconds. Missing fields
+/// take the defaults of the builder and unknown fields are rejected.
+///
+/// Options that aren't plain data are set on the builder returned by `WsClientBuilder::try_from`: the
+/// credentials, proxies, TLS configuration, codecs, hooks, middlewares, preflight, response validator, retry
+/// policy, ID provider and metrics.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClientConfig {
+	/// See [`WsClientBuilder::max_request_body_size`].
+	pub max_request_body_size: usize,
+	/// See [`WsClientBuilder::max_response_size`].
+	pub max_response_size: usize,
+	/// See [`WsClientBuilder::request_timeout`].
+	pub request_timeout_ms: Option<u64>,
+	/// See [`WsClientBuilder::connection_timeout`].
+	pub connection_timeout_ms: u64,
+	/// See [`WsClientBuilder::connection_attempt_delay`].
+	pub connection_attempt_delay_ms: u64,
+	/// See [`WsClientBuilder::origin_header`].
+	pub origin_header: Option<String>,
+	/// See [`WsClientBuilder::handshake_url`].
+	pub handshake_url: String,
+	/// See [`WsClientBuilder::max_concurrent_requests`].
+	pub max_concurrent_requests: usize,
+	/// See [`WsClientBuilder::request_queue`].
+	pub request_queue: Option<RequestQueueConfig>,
+	/// See [`WsClientBuilder::max_notifs_per_subscription`].
+	pub max_notifs_per_subscription: usize,
+	/// See [`WsClientBuilder::subscription_overflow_policy`].
+	pub subscription_overflow_policy: OverflowPolicy,
+	/// See [`WsClientBuilder::batching_window`] and [`WsClientBuilder::notification_batching_window`].
+	pub batching_window: Option<BatchingWindowConfig>,
+	/// See [`WsClientBuilder::tls_session_resumption`].
+	pub tls_session_resumption: bool,
+	/// See [`WsClientBuilder::compression`].
+	pub compression: Option<u8>,
+	/// See [`WsClientBuilder::proxy_from_env`].
+	pub proxy_from_env: bool,
+	/// See [`WsClientBuilder::uds_framing`].
+	#[cfg(all(unix, feature = "uds"))]
+	pub uds_framing: UdsFraming,
+	/// See [`WsClientBuilder::reconnect`].
+	pub reconnect: Option<ReconnectConfig>,
+	/// See [`WsClientBuilder::max_redirections`].
+	pub max_redirections: usize,
+	/// See [`WsClientBuilder::write_queue`].
+	pub write_queue: Option<usize>,
+	/// See [`WsClientBuilder::ping_interval`].
+	pub ping_interval: Option<PingConfig>,
+	/// See [`WsClientBuilder::subscription_heartbeat`].
+	pub subscription_heartbeat_ms: Option<u64>,
+	/// See [`WsClientBuilder::number_options`].
+	pub number_options: Option<NumberOptions>,
+	/// See [`WsClientBuilder::lenient_batch_responses`].
+	pub lenient_batch_responses: bool,
+	/// See [`WsClientBuilder::split_rejected_batches`].
+	pub split_rejected_batches: bool,
+	/// See [`WsClientBuilder::strict_subscription_ids`].
+	pub strict_subscription_ids: bool,
+	/// See [`WsClientBuilder::cancel_dropped_requests`].
+	pub cancel_dropped_requests: bool,
+}
+
+impl Default for ClientConfig {
+	fn default() -> Self {
+		WsClientBuilder::default().to_config()
+	}
+}
+
+/// Error when a [`ClientConfig`] holds an option the builder rejects.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error("Invalid option `{option}`: {reason}")]
+pub struct InvalidConfig {
+	/// Field of the invalid option.
+	pub option: &'static str,
+	/// Why the option is invalid.
+	pub reason: &'static str,
+}
+
+/// See [`WsClientBuilder::request_queue`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RequestQueueConfig {
+	/// Max number of queued requests.
+	pub capacity: usize,
+	/// Max time a request waits in the queue.
+	#[serde(default)]
+	pub timeout_ms: Option<u64>,
+}
+
+/// See [`WsClientBuilder::batching_window`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchingWindowConfig {
+	/// Max time the messages are collected.
+	pub window_ms: u64,
+	/// Max number of messages in a batch.
+	pub max_messages: usize,
+	/// Only coalesce the notifications, see [`WsClientBuilder::notification_batching_window`].
+	#[serde(default)]
+	pub notifications_only: bool,
+}
+
+/// See [`WsClientBuilder::reconnect`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReconnectConfig {
+	/// Max number of retries.
+	pub max_retries: usize,
+	/// Delay before the first retry.
+	pub backoff_ms: u64,
+}
+
+/// See [`WsClientBuilder::ping_interval`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PingConfig {
+	/// Interval between the pings.
+	pub interval_ms: u64,
+	/// Max time to wait for a pong.
+	pub pong_timeout_ms: u64,
+}
@@ -0,0 +1,29 @@
+use jsonrpsee_types::error::ShutdownReason;
+use std::fmt;
+use std::sync::Arc;
+
+/// Context of the failure of the background task of a [`WsClient`](crate::WsClient), passed to the hook set
+/// with [`WsClientBuilder::on_background_error`](crate::WsClientBuilder::on_background_error).
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundError {
+	/// Reason of the shutdown, the calls fail with it in
+	/// [`Error::RestartNeeded`](jsonrpsee_types::error::Error::RestartNeeded).
+	pub reason: ShutdownReason,
+	/// `true` if the task panicked, the panic message is reported by the panic hook of the process.
+	pub panicked: bool,
+	/// Last message received from the server before the failure, if any.
+	pub last_frame: Option<String>,
+	/// Number of calls, subscription requests and batches waiting for a response when the last message was
+	/// received.
+	pub pending_requests: usize,
+}
+
+/// Hook shared by the builder and the background task.
+#[derive(Clone)]
+pub(crate) struct ErrorHook(pub(crate) Arc<dyn Fn(&BackgroundError) + Send + Sync>);
+
+impl fmt::Debug for ErrorHook {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("ErrorHook")
+	}
+}
@@ -0,0 +1,62 @@
+use futures::future::{self, BoxFuture, FutureExt};
+use jsonrpsee_types::jsonrpc::SubscriptionId;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Detects the subscriptions that received neither notifications nor heartbeats within `timeout`.
+pub(crate) struct HeartbeatMonitor {
+	timeout: Duration,
+	last_seen: HashMap<SubscriptionId, Instant>,
+	timer: BoxFuture<'static, ()>,
+}
+
+impl HeartbeatMonitor {
+	pub(crate) fn new(timeout: Duration) -> Self {
+		Self { timeout, last_seen: HashMap::new(), timer: Self::sleep(timeout) }
+	}
+
+	// NOTE: checks twice per timeout such that silent subscriptions are closed at most half a timeout late.
+	fn sleep(timeout: Duration) -> BoxFuture<'static, ()> {
+		async_std::task::sleep(timeout / 2).boxed()
+	}
+
+	/// Timeout after which silent subscriptions are closed.
+	pub(crate) fn timeout(&self) -> Duration {
+		self.timeout
+	}
+
+	/// Resolves when the subscriptions have to be checked by [`HeartbeatMonitor::on_timer`], never resolves if
+	/// `monitor` is `None`.
+	pub(crate) async fn timer(monitor: &mut Option<HeartbeatMonitor>) {
+		match monitor.as_mut() {
+			Some(monitor) => (&mut monitor.timer).await,
+			None => future::pending().await,
+		}
+	}
+
+	/// Records that a notification or a heartbeat of `sub_id` was received.
+	pub(crate) fn on_notification(&mut self, sub_id: &SubscriptionId) {
+		self.last_seen.insert(sub_id.clone(), Instant::now());
+	}
+
+	/// Returns the subscriptions of `active` that timed out.
+	///
+	/// Subscriptions that didn't receive anything yet are timed from the first check after they became active.
+	pub(crate) fn on_timer<'a>(&mut self, active: impl Iterator<Item = &'a SubscriptionId>) -> Vec<SubscriptionId> {
+		self.timer = Self::sleep(self.timeout);
+		let now = Instant::now();
+		let mut last_seen = HashMap::new();
+		let mut expired = Vec::new();
+		for sub_id in active {
+			let seen = self.last_seen.get(sub_id).copied().unwrap_or(now);
+			if now.duration_since(seen) > self.timeout {
+				expired.push(sub_id.clone());
+			} else {
+				last_seen.insert(sub_id.clone(), seen);
+			}
+		}
+		// NOTE: rebuilt such that the entries of closed subscriptions are dropped.
+		self.last_seen = last_seen;
+		expired
+	}
+}
@@ -0,0 +1,56 @@
+use jsonrpsee_types::jsonrpc::Id;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Generates the IDs of the requests sent by a [`WsClient`](crate::WsClient), see
+/// [`WsClientBuilder::id_provider`](crate::WsClientBuilder::id_provider).
+///
+/// The IDs must be unique among the requests in flight on a connection, responses with an ID that
+/// isn't in flight are a protocol violation.
+///
+/// Closures returning an [`Id`] are providers, for example `|| Id::Str(uuid::Uuid::new_v4().to_string())`.
+pub trait IdProvider: Send + Sync + 'static {
+	/// Returns the ID of the next request.
+	fn next_id(&self) -> Id;
+}
+
+impl<F: Fn() -> Id + Send + Sync + 'static> IdProvider for F {
+	fn next_id(&self) -> Id {
+		self()
+	}
+}
+
+/// Provider of string IDs made of a prefix and an incrementing counter, like `"client-a/0"`, `"client-a/1"`.
+///
+/// Clients that use distinct prefixes generate globally unique IDs, for example when they are multiplexed
+/// through the same proxy.
+#[derive(Debug)]
+pub struct PrefixedIds {
+	prefix: String,
+	next: AtomicU64,
+}
+
+impl PrefixedIds {
+	/// Create a new provider of IDs starting with `prefix`.
+	pub fn new(prefix: impl Into<String>) -> Self {
+		Self { prefix: prefix.into(), next: AtomicU64::new(0) }
+	}
+}
+
+impl IdProvider for PrefixedIds {
+	fn next_id(&self) -> Id {
+		// NOTE: `fetch_add` wraps on overflow which is intended.
+		Id::Str(format!("{}{}", self.prefix, self.next.fetch_add(1, Ordering::Relaxed)))
+	}
+}
+
+/// Provider shared by the builder and the background task.
+#[derive(Clone)]
+pub(crate) struct SharedIdProvider(pub(crate) Arc<dyn IdProvider>);
+
+impl fmt::Debug for SharedIdProvider {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("IdProvider")
+	}
+}
@@ -9,6 +9,18 @@ use crate::{
 use jsonrpsee_types::client::{BatchMessage, NotificationMessage, RequestMessage, SubscriptionMessage};
 use jsonrpsee_types::error::Error;
 use jsonrpsee_types::jsonrpc::{self, Request};
+use jsonrpsee_types::traits::Value;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+/// Notification or request that can be coalesced with others into a batch.
+#[derive(Debug)]
+pub enum Coalesced<V> {
+	/// Notification.
+	Notification(NotificationMessage),
+	/// Request.
+	Request(RequestMessage<V>),
+}
 
 /// JSONRPC WebSocket sender.
 #[derive(Debug)]
@@ -22,11 +34,16 @@ impl Sender {
 		Self { transport }
 	}
 
+	/// Sends a ping.
+	pub async fn send_ping(&mut self) -> Result<(), WsConnectError> {
+		self.transport.send_ping().await
+	}
+
 	/// Send a batch request.
-	pub async fn start_batch_request(
+	pub async fn start_batch_request<V>(
 		&mut self,
-		batch: BatchMessage,
-		request_manager: &mut RequestManager,
+		batch: BatchMessage<V>,
+		request_manager: &mut RequestManager<V>,
 	) -> Result<(), Error> {
 		let req_id = request_manager.next_request_id()?;
 		let mut calls = Vec::with_capacity(batch.requests.len());
@@ -34,12 +51,12 @@ impl Sender {
 
 		for (method, params) in batch.requests {
 			let batch_id = request_manager.next_batch_id();
-			ids.push(batch_id);
+			ids.push(batch_id.clone());
 			calls.push(jsonrpc::Call::MethodCall(jsonrpc::MethodCall {
 				jsonrpc: jsonrpc::Version::V2,
 				method,
 				params,
-				id: jsonrpc::Id::Num(batch_id),
+				id: batch_id,
 			}));
 		}
 
@@ -49,12 +66,18 @@ impl Sender {
 			return Err(Error::InvalidRequestId);
 		};
 
+		let size = calls.len();
 		let res =
 			self.transport.send_request(Request::Batch(calls)).await.map_err(|e| Error::TransportError(Box::new(e)));
 
 		match res {
-			Ok(_) => Ok(()),
+			Ok(_) => {
+				request_manager.batch_sent(size);
+				request_manager.request_sent(req_id, batch.span);
+				Ok(())
+			}
 			Err(e) => {
+				batch.span.event("Failed to send the request");
 				request_manager.reclaim_request_id(req_id);
 				Err(e)
 			}
@@ -65,10 +88,10 @@ impl Sender {
 	/// Instead, you have keep the request ID and use the Receiver to get the response.
 	///
 	/// Returns Ok() if the request was successfully sent otherwise Err(_).
-	pub async fn start_request(
+	pub async fn start_request<V>(
 		&mut self,
-		request: RequestMessage,
-		request_manager: &mut RequestManager,
+		request: RequestMessage<V>,
+		request_manager: &mut RequestManager<V>,
 	) -> Result<(), Error> {
 		let id = match request_manager.next_request_id() {
 			Ok(id) => id,
@@ -78,18 +101,25 @@ impl Sender {
 				return Err(Error::Custom(str_err));
 			}
 		};
+		request_manager.request_started(id, &request.method);
+		let wire_id = request_manager.wire_id(id);
+		request.span.record_id(&wire_id);
 		let req = jsonrpc::Request::Single(jsonrpc::Call::MethodCall(jsonrpc::MethodCall {
 			jsonrpc: jsonrpc::Version::V2,
 			method: request.method,
 			params: request.params,
-			id: jsonrpc::Id::Num(id),
+			id: wire_id,
 		}));
 		match self.transport.send_request(req).await {
 			Ok(_) => {
 				request_manager.insert_pending_call(id, request.send_back).expect("ID unused checked above; qed");
+				request_manager.request_sent(id, request.span);
 				Ok(())
 			}
 			Err(e) => {
+				request.span.event("Failed to send the request");
+				request_manager.request_ended(id, false);
+				request_manager.reclaim_request_id(id);
 				let str_err = e.to_string();
 				let _ = request.send_back.map(|tx| tx.send(Err(Error::TransportError(Box::new(e)))));
 				Err(Error::Custom(str_err))
@@ -110,15 +140,87 @@ impl Sender {
 		self.transport.send_request(request).await.map_err(|e| Error::TransportError(Box::new(e)))
 	}
 
+	/// Sends notifications and requests to the server as a single batch but it doesn't wait for the responses.
+	///
+	/// In contrast to [`Sender::start_batch_request`] every request of the batch is answered separately through
+	/// its own `send_back` channel.
+	pub async fn send_coalesced<V>(
+		&mut self,
+		messages: Vec<Coalesced<V>>,
+		request_manager: &mut RequestManager<V>,
+	) -> Result<(), Error> {
+		let mut calls = Vec::with_capacity(messages.len());
+		let mut pending = Vec::new();
+
+		for msg in messages {
+			match msg {
+				Coalesced::Notification(notif) => calls.push(jsonrpc::Call::Notification(jsonrpc::Notification {
+					jsonrpc: jsonrpc::Version::V2,
+					method: notif.method,
+					params: notif.params,
+				})),
+				Coalesced::Request(request) => {
+					let id = match request_manager.next_request_id() {
+						Ok(id) => id,
+						Err(err) => {
+							request.send_back.map(|tx| tx.send(Err(err)));
+							continue;
+						}
+					};
+					request_manager.request_started(id, &request.method);
+					let wire_id = request_manager.wire_id(id);
+					request.span.record_id(&wire_id);
+					calls.push(jsonrpc::Call::MethodCall(jsonrpc::MethodCall {
+						jsonrpc: jsonrpc::Version::V2,
+						method: request.method,
+						params: request.params,
+						id: wire_id,
+					}));
+					pending.push((id, request.send_back, request.span));
+				}
+			}
+		}
+
+		let size = calls.len();
+		let req = match size {
+			0 => return Ok(()),
+			1 => Request::Single(calls.remove(0)),
+			_ => Request::Batch(calls),
+		};
+
+		match self.transport.send_request(req).await {
+			Ok(_) => {
+				if size > 1 {
+					request_manager.batch_sent(size);
+				}
+				for (id, send_back, span) in pending {
+					request_manager.insert_pending_call(id, send_back).expect("ID unused checked above; qed");
+					request_manager.request_sent(id, span);
+				}
+				Ok(())
+			}
+			Err(e) => {
+				let str_err = e.to_string();
+				for (id, send_back, span) in pending {
+					span.event("Failed to send the request");
+					request_manager.request_ended(id, false);
+					request_manager.reclaim_request_id(id);
+					let _ = send_back.map(|tx| tx.send(Err(Error::Custom(str_err.clone()))));
+				}
+				Err(Error::TransportError(Box::new(e)))
+			}
+		}
+	}
+
 	/// Sends a request to the server to start a new subscription but it doesn't wait for a response.
 	/// Instead, you have keep the request ID and use the [`Receiver`] to get the response.
 	///
-	/// Returns `Ok()` if the request was successfully sent otherwise `Err(_)`.
-	pub async fn start_subscription(
+	/// Returns `Ok(request_id)` if the request was successfully sent otherwise `Err(_)`.
+	pub async fn start_subscription<V>(
 		&mut self,
-		subscription: SubscriptionMessage,
-		request_manager: &mut RequestManager,
-	) -> Result<(), Error> {
+		subscription: SubscriptionMessage<V>,
+		request_manager: &mut RequestManager<V>,
+	) -> Result<u64, Error> {
 		let id = match request_manager.next_request_id() {
 			Ok(id) => id,
 			Err(err) => {
@@ -128,21 +230,32 @@ impl Sender {
 			}
 		};
 
+		let wire_id = request_manager.wire_id(id);
+		subscription.span.record_id(&wire_id);
 		let req = jsonrpc::Request::Single(jsonrpc::Call::MethodCall(jsonrpc::MethodCall {
 			jsonrpc: jsonrpc::Version::V2,
 			method: subscription.subscribe_method,
 			params: subscription.params,
-			id: jsonrpc::Id::Num(id as u64),
+			id: wire_id,
 		}));
 		if let Err(e) = self.transport.send_request(req).await {
+			subscription.span.event("Failed to send the request");
+			request_manager.reclaim_request_id(id);
 			let str_err = e.to_string();
 			let _ = subscription.send_back.send(Err(Error::TransportError(Box::new(e))));
 			return Err(Error::Custom(str_err));
 		}
 		request_manager
-			.insert_pending_subscription(id, subscription.send_back, subscription.unsubscribe_method)
+			.insert_pending_subscription(
+				id,
+				subscription.send_back,
+				subscription.unsubscribe_method,
+				subscription.overflow_policy,
+				subscription.filter,
+			)
 			.expect("Request ID unused checked above; qed");
-		Ok(())
+		request_manager.request_sent(id, subscription.span);
+		Ok(id)
 	}
 }
 
@@ -159,7 +272,17 @@ impl Receiver {
 	}
 
 	/// Reads the next response, fails if the response ID was not a number.
-	pub async fn next_response(&mut self) -> Result<jsonrpc::Response, WsConnectError> {
+	pub async fn next_response<V: Value>(&mut self) -> Result<jsonrpc::Response<V>, WsConnectError> {
 		self.transport.next_response().await
 	}
+
+	/// Returns a flag that is set when a pong is received.
+	pub fn pong_flag(&self) -> Arc<AtomicBool> {
+		self.transport.pong_flag()
+	}
+
+	/// Returns `false` if the transport has no pings.
+	pub fn supports_ping(&self) -> bool {
+		self.transport.supports_ping()
+	}
 }
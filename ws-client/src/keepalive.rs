@@ -0,0 +1,47 @@
+use crate::jsonrpc_transport;
+use crate::transport::WsConnectError;
+use futures::future::{self, BoxFuture, FutureExt};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Pings the server every `interval` and fails if no pong was received within `pong_timeout` of a ping.
+pub(crate) struct Keepalive {
+	interval: Duration,
+	pong_timeout: Duration,
+	pong: Arc<AtomicBool>,
+	awaiting_pong: bool,
+	timer: BoxFuture<'static, ()>,
+}
+
+impl Keepalive {
+	pub(crate) fn new(interval: Duration, pong_timeout: Duration, pong: Arc<AtomicBool>) -> Self {
+		Self { interval, pong_timeout, pong, awaiting_pong: false, timer: async_std::task::sleep(interval).boxed() }
+	}
+
+	/// Resolves when the keepalive has to be driven by [`Keepalive::on_timer`], never resolves if `keepalive`
+	/// is `None`.
+	pub(crate) async fn timer(keepalive: &mut Option<Keepalive>) {
+		match keepalive.as_mut() {
+			Some(keepalive) => (&mut keepalive.timer).await,
+			None => future::pending().await,
+		}
+	}
+
+	/// Sends the next ping or checks that the pong of the last ping was received.
+	pub(crate) async fn on_timer(&mut self, sender: &mut jsonrpc_transport::Sender) -> Result<(), WsConnectError> {
+		if self.awaiting_pong {
+			if !self.pong.swap(false, Ordering::Relaxed) {
+				return Err(WsConnectError::PongTimeout(self.pong_timeout));
+			}
+			self.awaiting_pong = false;
+			self.timer = async_std::task::sleep(self.interval.saturating_sub(self.pong_timeout)).boxed();
+		} else {
+			self.pong.store(false, Ordering::Relaxed);
+			sender.send_ping().await?;
+			self.awaiting_pong = true;
+			self.timer = async_std::task::sleep(self.pong_timeout).boxed();
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,95 @@
+use crate::manager::RequestManager;
+use crate::queue::{fail, is_dropped, needs_slot};
+use crate::transport::{Receiver, Sender, WsTransportClientBuilder};
+use futures::channel::mpsc;
+use futures::future::{self, Either};
+use futures::StreamExt;
+use jsonrpsee_types::{
+	client::FrontToBack,
+	error::{Error, ShutdownReason},
+};
+
+/// Connection dialed once the client is first used, see
+/// [`WsClientBuilder::build_lazy`](crate::WsClientBuilder::build_lazy).
+#[derive(Debug)]
+pub(crate) struct LazyConnection {
+	transport: WsTransportClientBuilder<'static>,
+	max_queued: usize,
+}
+
+impl LazyConnection {
+	pub(crate) fn new(transport: WsTransportClientBuilder<'static>, max_queued: usize) -> Self {
+		Self { transport, max_queued }
+	}
+
+	/// Waits for a call of the frontend and dials the server, returns the connection and the messages received
+	/// meanwhile, `None` if the frontend was dropped.
+	///
+	/// Up to `max_queued` calls wait for the connection, further calls fail with [`Error::MaxSlotsExceeded`].
+	/// If dialing fails, the waiting calls fail with [`Error::ConnectionLost`] and the server is dialed again
+	/// on the next call.
+	pub(crate) async fn connect<V>(
+		&self,
+		frontend: &mut mpsc::Receiver<FrontToBack<V>>,
+		// NOTE: borrowed mutably such that the future is `Send` without `V: Sync`.
+		manager: &mut RequestManager<V>,
+	) -> Option<(Sender, Receiver, Vec<FrontToBack<V>>)> {
+		let mut pending = Vec::new();
+		loop {
+			while !pending.iter().any(needs_connection) {
+				let msg = frontend.next().await?;
+				self.enqueue(&mut pending, msg, manager);
+			}
+
+			let dial = self.transport.clone().build();
+			futures::pin_mut!(dial);
+			let dialed = loop {
+				match future::select(dial.as_mut(), frontend.next()).await {
+					Either::Left((dialed, _)) => break dialed,
+					Either::Right((Some(msg), _)) => self.enqueue(&mut pending, msg, manager),
+					Either::Right((None, _)) => return None,
+				}
+			};
+
+			match dialed {
+				Ok((sender, receiver)) => return Some((sender, receiver, pending)),
+				Err(err) => {
+					log::debug!("[backend]: lazy connection failed: {:?}", err);
+					let reason = ShutdownReason::Transport(err.to_string());
+					// NOTE: the notification handlers remain registered for the next attempt.
+					let (handlers, failed): (Vec<_>, Vec<_>) =
+						pending.into_iter().partition(|msg| matches!(msg, FrontToBack::RegisterNotificationHandler(_)));
+					for msg in failed {
+						fail(msg, || Error::ConnectionLost(reason.clone()));
+					}
+					pending = handlers;
+				}
+			}
+		}
+	}
+
+	fn enqueue<V>(&self, pending: &mut Vec<FrontToBack<V>>, msg: FrontToBack<V>, manager: &RequestManager<V>) {
+		match msg {
+			FrontToBack::MemoryStats(send_back) => {
+				let _ = send_back.send(manager.memory_stats());
+			}
+			// NOTE: the subscriptions are only sent once connected.
+			FrontToBack::ActiveSubscriptions(send_back) => {
+				let _ = send_back.send(Vec::new());
+			}
+			// Nothing was sent yet, the dropped calls are just forgotten.
+			FrontToBack::RequestCancelled | FrontToBack::SubscriptionClosed(_) => {
+				pending.retain(|msg| !is_dropped(msg))
+			}
+			msg if needs_slot(&msg) && pending.iter().filter(|msg| needs_slot(msg)).count() >= self.max_queued => {
+				fail(msg, || Error::MaxSlotsExceeded)
+			}
+			msg => pending.push(msg),
+		}
+	}
+}
+
+/// Returns `true` if `msg` has to be sent to the server.
+fn needs_connection<V>(msg: &FrontToBack<V>) -> bool {
+	needs_slot(msg) || matches!(msg, FrontToBack::Notification(_))
+}
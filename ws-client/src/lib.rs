@@ -4,12 +4,36 @@
 //!
 //! `jsonrpsee-ws-client` is a [JSON RPC](https://www.jsonrpc.org/specification) WebSocket client library that's is built for `async/await`.
 
+/// Fluent method calls.
+mod call;
 /// WebSocket Client.
 pub mod client;
+/// Configuration loadable from configuration files.
+mod config;
+/// Diagnostics of the failures of the background task.
+mod diagnostics;
+/// Detection of silent subscriptions.
+mod heartbeat;
+/// Request ID generation.
+mod id;
 /// JSONRPC WebSocket transport.
 pub mod jsonrpc_transport;
+/// Ping/pong keepalive.
+mod keepalive;
+/// Connection dialed on first use.
+mod lazy;
 /// Request manager.
 pub mod manager;
+/// Client metrics.
+mod metrics;
+/// Hooks around method calls.
+mod middleware;
+/// HTTP and SOCKS5 proxy clients.
+mod proxy;
+/// Requests waiting for a free request slot.
+mod queue;
+/// SOCKS5 proxy client.
+mod socks;
 /// Stream.
 pub mod stream;
 /// WebSocket transport.
@@ -18,5 +42,22 @@ pub mod transport;
 #[cfg(test)]
 mod tests;
 
-pub use client::{WsClient, WsClientBuilder};
+pub use call::CallBuilder;
+pub use client::{CancelHandle, ConnectionEvent, SubscriptionDescriptor, WsClient, WsClientBuilder};
+pub use config::{BatchingWindowConfig, ClientConfig, InvalidConfig, PingConfig, ReconnectConfig, RequestQueueConfig};
+pub use diagnostics::BackgroundError;
+pub use id::{IdProvider, PrefixedIds};
 pub use jsonrpsee_types::client::Subscription as WsSubscription;
+pub use jsonrpsee_types::client::{ActiveSubscription, MemoryStats, NotifFilter, OverflowPolicy};
+#[cfg(feature = "cbor")]
+pub use jsonrpsee_types::wire::Cbor;
+pub use jsonrpsee_types::wire::{Codec, Json};
+pub use metrics::ClientMetrics;
+pub use middleware::{CallContext, ClientMiddleware};
+pub use proxy::{HttpProxy, Proxy};
+/// Re-exported for [`WsClientBuilder::tls_config`].
+pub use rustls;
+pub use socks::Socks5Proxy;
+pub use transport::Direction;
+#[cfg(all(unix, feature = "uds"))]
+pub use transport::UdsFraming;
@@ -2,26 +2,30 @@
 //!
 //! Definitions:
 //!
-//!    - RequestId: request ID in the JSONRPC-v2 specification
-//!    > **Note**: The spec allow number, string or null but this crate only supports numbers.
+//!    - RequestId: slot occupied by a request, which is also its ID in the JSONRPC-v2 specification unless
+//!      an [`IdProvider`](crate::IdProvider) generates the IDs.
 //!    - SubscriptionId: unique ID generated by server
 
-use fnv::FnvHashMap;
+use crate::id::SharedIdProvider;
+use crate::metrics::SharedMetrics;
+use fnv::{FnvHashMap, FnvHashSet};
 use futures::channel::{mpsc, oneshot};
 use jsonrpsee_types::{
+	client::{MemoryStats, NotifFilter, NotifReceiver, NotifSendError, NotifSender, OverflowPolicy, RequestSpan},
 	error::Error,
-	jsonrpc::{JsonValue, SubscriptionId},
+	jsonrpc::{self, Id, JsonValue, Params, SubscriptionId},
 };
 use std::collections::{
 	hash_map::{Entry, HashMap},
 	VecDeque,
 };
+use std::time::Instant;
 
 #[derive(Debug)]
-enum Kind {
-	PendingMethodCall(PendingCallOneshot),
-	PendingSubscription((PendingSubscriptionOneshot, UnsubscribeMethod)),
-	Subscription((SubscriptionSink, UnsubscribeMethod)),
+enum Kind<V> {
+	PendingMethodCall(PendingCallOneshot<V>),
+	PendingSubscription((PendingSubscriptionOneshot<V>, UnsubscribeMethod, PendingSubscriptionSink<V>)),
+	Subscription((SubscriptionSink<V>, UnsubscribeMethod)),
 }
 
 #[derive(Debug)]
@@ -37,82 +41,314 @@ pub enum RequestStatus {
 	Invalid,
 }
 
-type PendingCallOneshot = Option<oneshot::Sender<Result<JsonValue, Error>>>;
-type PendingBatchOneshot = oneshot::Sender<Result<Vec<JsonValue>, Error>>;
-type PendingSubscriptionOneshot = oneshot::Sender<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>;
-type SubscriptionSink = mpsc::Sender<JsonValue>;
+type PendingCallOneshot<V> = Option<oneshot::Sender<Result<V, Error>>>;
+type PendingBatchOneshot<V> = oneshot::Sender<Result<Vec<Result<V, jsonrpc::Error>>, Error>>;
+type PendingSubscriptionOneshot<V> = oneshot::Sender<Result<(NotifReceiver<V>, SubscriptionId), Error>>;
+/// How the notifications of a pending subscription will be buffered.
+type PendingSubscriptionSink<V> = (OverflowPolicy, Option<NotifFilter<V>>);
+type SubscriptionSink<V> = NotifSender<V>;
 type UnsubscribeMethod = String;
 /// Unique ID that are generated by the RequestManager.
 // TODO: new type for this https://github.com/paritytech/jsonrpsee/issues/249
 type RequestId = u64;
-/// Wrapping counter for requests within a batch request.
-// TODO: new type for this https://github.com/paritytech/jsonrpsee/issues/249
-type BatchId = u64;
+/// ID of a request within a batch request, a wrapping counter unless an `IdProvider` generates the IDs.
+type BatchId = Id;
 
 #[derive(Debug)]
 /// Batch state.
-pub struct BatchState {
+pub struct BatchState<V = JsonValue> {
 	/// Order that the request was performed in.
 	pub order: FnvHashMap<BatchId, usize>,
 	/// Request ID fetch from the `RequestManager`
 	pub request_id: RequestId,
 	/// Oneshot send back.
-	pub send_back: PendingBatchOneshot,
+	pub send_back: PendingBatchOneshot<V>,
+	/// Responses received so far, in the order of the requests.
+	pub responses: Vec<Option<Result<V, jsonrpc::Error>>>,
+	/// Number of batches sent before this one, to tell the oldest pending batch.
+	pub seq: u64,
+}
+
+impl<V> BatchState<V> {
+	/// Returns the responses in the order of the requests.
+	///
+	/// # Panics
+	///
+	/// Panics if not all responses were received.
+	pub fn into_responses(self) -> (PendingBatchOneshot<V>, Vec<Result<V, jsonrpc::Error>>) {
+		let responses = self.responses.into_iter().map(|rp| rp.expect("Batch responses are complete; qed")).collect();
+		(self.send_back, responses)
+	}
 }
 
 #[derive(Debug)]
 /// Manages and monitors JSONRPC v2 method calls and subscriptions.
-pub struct RequestManager {
+pub struct RequestManager<V = JsonValue> {
 	/// Batch ID.
-	batch_id: BatchId,
+	batch_id: u64,
 	/// Vacant requestIDs.
 	free_slots: VecDeque<RequestId>,
+	/// Request ID that replaces the slot of the next cancelled call.
+	next_slot: RequestId,
+	/// Cancelled calls still waiting for a response, their request IDs are never reused.
+	cancelled: FnvHashSet<RequestId>,
 	/// List of requests that are waiting for a response from the server.
 	// NOTE: FnvHashMap is used here because RequestId is not under the caller's control and is known to be a short key.
-	requests: FnvHashMap<RequestId, Kind>,
+	requests: FnvHashMap<RequestId, Kind<V>>,
 	/// Reverse lookup, to find a request ID in constant time by `subscription ID` instead of looking through all requests.
 	subscriptions: HashMap<SubscriptionId, RequestId>,
 	/// Pending batch requests
-	batches: FnvHashMap<Vec<BatchId>, BatchState>,
+	batches: FnvHashMap<Vec<BatchId>, BatchState<V>>,
+	/// Generates the IDs sent to the server, the request IDs are sent if `None`.
+	id_provider: Option<SharedIdProvider>,
+	/// Request IDs by the generated IDs of the requests in flight.
+	generated_ids: HashMap<Id, RequestId>,
+	/// Reverse lookup of `generated_ids`.
+	request_ids: FnvHashMap<RequestId, Id>,
+	/// Number of batch requests sent.
+	batches_sent: u64,
+	/// Match responses to batch requests that the server splits across frames.
+	lenient_batches: bool,
+	/// Match the errors without ID to pending batch requests, for servers that reject batches.
+	split_rejected_batches: bool,
+	/// Reject the subscription IDs that are not well-formed.
+	strict_subscription_ids: bool,
+	/// Notify the server of the cancelled calls.
+	cancel_notifications: bool,
+	/// Sinks of the notifications sent without a subscription, by method.
+	notification_handlers: HashMap<String, Vec<mpsc::Sender<Params>>>,
+	/// Metrics of the requests and notifications.
+	metrics: Option<SharedMetrics>,
+	/// Method and start of the calls in flight, only tracked with metrics.
+	started_calls: FnvHashMap<RequestId, (String, Instant)>,
+	/// Spans of the requests in flight, only tracked if they are enabled.
+	spans: FnvHashMap<RequestId, RequestSpan>,
 }
 
-impl RequestManager {
+impl<V> RequestManager<V> {
 	/// Create a new `RequestManager` with specified capacity.
 	pub fn new(slot_capacity: usize) -> Self {
 		Self {
 			batch_id: 0,
 			free_slots: (0..slot_capacity as u64).collect(),
+			next_slot: slot_capacity as u64,
+			cancelled: FnvHashSet::default(),
 			requests: FnvHashMap::default(),
 			subscriptions: HashMap::new(),
 			batches: HashMap::default(),
+			id_provider: None,
+			generated_ids: HashMap::new(),
+			request_ids: FnvHashMap::default(),
+			batches_sent: 0,
+			lenient_batches: false,
+			split_rejected_batches: false,
+			strict_subscription_ids: false,
+			cancel_notifications: false,
+			notification_handlers: HashMap::new(),
+			metrics: None,
+			started_calls: FnvHashMap::default(),
+			spans: FnvHashMap::default(),
+		}
+	}
+
+	/// Match the responses to batch requests individually, for servers that split them across frames or
+	/// answer them one by one.
+	pub(crate) fn with_lenient_batches(mut self, lenient: bool) -> Self {
+		self.lenient_batches = lenient;
+		self
+	}
+
+	/// Answer the oldest pending batch request with the errors without ID that reject batches, see
+	/// [`RequestManager::take_rejected_batch`].
+	pub(crate) fn with_split_rejected_batches(mut self, split: bool) -> Self {
+		self.split_rejected_batches = split;
+		self
+	}
+
+	/// Reject the subscription IDs that are empty or contain whitespace or control characters.
+	pub(crate) fn with_strict_subscription_ids(mut self, strict: bool) -> Self {
+		self.strict_subscription_ids = strict;
+		self
+	}
+
+	/// Return the IDs of the calls to notify the server of from [`RequestManager::cancel_dropped_calls`].
+	pub(crate) fn with_cancel_notifications(mut self, notify: bool) -> Self {
+		self.cancel_notifications = notify;
+		self
+	}
+
+	/// Returns `false` if strict subscription IDs are enabled and `sub_id` is not well-formed.
+	pub(crate) fn accepts_subscription_id(&self, sub_id: &SubscriptionId) -> bool {
+		match sub_id {
+			SubscriptionId::Str(id) if self.strict_subscription_ids => {
+				!id.is_empty() && id.bytes().all(|b| b.is_ascii_graphic())
+			}
+			_ => true,
 		}
 	}
 
+	/// Report the requests and notifications to `metrics`.
+	pub(crate) fn with_metrics(mut self, metrics: Option<SharedMetrics>) -> Self {
+		self.metrics = metrics;
+		self
+	}
+
+	/// Generate the IDs sent to the server with `id_provider`.
+	pub(crate) fn with_id_provider(mut self, id_provider: Option<SharedIdProvider>) -> Self {
+		self.id_provider = id_provider;
+		self
+	}
+
 	/// Get next batch ID.
 	pub fn next_batch_id(&mut self) -> BatchId {
+		if let Some(provider) = self.id_provider.as_ref() {
+			return provider.0.next_id();
+		}
 		let id = self.batch_id;
 		self.batch_id = self.batch_id.wrapping_add(1);
+		Id::Num(id)
+	}
+
+	/// Returns the ID sent to the server for the request occupying `request_id`.
+	pub fn wire_id(&mut self, request_id: RequestId) -> Id {
+		let provider = match self.id_provider.as_ref() {
+			Some(provider) => provider,
+			None => return Id::Num(request_id),
+		};
+		let id = provider.0.next_id();
+		self.generated_ids.insert(id.clone(), request_id);
+		self.request_ids.insert(request_id, id.clone());
 		id
 	}
 
+	/// Returns the ID sent to the server for the request in flight occupying `request_id`.
+	pub(crate) fn sent_id(&self, request_id: RequestId) -> Id {
+		self.request_ids.get(&request_id).cloned().unwrap_or(Id::Num(request_id))
+	}
+
+	/// Returns the request ID of the request the server sent a response with `id` to.
+	pub fn request_id_of(&self, id: &Id) -> Option<RequestId> {
+		match self.id_provider {
+			Some(_) => self.generated_ids.get(id).copied(),
+			None => id.as_number().copied(),
+		}
+	}
+
+	/// Removes all requests, used when the connection was lost.
+	///
+	/// Pending calls, batches and subscriptions are answered with the error returned by `err`, the active
+	/// subscriptions are returned as `(request ID, subscription ID, sink, unsubscribe method)`.
+	pub fn reset(
+		&mut self,
+		err: impl Fn() -> Error,
+	) -> Vec<(RequestId, SubscriptionId, SubscriptionSink<V>, UnsubscribeMethod)> {
+		let mut sub_ids: FnvHashMap<RequestId, SubscriptionId> =
+			self.subscriptions.drain().map(|(sub_id, request_id)| (request_id, sub_id)).collect();
+		let mut active = Vec::new();
+
+		for (request_id, kind) in self.requests.drain() {
+			self.free_slots.push_front(request_id);
+			match kind {
+				Kind::PendingMethodCall(Some(send_back)) => {
+					let _ = send_back.send(Err(err()));
+				}
+				Kind::PendingMethodCall(None) => (),
+				Kind::PendingSubscription((send_back, _, _)) => {
+					let _ = send_back.send(Err(err()));
+				}
+				Kind::Subscription((sink, unsubscribe_method)) => {
+					let sub_id = sub_ids.remove(&request_id).expect("Active subscriptions have a subscription ID; qed");
+					active.push((request_id, sub_id, sink, unsubscribe_method));
+				}
+			}
+		}
+		for (_, batch) in self.batches.drain() {
+			self.free_slots.push_front(batch.request_id);
+			let _ = batch.send_back.send(Err(err()));
+		}
+		if let Some(metrics) = self.metrics.as_ref() {
+			for (_, (method, started)) in self.started_calls.drain() {
+				metrics.0.on_request_end(&method, started.elapsed(), false);
+			}
+		}
+		for (_, span) in self.spans.drain() {
+			span.event("Connection lost");
+		}
+		self.cancelled.clear();
+		self.generated_ids.clear();
+		self.request_ids.clear();
+		active
+	}
+
 	/// Mark a used RequestID as free again.
 	pub fn reclaim_request_id(&mut self, request_id: RequestId) {
+		if let Some(id) = self.request_ids.remove(&request_id) {
+			self.generated_ids.remove(&id);
+		}
 		self.free_slots.push_back(request_id);
 	}
 
+	/// Returns `true` if a request slot is free.
+	pub fn has_free_slot(&self) -> bool {
+		!self.free_slots.is_empty()
+	}
+
 	/// Get the next available request ID.
 	pub fn next_request_id(&mut self) -> Result<RequestId, Error> {
 		self.free_slots.pop_front().ok_or(Error::MaxSlotsExceeded)
 	}
 
+	/// Removes the pending calls whose callers dropped the request, the response to them is ignored.
+	///
+	/// The slot of a cancelled call is replaced by a new request ID immediately, such that the late
+	/// response can't be taken for the response to another request.
+	///
+	/// Returns the IDs sent to the server of the cancelled calls, to notify the server of, empty unless enabled
+	/// by [`RequestManager::with_cancel_notifications`].
+	pub(crate) fn cancel_dropped_calls(&mut self) -> Vec<Id> {
+		let dropped: Vec<RequestId> = self
+			.requests
+			.iter()
+			.filter_map(|(request_id, kind)| match kind {
+				Kind::PendingMethodCall(Some(send_back)) if send_back.is_canceled() => Some(*request_id),
+				_ => None,
+			})
+			.collect();
+		let mut notify = Vec::new();
+		for request_id in dropped {
+			if self.cancel_notifications {
+				notify.push(self.sent_id(request_id));
+			}
+			self.requests.remove(&request_id);
+			self.request_ended(request_id, false);
+			self.request_answered(request_id, "Request cancelled");
+			self.cancelled.insert(request_id);
+			self.free_slots.push_back(self.next_slot);
+			self.next_slot += 1;
+		}
+		notify
+	}
+
+	/// Returns `true` if `id` is the ID of a cancelled call, which is forgotten with its response.
+	pub(crate) fn take_cancelled(&mut self, id: &Id) -> bool {
+		let request_id = match self.request_id_of(id) {
+			Some(request_id) if self.cancelled.remove(&request_id) => request_id,
+			_ => return false,
+		};
+		if let Some(id) = self.request_ids.remove(&request_id) {
+			self.generated_ids.remove(&id);
+		}
+		true
+	}
+
 	/// Tries to insert a new pending call.
 	///
 	/// Returns `Ok` if the pending request was successfully inserted otherwise `Err`.
 	pub fn insert_pending_call(
 		&mut self,
 		id: RequestId,
-		send_back: PendingCallOneshot,
-	) -> Result<(), PendingCallOneshot> {
+		send_back: PendingCallOneshot<V>,
+	) -> Result<(), PendingCallOneshot<V>> {
 		if let Entry::Vacant(v) = self.requests.entry(id) {
 			v.insert(Kind::PendingMethodCall(send_back));
 			Ok(())
@@ -121,22 +357,64 @@ impl RequestManager {
 		}
 	}
 
+	/// Records that the call to `method` occupying `request_id` is sent.
+	pub(crate) fn request_started(&mut self, request_id: RequestId, method: &str) {
+		if let Some(metrics) = self.metrics.as_ref() {
+			metrics.0.on_request_start(method);
+			self.started_calls.insert(request_id, (method.to_owned(), Instant::now()));
+		}
+	}
+
+	/// Records that the call occupying `request_id` was answered or failed.
+	pub(crate) fn request_ended(&mut self, request_id: RequestId, success: bool) {
+		if let (Some(metrics), Some((method, started))) =
+			(self.metrics.as_ref(), self.started_calls.remove(&request_id))
+		{
+			metrics.0.on_request_end(&method, started.elapsed(), success);
+		}
+	}
+
+	/// Keeps the span of the request occupying `request_id`, which was sent, until it's answered.
+	pub(crate) fn request_sent(&mut self, request_id: RequestId, span: RequestSpan) {
+		if span.is_enabled() {
+			span.event("Request sent");
+			self.spans.insert(request_id, span);
+		}
+	}
+
+	/// Emits `message` in the span of the request occupying `request_id`, which was answered, and drops the span.
+	pub(crate) fn request_answered(&mut self, request_id: RequestId, message: &str) {
+		if let Some(span) = self.spans.remove(&request_id) {
+			span.event(message);
+		}
+	}
+
+	/// Records that a batch request of `size` calls was sent.
+	pub(crate) fn batch_sent(&self, size: usize) {
+		if let Some(metrics) = self.metrics.as_ref() {
+			metrics.0.on_batch(size);
+		}
+	}
+
 	/// Tries to insert a new batch request
 	///
 	/// Returns `Ok` if the pending request was successfully inserted otherwise `Err`.
 	pub fn insert_pending_batch(
 		&mut self,
 		mut batch: Vec<BatchId>,
-		send_back: PendingBatchOneshot,
+		send_back: PendingBatchOneshot<V>,
 		request_id: RequestId,
-	) -> Result<(), PendingBatchOneshot> {
+	) -> Result<(), PendingBatchOneshot<V>> {
 		let mut order = FnvHashMap::with_capacity_and_hasher(batch.len(), Default::default());
 		for (idx, batch_id) in batch.iter().enumerate() {
-			order.insert(*batch_id, idx);
+			order.insert(batch_id.clone(), idx);
 		}
 		batch.sort_unstable();
+		let responses = (0..batch.len()).map(|_| None).collect();
 		if let Entry::Vacant(v) = self.batches.entry(batch) {
-			v.insert(BatchState { order, request_id, send_back });
+			let seq = self.batches_sent;
+			self.batches_sent += 1;
+			v.insert(BatchState { order, request_id, send_back, responses, seq });
 			Ok(())
 		} else {
 			Err(send_back)
@@ -148,11 +426,13 @@ impl RequestManager {
 	pub fn insert_pending_subscription(
 		&mut self,
 		id: RequestId,
-		send_back: PendingSubscriptionOneshot,
+		send_back: PendingSubscriptionOneshot<V>,
 		unsubscribe_method: UnsubscribeMethod,
-	) -> Result<(), PendingSubscriptionOneshot> {
+		overflow_policy: OverflowPolicy,
+		filter: Option<NotifFilter<V>>,
+	) -> Result<(), PendingSubscriptionOneshot<V>> {
 		if let Entry::Vacant(v) = self.requests.entry(id) {
-			v.insert(Kind::PendingSubscription((send_back, unsubscribe_method)));
+			v.insert(Kind::PendingSubscription((send_back, unsubscribe_method, (overflow_policy, filter))));
 			Ok(())
 		} else {
 			Err(send_back)
@@ -166,9 +446,9 @@ impl RequestManager {
 		&mut self,
 		request_id: RequestId,
 		subscription_id: SubscriptionId,
-		send_back: SubscriptionSink,
+		send_back: SubscriptionSink<V>,
 		unsubscribe_method: String,
-	) -> Result<(), SubscriptionSink> {
+	) -> Result<(), SubscriptionSink<V>> {
 		if let (Entry::Vacant(request), Entry::Vacant(subscription)) =
 			(self.requests.entry(request_id), self.subscriptions.entry(subscription_id))
 		{
@@ -186,7 +466,7 @@ impl RequestManager {
 	pub fn complete_pending_subscription(
 		&mut self,
 		request_id: RequestId,
-	) -> Option<(PendingSubscriptionOneshot, UnsubscribeMethod)> {
+	) -> Option<(PendingSubscriptionOneshot<V>, UnsubscribeMethod, PendingSubscriptionSink<V>)> {
 		match self.requests.entry(request_id) {
 			Entry::Occupied(request) if matches!(request.get(), Kind::PendingSubscription(_)) => {
 				let (_req_id, kind) = request.remove_entry();
@@ -203,7 +483,7 @@ impl RequestManager {
 	/// Tries to complete a pending batch request
 	///
 	/// Returns `Some` if the subscription was completed otherwise `None`.
-	pub fn complete_pending_batch(&mut self, batch: Vec<BatchId>) -> Option<BatchState> {
+	pub fn complete_pending_batch(&mut self, batch: Vec<BatchId>) -> Option<BatchState<V>> {
 		match self.batches.entry(batch) {
 			Entry::Occupied(request) => {
 				let (_digest, state) = request.remove_entry();
@@ -213,10 +493,87 @@ impl RequestManager {
 		}
 	}
 
+	/// Returns the oldest pending batch request if `response` is an error without ID that servers without
+	/// batch support answer batches with, a parse error or an invalid request error.
+	///
+	/// Returns `None` unless enabled by [`RequestManager::with_split_rejected_batches`], the caller is expected to
+	/// reclaim the request ID of the batch.
+	pub fn take_rejected_batch(&mut self, response: &jsonrpc::Output<V>) -> Option<BatchState<V>> {
+		let rejection = match response {
+			jsonrpc::Output::Failure(failure) if self.split_rejected_batches && failure.id == Id::Null => {
+				&failure.error
+			}
+			_ => return None,
+		};
+		if !matches!(rejection.code, jsonrpc::ErrorCode::ParseError | jsonrpc::ErrorCode::InvalidRequest) {
+			return None;
+		}
+		let digest = self.batches.iter().min_by_key(|(_, state)| state.seq).map(|(digest, _)| digest.clone())?;
+		self.batches.remove(&digest)
+	}
+
+	/// Returns `true` if lenient batches are enabled and the response to `id` belongs to a pending batch
+	/// request rather than to a pending call or subscription.
+	///
+	/// Pending calls and subscriptions take precedence, unless an `IdProvider` generates the IDs the
+	/// requests of a batch share their IDs with them.
+	pub fn is_split_batch_response(&self, id: &Id) -> bool {
+		if !self.lenient_batches {
+			return false;
+		}
+		let pending_request = self.request_id_of(id).and_then(|request_id| self.requests.get(&request_id));
+		if matches!(pending_request, Some(Kind::PendingMethodCall(_)) | Some(Kind::PendingSubscription(_))) {
+			return false;
+		}
+		self.batches.keys().any(|batch| batch.binary_search(id).is_ok())
+	}
+
+	/// Adds the response to `id` to the pending batch request containing `id`.
+	///
+	/// Returns `Some` with the completed batch once all its responses were received, the caller is expected
+	/// to reclaim its request ID.
+	pub fn add_batch_response(&mut self, id: &Id, response: Result<V, jsonrpc::Error>) -> Option<BatchState<V>> {
+		let (digest, state) = self.batches.iter_mut().find(|(batch, _)| batch.binary_search(id).is_ok())?;
+		let pos = *state.order.get(id).expect("The batch contains the ID checked above; qed");
+		state.responses[pos] = Some(response);
+		if state.responses.iter().any(Option::is_none) {
+			return None;
+		}
+		let digest = digest.clone();
+		self.batches.remove(&digest)
+	}
+
+	/// Delivers the notifications of `method` sent without a subscription to `sink`.
+	pub fn insert_notification_handler(&mut self, method: String, sink: mpsc::Sender<Params>) {
+		self.notification_handlers.entry(method).or_default().push(sink);
+	}
+
+	/// Sends a notification to the handlers of its method, the handlers that were dropped are removed.
+	///
+	/// Returns `false` if the method has no handlers.
+	pub fn send_to_notification_handlers(&mut self, method: &str, params: Params) -> bool {
+		let handlers = match self.notification_handlers.get_mut(method) {
+			Some(handlers) => handlers,
+			None => return false,
+		};
+		handlers.retain_mut(|sink| match sink.try_send(params.clone()) {
+			Ok(()) => true,
+			Err(err) if err.is_disconnected() => false,
+			Err(_) => {
+				log::warn!("Notification handler of {:?} is full, dropping notification", method);
+				true
+			}
+		});
+		if handlers.is_empty() {
+			self.notification_handlers.remove(method);
+		}
+		true
+	}
+
 	/// Tries to complete a pending call..
 	///
 	/// Returns `Some` if the call was completed otherwise `None`.
-	pub fn complete_pending_call(&mut self, request_id: RequestId) -> Option<PendingCallOneshot> {
+	pub fn complete_pending_call(&mut self, request_id: RequestId) -> Option<PendingCallOneshot<V>> {
 		match self.requests.entry(request_id) {
 			Entry::Occupied(request) if matches!(request.get(), Kind::PendingMethodCall(_)) => {
 				let (_req_id, kind) = request.remove_entry();
@@ -237,7 +594,7 @@ impl RequestManager {
 		&mut self,
 		request_id: RequestId,
 		subscription_id: SubscriptionId,
-	) -> Option<(SubscriptionSink, UnsubscribeMethod, SubscriptionId)> {
+	) -> Option<(SubscriptionSink<V>, UnsubscribeMethod, SubscriptionId)> {
 		match (self.requests.entry(request_id), self.subscriptions.entry(subscription_id)) {
 			(Entry::Occupied(request), Entry::Occupied(subscription))
 				if matches!(request.get(), Kind::Subscription(_)) =>
@@ -266,7 +623,7 @@ impl RequestManager {
 	/// Get a mutable reference to underlying `Sink` in order to send messages to the subscription.
 	///
 	/// Returns `Some` if the `request_id` was registered as a subscription otherwise `None`.
-	pub fn as_subscription_mut(&mut self, request_id: &RequestId) -> Option<&mut SubscriptionSink> {
+	pub fn as_subscription_mut(&mut self, request_id: &RequestId) -> Option<&mut SubscriptionSink<V>> {
 		if let Some(Kind::Subscription((sink, _))) = self.requests.get_mut(request_id) {
 			Some(sink)
 		} else {
@@ -274,18 +631,80 @@ impl RequestManager {
 		}
 	}
 
+	/// Buffers a notification with `method` for the subscription occupying `request_id`.
+	///
+	/// Returns `None` if `request_id` was not registered as a subscription.
+	pub(crate) async fn send_notification(
+		&mut self,
+		request_id: &RequestId,
+		method: &str,
+		notif: V,
+	) -> Option<Result<(), NotifSendError>> {
+		let sink = match self.requests.get_mut(request_id) {
+			Some(Kind::Subscription((sink, _))) => sink,
+			_ => return None,
+		};
+		let dropped = sink.dropped();
+		let res = sink.send(notif).await;
+		if let Some(metrics) = self.metrics.as_ref() {
+			metrics.0.on_notification(method);
+			for _ in dropped..sink.dropped() + u64::from(res.is_err()) {
+				metrics.0.on_notification_dropped(method);
+			}
+		}
+		Some(res)
+	}
+
 	/// Reverse lookup to get the request ID for a subscription ID.
 	///
 	/// Returns `Some` if the subscription ID was registered as a subscription otherwise `None`.
 	pub fn get_request_id_by_subscription_id(&self, sub_id: &SubscriptionId) -> Option<RequestId> {
 		self.subscriptions.get(sub_id).copied()
 	}
+
+	/// Returns the IDs of the active subscriptions.
+	pub fn active_subscriptions(&self) -> impl Iterator<Item = &SubscriptionId> {
+		self.subscriptions.keys()
+	}
+
+	/// Returns the request ID, subscription ID and number of buffered notifications of each active subscription.
+	pub(crate) fn subscription_buffers(&self) -> impl Iterator<Item = (RequestId, &SubscriptionId, usize)> {
+		self.subscriptions.iter().filter_map(move |(sub_id, request_id)| match self.requests.get(request_id) {
+			Some(Kind::Subscription((sink, _))) => Some((*request_id, sub_id, sink.buffered())),
+			_ => None,
+		})
+	}
+
+	/// Returns the number of calls, subscription requests and batches waiting for a response.
+	pub(crate) fn pending_requests(&self) -> usize {
+		self.requests.values().filter(|kind| !matches!(kind, Kind::Subscription(_))).count() + self.batches.len()
+	}
+
+	/// Returns the number of requests, subscriptions and buffered notifications.
+	pub(crate) fn memory_stats(&self) -> MemoryStats {
+		let buffered_notifications = self
+			.requests
+			.values()
+			.map(|kind| match kind {
+				Kind::Subscription((sink, _)) => sink.buffered(),
+				_ => 0,
+			})
+			.sum();
+		MemoryStats {
+			pending_requests: self.pending_requests(),
+			free_request_slots: self.free_slots.len(),
+			active_subscriptions: self.subscriptions.len(),
+			buffered_notifications,
+			notification_handlers: self.notification_handlers.values().map(Vec::len).sum(),
+		}
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::{Error, RequestManager};
-	use futures::channel::{mpsc, oneshot};
+	use futures::channel::oneshot;
+	use jsonrpsee_types::client::{notif_channel, NotifReceiver, OverflowPolicy};
 	use jsonrpsee_types::jsonrpc::{JsonValue, SubscriptionId};
 	const TEST_LIMIT: usize = 10;
 
@@ -300,11 +719,19 @@ mod tests {
 
 	#[test]
 	fn insert_remove_subscription_works() {
-		let (pending_sub_tx, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
-		let (sub_tx, _) = mpsc::channel::<JsonValue>(1);
+		let (pending_sub_tx, _) = oneshot::channel::<Result<(NotifReceiver<JsonValue>, SubscriptionId), Error>>();
+		let (sub_tx, _rx) = notif_channel::<JsonValue>(1, OverflowPolicy::default());
 		let mut manager = RequestManager::new(TEST_LIMIT);
-		assert!(manager.insert_pending_subscription(1, pending_sub_tx, "unsubscribe_method".into()).is_ok());
-		let (_send_back_oneshot, unsubscribe_method) = manager.complete_pending_subscription(1).unwrap();
+		assert!(manager
+			.insert_pending_subscription(
+				1,
+				pending_sub_tx,
+				"unsubscribe_method".into(),
+				OverflowPolicy::default(),
+				None
+			)
+			.is_ok());
+		let (_send_back_oneshot, unsubscribe_method, _) = manager.complete_pending_subscription(1).unwrap();
 		assert!(manager
 			.insert_subscription(1, SubscriptionId::Str("uniq_id_from_server".to_string()), sub_tx, unsubscribe_method)
 			.is_ok());
@@ -317,13 +744,15 @@ mod tests {
 	fn pending_method_call_faulty() {
 		let (request_tx1, _) = oneshot::channel::<Result<JsonValue, Error>>();
 		let (request_tx2, _) = oneshot::channel::<Result<JsonValue, Error>>();
-		let (pending_sub_tx, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
-		let (sub_tx, _) = mpsc::channel::<JsonValue>(1);
+		let (pending_sub_tx, _) = oneshot::channel::<Result<(NotifReceiver<JsonValue>, SubscriptionId), Error>>();
+		let (sub_tx, _rx) = notif_channel::<JsonValue>(1, OverflowPolicy::default());
 
 		let mut manager = RequestManager::new(TEST_LIMIT);
 		assert!(manager.insert_pending_call(0, Some(request_tx1)).is_ok());
 		assert!(manager.insert_pending_call(0, Some(request_tx2)).is_err());
-		assert!(manager.insert_pending_subscription(0, pending_sub_tx, "beef".to_string()).is_err());
+		assert!(manager
+			.insert_pending_subscription(0, pending_sub_tx, "beef".to_string(), OverflowPolicy::default(), None)
+			.is_err());
 		assert!(manager.insert_subscription(0, SubscriptionId::Num(137), sub_tx, "bibimbap".to_string()).is_err());
 
 		assert!(manager.remove_subscription(0, SubscriptionId::Num(137)).is_none());
@@ -334,14 +763,18 @@ mod tests {
 	#[test]
 	fn pending_subscription_faulty() {
 		let (request_tx, _) = oneshot::channel::<Result<JsonValue, Error>>();
-		let (pending_sub_tx1, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
-		let (pending_sub_tx2, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
-		let (sub_tx, _) = mpsc::channel::<JsonValue>(1);
+		let (pending_sub_tx1, _) = oneshot::channel::<Result<(NotifReceiver<JsonValue>, SubscriptionId), Error>>();
+		let (pending_sub_tx2, _) = oneshot::channel::<Result<(NotifReceiver<JsonValue>, SubscriptionId), Error>>();
+		let (sub_tx, _rx) = notif_channel::<JsonValue>(1, OverflowPolicy::default());
 
 		let mut manager = RequestManager::new(TEST_LIMIT);
-		assert!(manager.insert_pending_subscription(99, pending_sub_tx1, "beef".to_string()).is_ok());
+		assert!(manager
+			.insert_pending_subscription(99, pending_sub_tx1, "beef".to_string(), OverflowPolicy::default(), None)
+			.is_ok());
 		assert!(manager.insert_pending_call(99, Some(request_tx)).is_err());
-		assert!(manager.insert_pending_subscription(99, pending_sub_tx2, "vegan".to_string()).is_err());
+		assert!(manager
+			.insert_pending_subscription(99, pending_sub_tx2, "vegan".to_string(), OverflowPolicy::default(), None)
+			.is_err());
 
 		assert!(manager.insert_subscription(99, SubscriptionId::Num(0), sub_tx, "bibimbap".to_string()).is_err());
 
@@ -353,15 +786,17 @@ mod tests {
 	#[test]
 	fn active_subscriptions_faulty() {
 		let (request_tx, _) = oneshot::channel::<Result<JsonValue, Error>>();
-		let (pending_sub_tx, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
-		let (sub_tx1, _) = mpsc::channel::<JsonValue>(1);
-		let (sub_tx2, _) = mpsc::channel::<JsonValue>(1);
+		let (pending_sub_tx, _) = oneshot::channel::<Result<(NotifReceiver<JsonValue>, SubscriptionId), Error>>();
+		let (sub_tx1, _rx) = notif_channel::<JsonValue>(1, OverflowPolicy::default());
+		let (sub_tx2, _rx) = notif_channel::<JsonValue>(1, OverflowPolicy::default());
 
 		let mut manager = RequestManager::new(TEST_LIMIT);
 
 		assert!(manager.insert_subscription(3, SubscriptionId::Num(0), sub_tx1, "bibimbap".to_string()).is_ok());
 		assert!(manager.insert_subscription(3, SubscriptionId::Num(1), sub_tx2, "bibimbap".to_string()).is_err());
-		assert!(manager.insert_pending_subscription(3, pending_sub_tx, "beef".to_string()).is_err());
+		assert!(manager
+			.insert_pending_subscription(3, pending_sub_tx, "beef".to_string(), OverflowPolicy::default(), None)
+			.is_err());
 		assert!(manager.insert_pending_call(3, Some(request_tx)).is_err());
 
 		assert!(manager.remove_subscription(3, SubscriptionId::Num(7)).is_none());
@@ -373,7 +808,7 @@ mod tests {
 
 	#[test]
 	fn request_manager_limit_works() {
-		let mut manager = RequestManager::new(TEST_LIMIT);
+		let mut manager: RequestManager = RequestManager::new(TEST_LIMIT);
 		for id in 0..TEST_LIMIT {
 			assert_eq!(id as u64, manager.next_request_id().unwrap());
 		}
@@ -0,0 +1,40 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Callbacks of a [`WsClient`](crate::WsClient) to export metrics, such as Prometheus counters and histograms,
+/// see [`WsClientBuilder::metrics`](crate::WsClientBuilder::metrics).
+///
+/// The callbacks are called from the background task of the client and must not block, they do nothing by
+/// default.
+pub trait ClientMetrics: Send + Sync + 'static {
+	/// A call to `method` is sent, the calls in flight are the calls that started but didn't end yet.
+	fn on_request_start(&self, _method: &str) {}
+
+	/// The call to `method` ended `elapsed` after it started.
+	///
+	/// `success` is `false` if the server answered with an error or the call failed without an answer, such as
+	/// when the request couldn't be sent or the connection was lost.
+	fn on_request_end(&self, _method: &str, _elapsed: Duration, _success: bool) {}
+
+	/// A batch request of `size` calls was sent.
+	fn on_batch(&self, _size: usize) {}
+
+	/// A notification with `method` was received for a subscription.
+	fn on_notification(&self, _method: &str) {}
+
+	/// A notification of a subscription to `method` was dropped because the buffer of the subscription was full,
+	/// either the received one or the oldest buffered one, see [`OverflowPolicy`](crate::OverflowPolicy), or
+	/// because the subscription was dropped.
+	fn on_notification_dropped(&self, _method: &str) {}
+}
+
+/// Metrics shared by the builder and the background task.
+#[derive(Clone)]
+pub(crate) struct SharedMetrics(pub(crate) Arc<dyn ClientMetrics>);
+
+impl fmt::Debug for SharedMetrics {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("ClientMetrics")
+	}
+}
@@ -0,0 +1,82 @@
+use jsonrpsee_types::{
+	error::Error,
+	jsonrpc::{JsonMap, JsonValue, Params},
+};
+use std::fmt;
+use std::sync::Arc;
+
+/// Hooks around the method calls of a [`WsClient`](crate::WsClient), see
+/// [`WsClientBuilder::middleware`](crate::WsClientBuilder::middleware).
+///
+/// Middlewares are called in the order they were registered, for example to inject authentication
+/// parameters, to cache results or to collect metrics. Notifications, batch requests and subscriptions
+/// are not passed through middlewares.
+pub trait ClientMiddleware<V = JsonValue>: Send + Sync + 'static {
+	/// Called before the parameters of a call are serialized, the call can be modified.
+	///
+	/// Returning `Some` answers the call with the result without sending it, the remaining middlewares
+	/// are not called.
+	fn on_request(&self, _call: &mut CallContext) -> Option<V> {
+		None
+	}
+
+	/// Called once the response to a call was received, or it failed, before the result is decoded.
+	fn on_response(&self, _call: &CallContext, _response: &mut Result<V, Error>) {}
+}
+
+/// Method call passed through the [`ClientMiddleware`]s.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallContext {
+	/// Method of the call.
+	pub method: String,
+	/// Parameters of the call.
+	pub params: Params,
+	/// Metadata shared by the middlewares of the call, not sent to the server.
+	pub metadata: JsonMap<String, JsonValue>,
+}
+
+impl CallContext {
+	/// Create the context of a call without metadata.
+	pub fn new(method: String, params: Params) -> Self {
+		Self { method, params, metadata: JsonMap::new() }
+	}
+}
+
+/// Middlewares of a client.
+pub(crate) struct Middlewares<V>(pub(crate) Vec<Arc<dyn ClientMiddleware<V>>>);
+
+impl<V> Clone for Middlewares<V> {
+	fn clone(&self) -> Self {
+		Self(self.0.clone())
+	}
+}
+
+impl<V> Default for Middlewares<V> {
+	fn default() -> Self {
+		Self(Vec::new())
+	}
+}
+
+impl<V> fmt::Debug for Middlewares<V> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Middlewares({})", self.0.len())
+	}
+}
+
+/// Compresses the designated params and decompresses the designated results, see
+/// [`CompressedFields`](jsonrpsee_types::codec::CompressedFields).
+#[cfg(feature = "compression")]
+impl ClientMiddleware<JsonValue> for jsonrpsee_types::codec::CompressedFields {
+	fn on_request(&self, call: &mut CallContext) -> Option<JsonValue> {
+		self.compress_params(&call.method, &mut call.params);
+		None
+	}
+
+	fn on_response(&self, call: &CallContext, response: &mut Result<JsonValue, Error>) {
+		if let Ok(result) = response {
+			if let Err(err) = self.decompress_result(&call.method, result) {
+				*response = Err(err);
+			}
+		}
+	}
+}
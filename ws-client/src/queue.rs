@@ -0,0 +1,104 @@
+use crate::manager::RequestManager;
+use futures::future;
+use jsonrpsee_types::{client::FrontToBack, error::Error};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Requests waiting for a free request slot, in the order they were made, see
+/// [`WsClientBuilder::request_queue`](crate::WsClientBuilder::request_queue).
+pub(crate) struct RequestQueue<V> {
+	capacity: usize,
+	timeout: Option<Duration>,
+	queued: VecDeque<(Instant, FrontToBack<V>)>,
+}
+
+impl<V> RequestQueue<V> {
+	pub(crate) fn new(capacity: usize, timeout: Option<Duration>) -> Self {
+		Self { capacity, timeout, queued: VecDeque::new() }
+	}
+
+	/// Returns `msg` if it can be sent right away, otherwise queues it or fails it with
+	/// [`Error::MaxSlotsExceeded`] if the queue is full.
+	///
+	/// Requests are only sent right away if none is queued, such that they get the slots in order.
+	pub(crate) fn admit(&mut self, msg: FrontToBack<V>, manager: &RequestManager<V>) -> Option<FrontToBack<V>> {
+		if !needs_slot(&msg) || (self.queued.is_empty() && manager.has_free_slot()) {
+			return Some(msg);
+		}
+		if self.queued.len() >= self.capacity {
+			fail(msg, || Error::MaxSlotsExceeded);
+		} else {
+			self.queued.push_back((Instant::now(), msg));
+		}
+		None
+	}
+
+	/// Returns the next queued request if a slot is free, skipping the requests dropped by the frontend.
+	pub(crate) fn pop(&mut self, manager: &RequestManager<V>) -> Option<FrontToBack<V>> {
+		if !manager.has_free_slot() {
+			return None;
+		}
+		while let Some((_, msg)) = self.queued.pop_front() {
+			if !is_dropped(&msg) {
+				return Some(msg);
+			}
+		}
+		None
+	}
+
+	/// Fails the requests that waited for a slot longer than the timeout with [`Error::MaxSlotsExceeded`].
+	pub(crate) fn remove_expired(&mut self) {
+		let timeout = match self.timeout {
+			Some(timeout) => timeout,
+			None => return,
+		};
+		while let Some((queued_at, _)) = self.queued.front() {
+			if queued_at.elapsed() < timeout {
+				break;
+			}
+			let (_, msg) = self.queued.pop_front().expect("Checked above; qed");
+			fail(msg, || Error::MaxSlotsExceeded);
+		}
+	}
+
+	/// Resolves when the oldest queued request has to be failed by [`RequestQueue::remove_expired`], never
+	/// resolves if `queue` is `None`, empty or without timeout.
+	pub(crate) async fn timer(queue: &Option<RequestQueue<V>>) {
+		let expires = queue.as_ref().and_then(|queue| Some(queue.queued.front()?.0 + queue.timeout?));
+		match expires {
+			Some(expires) => async_std::task::sleep(expires.saturating_duration_since(Instant::now())).await,
+			None => future::pending().await,
+		}
+	}
+}
+
+/// Returns `true` if `msg` takes a request slot until it's answered.
+pub(crate) fn needs_slot<V>(msg: &FrontToBack<V>) -> bool {
+	matches!(msg, FrontToBack::StartRequest(_) | FrontToBack::Subscribe(_) | FrontToBack::Batch(_))
+}
+
+/// Returns `true` if the frontend doesn't wait for the answer to `msg` anymore.
+pub(crate) fn is_dropped<V>(msg: &FrontToBack<V>) -> bool {
+	match msg {
+		FrontToBack::StartRequest(request) => matches!(request.send_back, Some(ref tx) if tx.is_canceled()),
+		FrontToBack::Subscribe(subscribe) => subscribe.send_back.is_canceled(),
+		FrontToBack::Batch(batch) => batch.send_back.is_canceled(),
+		_ => false,
+	}
+}
+
+/// Answers `msg` with the error returned by `error`, if the frontend waits for an answer.
+pub(crate) fn fail<V>(msg: FrontToBack<V>, error: impl Fn() -> Error) {
+	match msg {
+		FrontToBack::StartRequest(request) => {
+			request.send_back.map(|tx| tx.send(Err(error())));
+		}
+		FrontToBack::Subscribe(subscribe) => {
+			let _ = subscribe.send_back.send(Err(error()));
+		}
+		FrontToBack::Batch(batch) => {
+			let _ = batch.send_back.send(Err(error()));
+		}
+		_ => {}
+	}
+}
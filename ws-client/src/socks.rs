@@ -0,0 +1,120 @@
+use crate::transport::WsNewError;
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use std::convert::TryFrom;
+use std::net::{IpAddr, SocketAddr};
+
+const VERSION: u8 = 5;
+const NO_AUTH: u8 = 0;
+const USERNAME_PASSWORD: u8 = 2;
+const NO_ACCEPTABLE_METHOD: u8 = 0xff;
+const CONNECT: u8 = 1;
+const IPV4: u8 = 1;
+const DOMAIN_NAME: u8 = 3;
+const IPV6: u8 = 4;
+
+/// SOCKS5 proxy that connections are tunneled through, see
+/// [`WsClientBuilder::socks5_proxy`](crate::WsClientBuilder::socks5_proxy).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Socks5Proxy {
+	/// Address of the proxy.
+	pub addr: SocketAddr,
+	/// Username and password to authenticate with, if the proxy requires authentication.
+	pub auth: Option<(String, String)>,
+}
+
+/// Ask the proxy behind `stream` to connect to `host` and `port`, the stream is tunneled to the server
+/// once this returns.
+///
+/// Domain names are resolved by the proxy.
+pub(crate) async fn connect<S>(stream: &mut S, proxy: &Socks5Proxy, host: &str, port: u16) -> Result<(), WsNewError>
+where
+	S: AsyncRead + AsyncWrite + Unpin,
+{
+	// Method negotiation (RFC 1928 section 3).
+	let method = if proxy.auth.is_some() { USERNAME_PASSWORD } else { NO_AUTH };
+	stream.write_all(&[VERSION, 1, method]).await?;
+	let mut reply = [0; 2];
+	stream.read_exact(&mut reply).await?;
+	if reply[0] != VERSION {
+		return Err(WsNewError::Socks5(format!("unsupported version {}", reply[0])));
+	}
+	match (reply[1], proxy.auth.as_ref()) {
+		(NO_AUTH, _) => {}
+		(USERNAME_PASSWORD, Some((username, password))) => authenticate(stream, username, password).await?,
+		(NO_ACCEPTABLE_METHOD, _) => return Err(WsNewError::Socks5("no acceptable authentication method".into())),
+		(method, _) => return Err(WsNewError::Socks5(format!("unexpected authentication method {}", method))),
+	}
+
+	// Connect request (RFC 1928 section 4).
+	let mut request = vec![VERSION, CONNECT, 0];
+	match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+		Ok(IpAddr::V4(ip)) => {
+			request.push(IPV4);
+			request.extend_from_slice(&ip.octets());
+		}
+		Ok(IpAddr::V6(ip)) => {
+			request.push(IPV6);
+			request.extend_from_slice(&ip.octets());
+		}
+		Err(_) => {
+			let len = u8::try_from(host.len()).map_err(|_| WsNewError::Socks5("domain name too long".into()))?;
+			request.extend_from_slice(&[DOMAIN_NAME, len]);
+			request.extend_from_slice(host.as_bytes());
+		}
+	}
+	request.extend_from_slice(&port.to_be_bytes());
+	stream.write_all(&request).await?;
+
+	// Reply (RFC 1928 section 6), the bound address is skipped.
+	let mut reply = [0; 4];
+	stream.read_exact(&mut reply).await?;
+	if reply[1] != 0 {
+		return Err(WsNewError::Socks5(reply_error(reply[1]).into()));
+	}
+	let addr_len = match reply[3] {
+		IPV4 => 4,
+		IPV6 => 16,
+		DOMAIN_NAME => {
+			let mut len = [0; 1];
+			stream.read_exact(&mut len).await?;
+			len[0] as usize
+		}
+		ty => return Err(WsNewError::Socks5(format!("unexpected address type {}", ty))),
+	};
+	stream.read_exact(&mut vec![0; addr_len + 2]).await?;
+	Ok(())
+}
+
+/// Username and password authentication (RFC 1929).
+async fn authenticate<S>(stream: &mut S, username: &str, password: &str) -> Result<(), WsNewError>
+where
+	S: AsyncRead + AsyncWrite + Unpin,
+{
+	let too_long = |_| WsNewError::Socks5("username or password too long".into());
+	let mut request = vec![1, u8::try_from(username.len()).map_err(too_long)?];
+	request.extend_from_slice(username.as_bytes());
+	request.push(u8::try_from(password.len()).map_err(too_long)?);
+	request.extend_from_slice(password.as_bytes());
+	stream.write_all(&request).await?;
+
+	let mut reply = [0; 2];
+	stream.read_exact(&mut reply).await?;
+	if reply[1] != 0 {
+		return Err(WsNewError::Socks5("authentication failed".into()));
+	}
+	Ok(())
+}
+
+fn reply_error(code: u8) -> &'static str {
+	match code {
+		1 => "general failure",
+		2 => "connection not allowed by ruleset",
+		3 => "network unreachable",
+		4 => "host unreachable",
+		5 => "connection refused",
+		6 => "TTL expired",
+		7 => "command not supported",
+		8 => "address type not supported",
+		_ => "unknown error",
+	}
+}
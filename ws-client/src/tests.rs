@@ -1,13 +1,18 @@
 #![cfg(test)]
 
-use crate::{WsClientBuilder, WsSubscription};
+use crate::{
+	BackgroundError, ConnectionEvent, Direction, PrefixedIds, SubscriptionDescriptor, WsClientBuilder, WsSubscription,
+};
 use jsonrpsee_test_utils::helpers::*;
 use jsonrpsee_test_utils::types::{Id, WebSocketTestServer};
 use jsonrpsee_types::{
-	error::Error,
-	jsonrpc::{self, Params},
+	error::{Error, ShutdownReason},
+	jsonrpc::{self, Params, SubscriptionId},
 	traits::{Client, SubscriptionClient},
 };
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 fn assert_error_response(response: Result<jsonrpc::JsonValue, Error>, code: jsonrpc::ErrorCode, message: String) {
 	let expected = jsonrpc::Error { code, message, data: None };
@@ -33,6 +38,137 @@ async fn method_call_works() {
 	assert_eq!(response, exp);
 }
 
+#[tokio::test]
+async fn raw_messages_are_tapped() {
+	let response = ok_response("hello".into(), Id::Num(0_u64));
+	let server = WebSocketTestServer::with_hardcoded_response("127.0.0.1:0".parse().unwrap(), response.clone()).await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let tapped = Arc::new(Mutex::new(Vec::new()));
+	let tap = tapped.clone();
+	let client = WsClientBuilder::default()
+		.on_raw_message(move |direction, message| tap.lock().unwrap().push((direction, message.to_owned())))
+		.build(&uri)
+		.await
+		.unwrap();
+	let _: String = client.request("say_hello", Params::Array(vec![1.into()])).await.unwrap();
+
+	let tapped = tapped.lock().unwrap();
+	assert_eq!(
+		*tapped,
+		vec![
+			(Direction::Outgoing, r#"{"jsonrpc":"2.0","method":"say_hello","params":[1],"id":0}"#.to_owned()),
+			(Direction::Incoming, response),
+		]
+	);
+}
+
+#[tokio::test]
+async fn lazy_client_connects_on_first_call() {
+	let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+	let uri = to_ws_uri_string(addr);
+	let client = WsClientBuilder::default().build_lazy(&uri, 1).unwrap();
+	let err = client.request::<String, _, _>("say_hello", Params::None).await.unwrap_err();
+	assert!(matches!(err, Error::ConnectionLost(ShutdownReason::Transport(_))));
+	assert!(client.is_connected());
+
+	let _server = WebSocketTestServer::with_hardcoded_response(addr, ok_response("hello".into(), Id::Num(0_u64))).await;
+	let response: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(response, "hello");
+}
+
+#[tokio::test]
+async fn fallback_endpoints_are_tried_in_order() {
+	let down = to_ws_uri_string(std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap());
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0_u64)),
+	)
+	.await;
+	let up = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().build_with_fallbacks(&[&down, &up]).await.unwrap();
+	let response: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(response, "hello");
+
+	let err = WsClientBuilder::default().build_with_fallbacks(&[&down]).await.unwrap_err();
+	assert!(matches!(err, Error::TransportError(_)));
+}
+
+#[tokio::test]
+async fn call_builder_works() {
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0_u64)),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().build(&uri).await.unwrap();
+	let hello: String = client.call("say_hello").params((1, 2)).timeout(Duration::from_secs(5)).send().await.unwrap();
+	assert_eq!(hello, "hello");
+}
+
+#[tokio::test]
+async fn method_call_over_established_stream_works() {
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0_u64)),
+	)
+	.await;
+	let stream = async_std::net::TcpStream::connect(server.local_addr()).await.unwrap();
+	let client = WsClientBuilder::default().build_with_stream("127.0.0.1", stream).await.unwrap();
+	let response: jsonrpc::JsonValue = client.request("say_hello", jsonrpc::Params::None).await.unwrap();
+	assert_eq!(response, jsonrpc::JsonValue::String("hello".to_string()));
+}
+
+#[tokio::test]
+async fn method_call_with_string_ids_works() {
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Str("client-a/0".into())),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().id_provider(PrefixedIds::new("client-a/")).build(&uri).await.unwrap();
+	let response: jsonrpc::JsonValue = client.request("say_hello", jsonrpc::Params::None).await.unwrap();
+	assert_eq!(response, jsonrpc::JsonValue::String("hello".to_string()));
+}
+
+#[tokio::test]
+async fn batch_request_with_custom_ids_works() {
+	let server_response =
+		r#"[{"jsonrpc":"2.0","result":"goodbye","id":"b"}, {"jsonrpc":"2.0","result":"hello","id":"a"}]"#;
+	let server =
+		WebSocketTestServer::with_hardcoded_response("127.0.0.1:0".parse().unwrap(), server_response.to_string()).await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let ids = Mutex::new(vec!["b", "a"]);
+	let client = WsClientBuilder::default()
+		.id_provider(move || jsonrpc::Id::Str(ids.lock().unwrap().pop().unwrap().into()))
+		.build(&uri)
+		.await
+		.unwrap();
+	let batch = vec![("say_hello".to_string(), Params::None), ("say_goodbye".to_string(), Params::None)];
+	let response: Vec<String> = client.batch_request(batch).await.unwrap();
+	assert_eq!(response, vec!["hello".to_string(), "goodbye".to_string()]);
+}
+
+#[tokio::test]
+async fn response_rejected_by_invariant() {
+	use jsonrpsee_types::validation::{InvalidField, ResponseValidator};
+
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0_u64)),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let validator = ResponseValidator::new().invariant(|_, value| match value.as_str() {
+		Some(s) if s.len() <= 3 => Ok(()),
+		_ => Err(InvalidField::new("$", "at most 3 characters", value.to_string())),
+	});
+	let client = WsClientBuilder::default().response_validator(validator).build(&uri).await.unwrap();
+	let err = client.request::<jsonrpc::JsonValue, _, _>("say_hello", jsonrpc::Params::None).await.unwrap_err();
+	assert!(matches!(err, Error::InvalidResponseField(field) if field.mismatch.got == r#""hello""#));
+}
+
 #[tokio::test]
 async fn notif_works() {
 	// this empty string shouldn't be read because the server shouldn't respond to notifications.
@@ -114,6 +250,310 @@ async fn subscription_works() {
 	}
 }
 
+#[tokio::test]
+async fn subscription_try_next_surfaces_decode_errors() {
+	let server = WebSocketTestServer::with_hardcoded_subscription(
+		"127.0.0.1:0".parse().unwrap(),
+		server_subscription_id_response(Id::Num(0)),
+		server_subscription_response(jsonrpc::JsonValue::String("hello my friend".to_owned())),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().build(&uri).await.unwrap();
+	let mut sub: WsSubscription<u64> =
+		client.subscribe("subscribe_hello", jsonrpc::Params::None, "unsubscribe_hello").await.unwrap();
+	assert!(matches!(sub.try_next().await, Some(Err(Error::ParseError(_)))));
+}
+
+#[tokio::test]
+async fn subscription_stream_detects_stalls() {
+	let server = WebSocketTestServer::with_hardcoded_subscription(
+		"127.0.0.1:0".parse().unwrap(),
+		server_subscription_id_response(Id::Num(0)),
+		server_subscription_response(jsonrpc::JsonValue::String("hello my friend".to_owned())),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().build(&uri).await.unwrap();
+	let mut sub: WsSubscription<String> =
+		client.subscribe("subscribe_hello", jsonrpc::Params::None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(futures::StreamExt::next(&mut sub).await, Some("hello my friend".to_owned()));
+	// NOTE: the server sends a notification every second.
+	let stalled = tokio::time::timeout(Duration::from_millis(100), futures::StreamExt::next(&mut sub)).await;
+	assert!(stalled.is_err());
+}
+
+#[tokio::test]
+async fn memory_stats_report_subscriptions() {
+	let server = WebSocketTestServer::with_hardcoded_subscription(
+		"127.0.0.1:0".parse().unwrap(),
+		server_subscription_id_response(Id::Num(0)),
+		server_subscription_response(jsonrpc::JsonValue::String("hello my friend".to_owned())),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().max_concurrent_requests(8).build(&uri).await.unwrap();
+	let _handler = client.on_notification("hello").await.unwrap();
+	let mut sub: WsSubscription<String> =
+		client.subscribe("subscribe_hello", jsonrpc::Params::None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(sub.next().await, Some("hello my friend".to_owned()));
+
+	let stats = client.memory_stats().await.unwrap();
+	assert_eq!(stats.pending_requests, 0);
+	assert_eq!(stats.free_request_slots, 7);
+	assert_eq!(stats.active_subscriptions, 1);
+	assert_eq!(stats.notification_handlers, 1);
+}
+
+#[tokio::test]
+async fn active_subscriptions_are_described() {
+	let server = WebSocketTestServer::with_hardcoded_subscription(
+		"127.0.0.1:0".parse().unwrap(),
+		server_subscription_id_response(Id::Num(0)),
+		server_subscription_response(jsonrpc::JsonValue::String("hello my friend".to_owned())),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().build(&uri).await.unwrap();
+	assert!(client.active_subscriptions().await.unwrap().is_empty());
+
+	let params = Params::Array(vec!["en".into()]);
+	let mut sub: WsSubscription<String> =
+		client.subscribe("subscribe_hello", params.clone(), "unsubscribe_hello").await.unwrap();
+	assert_eq!(sub.next().await, Some("hello my friend".to_owned()));
+
+	let active = client.active_subscriptions().await.unwrap();
+	assert_eq!(active.len(), 1);
+	assert_eq!(active[0].subscribe_method, "subscribe_hello");
+	assert_eq!(active[0].params, params);
+	assert_eq!(active[0].subscription_id, SubscriptionId::Str("D3wwzU6vvoUUYehv4qoFzq42DZnLoAETeFzeyk8swH4o".into()));
+
+	drop(sub);
+	assert!(client.active_subscriptions().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn write_queue_works() {
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0_u64)),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().write_queue(Some(1)).build(&uri).await.unwrap();
+	let large = Params::Array(vec!["a".repeat(1024 * 1024).into()]);
+	let response: String = client.request("say_hello", large).await.unwrap();
+	assert_eq!(response, "hello");
+}
+
+#[tokio::test]
+async fn write_queue_processes_notifications_during_large_writes() {
+	use std::sync::atomic::Ordering;
+
+	let server = WebSocketTestServer::with_hardcoded_subscription(
+		"127.0.0.1:0".parse().unwrap(),
+		server_subscription_id_response(Id::Num(0)),
+		server_subscription_response(jsonrpc::JsonValue::String("hello my friend".to_owned())),
+	)
+	.await;
+	let (proxy_addr, stalled) = stalling_proxy(server.local_addr());
+	let uri = to_ws_uri_string(proxy_addr);
+	let client = WsClientBuilder::default()
+		.write_queue(Some(1))
+		.max_request_body_size(64 * 1024 * 1024)
+		.build(&uri)
+		.await
+		.unwrap();
+	let client = Arc::new(client);
+	let mut sub: WsSubscription<String> =
+		client.subscribe("subscribe_hello", Params::None, "unsubscribe_hello").await.unwrap();
+
+	// The socket buffers are much smaller than the request, which can't be written until the proxy resumes.
+	stalled.store(true, Ordering::SeqCst);
+	let large = Params::Array(vec!["a".repeat(32 * 1024 * 1024).into()]);
+	let writer = client.clone();
+	let request = tokio::spawn(async move { writer.request::<String, _, _>("say_hello", large).await });
+
+	assert_eq!(sub.next().await, Some("hello my friend".to_owned()));
+	assert!(!request.is_finished());
+	stalled.store(false, Ordering::SeqCst);
+}
+
+#[tokio::test]
+async fn strict_subscription_ids_reject_malformed_ids() {
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("not\tan id".into(), Id::Num(0_u64)),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().build(&uri).await.unwrap();
+	let sub: Result<WsSubscription<String>, _> =
+		client.subscribe("subscribe_hello", jsonrpc::Params::None, "unsubscribe_hello").await;
+	assert!(sub.is_ok());
+
+	let client = WsClientBuilder::default().strict_subscription_ids(true).build(&uri).await.unwrap();
+	let sub: Result<WsSubscription<String>, _> =
+		client.subscribe("subscribe_hello", jsonrpc::Params::None, "unsubscribe_hello").await;
+	assert!(matches!(sub, Err(Error::InvalidSubscriptionId)));
+}
+
+#[tokio::test]
+async fn subscriptions_can_be_restored_on_another_client() {
+	let server = WebSocketTestServer::with_hardcoded_subscription(
+		"127.0.0.1:0".parse().unwrap(),
+		server_subscription_id_response(Id::Num(0)),
+		server_subscription_response(jsonrpc::JsonValue::String("hello my friend".to_owned())),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().build(&uri).await.unwrap();
+	let params = Params::Array(vec!["news".into()]);
+	let _sub: WsSubscription<String> =
+		client.subscribe("subscribe_hello", params.clone(), "unsubscribe_hello").await.unwrap();
+	let exported = client.subscriptions();
+	assert_eq!(
+		exported,
+		vec![SubscriptionDescriptor {
+			subscribe_method: "subscribe_hello".to_owned(),
+			params,
+			unsubscribe_method: "unsubscribe_hello".to_owned(),
+		}]
+	);
+
+	let other = WsClientBuilder::default().build(&uri).await.unwrap();
+	let mut restored = other.restore_subscriptions::<String>(exported.clone()).await;
+	let mut sub = restored.pop().unwrap().unwrap();
+	assert_eq!(sub.next().await, Some("hello my friend".to_owned()));
+	assert_eq!(other.subscriptions(), exported);
+}
+
+#[tokio::test]
+async fn reconnect_reissues_subscriptions() {
+	use futures::StreamExt;
+
+	let server = WebSocketTestServer::with_hardcoded_subscription(
+		"127.0.0.1:0".parse().unwrap(),
+		server_subscription_id_response(Id::Num(0)),
+		server_subscription_response(jsonrpc::JsonValue::String("hello my friend".to_owned())),
+	)
+	.await;
+	let (proxy_addr, proxied) = proxy(server.local_addr());
+	let uri = to_ws_uri_string(proxy_addr);
+	let client = WsClientBuilder::default().reconnect(3, Duration::from_millis(50)).build(&uri).await.unwrap();
+	let mut sub: WsSubscription<String> =
+		client.subscribe("subscribe_hello", Params::None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(sub.next().await, Some("hello my friend".to_owned()));
+	let mut events = client.connection_events();
+
+	// Drop the connection.
+	for connection in proxied.lock().unwrap().drain(..) {
+		connection.shutdown(std::net::Shutdown::Both).unwrap();
+	}
+
+	assert_eq!(sub.next().await, Some("hello my friend".to_owned()));
+	assert!(client.is_connected());
+	assert_eq!(client.subscriptions().len(), 1);
+	assert!(matches!(events.next().await, Some(ConnectionEvent::Disconnected { .. })));
+	assert_eq!(events.next().await, Some(ConnectionEvent::Connected));
+}
+
+/// Forwards the connections accepted on the returned address to `target`, shutting down the returned
+/// streams drops the connections.
+fn proxy(target: std::net::SocketAddr) -> (std::net::SocketAddr, Arc<Mutex<Vec<std::net::TcpStream>>>) {
+	let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+	let addr = listener.local_addr().unwrap();
+	let connections = Arc::new(Mutex::new(Vec::new()));
+	let proxied = connections.clone();
+	std::thread::spawn(move || {
+		for inbound in listener.incoming() {
+			let inbound = inbound.unwrap();
+			let outbound = std::net::TcpStream::connect(target).unwrap();
+			for (mut from, mut to) in [
+				(inbound.try_clone().unwrap(), outbound.try_clone().unwrap()),
+				(outbound.try_clone().unwrap(), inbound.try_clone().unwrap()),
+			] {
+				std::thread::spawn(move || std::io::copy(&mut from, &mut to));
+			}
+			proxied.lock().unwrap().extend(vec![inbound, outbound]);
+		}
+	});
+	(addr, connections)
+}
+
+/// Proxies a single connection to `target`, the writes of the client aren't forwarded while the returned flag
+/// is set.
+fn stalling_proxy(target: std::net::SocketAddr) -> (std::net::SocketAddr, Arc<std::sync::atomic::AtomicBool>) {
+	use std::io::{Read, Write};
+	use std::sync::atomic::{AtomicBool, Ordering};
+
+	let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+	let addr = listener.local_addr().unwrap();
+	let stalled = Arc::new(AtomicBool::new(false));
+	let stall = stalled.clone();
+	std::thread::spawn(move || {
+		let inbound = listener.accept().unwrap().0;
+		let outbound = std::net::TcpStream::connect(target).unwrap();
+		let (mut from, mut to) = (outbound.try_clone().unwrap(), inbound.try_clone().unwrap());
+		std::thread::spawn(move || std::io::copy(&mut from, &mut to));
+		let (mut from, mut to) = (inbound, outbound);
+		let mut buf = [0; 4096];
+		loop {
+			while stall.load(Ordering::SeqCst) {
+				std::thread::sleep(Duration::from_millis(1));
+			}
+			match from.read(&mut buf) {
+				Ok(0) | Err(_) => break,
+				Ok(n) if to.write_all(&buf[..n]).is_err() => break,
+				Ok(_) => (),
+			}
+		}
+	});
+	(addr, stalled)
+}
+
+#[tokio::test]
+async fn calls_wait_for_room_in_the_full_frontend_channel() {
+	use std::sync::atomic::Ordering;
+
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0_u64)),
+	)
+	.await;
+	let (proxy_addr, stalled) = stalling_proxy(server.local_addr());
+	let uri = to_ws_uri_string(proxy_addr);
+	let client = WsClientBuilder::default()
+		.max_concurrent_requests(1)
+		.max_request_body_size(64 * 1024 * 1024)
+		.build(&uri)
+		.await
+		.unwrap();
+	let client = Arc::new(client);
+
+	// The background task blocks on writing the large notification, the next calls fill its channel.
+	stalled.store(true, Ordering::SeqCst);
+	let large = Params::Array(vec!["a".repeat(32 * 1024 * 1024).into()]);
+	client.notification("say_hello", large).await.unwrap();
+	let mut queued = 0;
+	let pending = loop {
+		let sender = client.clone();
+		let call = tokio::spawn(async move { sender.notification("say_hello", Params::None).await });
+		tokio::time::sleep(Duration::from_millis(50)).await;
+		if !call.is_finished() {
+			break call;
+		}
+		call.await.unwrap().unwrap();
+		queued += 1;
+		assert!(queued < 8, "The frontend channel never filled up");
+	};
+
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	assert!(!pending.is_finished());
+	stalled.store(false, Ordering::SeqCst);
+	pending.await.unwrap().unwrap();
+}
+
 #[tokio::test]
 async fn response_with_wrong_id() {
 	let server = WebSocketTestServer::with_hardcoded_response(
@@ -124,7 +564,9 @@ async fn response_with_wrong_id() {
 	let uri = to_ws_uri_string(server.local_addr());
 	let client = WsClientBuilder::default().build(&uri).await.unwrap();
 	let err: Result<String, Error> = client.request("say_hello", jsonrpc::Params::None).await;
-	assert!(matches!(err, Err(Error::RestartNeeded(e)) if e.to_string().contains("Invalid request ID")));
+	assert!(
+		matches!(err, Err(Error::RestartNeeded(ShutdownReason::ProtocolViolation(e))) if e.contains("Invalid request ID"))
+	);
 }
 
 #[tokio::test]
@@ -152,6 +594,197 @@ async fn batch_request_out_of_order_response() {
 	assert_eq!(response, vec!["hello".to_string(), "goodbye".to_string(), "here's your swag".to_string()]);
 }
 
+#[tokio::test]
+async fn batch_request_results_keep_errors_per_call() {
+	let batch_request = vec![("say_hello".to_string(), Params::None), ("say_goodbye".to_string(), Params::None)];
+	let server_response = r#"[{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}, {"jsonrpc":"2.0","result":"hello","id":0}]"#.to_string();
+	let server = WebSocketTestServer::with_hardcoded_response("127.0.0.1:0".parse().unwrap(), server_response).await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().build(&uri).await.unwrap();
+
+	let results: Vec<Result<String, jsonrpc::Error>> =
+		client.batch_request_results(batch_request.clone()).await.unwrap();
+	assert_eq!(results, vec![Ok("hello".to_string()), Err(jsonrpc::Error::method_not_found())]);
+
+	// The error of a call fails the whole batch without closing the connection.
+	let client = WsClientBuilder::default().build(&uri).await.unwrap();
+	let err = client.batch_request::<String, _, _>(batch_request).await.unwrap_err();
+	assert!(matches!(err, Error::Request(ref e) if e == &jsonrpc::Error::method_not_found()));
+	assert!(client.is_connected());
+}
+
+#[tokio::test]
+async fn typed_batch_request_works() {
+	use jsonrpsee_types::batch::BatchRequestBuilder;
+
+	#[derive(Debug, PartialEq, serde::Deserialize)]
+	struct Header {
+		number: u64,
+	}
+
+	let server_response =
+		r#"[{"jsonrpc":"2.0","result":{"number":7},"id":1},{"jsonrpc":"2.0","result":7,"id":0}]"#.to_string();
+	let server = WebSocketTestServer::with_hardcoded_response("127.0.0.1:0".parse().unwrap(), server_response).await;
+	let client = WsClientBuilder::default().build(&to_ws_uri_string(server.local_addr())).await.unwrap();
+
+	let mut batch = BatchRequestBuilder::new();
+	let number = batch.insert::<u64>("chain_getBlockNumber", Params::None);
+	let header = batch.insert::<Header>("chain_getHeader", Params::None);
+	let responses = batch.send(&client).await.unwrap();
+	assert_eq!(responses.get(number).unwrap(), 7);
+	assert_eq!(responses.get(header).unwrap(), Header { number: 7 });
+	assert_eq!(responses.into_values().len(), 2);
+}
+
+#[tokio::test]
+async fn batching_window_coalesces_requests() {
+	let server_response = r#"[{"jsonrpc":"2.0","result":"hello","id":0}, {"jsonrpc":"2.0","result":"goodbye","id":1}]"#;
+	let server =
+		WebSocketTestServer::with_hardcoded_response("127.0.0.1:0".parse().unwrap(), server_response.to_string()).await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().batching_window(Duration::from_secs(1), 2).build(&uri).await.unwrap();
+	let (hello, goodbye) = futures::join!(
+		client.request::<String, _, _>("say_hello", Params::None),
+		client.request::<String, _, _>("say_goodbye", Params::None)
+	);
+	assert_eq!(hello.unwrap(), "hello");
+	assert_eq!(goodbye.unwrap(), "goodbye");
+}
+
+#[tokio::test]
+async fn notification_batching_window_coalesces_notifications_only() {
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0_u64)),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let tapped = Arc::new(Mutex::new(Vec::new()));
+	let tap = tapped.clone();
+	let client = WsClientBuilder::default()
+		.notification_batching_window(Duration::from_secs(10), 3)
+		.on_raw_message(move |direction, message| {
+			if direction == Direction::Outgoing {
+				tap.lock().unwrap().push(message.to_owned())
+			}
+		})
+		.build(&uri)
+		.await
+		.unwrap();
+
+	// Requests are not delayed by the window.
+	let hello = client.request::<String, _, _>("say_hello", Params::None);
+	assert_eq!(tokio::time::timeout(Duration::from_secs(5), hello).await.unwrap().unwrap(), "hello");
+
+	let (a, b, c) = futures::join!(
+		client.notification("telemetry", Params::Array(vec![1.into()])),
+		client.notification("telemetry", Params::Array(vec![2.into()])),
+		client.notification("telemetry", Params::Array(vec![3.into()]))
+	);
+	a.and(b).and(c).unwrap();
+	let batch = tokio::time::timeout(Duration::from_secs(5), async {
+		loop {
+			if let Some(batch) = tapped.lock().unwrap().get(1).cloned() {
+				return batch;
+			}
+			tokio::time::sleep(Duration::from_millis(10)).await;
+		}
+	})
+	.await
+	.unwrap();
+	assert_eq!(
+		batch,
+		r#"[{"jsonrpc":"2.0","method":"telemetry","params":[1]},{"jsonrpc":"2.0","method":"telemetry","params":[2]},{"jsonrpc":"2.0","method":"telemetry","params":[3]}]"#
+	);
+}
+
+#[tokio::test]
+async fn metrics_report_calls_and_batches() {
+	use crate::ClientMetrics;
+
+	#[derive(Default)]
+	struct Recorder(Mutex<Vec<String>>);
+
+	impl ClientMetrics for Arc<Recorder> {
+		fn on_request_start(&self, method: &str) {
+			self.0.lock().unwrap().push(format!("start {}", method));
+		}
+
+		fn on_request_end(&self, method: &str, _elapsed: Duration, success: bool) {
+			self.0.lock().unwrap().push(format!("end {} {}", method, success));
+		}
+
+		fn on_batch(&self, size: usize) {
+			self.0.lock().unwrap().push(format!("batch {}", size));
+		}
+	}
+
+	let server_response = r#"[{"jsonrpc":"2.0","result":"hello","id":0}, {"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}]"#;
+	let server =
+		WebSocketTestServer::with_hardcoded_response("127.0.0.1:0".parse().unwrap(), server_response.to_string()).await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let recorder = Arc::new(Recorder::default());
+	let client = WsClientBuilder::default()
+		.batching_window(Duration::from_secs(1), 2)
+		.metrics(recorder.clone())
+		.build(&uri)
+		.await
+		.unwrap();
+	let (hello, goodbye) = futures::join!(
+		client.request::<String, _, _>("say_hello", Params::None),
+		client.request::<String, _, _>("say_goodbye", Params::None)
+	);
+	assert_eq!(hello.unwrap(), "hello");
+	assert!(goodbye.is_err());
+	assert_eq!(
+		*recorder.0.lock().unwrap(),
+		vec!["start say_hello", "start say_goodbye", "batch 2", "end say_hello true", "end say_goodbye false"]
+	);
+}
+
+#[tokio::test]
+async fn on_disconnect_returns_shutdown_reason() {
+	use futures::StreamExt;
+
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response(jsonrpc::JsonValue::String("foo".into()), Id::Num(99_u64)),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().build(&uri).await.unwrap();
+	let mut events = client.connection_events();
+	let (reason, _) =
+		futures::join!(client.on_disconnect(), client.request::<String, _, _>("say_hello", jsonrpc::Params::None));
+	assert!(matches!(reason, ShutdownReason::ProtocolViolation(_)));
+	assert_eq!(events.next().await, Some(ConnectionEvent::Disconnected { reason: reason.clone() }));
+	assert_eq!(events.next().await, None);
+	assert_eq!(client.on_disconnect().await, reason);
+}
+
+#[tokio::test]
+async fn background_error_hook_receives_context() {
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response(jsonrpc::JsonValue::String("foo".into()), Id::Num(99_u64)),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let (hook_tx, hook_rx) = std::sync::mpsc::channel();
+	let client = WsClientBuilder::default()
+		.on_background_error(move |err: &BackgroundError| hook_tx.send(err.clone()).unwrap())
+		.build(&uri)
+		.await
+		.unwrap();
+	client.request::<String, _, _>("say_hello", jsonrpc::Params::None).await.unwrap_err();
+	let reason = client.on_disconnect().await;
+	let err = hook_rx.recv().unwrap();
+	assert_eq!(err.reason, reason);
+	assert!(!err.panicked);
+	assert!(err.last_frame.unwrap().contains("99"));
+	assert_eq!(err.pending_requests, 1);
+}
+
 #[tokio::test]
 async fn is_connected_works() {
 	let server = WebSocketTestServer::with_hardcoded_response(
@@ -166,9 +799,711 @@ async fn is_connected_works() {
 	assert!(!client.is_connected())
 }
 
+#[tokio::test]
+async fn response_larger_than_max_response_size_drops_connection() {
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("a".repeat(1024).into(), Id::Num(0_u64)),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().max_response_size(512).build(&uri).await.unwrap();
+	let err = client.request::<String, _, _>("say_hello", Params::None).await.unwrap_err();
+	assert!(matches!(err, Error::RestartNeeded(ShutdownReason::MessageTooLarge)));
+}
+
+#[tokio::test]
+async fn request_larger_than_max_request_body_size_fails() {
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0_u64)),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().max_request_body_size(512).build(&uri).await.unwrap();
+	let err =
+		client.request::<String, _, _>("say_hello", Params::Array(vec!["a".repeat(1024).into()])).await.unwrap_err();
+	assert!(matches!(err, Error::TransportError(_)));
+	assert!(client.is_connected());
+}
+
 async fn run_batch_request_with_response(batch: Vec<(String, Params)>, response: String) -> Result<Vec<String>, Error> {
 	let server = WebSocketTestServer::with_hardcoded_response("127.0.0.1:0".parse().unwrap(), response).await;
 	let uri = to_ws_uri_string(server.local_addr());
 	let client = WsClientBuilder::default().build(&uri).await.unwrap();
 	client.batch_request(batch).await
 }
+
+#[tokio::test]
+async fn tls_config_trusts_private_ca() {
+	use rustls::internal::pemfile;
+
+	// `test-certs` holds a self-signed CA and a certificate for `localhost` issued by it.
+	let ca = pemfile::certs(&mut &include_bytes!("../test-certs/ca.pem")[..]).unwrap();
+	let certs = pemfile::certs(&mut &include_bytes!("../test-certs/localhost.pem")[..]).unwrap();
+	let mut keys = pemfile::pkcs8_private_keys(&mut &include_bytes!("../test-certs/localhost.key")[..]).unwrap();
+
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0_u64)),
+	)
+	.await;
+	let mut server_config = rustls::ServerConfig::new(rustls::NoClientAuth::new());
+	server_config.set_single_cert(certs, keys.remove(0)).unwrap();
+	let addr = tls_terminator(server_config, server.local_addr()).await;
+	let uri = format!("wss://localhost:{}", addr.port());
+
+	assert!(WsClientBuilder::default().build(&uri).await.is_err());
+
+	let mut config = rustls::ClientConfig::new();
+	config.root_store.add(&ca[0]).unwrap();
+	let client = WsClientBuilder::default().tls_config(config).build(&uri).await.unwrap();
+	let response: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(response, "hello");
+}
+
+/// Accepts TLS connections and forwards the decrypted streams to `target`.
+async fn tls_terminator(config: rustls::ServerConfig, target: std::net::SocketAddr) -> std::net::SocketAddr {
+	use futures::{AsyncReadExt as _, FutureExt as _};
+
+	let listener = async_std::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	let acceptor = async_tls::TlsAcceptor::from(Arc::new(config));
+	async_std::task::spawn(async move {
+		loop {
+			let (socket, _) = listener.accept().await.unwrap();
+			let acceptor = acceptor.clone();
+			async_std::task::spawn(async move {
+				let tls = match acceptor.accept(socket).await {
+					Ok(tls) => tls,
+					Err(_) => return,
+				};
+				let plain = async_std::net::TcpStream::connect(target).await.unwrap();
+				let (tls_read, mut tls_write) = tls.split();
+				let (plain_read, mut plain_write) = (&plain).split();
+				let _ = futures::future::select(
+					futures::io::copy(tls_read, &mut plain_write).boxed(),
+					futures::io::copy(plain_read, &mut tls_write).boxed(),
+				)
+				.await;
+			});
+		}
+	});
+	addr
+}
+
+#[tokio::test]
+async fn ping_keeps_connection_alive() {
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0_u64)),
+	)
+	.await;
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default()
+		.ping_interval(Duration::from_millis(50), Duration::from_millis(40))
+		.build(&uri)
+		.await
+		.unwrap();
+	tokio::time::sleep(Duration::from_millis(300)).await;
+	let response: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(response, "hello");
+}
+
+#[tokio::test]
+async fn missing_pong_drops_connection() {
+	use futures::io::{BufReader, BufWriter};
+
+	// Completes the handshake but never reads from the connection, thus never answers pings.
+	let listener = async_std::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	async_std::task::spawn(async move {
+		let (socket, _) = listener.accept().await.unwrap();
+		let mut server = soketto::handshake::Server::new(BufReader::new(BufWriter::new(socket)));
+		let key = server.receive_request().await.unwrap().into_key();
+		server
+			.send_response(&soketto::handshake::server::Response::Accept { key: &key, protocol: None })
+			.await
+			.unwrap();
+		async_std::task::sleep(Duration::from_secs(60)).await;
+		drop(server);
+	});
+
+	let client = WsClientBuilder::default()
+		.ping_interval(Duration::from_millis(50), Duration::from_millis(40))
+		.build(&to_ws_uri_string(addr))
+		.await
+		.unwrap();
+	tokio::time::sleep(Duration::from_millis(300)).await;
+	let err = client.request::<String, _, _>("say_hello", Params::None).await.unwrap_err();
+	assert!(matches!(err, Error::RestartNeeded(ShutdownReason::Timeout)));
+}
+
+#[tokio::test]
+async fn lenient_batch_responses_accept_split_batches() {
+	use futures::io::{BufReader, BufWriter};
+
+	// Answers the batch request with an array of two responses followed by a single response.
+	let listener = async_std::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	async_std::task::spawn(async move {
+		let (socket, _) = listener.accept().await.unwrap();
+		let mut server = soketto::handshake::Server::new(BufReader::new(BufWriter::new(socket)));
+		let key = server.receive_request().await.unwrap().into_key();
+		server
+			.send_response(&soketto::handshake::server::Response::Accept { key: &key, protocol: None })
+			.await
+			.unwrap();
+		let (mut sender, mut receiver) = server.into_builder().finish();
+		receiver.receive_data(&mut Vec::new()).await.unwrap();
+		sender
+			.send_text(r#"[{"jsonrpc":"2.0","result":"goodbye","id":1},{"jsonrpc":"2.0","result":"hello","id":0}]"#)
+			.await
+			.unwrap();
+		sender.send_text(r#"{"jsonrpc":"2.0","result":"here's your swag","id":2}"#).await.unwrap();
+		sender.flush().await.unwrap();
+		async_std::task::sleep(Duration::from_secs(60)).await;
+	});
+
+	let client = WsClientBuilder::default().lenient_batch_responses(true).build(&to_ws_uri_string(addr)).await.unwrap();
+	let batch = vec![
+		("say_hello".to_string(), Params::None),
+		("say_goodbye".to_string(), Params::None),
+		("get_swag".to_string(), Params::None),
+	];
+	let response: Vec<String> = client.batch_request(batch).await.unwrap();
+	assert_eq!(response, vec!["hello".to_string(), "goodbye".to_string(), "here's your swag".to_string()]);
+}
+
+#[tokio::test]
+async fn notification_handler_receives_plain_notifications() {
+	use futures::io::{BufReader, BufWriter};
+	use futures::StreamExt;
+
+	// Pushes notifications without a subscription once the client sent a notification.
+	let listener = async_std::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	async_std::task::spawn(async move {
+		let (socket, _) = listener.accept().await.unwrap();
+		let mut server = soketto::handshake::Server::new(BufReader::new(BufWriter::new(socket)));
+		let key = server.receive_request().await.unwrap().into_key();
+		server
+			.send_response(&soketto::handshake::server::Response::Accept { key: &key, protocol: None })
+			.await
+			.unwrap();
+		let (mut sender, mut receiver) = server.into_builder().finish();
+		receiver.receive_data(&mut Vec::new()).await.unwrap();
+		sender.send_text(r#"{"jsonrpc":"2.0","method":"unhandled","params":[0]}"#).await.unwrap();
+		sender.send_text(r#"{"jsonrpc":"2.0","method":"chain_newHead","params":[1]}"#).await.unwrap();
+		sender.flush().await.unwrap();
+		async_std::task::sleep(Duration::from_secs(60)).await;
+	});
+
+	let client = WsClientBuilder::default().build(&to_ws_uri_string(addr)).await.unwrap();
+	let mut heads = client.on_notification("chain_newHead").await.unwrap();
+	client.notification("ready", Params::None).await.unwrap();
+	assert_eq!(heads.next().await, Some(Params::Array(vec![1.into()])));
+	assert!(client.is_connected());
+}
+
+#[tokio::test]
+async fn silent_subscription_is_closed() {
+	use futures::io::{BufReader, BufWriter};
+
+	// Sends heartbeats for longer than the timeout of the client, then a notification and then nothing.
+	let listener = async_std::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	let (unsubscribed_tx, unsubscribed_rx) = futures::channel::oneshot::channel();
+	async_std::task::spawn(async move {
+		let (socket, _) = listener.accept().await.unwrap();
+		let mut server = soketto::handshake::Server::new(BufReader::new(BufWriter::new(socket)));
+		let key = server.receive_request().await.unwrap().into_key();
+		server
+			.send_response(&soketto::handshake::server::Response::Accept { key: &key, protocol: None })
+			.await
+			.unwrap();
+		let (mut sender, mut receiver) = server.into_builder().finish();
+		let mut request = Vec::new();
+		receiver.receive_data(&mut request).await.unwrap();
+		let request: jsonrpc::JsonValue = serde_json::from_slice(&request).unwrap();
+		sender.send_text(format!(r#"{{"jsonrpc":"2.0","result":"a","id":{}}}"#, request["id"])).await.unwrap();
+		sender.flush().await.unwrap();
+		for _ in 0..6 {
+			async_std::task::sleep(Duration::from_millis(40)).await;
+			let heartbeat =
+				r#"{"jsonrpc":"2.0","method":"rpc_subscriptionHeartbeat","params":{"subscription":"a","result":null}}"#;
+			sender.send_text(heartbeat).await.unwrap();
+			sender.flush().await.unwrap();
+		}
+		sender
+			.send_text(r#"{"jsonrpc":"2.0","method":"hello","params":{"subscription":"a","result":"alive"}}"#)
+			.await
+			.unwrap();
+		sender.flush().await.unwrap();
+		let mut unsubscribe = Vec::new();
+		receiver.receive_data(&mut unsubscribe).await.unwrap();
+		unsubscribed_tx.send(String::from_utf8(unsubscribe).unwrap()).unwrap();
+		async_std::task::sleep(Duration::from_secs(60)).await;
+	});
+
+	let client = WsClientBuilder::default()
+		.subscription_heartbeat(Duration::from_millis(100))
+		.build(&to_ws_uri_string(addr))
+		.await
+		.unwrap();
+	let mut sub: WsSubscription<String> =
+		client.subscribe("subscribe_hello", Params::None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(sub.next().await, Some("alive".to_string()));
+	assert_eq!(sub.next().await, None);
+	assert!(unsubscribed_rx.await.unwrap().contains("unsubscribe_hello"));
+	assert!(client.is_connected());
+}
+
+#[tokio::test]
+async fn cancelled_request_frees_its_slot() {
+	use futures::io::{BufReader, BufWriter};
+
+	// Answers the first request only once the second request arrived, then answers the second request.
+	let listener = async_std::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	async_std::task::spawn(async move {
+		let (socket, _) = listener.accept().await.unwrap();
+		let mut server = soketto::handshake::Server::new(BufReader::new(BufWriter::new(socket)));
+		let key = server.receive_request().await.unwrap().into_key();
+		server
+			.send_response(&soketto::handshake::server::Response::Accept { key: &key, protocol: None })
+			.await
+			.unwrap();
+		let (mut sender, mut receiver) = server.into_builder().finish();
+		let mut requests = Vec::new();
+		for _ in 0..2 {
+			let mut request = Vec::new();
+			receiver.receive_data(&mut request).await.unwrap();
+			requests.push(serde_json::from_slice::<jsonrpc::JsonValue>(&request).unwrap());
+		}
+		for request in requests {
+			let response = format!(r#"{{"jsonrpc":"2.0","result":{},"id":{}}}"#, request["method"], request["id"]);
+			sender.send_text(response).await.unwrap();
+			sender.flush().await.unwrap();
+		}
+		async_std::task::sleep(Duration::from_secs(60)).await;
+	});
+
+	let client = WsClientBuilder::default().max_concurrent_requests(1).build(&to_ws_uri_string(addr)).await.unwrap();
+	let (handle, slow) = client.cancellable_request::<String, _, _>("slow", Params::None);
+	let cancel = async {
+		tokio::time::sleep(Duration::from_millis(100)).await;
+		handle.cancel();
+	};
+	let (slow, ()) = futures::join!(slow, cancel);
+	assert!(matches!(slow, Err(Error::RequestCancelled)));
+
+	let fast: String = client.request("fast", Params::None).await.unwrap();
+	assert_eq!(fast, "fast");
+	assert!(client.is_connected());
+}
+
+#[tokio::test]
+async fn request_queue_waits_for_free_slots() {
+	use futures::io::{BufReader, BufWriter};
+
+	// Answers every request with its method after 100 ms.
+	let listener = async_std::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	async_std::task::spawn(async move {
+		while let Ok((socket, _)) = listener.accept().await {
+			async_std::task::spawn(async move {
+				let mut server = soketto::handshake::Server::new(BufReader::new(BufWriter::new(socket)));
+				let key = server.receive_request().await.unwrap().into_key();
+				server
+					.send_response(&soketto::handshake::server::Response::Accept { key: &key, protocol: None })
+					.await
+					.unwrap();
+				let (mut sender, mut receiver) = server.into_builder().finish();
+				loop {
+					let mut request = Vec::new();
+					if receiver.receive_data(&mut request).await.is_err() {
+						return;
+					}
+					let request = serde_json::from_slice::<jsonrpc::JsonValue>(&request).unwrap();
+					async_std::task::sleep(Duration::from_millis(100)).await;
+					let response =
+						format!(r#"{{"jsonrpc":"2.0","result":{},"id":{}}}"#, request["method"], request["id"]);
+					sender.send_text(response).await.unwrap();
+					sender.flush().await.unwrap();
+				}
+			});
+		}
+	});
+	let uri = to_ws_uri_string(addr);
+
+	let client =
+		WsClientBuilder::default().max_concurrent_requests(1).request_queue(1, None).build(&uri).await.unwrap();
+	let (a, b, c) = futures::join!(
+		client.request::<String, _, _>("a", Params::None),
+		client.request::<String, _, _>("b", Params::None),
+		client.request::<String, _, _>("c", Params::None),
+	);
+	assert_eq!(a.unwrap(), "a");
+	assert_eq!(b.unwrap(), "b");
+	assert!(matches!(c, Err(Error::MaxSlotsExceeded)));
+
+	let client = WsClientBuilder::default()
+		.max_concurrent_requests(1)
+		.request_queue(4, Some(Duration::from_millis(20)))
+		.build(&uri)
+		.await
+		.unwrap();
+	let (a, b) = futures::join!(
+		client.request::<String, _, _>("a", Params::None),
+		client.request::<String, _, _>("b", Params::None),
+	);
+	assert_eq!(a.unwrap(), "a");
+	assert!(matches!(b, Err(Error::MaxSlotsExceeded)));
+	assert!(client.is_connected());
+}
+
+#[tokio::test]
+async fn scoped_client_shuts_down_with_scope() {
+	use futures::io::{BufReader, BufWriter};
+
+	// Completes the handshake but never answers.
+	let listener = async_std::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	async_std::task::spawn(async move {
+		let (socket, _) = listener.accept().await.unwrap();
+		let mut server = soketto::handshake::Server::new(BufReader::new(BufWriter::new(socket)));
+		let key = server.receive_request().await.unwrap().into_key();
+		server
+			.send_response(&soketto::handshake::server::Response::Accept { key: &key, protocol: None })
+			.await
+			.unwrap();
+		async_std::task::sleep(Duration::from_secs(60)).await;
+		drop(server);
+	});
+
+	let mut scope = tokio::task::JoinSet::new();
+	let client = Arc::new(
+		WsClientBuilder::default()
+			.build_scoped(&to_ws_uri_string(addr), |task| {
+				scope.spawn(task);
+			})
+			.await
+			.unwrap(),
+	);
+	let pending = {
+		let client = client.clone();
+		tokio::spawn(async move { client.request::<String, _, _>("say_hello", Params::None).await })
+	};
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	drop(scope);
+
+	let err = pending.await.unwrap().unwrap_err();
+	assert!(matches!(err, Error::RestartNeeded(ShutdownReason::Aborted)));
+	let err = client.request::<String, _, _>("say_hello", Params::None).await.unwrap_err();
+	assert!(matches!(err, Error::RestartNeeded(ShutdownReason::Aborted)));
+	assert!(!client.is_connected());
+}
+
+#[tokio::test]
+async fn middlewares_modify_and_short_circuit_calls() {
+	use crate::{CallContext, ClientMiddleware};
+
+	struct Auth;
+
+	impl ClientMiddleware for Auth {
+		fn on_request(&self, call: &mut CallContext) -> Option<jsonrpc::JsonValue> {
+			call.params = Params::Array(vec!["token".into()]);
+			call.metadata.insert("authenticated".into(), true.into());
+			None
+		}
+	}
+
+	struct Cache;
+
+	impl ClientMiddleware for Cache {
+		fn on_request(&self, call: &mut CallContext) -> Option<jsonrpc::JsonValue> {
+			(call.method == "cached").then(|| "from cache".into())
+		}
+	}
+
+	#[derive(Default)]
+	struct Recorder(Mutex<Vec<(String, Params, bool)>>);
+
+	impl ClientMiddleware for Arc<Recorder> {
+		fn on_response(&self, call: &CallContext, response: &mut Result<jsonrpc::JsonValue, Error>) {
+			assert!(response.is_ok());
+			let authenticated = call.metadata.get("authenticated") == Some(&true.into());
+			self.0.lock().unwrap().push((call.method.clone(), call.params.clone(), authenticated));
+		}
+	}
+
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0)),
+	)
+	.await;
+	let recorder = Arc::new(Recorder::default());
+	let client = WsClientBuilder::default()
+		.middleware(Auth)
+		.middleware(Cache)
+		.middleware(recorder.clone())
+		.build(&to_ws_uri_string(server.local_addr()))
+		.await
+		.unwrap();
+
+	let sent: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(sent, "hello");
+	let cached: String = client.request("cached", Params::None).await.unwrap();
+	assert_eq!(cached, "from cache");
+	let token = Params::Array(vec!["token".into()]);
+	assert_eq!(
+		*recorder.0.lock().unwrap(),
+		vec![("say_hello".to_string(), token.clone(), true), ("cached".to_string(), token, true)]
+	);
+}
+
+#[tokio::test]
+async fn compression_is_negotiated_with_the_server() {
+	use futures::io::{BufReader, BufWriter};
+	use soketto::extension::deflate::Deflate;
+	use soketto::extension::Extension;
+
+	// Accepts `permessage-deflate` and answers the first call with its method name.
+	let listener = async_std::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	let (enabled_tx, enabled_rx) = futures::channel::oneshot::channel();
+	async_std::task::spawn(async move {
+		let (socket, _) = listener.accept().await.unwrap();
+		let mut server = soketto::handshake::Server::new(BufReader::new(BufWriter::new(socket)));
+		server.add_extension(Box::new(Deflate::new(soketto::Mode::Server)));
+		let key = server.receive_request().await.unwrap().into_key();
+		server
+			.send_response(&soketto::handshake::server::Response::Accept { key: &key, protocol: None })
+			.await
+			.unwrap();
+		let extensions: Vec<_> = server.drain_extensions().collect();
+		enabled_tx.send(extensions.iter().any(|e| e.is_enabled())).unwrap();
+		let mut builder = server.into_builder();
+		builder.add_extensions(extensions);
+		let (mut sender, mut receiver) = builder.finish();
+		let mut request = Vec::new();
+		receiver.receive_data(&mut request).await.unwrap();
+		let request: serde_json::Value = serde_json::from_slice(&request).unwrap();
+		let response = serde_json::json!({"jsonrpc": "2.0", "result": request["method"], "id": request["id"]});
+		sender.send_text(response.to_string()).await.unwrap();
+		sender.flush().await.unwrap();
+		async_std::task::sleep(Duration::from_secs(60)).await;
+	});
+
+	let client = WsClientBuilder::default().compression(Some(12)).build(&to_ws_uri_string(addr)).await.unwrap();
+	assert!(enabled_rx.await.unwrap());
+	let response: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(response, "say_hello");
+}
+
+#[tokio::test]
+async fn socks5_proxy_tunnels_connection() {
+	use futures::io::{AsyncReadExt, AsyncWriteExt};
+
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0_u64)),
+	)
+	.await;
+	let server_addr = server.local_addr();
+
+	// Authenticates the client and tunnels its connection to the server, whatever the requested host.
+	let listener = async_std::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let proxy_addr = listener.local_addr().unwrap();
+	let (target_tx, target_rx) = futures::channel::oneshot::channel();
+	async_std::task::spawn(async move {
+		let (mut client, _) = listener.accept().await.unwrap();
+		let mut greeting = [0; 3];
+		client.read_exact(&mut greeting).await.unwrap();
+		assert_eq!(greeting, [5, 1, 2]);
+		client.write_all(&[5, 2]).await.unwrap();
+		let mut auth = [0; 13];
+		client.read_exact(&mut auth).await.unwrap();
+		assert_eq!(&auth, b"\x01\x04user\x06secret");
+		client.write_all(&[1, 0]).await.unwrap();
+		let mut request = [0; 5];
+		client.read_exact(&mut request).await.unwrap();
+		assert_eq!(request[..4], [5, 1, 0, 3]);
+		let mut host = vec![0; request[4] as usize + 2];
+		client.read_exact(&mut host).await.unwrap();
+		let port = u16::from_be_bytes([host[host.len() - 2], host[host.len() - 1]]);
+		host.truncate(host.len() - 2);
+		target_tx.send((String::from_utf8(host).unwrap(), port)).unwrap();
+		client.write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 0]).await.unwrap();
+
+		let server = async_std::net::TcpStream::connect(server_addr).await.unwrap();
+		let (client_reader, mut server_writer) = (client.clone(), server.clone());
+		let upstream = futures::io::copy(client_reader, &mut server_writer);
+		let downstream = futures::io::copy(server, &mut client);
+		let _ = futures::future::join(upstream, downstream).await;
+	});
+
+	// The host isn't resolvable locally, it's resolved by the proxy.
+	let client = WsClientBuilder::default()
+		.socks5_proxy(proxy_addr, Some(("user".into(), "secret".into())))
+		.build("ws://node.invalid:9944")
+		.await
+		.unwrap();
+	assert_eq!(target_rx.await.unwrap(), ("node.invalid".to_string(), 9944));
+	let response: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(response, "hello");
+}
+
+#[tokio::test]
+async fn http_proxy_tunnels_connection() {
+	use futures::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0_u64)),
+	)
+	.await;
+	let server_addr = server.local_addr();
+
+	// Checks the credentials and tunnels the connection to the server, whatever the requested host.
+	let listener = async_std::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let proxy_addr = listener.local_addr().unwrap();
+	let (request_tx, request_rx) = futures::channel::oneshot::channel();
+	async_std::task::spawn(async move {
+		let (mut client, _) = listener.accept().await.unwrap();
+		let mut lines = Vec::new();
+		let mut reader = BufReader::new(client.clone());
+		loop {
+			let mut line = String::new();
+			reader.read_line(&mut line).await.unwrap();
+			if line == "\r\n" {
+				break;
+			}
+			lines.push(line.trim_end().to_string());
+		}
+		request_tx.send(lines).unwrap();
+		client.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").await.unwrap();
+
+		let server = async_std::net::TcpStream::connect(server_addr).await.unwrap();
+		let (client_reader, mut server_writer) = (client.clone(), server.clone());
+		let upstream = futures::io::copy(client_reader, &mut server_writer);
+		let downstream = futures::io::copy(server, &mut client);
+		let _ = futures::future::join(upstream, downstream).await;
+	});
+
+	let client = WsClientBuilder::default()
+		.http_proxy(proxy_addr, Some(("user".into(), "secret".into())))
+		.build("ws://node.invalid:9944")
+		.await
+		.unwrap();
+	assert_eq!(
+		request_rx.await.unwrap(),
+		vec![
+			"CONNECT node.invalid:9944 HTTP/1.1".to_string(),
+			"Host: node.invalid:9944".to_string(),
+			"Proxy-Authorization: Basic dXNlcjpzZWNyZXQ=".to_string()
+		]
+	);
+	let response: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(response, "hello");
+}
+
+#[tokio::test]
+async fn handshake_redirects_are_followed() {
+	use futures::io::{AsyncReadExt, AsyncWriteExt};
+
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0_u64)),
+	)
+	.await;
+	// Redirects every handshake to the server.
+	let listener = async_std::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	let location = format!("http://{}/rpc", server.local_addr());
+	async_std::task::spawn(async move {
+		while let Ok((mut socket, _)) = listener.accept().await {
+			let _len = socket.read(&mut [0; 4096]).await.unwrap();
+			let response = format!("HTTP/1.1 301 Moved Permanently\r\nLocation: {}\r\n\r\n", location);
+			socket.write_all(response.as_bytes()).await.unwrap();
+		}
+	});
+
+	let uri = to_ws_uri_string(addr);
+	assert!(WsClientBuilder::default().build(&uri).await.is_err());
+	let client = WsClientBuilder::default().max_redirections(1).build(&uri).await.unwrap();
+	let hello: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(hello, "hello");
+}
+
+#[tokio::test]
+async fn authorization_header_is_passed_and_redacted() {
+	use futures::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0_u64)),
+	)
+	.await;
+	let server_addr = server.local_addr();
+
+	// Records the headers of the handshake and relays the connection to the server.
+	let listener = async_std::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	let (headers_tx, headers_rx) = futures::channel::oneshot::channel();
+	async_std::task::spawn(async move {
+		let (mut client, _) = listener.accept().await.unwrap();
+		let mut request = String::new();
+		let mut reader = BufReader::new(client.clone());
+		while !request.ends_with("\r\n\r\n") {
+			reader.read_line(&mut request).await.unwrap();
+		}
+		let mut server = async_std::net::TcpStream::connect(server_addr).await.unwrap();
+		server.write_all(request.as_bytes()).await.unwrap();
+		headers_tx.send(request.lines().map(ToString::to_string).collect::<Vec<_>>()).unwrap();
+
+		let (client_reader, mut server_writer) = (client.clone(), server.clone());
+		let upstream = futures::io::copy(client_reader, &mut server_writer);
+		let downstream = futures::io::copy(server, &mut client);
+		let _ = futures::future::join(upstream, downstream).await;
+	});
+
+	let builder = WsClientBuilder::default().basic_auth("user", "secret");
+	assert!(!format!("{:?}", builder).contains("dXNlcjpzZWNyZXQ="));
+	let bearer = WsClientBuilder::default().bearer_token("t0ken");
+	assert!(!format!("{:?}", bearer).contains("t0ken"));
+
+	let client = builder.build(&to_ws_uri_string(addr)).await.unwrap();
+	let headers = headers_rx.await.unwrap();
+	assert!(headers.contains(&"Authorization: Basic dXNlcjpzZWNyZXQ=".to_string()));
+	let response: String = client.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(response, "hello");
+}
+
+#[test]
+fn config_round_trips_through_builder() {
+	use crate::ClientConfig;
+
+	assert_eq!(serde_json::from_str::<ClientConfig>("{}").unwrap(), ClientConfig::default());
+	assert!(serde_json::from_str::<ClientConfig>(r#"{"max_request_size":1}"#).is_err());
+
+	let config: ClientConfig = serde_json::from_str(
+		r#"{
+			"request_timeout_ms": 5000,
+			"origin_header": "https://example.com",
+			"subscription_overflow_policy": "DropOldest",
+			"batching_window": { "window_ms": 5, "max_messages": 32, "notifications_only": true },
+			"reconnect": { "max_retries": 3, "backoff_ms": 100 },
+			"ping_interval": { "interval_ms": 1000, "pong_timeout_ms": 500 },
+			"number_options": { "large_integers": "Stringify" },
+			"split_rejected_batches": true
+		}"#,
+	)
+	.unwrap();
+	assert_eq!(config.max_concurrent_requests, ClientConfig::default().max_concurrent_requests);
+	assert_eq!(WsClientBuilder::try_from(config.clone()).unwrap().to_config(), config);
+
+	let config: ClientConfig = serde_json::from_str(r#"{"compression": 20}"#).unwrap();
+	assert_eq!(WsClientBuilder::try_from(config).unwrap_err().option, "compression");
+}
@@ -24,17 +24,42 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use crate::proxy::Proxy;
 use async_std::net::TcpStream;
-use async_tls::client::TlsStream;
+use async_tls::{client::TlsStream, TlsConnector};
+use futures::channel::{mpsc, oneshot};
+use futures::future::{self, Either};
 use futures::io::{BufReader, BufWriter};
 use futures::prelude::*;
-use jsonrpsee_types::jsonrpc;
+#[cfg(all(unix, feature = "uds"))]
+use jsonrpsee_types::framing::{Decoder, Framing, FramingError};
+use jsonrpsee_types::{error::ShutdownReason, jsonrpc, redact::LogRedactor, traits::Value, wire::Codec};
+use once_cell::sync::OnceCell;
 use soketto::connection;
+use soketto::extension::deflate::Deflate;
 use soketto::handshake::client::{Client as WsRawClient, ServerResponse};
-use std::{borrow::Cow, io, net::SocketAddr, time::Duration};
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{borrow::Cow, fmt, io, net::SocketAddr, sync::Arc, time::Duration};
 use thiserror::Error;
 
 type TlsOrPlain = crate::stream::EitherStream<TcpStream, TlsStream<TcpStream>>;
+type BoxedStream = BufReader<BufWriter<Box<dyn WsStream>>>;
+#[cfg(all(unix, feature = "uds"))]
+type LinesReader = futures::io::ReadHalf<Box<dyn WsStream>>;
+#[cfg(all(unix, feature = "uds"))]
+type LinesWriter = BufWriter<futures::io::WriteHalf<Box<dyn WsStream>>>;
+
+/// Number of TLS sessions kept around for resumption, shared by all clients.
+const TLS_SESSION_CACHE_SIZE: usize = 256;
+
+/// Stream which the WebSocket connection can be established over.
+///
+/// Implemented for every `AsyncRead + AsyncWrite` stream, such as an established TLS tunnel,
+/// a multiplexed substream or an in-memory duplex.
+pub trait WsStream: AsyncRead + AsyncWrite + Send + Unpin + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin + 'static> WsStream for T {}
 
 /// String representation of the host (domain or IP address) of an URL.
 #[derive(Clone, Debug)]
@@ -47,20 +72,76 @@ impl Host {
 	}
 }
 
+impl From<&str> for Host {
+	fn from(host: &str) -> Self {
+		Host(host.to_owned())
+	}
+}
+
 /// Sending end of WebSocket transport.
-#[derive(Debug)]
 pub struct Sender {
-	inner: connection::Sender<BufReader<BufWriter<TlsOrPlain>>>,
+	inner: SenderKind,
+	/// Max size of a request.
+	max_request_size: usize,
+	/// Hook receiving the requests.
+	tap: Option<RawTap>,
+	/// Codec negotiated during the handshake, the requests are sent as JSON if `None`.
+	codec: Option<Arc<dyn Codec>>,
+	/// Masking of the params in logs.
+	redactor: LogRedactor,
+}
+
+enum SenderKind {
+	Ws(connection::Sender<BoxedStream>),
+	#[cfg(all(unix, feature = "uds"))]
+	Lines(LinesWriter),
+	/// Messages written by a separate writer task, see [`WsTransportClientBuilder::write_queue`].
+	Queued(mpsc::Sender<Outgoing>),
+}
+
+/// Message handed over to the writer task.
+enum Outgoing {
+	Message(Vec<u8>),
+	Ping,
+}
+
+impl fmt::Debug for Sender {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Sender").finish()
+	}
 }
 
 /// Receiving end of WebSocket transport.
-#[derive(Debug)]
 pub struct Receiver {
-	inner: connection::Receiver<BufReader<BufWriter<TlsOrPlain>>>,
+	inner: ReceiverKind,
+	/// Set when a pong is received.
+	pong: Arc<AtomicBool>,
+	/// Max size of a message, after decompression.
+	max_message_size: usize,
+	/// Error of the writer task, if messages are written by a separate task.
+	writer_error: Option<oneshot::Receiver<WsConnectError>>,
+	/// Hook receiving the messages.
+	tap: Option<RawTap>,
+	/// Codec negotiated during the handshake, the messages are received as JSON if `None`.
+	codec: Option<Arc<dyn Codec>>,
+	/// Masking of the params in logs.
+	redactor: LogRedactor,
+}
+
+enum ReceiverKind {
+	Ws(connection::Receiver<BoxedStream>),
+	#[cfg(all(unix, feature = "uds"))]
+	Lines(LinesReader, Decoder),
+}
+
+impl fmt::Debug for Receiver {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Receiver").finish()
+	}
 }
 
 /// Builder for a WebSocket transport [`Sender`] and ['Receiver`] pair.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct WsTransportClientBuilder<'a> {
 	/// Socket addresses to try to connect to.
 	pub sockaddrs: Vec<SocketAddr>,
@@ -72,11 +153,148 @@ pub struct WsTransportClientBuilder<'a> {
 	pub handshake_url: Cow<'a, str>,
 	/// Timeout for the connection.
 	pub timeout: Duration,
+	/// Delay before a connection attempt to the next socket address is started while the earlier ones are
+	/// still pending, see [`WsTransportClientBuilder::with_attempt_delay`].
+	pub attempt_delay: Duration,
 	/// `Origin` header to pass during the HTTP handshake. If `None`, no
 	/// `Origin` header is passed.
 	pub origin: Option<Cow<'a, str>>,
-	/// Max payload size
+	/// `Authorization` header to pass during the HTTP handshake. If `None`, no `Authorization` header is
+	/// passed.
+	pub authorization: Option<Authorization>,
+	/// Max payload size of the requests.
 	pub max_request_body_size: usize,
+	/// Max payload size of the messages received from the server.
+	pub max_response_size: usize,
+	/// Resume TLS sessions of earlier connections when connecting in TLS mode.
+	pub tls_session_resumption: bool,
+	/// Custom TLS configuration, the default configuration is used if `None`.
+	pub tls_config: Option<TlsConfig>,
+	/// Max window bits of the `permessage-deflate` extension offered during the HTTP handshake, within
+	/// `9..=15`. If `None`, compression is not offered.
+	pub compression: Option<u8>,
+	/// Proxy to tunnel the connection through and port of the server to connect to through it.
+	/// If set, `sockaddrs` are not used and `host` is resolved by the proxy.
+	pub proxy: Option<(Proxy, u16)>,
+	/// Max number of redirects followed during the HTTP handshake, redirects are not followed if `0`.
+	pub max_redirections: usize,
+	/// Capacity of the queue of the messages written by a separate writer task. If `None`, the messages
+	/// are written by the sender.
+	pub write_queue: Option<usize>,
+	/// Hook receiving the messages exchanged with the server.
+	pub raw_tap: Option<RawTap>,
+	/// Codecs offered during the HTTP handshake, in order of preference.
+	pub codecs: Codecs,
+	/// Masking of the params of the logged messages.
+	pub redactor: LogRedactor,
+	/// Unix domain socket to connect to and the framing of the messages exchanged over it.
+	/// If set, `sockaddrs`, `mode` and `proxy` are not used.
+	#[cfg(all(unix, feature = "uds"))]
+	pub unix_socket: Option<(std::path::PathBuf, UdsFraming)>,
+}
+
+/// Credentials passed in the `Authorization` header during the HTTP handshake, the credentials are
+/// redacted when formatted with `Debug`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Authorization(String);
+
+impl Authorization {
+	/// Credentials of the `Basic` scheme, `username` and `password` encoded in base64.
+	pub fn basic(username: &str, password: &str) -> Self {
+		Self(format!("Basic {}", base64::encode(format!("{}:{}", username, password))))
+	}
+
+	/// Credentials of the `Bearer` scheme.
+	pub fn bearer(token: &str) -> Self {
+		Self(format!("Bearer {}", token))
+	}
+
+	/// Authentication scheme of the credentials.
+	pub fn scheme(&self) -> &str {
+		self.0.split(' ').next().unwrap_or_default()
+	}
+}
+
+impl fmt::Debug for Authorization {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Authorization({} <redacted>)", self.scheme())
+	}
+}
+
+/// Framing of the messages exchanged over a Unix domain socket, see
+/// [`WsClientBuilder::uds_framing`](crate::WsClientBuilder::uds_framing).
+#[cfg(all(unix, feature = "uds"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum UdsFraming {
+	/// WebSocket frames, after a WebSocket handshake over the socket.
+	#[default]
+	WebSocket,
+	/// JSON-RPC messages delimited by newlines, without any handshake.
+	///
+	/// WebSocket pings are not available, thus [`WsClientBuilder::ping_interval`] has no effect.
+	///
+	/// [`WsClientBuilder::ping_interval`]: crate::WsClientBuilder::ping_interval
+	NewlineDelimited,
+}
+
+/// Direction of a message passed to a [`RawTap`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+	/// Sent to the server.
+	Outgoing,
+	/// Received from the server.
+	Incoming,
+}
+
+type Tap = dyn Fn(Direction, &str) + Send + Sync;
+
+/// Hook receiving the JSON payloads exchanged with the server as they are on the wire, before the
+/// WebSocket framing, the compression and TLS, see
+/// [`WsClientBuilder::on_raw_message`](crate::WsClientBuilder::on_raw_message).
+///
+/// The hook is called from the background task of the client and must not block.
+#[derive(Clone)]
+pub struct RawTap(pub Arc<Tap>);
+
+impl RawTap {
+	fn tap(&self, direction: Direction, message: &[u8]) {
+		(self.0)(direction, &String::from_utf8_lossy(message))
+	}
+}
+
+impl fmt::Debug for RawTap {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("RawTap")
+	}
+}
+
+/// Codecs offered to the server, see [`WsClientBuilder::codec`](crate::WsClientBuilder::codec).
+#[derive(Clone, Default)]
+pub struct Codecs(pub Vec<Arc<dyn Codec>>);
+
+impl Codecs {
+	/// Returns the codec named by the `subprotocol` that the server accepted.
+	fn accepted(&self, subprotocol: Option<&str>) -> Option<Arc<dyn Codec>> {
+		let subprotocol = subprotocol?;
+		self.0.iter().find(|codec| codec.subprotocol() == subprotocol).cloned()
+	}
+}
+
+impl fmt::Debug for Codecs {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_list().entries(self.0.iter().map(|codec| codec.subprotocol())).finish()
+	}
+}
+
+/// Custom [`rustls::ClientConfig`] used to establish TLS streams, such as a root store with private CAs,
+/// client certificates for mutual TLS or ALPN protocols.
+#[derive(Clone)]
+pub struct TlsConfig(pub Arc<rustls::ClientConfig>);
+
+impl fmt::Debug for TlsConfig {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("TlsConfig").field("alpn_protocols", &self.0.alpn_protocols).finish()
+	}
 }
 
 /// Stream mode, either plain TCP or TLS.
@@ -110,9 +328,26 @@ pub enum WsNewError {
 		status_code: u16,
 	},
 
+	/// RawServer redirected our handshake, see [`WsTransportClientBuilder::max_redirections`].
+	#[error("Server redirected to {} with status code: {}", location, status_code)]
+	Redirected {
+		/// HTTP status code that the server returned.
+		status_code: u16,
+		/// URL or path that the server redirected to.
+		location: String,
+	},
+
 	/// Timeout while trying to connect.
 	#[error("Timeout when trying to connect")]
 	Timeout,
+
+	/// SOCKS5 proxy failed to connect to the server.
+	#[error("SOCKS5 proxy error: {0}")]
+	Socks5(String),
+
+	/// HTTP proxy failed to connect to the server.
+	#[error("HTTP proxy error: {0}")]
+	HttpProxy(String),
 }
 
 /// Error that can happen during the initial handshake.
@@ -153,33 +388,244 @@ pub enum WsConnectError {
 	/// Failed to parse the JSON returned by the server into a JSON-RPC response.
 	#[error("error while parsing the response body")]
 	ParseError(#[source] serde_json::error::Error),
+
+	/// The negotiated codec failed to serialize a request or to deserialize a message.
+	#[error("codec error: {0}")]
+	Codec(String),
+
+	/// A request or a message received from the server exceeded its maximum size.
+	#[error("the message was too large")]
+	TooLarge,
+
+	/// The server didn't answer a ping in time.
+	#[error("no pong received within {0:?}")]
+	PongTimeout(Duration),
+
+	/// Error in the newline-delimited connection over a Unix domain socket.
+	#[cfg(all(unix, feature = "uds"))]
+	#[error("error in the Unix domain socket connection")]
+	Io(#[source] io::Error),
 }
 
 impl Sender {
 	/// Sends out out a request. Returns a `Future` that finishes when the request has been
-	/// successfully sent.
+	/// successfully sent, or handed over to the writer task.
 	pub async fn send_request(&mut self, request: jsonrpc::Request) -> Result<(), WsConnectError> {
-		log::debug!("send: {}", request);
-		let request = jsonrpc::to_vec(&request).map_err(WsConnectError::Serialization)?;
-		self.inner.send_binary(request).await?;
-		self.inner.flush().await?;
+		log::debug!("send: {}", self.redactor.message(&request));
+		let json = jsonrpc::to_vec(&request).map_err(WsConnectError::Serialization)?;
+		let encoded = match self.codec.as_ref() {
+			Some(codec) => Some(codec.encode(&jsonrpc::to_value(&request).map_err(WsConnectError::Serialization)?)?),
+			None => None,
+		};
+		if encoded.as_ref().map_or(json.len(), Vec::len) > self.max_request_size {
+			return Err(WsConnectError::TooLarge);
+		}
+		if let Some(tap) = self.tap.as_ref() {
+			tap.tap(Direction::Outgoing, &json);
+		}
+		self.send(Outgoing::Message(encoded.unwrap_or(json))).await
+	}
+
+	/// Sends out a ping with an empty payload, does nothing if the transport has no pings.
+	pub async fn send_ping(&mut self) -> Result<(), WsConnectError> {
+		self.send(Outgoing::Ping).await
+	}
+
+	async fn send(&mut self, message: Outgoing) -> Result<(), WsConnectError> {
+		if let SenderKind::Queued(queue) = &mut self.inner {
+			// NOTE: the writer task only stops once it failed, the error is returned by the receiver.
+			return queue.send(message).await.map_err(|_| WsConnectError::Ws(connection::Error::Closed));
+		}
+		self.write(message).await?;
+		self.flush().await
+	}
+
+	/// Writes `message` without flushing it, not available for queued senders.
+	async fn write(&mut self, message: Outgoing) -> Result<(), WsConnectError> {
+		match (&mut self.inner, message) {
+			(SenderKind::Ws(sender), Outgoing::Message(message)) => sender.send_binary(message).await?,
+			(SenderKind::Ws(sender), Outgoing::Ping) => {
+				log::trace!("send: ping");
+				let payload = soketto::data::ByteSlice125::try_from(&[][..]).expect("Empty payload is valid; qed");
+				sender.send_ping(payload).await?;
+			}
+			#[cfg(all(unix, feature = "uds"))]
+			(SenderKind::Lines(writer), Outgoing::Message(message)) => {
+				let mut framed = Vec::with_capacity(message.len() + 1);
+				Framing::NewlineDelimited.encode(&message, &mut framed);
+				writer.write_all(&framed).await.map_err(WsConnectError::Io)?;
+			}
+			#[cfg(all(unix, feature = "uds"))]
+			(SenderKind::Lines(_), Outgoing::Ping) => (),
+			(SenderKind::Queued(_), _) => unreachable!("Queued messages are written by the writer task; qed"),
+		}
 		Ok(())
 	}
+
+	async fn flush(&mut self) -> Result<(), WsConnectError> {
+		match &mut self.inner {
+			SenderKind::Ws(sender) => sender.flush().await?,
+			#[cfg(all(unix, feature = "uds"))]
+			SenderKind::Lines(writer) => writer.flush().await.map_err(WsConnectError::Io)?,
+			SenderKind::Queued(_) => (),
+		}
+		Ok(())
+	}
+}
+
+/// Writes the messages of `queue` with `sender` until the queue is closed, the messages queued meanwhile
+/// are flushed together.
+///
+/// The first error is sent to `error`, which stops the task.
+async fn write_queued(mut sender: Sender, mut queue: mpsc::Receiver<Outgoing>, error: oneshot::Sender<WsConnectError>) {
+	while let Some(message) = queue.next().await {
+		let mut res = sender.write(message).await;
+		while let (Ok(()), Ok(message)) = (&res, queue.try_recv()) {
+			res = sender.write(message).await;
+		}
+		if let Err(err) = res.and(sender.flush().await) {
+			log::debug!("[writer]: failed to write: {:?}", err);
+			let _ = error.send(err);
+			return;
+		}
+	}
 }
 
 impl Receiver {
 	/// Returns a `Future` resolving when the server sent us something back.
-	pub async fn next_response(&mut self) -> Result<jsonrpc::Response, WsConnectError> {
-		let mut message = Vec::new();
-		self.inner.receive_data(&mut message).await?;
+	///
+	/// Pongs are not returned, see [`Receiver::pong_flag`].
+	pub async fn next_response<V: Value>(&mut self) -> Result<jsonrpc::Response<V>, WsConnectError> {
+		let read = receive(&mut self.inner, &self.pong);
+		let message = match self.writer_error.as_mut() {
+			Some(writer_error) => {
+				futures::pin_mut!(read);
+				match future::select(read, writer_error).await {
+					Either::Left((message, _)) => message?,
+					Either::Right((Ok(err), _)) => return Err(err),
+					// The writer task is only dropped along with the sender.
+					Either::Right((Err(_), read)) => read.await?,
+				}
+			}
+			None => read.await?,
+		};
+		// NOTE: the limit is checked again because extensions such as compression may inflate a message
+		// beyond the limit of its frames.
+		if message.len() > self.max_message_size {
+			return Err(WsConnectError::TooLarge);
+		}
+		let message = match self.codec.as_ref() {
+			Some(codec) => jsonrpc::to_vec(&codec.decode(&message)?).map_err(WsConnectError::ParseError)?,
+			None => message,
+		};
+		if let Some(tap) = self.tap.as_ref() {
+			tap.tap(Direction::Incoming, &message);
+		}
 
 		let response = jsonrpc::from_slice(&message).map_err(WsConnectError::ParseError)?;
-		log::debug!("recv: {}", response);
+		log::debug!("recv: {}", self.redactor.message(&response));
 		Ok(response)
 	}
+
+	/// Returns a flag that is set when a pong is received, the flag is never cleared by the receiver.
+	pub fn pong_flag(&self) -> Arc<AtomicBool> {
+		self.pong.clone()
+	}
+
+	/// Returns `false` if the transport has no pings, such as newline-delimited JSON-RPC over a Unix domain
+	/// socket.
+	pub fn supports_ping(&self) -> bool {
+		matches!(self.inner, ReceiverKind::Ws(_))
+	}
+}
+
+/// Receives the next message, pongs set the `pong` flag.
+async fn receive(inner: &mut ReceiverKind, pong: &AtomicBool) -> Result<Vec<u8>, WsConnectError> {
+	let mut message = Vec::new();
+	match inner {
+		ReceiverKind::Ws(receiver) => loop {
+			match receiver.receive(&mut message).await.map_err(too_large)? {
+				soketto::Incoming::Data(_) => break,
+				soketto::Incoming::Pong(_) => {
+					log::trace!("recv: pong");
+					pong.store(true, Ordering::Relaxed);
+				}
+			}
+		},
+		#[cfg(all(unix, feature = "uds"))]
+		ReceiverKind::Lines(reader, decoder) => message = read_message(reader, decoder).await?,
+	}
+	Ok(message)
+}
+
+/// Maps the errors of messages exceeding the maximum size to [`WsConnectError::TooLarge`].
+fn too_large(err: soketto::connection::Error) -> WsConnectError {
+	match err {
+		soketto::connection::Error::MessageTooLarge { .. }
+		| soketto::connection::Error::Codec(soketto::base::Error::PayloadTooLarge { .. }) => WsConnectError::TooLarge,
+		err => WsConnectError::Ws(err),
+	}
+}
+
+/// Reads from `reader` until `decoder` returns the next message.
+#[cfg(all(unix, feature = "uds"))]
+async fn read_message(reader: &mut LinesReader, decoder: &mut Decoder) -> Result<Vec<u8>, WsConnectError> {
+	let mut chunk = [0; 4096];
+	loop {
+		match decoder.next_message() {
+			Ok(Some(message)) => return Ok(message),
+			Ok(None) => (),
+			Err(FramingError::TooLarge) => return Err(WsConnectError::TooLarge),
+			Err(e) => return Err(WsConnectError::Io(io::Error::new(io::ErrorKind::InvalidData, e))),
+		}
+		let read = reader.read(&mut chunk).await.map_err(WsConnectError::Io)?;
+		if read == 0 {
+			return Err(WsConnectError::Io(io::ErrorKind::UnexpectedEof.into()));
+		}
+		decoder.extend(&chunk[..read]);
+	}
 }
 
 impl<'a> WsTransportClientBuilder<'a> {
+	/// Sets the TLS configuration to use when connecting in TLS mode.
+	///
+	/// The session storage of `config` is used, whatever [`Self::tls_session_resumption`] is set to.
+	///
+	/// By default, the Mozilla root certificates are trusted.
+	pub fn with_tls_config(mut self, config: rustls::ClientConfig) -> Self {
+		self.tls_config = Some(TlsConfig(Arc::new(config)));
+		self
+	}
+
+	/// Offers the `permessage-deflate` extension during the HTTP handshake, limiting the LZ77 window of both
+	/// sides to `2^max_window_bits` bytes. Messages are compressed if the server accepts the extension.
+	///
+	/// Disabled by default.
+	///
+	/// # Panics
+	///
+	/// Panics if `max_window_bits` is not within `9..=15`.
+	pub fn with_compression(mut self, max_window_bits: u8) -> Self {
+		assert!((9..=15).contains(&max_window_bits), "max window bits have to be within 9..=15");
+		self.compression = Some(max_window_bits);
+		self
+	}
+
+	/// Connects to the Unix domain socket at `path` instead of a TCP socket.
+	#[cfg(all(unix, feature = "uds"))]
+	pub fn with_unix_socket(mut self, path: impl Into<std::path::PathBuf>, framing: UdsFraming) -> Self {
+		self.unix_socket = Some((path.into(), framing));
+		self
+	}
+
+	/// Tunnels the connection through `proxy`, which connects to `host` on `port`.
+	///
+	/// By default, no proxy is used.
+	pub fn with_proxy(mut self, proxy: Proxy, port: u16) -> Self {
+		self.proxy = Some((proxy, port));
+		self
+	}
+
 	/// Sets the URL to pass during the HTTP handshake.
 	///
 	/// The default URL is `/`.
@@ -196,6 +642,25 @@ impl<'a> WsTransportClientBuilder<'a> {
 		self
 	}
 
+	/// Sets the `Authorization` header to pass during the HTTP handshake.
+	///
+	/// The header is not passed to another host that the handshake is redirected to.
+	///
+	/// By default, no `Authorization` header is sent.
+	pub fn with_authorization(mut self, authorization: Authorization) -> Self {
+		self.authorization = Some(authorization);
+		self
+	}
+
+	/// Writes the messages from a separate task, which the sender hands them over to through a queue of
+	/// `capacity` messages, such that writing large messages doesn't delay the receiver.
+	///
+	/// By default, the messages are written by the sender.
+	pub fn with_write_queue(mut self, capacity: usize) -> Self {
+		self.write_queue = Some(capacity);
+		self
+	}
+
 	/// Sets the timeout to use when establishing the TCP connection.
 	///
 	/// The default timeout is 10 seconds.
@@ -204,66 +669,356 @@ impl<'a> WsTransportClientBuilder<'a> {
 		self
 	}
 
+	/// Sets the delay before a connection attempt to the next socket address is started while the earlier
+	/// ones are still pending, as in RFC 8305 ("Happy Eyeballs").
+	///
+	/// The socket addresses are tried alternating between IPv6 and IPv4, starting with the family of the first
+	/// address, and the first connection established wins. An attempt that fails starts the next one right
+	/// away.
+	///
+	/// The default delay is 250 milliseconds.
+	pub fn with_attempt_delay(mut self, delay: Duration) -> Self {
+		self.attempt_delay = delay;
+		self
+	}
+
+	/// Converts the builder into one that doesn't borrow its handshake URL and `Origin` header.
+	pub fn into_owned(self) -> WsTransportClientBuilder<'static> {
+		WsTransportClientBuilder {
+			sockaddrs: self.sockaddrs,
+			host: self.host,
+			mode: self.mode,
+			handshake_url: Cow::Owned(self.handshake_url.into_owned()),
+			timeout: self.timeout,
+			attempt_delay: self.attempt_delay,
+			origin: self.origin.map(|origin| Cow::Owned(origin.into_owned())),
+			authorization: self.authorization,
+			max_request_body_size: self.max_request_body_size,
+			max_response_size: self.max_response_size,
+			tls_session_resumption: self.tls_session_resumption,
+			tls_config: self.tls_config,
+			compression: self.compression,
+			proxy: self.proxy,
+			max_redirections: self.max_redirections,
+			write_queue: self.write_queue,
+			raw_tap: self.raw_tap,
+			codecs: self.codecs,
+			redactor: self.redactor,
+			#[cfg(all(unix, feature = "uds"))]
+			unix_socket: self.unix_socket,
+		}
+	}
+
 	/// Try establish the connection.
-	pub async fn build(self) -> Result<(Sender, Receiver), WsHandshakeError> {
-		for sockaddr in &self.sockaddrs {
-			match self.try_connect(*sockaddr).await {
-				Ok(res) => return Ok(res),
-				Err(e) => {
+	///
+	/// Redirects are followed up to [`WsTransportClientBuilder::max_redirections`] times.
+	pub async fn build(mut self) -> Result<(Sender, Receiver), WsHandshakeError> {
+		let mut redirections = 0;
+		loop {
+			match self.connect().await {
+				Err(WsHandshakeError::Connect(WsNewError::Redirected { location, .. }))
+					if redirections < self.max_redirections =>
+				{
+					redirections += 1;
+					log::debug!("Following redirect {} to: {}", redirections, location);
+					self.redirect(&location)?;
+				}
+				res => return res.map(|(sender, receiver)| self.split(sender, receiver)),
+			}
+		}
+	}
+
+	async fn connect(&self) -> Result<(Sender, Receiver), WsHandshakeError> {
+		#[cfg(all(unix, feature = "uds"))]
+		if let Some((path, framing)) = self.unix_socket.as_ref() {
+			return self.try_connect_unix(path, *framing).await.map_err(WsHandshakeError::Connect);
+		}
+		if let Some((proxy, port)) = self.proxy.as_ref() {
+			return self.try_connect_through_proxy(proxy, *port).await.map_err(WsHandshakeError::Connect);
+		}
+		let mut sockaddrs = interleave_families(&self.sockaddrs).into_iter();
+		let mut attempts = stream::FuturesUnordered::new();
+		loop {
+			if attempts.is_empty() {
+				match sockaddrs.next() {
+					Some(sockaddr) => attempts.push(self.attempt(sockaddr)),
+					None => return Err(WsHandshakeError::NoAddressFound),
+				}
+			}
+			let delay = async_std::task::sleep(self.attempt_delay);
+			futures::pin_mut!(delay);
+			let attempted = match future::select(attempts.next(), delay).await {
+				Either::Left((attempted, _)) => attempted,
+				Either::Right(_) => None,
+			};
+			match attempted {
+				Some((_, Ok(res))) => return Ok(res),
+				Some((_, Err(e @ WsNewError::Redirected { .. }))) => return Err(WsHandshakeError::Connect(e)),
+				Some((sockaddr, Err(e))) => {
 					log::debug!("Failed to connect to sockaddr: {:?} with err: {:?}", sockaddr, e);
 				}
+				None => (),
+			}
+			if let Some(sockaddr) = sockaddrs.next() {
+				attempts.push(self.attempt(sockaddr));
 			}
 		}
-		Err(WsHandshakeError::NoAddressFound)
 	}
 
-	async fn try_connect(&self, sockaddr: SocketAddr) -> Result<(Sender, Receiver), WsNewError> {
-		// Try establish the TCP connection.
-		let tcp_stream = {
-			let socket = TcpStream::connect(sockaddr);
-			let timeout = async_std::task::sleep(self.timeout);
-			futures::pin_mut!(socket, timeout);
-			match future::select(socket, timeout).await {
-				future::Either::Left((socket, _)) => {
-					let socket = socket?;
-					if let Err(err) = socket.set_nodelay(true) {
-						log::warn!("set nodelay failed: {:?}", err);
+	async fn attempt(&self, sockaddr: SocketAddr) -> (SocketAddr, Result<(Sender, Receiver), WsNewError>) {
+		(sockaddr, self.try_connect(sockaddr).await)
+	}
+
+	/// Targets the server at `location`, an absolute URL or a path relative to the handshake URL.
+	///
+	/// `http` and `https` URLs are followed as `ws` and `wss` URLs.
+	fn redirect(&mut self, location: &str) -> Result<(), WsHandshakeError> {
+		let invalid = |e: url::ParseError| WsHandshakeError::Url(format!("Invalid redirect: {}", e).into());
+		let url = match url::Url::parse(location) {
+			Ok(mut url) => {
+				let scheme = match url.scheme() {
+					"http" => Some("ws"),
+					"https" => Some("wss"),
+					_ => None,
+				};
+				if let Some(scheme) = scheme {
+					url.set_scheme(scheme).expect("Switching between special schemes is valid; qed");
+				}
+				if url.host_str() != Some(self.host.as_str()) && self.authorization.take().is_some() {
+					log::debug!("Not passing the Authorization header to the host redirected to");
+				}
+				match self.proxy.as_mut() {
+					Some((_, port)) => {
+						let (host, new_port, mode) = parse_url_unresolved(url.as_str())?;
+						*port = new_port;
+						self.host = host;
+						self.mode = mode;
 					}
-					match self.mode {
-						Mode::Plain => TlsOrPlain::Plain(socket),
-						Mode::Tls => {
-							let connector = async_tls::TlsConnector::default();
-							let dns_name = webpki::DNSNameRef::try_from_ascii_str(self.host.as_str())?;
-							let tls_stream = connector.connect(&dns_name.to_owned(), socket).await?;
-							TlsOrPlain::Tls(tls_stream)
-						}
+					None => {
+						let (sockaddrs, host, mode) = parse_url(url.as_str())?;
+						self.sockaddrs = sockaddrs;
+						self.host = host;
+						self.mode = mode;
 					}
 				}
-				future::Either::Right((_, _)) => return Err(WsNewError::Timeout),
+				url
 			}
+			// NOTE: the host is irrelevant, only the path of the handshake URL is resolved.
+			Err(url::ParseError::RelativeUrlWithoutBase) => url::Url::parse("ws://redirect.invalid")
+				.and_then(|base| base.join(&self.handshake_url))
+				.and_then(|current| current.join(location))
+				.map_err(invalid)?,
+			Err(e) => return Err(invalid(e)),
+		};
+		self.handshake_url = match url.query() {
+			Some(query) => format!("{}?{}", url.path(), query).into(),
+			None => url.path().to_owned().into(),
+		};
+		Ok(())
+	}
+
+	/// Try establish the connection over an already established stream.
+	///
+	/// Only the WebSocket handshake is performed, `sockaddrs`, `mode` and `timeout` are not used.
+	pub async fn build_with_stream(self, stream: impl WsStream) -> Result<(Sender, Receiver), WsNewError> {
+		let (sender, receiver) = self.handshake(Box::new(stream)).await?;
+		Ok(self.split(sender, receiver))
+	}
+
+	/// Hands the writes of `sender` over to a writer task if [`WsTransportClientBuilder::write_queue`] is set.
+	///
+	/// The writer task stops once the returned sender is dropped and its queue is written.
+	fn split(&self, sender: Sender, mut receiver: Receiver) -> (Sender, Receiver) {
+		let capacity = match self.write_queue {
+			Some(capacity) => capacity,
+			None => return (sender, receiver),
 		};
+		let (queue_tx, queue_rx) = mpsc::channel(capacity);
+		let (error_tx, error_rx) = oneshot::channel();
+		let queued = Sender {
+			inner: SenderKind::Queued(queue_tx),
+			max_request_size: sender.max_request_size,
+			tap: sender.tap.clone(),
+			codec: sender.codec.clone(),
+			redactor: sender.redactor.clone(),
+		};
+		async_std::task::spawn(write_queued(sender, queue_rx, error_tx));
+		receiver.writer_error = Some(error_rx);
+		(queued, receiver)
+	}
+
+	async fn try_connect(&self, sockaddr: SocketAddr) -> Result<(Sender, Receiver), WsNewError> {
+		// Try establish the TCP connection.
+		let socket = self.within_timeout(TcpStream::connect(sockaddr)).await??;
+		let stream = self.upgrade(socket).await?;
+		self.handshake(Box::new(stream)).await
+	}
+
+	async fn try_connect_through_proxy(&self, proxy: &Proxy, port: u16) -> Result<(Sender, Receiver), WsNewError> {
+		// Establish the TCP connection to the proxy and the tunnel to the server within the timeout.
+		let socket = self
+			.within_timeout(async {
+				let mut socket = TcpStream::connect(proxy.addr()).await?;
+				proxy.connect(&mut socket, self.host.as_str(), port).await?;
+				Ok::<_, WsNewError>(socket)
+			})
+			.await??;
+		let stream = self.upgrade(socket).await?;
+		self.handshake(Box::new(stream)).await
+	}
+
+	#[cfg(all(unix, feature = "uds"))]
+	async fn try_connect_unix(
+		&self,
+		path: &std::path::Path,
+		framing: UdsFraming,
+	) -> Result<(Sender, Receiver), WsNewError> {
+		let socket: Box<dyn WsStream> =
+			Box::new(self.within_timeout(async_std::os::unix::net::UnixStream::connect(path)).await??);
+		match framing {
+			UdsFraming::WebSocket => self.handshake(socket).await,
+			UdsFraming::NewlineDelimited => {
+				let (reader, writer) = socket.split();
+				let receiver =
+					ReceiverKind::Lines(reader, Decoder::new(Framing::NewlineDelimited, self.max_response_size));
+				Ok((
+					Sender {
+						inner: SenderKind::Lines(BufWriter::new(writer)),
+						max_request_size: self.max_request_body_size,
+						tap: self.raw_tap.clone(),
+						codec: None,
+						redactor: self.redactor.clone(),
+					},
+					Receiver {
+						inner: receiver,
+						pong: Arc::new(AtomicBool::new(false)),
+						max_message_size: self.max_response_size,
+						writer_error: None,
+						tap: self.raw_tap.clone(),
+						codec: None,
+						redactor: self.redactor.clone(),
+					},
+				))
+			}
+		}
+	}
+
+	async fn within_timeout<T>(&self, future: impl Future<Output = T>) -> Result<T, WsNewError> {
+		let timeout = async_std::task::sleep(self.timeout);
+		futures::pin_mut!(future, timeout);
+		match future::select(future, timeout).await {
+			future::Either::Left((output, _)) => Ok(output),
+			future::Either::Right((_, _)) => Err(WsNewError::Timeout),
+		}
+	}
 
-		let mut client =
-			WsRawClient::new(BufReader::new(BufWriter::new(tcp_stream)), self.host.as_str(), &self.handshake_url);
+	/// Establish the TLS stream over `socket` in TLS mode.
+	async fn upgrade(&self, socket: TcpStream) -> Result<TlsOrPlain, WsNewError> {
+		if let Err(err) = socket.set_nodelay(true) {
+			log::warn!("set nodelay failed: {:?}", err);
+		}
+		match self.mode {
+			Mode::Plain => Ok(TlsOrPlain::Plain(socket)),
+			Mode::Tls => {
+				let connector = match self.tls_config.as_ref() {
+					Some(config) => TlsConnector::from(config.0.clone()),
+					None => tls_connector(self.tls_session_resumption),
+				};
+				let dns_name = webpki::DNSNameRef::try_from_ascii_str(self.host.as_str())?;
+				let tls_stream = connector.connect(&dns_name.to_owned(), socket).await?;
+				Ok(TlsOrPlain::Tls(tls_stream))
+			}
+		}
+	}
+
+	async fn handshake(&self, stream: Box<dyn WsStream>) -> Result<(Sender, Receiver), WsNewError> {
+		// NOTE: soketto can't pass custom headers, they are appended to the value of the `Host` header.
+		let mut host = self.host.as_str().to_owned();
+		if let Some(authorization) = self.authorization.as_ref() {
+			if authorization.0.contains(|c: char| c.is_ascii_control()) {
+				return Err(WsNewError::Io(io::Error::new(
+					io::ErrorKind::InvalidInput,
+					"invalid Authorization header",
+				)));
+			}
+			host.push_str("\r\nAuthorization: ");
+			host.push_str(&authorization.0);
+		}
+		let mut client = WsRawClient::new(BufReader::new(BufWriter::new(stream)), &host, &self.handshake_url);
 		if let Some(origin) = self.origin.as_ref() {
 			client.set_origin(origin);
 		}
+		if let Some(max_window_bits) = self.compression {
+			let mut deflate = Deflate::new(soketto::Mode::Client);
+			deflate.set_max_client_window_bits(max_window_bits);
+			deflate.set_max_server_window_bits(max_window_bits);
+			client.add_extension(Box::new(deflate));
+		}
+		for codec in &self.codecs.0 {
+			client.add_protocol(codec.subprotocol());
+		}
 
 		// Perform the initial handshake.
-		match client.handshake().await? {
-			ServerResponse::Accepted { .. } => {}
-			ServerResponse::Rejected { status_code } | ServerResponse::Redirect { status_code, .. } => {
-				// TODO: HTTP redirects also lead here
+		let codec = match client.handshake().await? {
+			ServerResponse::Accepted { protocol } => self.codecs.accepted(protocol.as_deref()),
+			ServerResponse::Rejected { status_code } => {
 				return Err(WsNewError::Rejected { status_code });
 			}
-		}
+			ServerResponse::Redirect { status_code, location } => {
+				return Err(WsNewError::Redirected { status_code, location });
+			}
+		};
 
 		// If the handshake succeeded, return.
 		let mut builder = client.into_builder();
-		builder.set_max_message_size(self.max_request_body_size);
+		builder.set_max_message_size(self.max_response_size);
+		builder.set_max_frame_size(self.max_response_size);
 		let (sender, receiver) = builder.finish();
-		Ok((Sender { inner: sender }, Receiver { inner: receiver }))
+		Ok((
+			Sender {
+				inner: SenderKind::Ws(sender),
+				max_request_size: self.max_request_body_size,
+				tap: self.raw_tap.clone(),
+				codec: codec.clone(),
+				redactor: self.redactor.clone(),
+			},
+			Receiver {
+				inner: ReceiverKind::Ws(receiver),
+				pong: Arc::new(AtomicBool::new(false)),
+				max_message_size: self.max_response_size,
+				writer_error: None,
+				tap: self.raw_tap.clone(),
+				codec,
+				redactor: self.redactor.clone(),
+			},
+		))
+	}
+}
+
+/// Returns the connector used to establish TLS streams.
+///
+/// With session resumption enabled, every connection shares the same connector and thus the same
+/// in-memory session cache, so reconnecting to an endpoint we already talked to resumes the earlier
+/// session (by session ID or ticket) instead of doing a full handshake.
+fn tls_connector(session_resumption: bool) -> TlsConnector {
+	static SHARED: OnceCell<TlsConnector> = OnceCell::new();
+
+	if session_resumption {
+		return SHARED.get_or_init(|| tls_config(true).into()).clone();
 	}
+	tls_config(false).into()
+}
+
+fn tls_config(session_resumption: bool) -> Arc<rustls::ClientConfig> {
+	let mut config = rustls::ClientConfig::new();
+	config.root_store.add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+	if session_resumption {
+		config.set_persistence(rustls::ClientSessionMemoryCache::new(TLS_SESSION_CACHE_SIZE));
+	} else {
+		config.set_persistence(Arc::new(rustls::NoClientSessionStorage {}));
+		config.enable_tickets = false;
+	}
+	Arc::new(config)
 }
 
 impl From<io::Error> for WsNewError {
@@ -290,12 +1045,38 @@ impl From<WsNewError> for WsHandshakeError {
 	}
 }
 
+impl From<jsonrpsee_types::error::Error> for WsConnectError {
+	fn from(err: jsonrpsee_types::error::Error) -> Self {
+		WsConnectError::Codec(err.to_string())
+	}
+}
+
 impl From<soketto::connection::Error> for WsConnectError {
 	fn from(err: soketto::connection::Error) -> Self {
 		WsConnectError::Ws(err)
 	}
 }
 
+impl From<WsConnectError> for ShutdownReason {
+	fn from(err: WsConnectError) -> ShutdownReason {
+		match err {
+			WsConnectError::Ws(soketto::connection::Error::Closed) => {
+				// NOTE: `soketto` doesn't expose the close code and reason of the close frame.
+				ShutdownReason::ConnectionClosedByServer { code: None, reason: None }
+			}
+			#[cfg(all(unix, feature = "uds"))]
+			WsConnectError::Io(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+				ShutdownReason::ConnectionClosedByServer { code: None, reason: None }
+			}
+			WsConnectError::ParseError(e) => ShutdownReason::ProtocolViolation(format!("Parse error: {}", e)),
+			WsConnectError::Codec(e) => ShutdownReason::ProtocolViolation(e),
+			WsConnectError::PongTimeout(_) => ShutdownReason::Timeout,
+			WsConnectError::TooLarge => ShutdownReason::MessageTooLarge,
+			e => ShutdownReason::Transport(e.to_string()),
+		}
+	}
+}
+
 /// Helper to parse an URL to a WebSocket address.
 pub fn parse_url(url: impl AsRef<str>) -> Result<(Vec<SocketAddr>, Host, Mode), WsHandshakeError> {
 	let url = url::Url::parse(url.as_ref()).map_err(|e| WsHandshakeError::Url(format!("Invalid URL: {}", e).into()))?;
@@ -310,9 +1091,121 @@ pub fn parse_url(url: impl AsRef<str>) -> Result<(Vec<SocketAddr>, Host, Mode),
 	Ok((sockaddrs, host, mode))
 }
 
+/// Orders `sockaddrs` alternating between IPv6 and IPv4, starting with the family of the first address and
+/// keeping the order within each family.
+fn interleave_families(sockaddrs: &[SocketAddr]) -> Vec<SocketAddr> {
+	let first_is_ipv6 = match sockaddrs.first() {
+		Some(sockaddr) => sockaddr.is_ipv6(),
+		None => return Vec::new(),
+	};
+	let (preferred, other): (Vec<_>, Vec<_>) =
+		sockaddrs.iter().copied().partition(|sockaddr| sockaddr.is_ipv6() == first_is_ipv6);
+	let mut interleaved = Vec::with_capacity(sockaddrs.len());
+	let (mut preferred, mut other) = (preferred.into_iter(), other.into_iter());
+	loop {
+		match (preferred.next(), other.next()) {
+			(None, None) => return interleaved,
+			(first, second) => interleaved.extend(first.into_iter().chain(second)),
+		}
+	}
+}
+
+/// Helper to parse an URL to the host, port and mode of a WebSocket address, without resolving the host.
+pub fn parse_url_unresolved(url: impl AsRef<str>) -> Result<(Host, u16, Mode), WsHandshakeError> {
+	let url = url::Url::parse(url.as_ref()).map_err(|e| WsHandshakeError::Url(format!("Invalid URL: {}", e).into()))?;
+	let mode = match url.scheme() {
+		"ws" => Mode::Plain,
+		"wss" => Mode::Tls,
+		_ => return Err(WsHandshakeError::Url("URL scheme not supported, expects 'ws' or 'wss'".into())),
+	};
+	let host = Host(url.host_str().ok_or_else(|| WsHandshakeError::Url("No host in URL".into()))?.into());
+	let port = url.port_or_known_default().ok_or_else(|| WsHandshakeError::Url("No port in URL".into()))?;
+	Ok((host, port, mode))
+}
+
 #[cfg(test)]
 mod tests {
-	use super::{parse_url, Mode, WsHandshakeError};
+	use super::{
+		interleave_families, parse_url, tls_config, Authorization, Codecs, Host, Mode, WsHandshakeError,
+		WsTransportClientBuilder,
+	};
+	use std::time::{Duration, Instant};
+
+	fn transport_builder(handshake_url: &str) -> WsTransportClientBuilder<'static> {
+		WsTransportClientBuilder {
+			sockaddrs: Vec::new(),
+			host: Host::from("127.0.0.1"),
+			mode: Mode::Plain,
+			handshake_url: handshake_url.to_owned().into(),
+			timeout: Duration::from_secs(10),
+			attempt_delay: Duration::from_millis(250),
+			origin: None,
+			authorization: None,
+			max_request_body_size: 1024,
+			max_response_size: 1024,
+			tls_session_resumption: false,
+			tls_config: None,
+			compression: None,
+			proxy: None,
+			max_redirections: 1,
+			write_queue: None,
+			raw_tap: None,
+			codecs: Codecs::default(),
+			redactor: Default::default(),
+			#[cfg(all(unix, feature = "uds"))]
+			unix_socket: None,
+		}
+	}
+
+	#[test]
+	fn redirect_to_path_is_relative_to_handshake_url() {
+		let mut builder = transport_builder("/api/v1?key=a");
+		builder.redirect("v2?key=b").unwrap();
+		assert_eq!(builder.handshake_url, "/api/v2?key=b");
+		builder.redirect("/other").unwrap();
+		assert_eq!(builder.handshake_url, "/other");
+		assert_eq!(builder.host.as_str(), "127.0.0.1");
+	}
+
+	#[test]
+	fn redirect_to_url_changes_host_and_mode() {
+		let mut builder = transport_builder("/").with_authorization(Authorization::bearer("token"));
+		builder.redirect("ws://127.0.0.1/rpc").unwrap();
+		assert!(builder.authorization.is_some());
+		builder.redirect("https://127.0.0.2:9944/rpc").unwrap();
+		assert!(builder.authorization.is_none());
+		assert_eq!(builder.handshake_url, "/rpc");
+		assert_eq!(builder.host.as_str(), "127.0.0.2");
+		assert_eq!(builder.mode, Mode::Tls);
+		assert_eq!(builder.sockaddrs, vec!["127.0.0.2:9944".parse().unwrap()]);
+		assert!(matches!(builder.redirect("ftp://127.0.0.2/"), Err(WsHandshakeError::Url(_))));
+	}
+
+	#[test]
+	fn sockaddrs_alternate_between_families() {
+		let sockaddrs: Vec<_> = ["[::1]:1", "[::2]:1", "[::3]:1", "127.0.0.1:1", "127.0.0.2:1"]
+			.iter()
+			.map(|sockaddr| sockaddr.parse().unwrap())
+			.collect();
+		let ordered: Vec<_> = interleave_families(&sockaddrs).iter().map(|sockaddr| sockaddr.to_string()).collect();
+		assert_eq!(ordered, ["[::1]:1", "127.0.0.1:1", "[::2]:1", "127.0.0.2:1", "[::3]:1"]);
+	}
+
+	#[tokio::test]
+	async fn unresponsive_address_doesnt_delay_connection() {
+		let server = jsonrpsee_test_utils::types::WebSocketTestServer::with_hardcoded_response(
+			"127.0.0.1:0".parse().unwrap(),
+			String::new(),
+		)
+		.await;
+		let mut builder = transport_builder("/").with_attempt_delay(Duration::from_millis(50));
+		// NOTE: a non-routable address, connecting to it hangs until the timeout or fails.
+		builder.sockaddrs = vec!["10.255.255.1:9944".parse().unwrap(), server.local_addr()];
+
+		let started = Instant::now();
+		assert!(builder.build().await.is_ok());
+		assert!(started.elapsed() < Duration::from_secs(5));
+	}
 
 	#[test]
 	fn ws_works() {
@@ -348,4 +1241,15 @@ mod tests {
 		assert_eq!(host.as_str(), "127.0.0.1");
 		assert_eq!(mode, Mode::Plain);
 	}
+
+	#[test]
+	fn tls_session_resumption_can_be_disabled() {
+		let resumes = |session_resumption| {
+			let config = tls_config(session_resumption);
+			config.session_persistence.put(b"session".to_vec(), b"state".to_vec());
+			config.enable_tickets && config.session_persistence.get(b"session").is_some()
+		};
+		assert!(resumes(true));
+		assert!(!resumes(false));
+	}
 }
@@ -24,6 +24,24 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+//! # jsonrpsee-ws-server
+//!
+//! `jsonrpsee-ws-server` is a [JSON RPC](https://www.jsonrpc.org/specification) WebSocket server library.
+//!
+//! ## Runtime support
+//!
+//! The tasks, timers and sockets of the server run on the runtime selected with one of these features:
+//!
+//! - `tokio1` (default): [`tokio 1.0`](https://docs.rs/tokio/1.2.0/tokio/) (mutually exclusive with `async-std`)
+//! - `async-std`: [`async-std`](https://docs.rs/async-std), without a nested `tokio` runtime (mutually exclusive
+//!   with `tokio1`)
+
+#[cfg(all(feature = "tokio1", feature = "async-std"))]
+compile_error!("feature `tokio1` and `async-std` are mutually exclusive");
+
+#[cfg(not(any(feature = "tokio1", feature = "async-std")))]
+compile_error!("feature `tokio1` or `async-std` must be enabled for this crate");
+
 extern crate alloc;
 
 mod server;
@@ -31,4 +49,17 @@ mod server;
 #[cfg(test)]
 mod tests;
 
-pub use server::{RpcContextModule, RpcModule, Server as WsServer, SubscriptionSink};
+#[cfg(feature = "cbor")]
+pub use jsonrpsee_types::wire::Cbor;
+pub use jsonrpsee_types::wire::{Codec, Json};
+pub use jsonrpsee_utils::server_utils::{
+	FairScheduler, Idempotency, MethodDispatcher, MethodFlags, MethodNormalization, Transformers, Turn,
+	IDEMPOTENCY_KEY_PARAM,
+};
+pub use server::{
+	AuditLog, AuditParams, AuditRecord, AuditSink, AuditWriter, BandwidthLimit, BandwidthLimitConfig, BandwidthPolicy,
+	BufferEvent, BufferWatermarks, BufferWatermarksConfig, CancellationToken, CloseReason, ConnectRequest,
+	ConnectionHandler, ConnectionInfo, ConnectionMemoryStats, Connections, FairSchedulerConfig, HandshakeLimits,
+	HandshakeLimitsConfig, IdempotencyConfig, InvalidConfig, LagPolicy, MethodTable, OnConnect, ReplayBuffer, Route,
+	RpcContextModule, RpcModule, Server as WsServer, ServerConfig, SubscriptionSink, ToSocketAddrs, Topics, Watermark,
+};
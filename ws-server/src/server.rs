@@ -24,58 +24,144 @@
 // IN background_task WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use futures::io::{BufReader, BufWriter};
+use futures::channel::oneshot;
+use futures::future::{self, BoxFuture, Either, FusedFuture, FutureExt};
+use futures::io::{AsyncRead, AsyncWrite, BufReader, BufWriter};
 use jsonrpsee_types::error::Error;
+use jsonrpsee_types::jsonrpc::SUBSCRIPTION_HEARTBEAT_METHOD;
+use jsonrpsee_types::wire::Codec;
 use parking_lot::Mutex;
 use rustc_hash::FxHashMap;
 use serde::Serialize;
-use serde_json::value::to_raw_value;
+use serde_json::value::{to_raw_value, RawValue};
 use soketto::handshake::{server::Response, Server as SokettoServer};
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
+use std::fmt::{self, Write as _};
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::{
-	net::{TcpListener, ToSocketAddrs},
-	sync::mpsc,
-};
-use tokio_stream::{wrappers::TcpListenerStream, StreamExt};
-use tokio_util::compat::TokioAsyncReadCompatExt;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 
-use jsonrpsee_types::v2::error::{METHOD_NOT_FOUND_CODE, METHOD_NOT_FOUND_MSG};
 use jsonrpsee_types::v2::{JsonRpcInvalidRequest, JsonRpcRequest, RpcError, RpcParams, TwoPointZero};
 use jsonrpsee_types::v2::{JsonRpcNotification, JsonRpcNotificationParams};
-use jsonrpsee_utils::server_utils::{send_error, ConnectionId, Methods};
+use jsonrpsee_utils::server_utils::{
+	send_error, ConfigIssue, ConfigReport, ConnectionId, FairScheduler, Idempotency, MethodDispatcher, MethodFlags,
+	MethodNormalization, Methods, Transformers,
+};
 
+mod audit;
+mod cancel;
+mod config;
+mod limits;
 mod module;
+mod pause;
+mod replay;
+mod routes;
+mod runtime;
+mod stats;
+mod tenants;
+mod watermarks;
 
+use audit::ConnectionAudit;
+pub use audit::{AuditLog, AuditParams, AuditRecord, AuditSink, AuditWriter};
+pub use cancel::CancellationToken;
+pub use config::{
+	BandwidthLimitConfig, BufferWatermarksConfig, FairSchedulerConfig, HandshakeLimitsConfig, IdempotencyConfig,
+	InvalidConfig, ServerConfig,
+};
+use limits::{Admission, Bandwidth, PendingHandshake};
+pub use limits::{BandwidthLimit, BandwidthPolicy, HandshakeLimits};
 pub use module::{RpcContextModule, RpcModule};
+use pause::Subscriber;
+use replay::Replay;
+pub use replay::ReplayBuffer;
+use routes::Endpoint;
+pub use routes::Route;
+use runtime::Listener;
+pub use runtime::ToSocketAddrs;
+pub use stats::ConnectionMemoryStats;
+use stats::ConnectionStats;
+use tenants::PendingConnect;
+pub use tenants::{ConnectRequest, MethodTable, OnConnect};
+use watermarks::BufferLevel;
+pub use watermarks::{BufferEvent, BufferWatermarks, Watermark};
 
 type SubscriptionId = u64;
+type Subscribers = Arc<Mutex<FxHashMap<(ConnectionId, SubscriptionId), Subscriber>>>;
 
 #[derive(Clone)]
 pub struct SubscriptionSink {
 	method: &'static str,
-	subscribers: Arc<Mutex<FxHashMap<(ConnectionId, SubscriptionId), mpsc::UnboundedSender<String>>>>,
+	subscribers: Subscribers,
+	/// When a notification was last sent, heartbeats are only sent on quiet subscriptions.
+	last_sent: Arc<Mutex<Instant>>,
 }
 
 impl SubscriptionSink {
+	fn new(method: &'static str, subscribers: Subscribers) -> Self {
+		Self { method, subscribers, last_sent: Arc::new(Mutex::new(Instant::now())) }
+	}
+
 	pub fn send<T>(&mut self, result: &T) -> anyhow::Result<()>
 	where
 		T: Serialize,
 	{
-		let result = to_raw_value(result)?;
+		self.send_raw(&to_raw_value(result)?)
+	}
+
+	/// Send a heartbeat, a notification with the [`SUBSCRIPTION_HEARTBEAT_METHOD`] method and a `null` result,
+	/// to the subscribers whenever nothing was sent to them for `interval`, such that clients can tell quiet
+	/// subscriptions from dead ones.
+	///
+	/// The heartbeats stop when the server is dropped. Must be called from within a runtime of the enabled
+	/// `tokio1` or `async-std` feature.
+	pub fn spawn_heartbeat(&self, interval: Duration) {
+		let subscribers = Arc::downgrade(&self.subscribers);
+		let method = self.method;
+		let last_sent = self.last_sent.clone();
+		runtime::spawn(async move {
+			loop {
+				runtime::sleep(interval).await;
+				match subscribers.upgrade() {
+					Some(subscribers) => SubscriptionSink { method, subscribers, last_sent: last_sent.clone() }
+						.heartbeat_if_quiet(interval),
+					None => return,
+				}
+			}
+		});
+	}
+
+	/// Sends a heartbeat if nothing was sent within `interval`.
+	fn heartbeat_if_quiet(&self, interval: Duration) {
+		if self.last_sent.lock().elapsed() < interval {
+			return;
+		}
+		let null = RawValue::from_string("null".into()).expect("null is valid JSON; qed");
+		if let Err(e) = self.broadcast(SUBSCRIPTION_HEARTBEAT_METHOD, &null) {
+			log::error!("Failed to serialize heartbeat: {:?}", e);
+		}
+	}
+
+	fn send_raw(&mut self, result: &RawValue) -> anyhow::Result<()> {
+		*self.last_sent.lock() = Instant::now();
+		self.broadcast(self.method, result)
+	}
+
+	fn broadcast(&self, method: &str, result: &RawValue) -> anyhow::Result<()> {
+		let mut notif = NotificationTemplate::new(method, result)?;
 
 		let mut errored = Vec::new();
 		let mut subs = self.subscribers.lock();
 
-		for ((conn_id, sub_id), sender) in subs.iter() {
-			let msg = serde_json::to_string(&JsonRpcNotification {
-				jsonrpc: TwoPointZero,
-				method: self.method,
-				params: JsonRpcNotificationParams { subscription: *sub_id, result: &*result },
-			})?;
+		// NOTE: heartbeats are sent to paused subscriptions too, they are still alive.
+		let heartbeat = method == SUBSCRIPTION_HEARTBEAT_METHOD;
+		for ((conn_id, sub_id), subscriber) in subs.iter_mut() {
+			let msg = notif.render(*sub_id).to_owned();
+			let sent = if heartbeat { subscriber.send_now(msg) } else { subscriber.send(msg) };
 
 			// Log broken connections
-			if sender.send(msg).is_err() {
+			if !sent {
 				errored.push((*conn_id, *sub_id));
 			}
 		}
@@ -89,17 +175,220 @@ impl SubscriptionSink {
 	}
 }
 
+/// Registry of named subscription topics, see [`RpcModule::register_topics`].
+///
+/// Values published to a topic are sent to all clients subscribed to it, the subscriptions of closed
+/// connections are removed when publishing.
+///
+/// Every subscriber queues the values that weren't handed to its connection yet, up to
+/// [`Topics::DEFAULT_SUBSCRIBER_CAPACITY`] by default, the values published while the queue is full are
+/// handled according to the [`LagPolicy`], see [`Topics::set_lag_policy`].
+#[derive(Clone)]
+pub struct Topics {
+	method: &'static str,
+	topics: Arc<Mutex<FxHashMap<String, Topic>>>,
+	/// Capacity of the queues of new subscribers and what happens once they're full.
+	lag: Arc<Mutex<(usize, LagPolicy)>>,
+}
+
+/// What happens to a value published to a subscriber whose queue is full, see [`Topics::set_lag_policy`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LagPolicy {
+	/// Drop the value, the subscriber misses it.
+	#[default]
+	DropNewest,
+	/// Remove the subscription, the client isn't notified and receives no further values.
+	Unsubscribe,
+}
+
+/// Subscribers of a topic and the values replayed to new subscribers.
+#[derive(Clone)]
+struct Topic {
+	sink: SubscriptionSink,
+	// NOTE: held while publishing and subscribing such that new subscribers receive every value exactly once,
+	// either replayed or live.
+	replay: Option<Arc<Mutex<Replay>>>,
+}
+
+impl Topic {
+	fn publish<T: Serialize>(&mut self, value: &T) -> anyhow::Result<()> {
+		let value = to_raw_value(value)?;
+		match self.replay.as_ref() {
+			Some(replay) => {
+				let mut replay = replay.lock();
+				self.sink.send_raw(&value)?;
+				replay.record(value);
+				Ok(())
+			}
+			None => self.sink.send_raw(&value),
+		}
+	}
+}
+
+impl Topics {
+	/// Number of values queued per subscriber unless set by [`Topics::set_lag_policy`].
+	pub const DEFAULT_SUBSCRIBER_CAPACITY: usize = 1024;
+
+	/// Queue up to `capacity` values per subscriber, `policy` applies to the values published while the queue
+	/// of a subscriber is full.
+	///
+	/// Only applies to the subscriptions made afterwards, capacities of `0` are treated as `1`.
+	pub fn set_lag_policy(&self, capacity: usize, policy: LagPolicy) {
+		*self.lag.lock() = (capacity.max(1), policy);
+	}
+
+	/// Define a new topic, returns `false` if the topic already exists.
+	pub fn add_topic(&self, topic: impl Into<String>) -> bool {
+		self.insert_topic(topic.into(), None)
+	}
+
+	/// Define a new topic whose last values are sent to new subscribers before the values published after
+	/// they subscribed, returns `false` if the topic already exists.
+	pub fn add_topic_with_replay(&self, topic: impl Into<String>, replay: ReplayBuffer) -> bool {
+		self.insert_topic(topic.into(), Some(Arc::new(Mutex::new(Replay::new(replay)))))
+	}
+
+	fn insert_topic(&self, topic: String, replay: Option<Arc<Mutex<Replay>>>) -> bool {
+		let mut topics = self.topics.lock();
+		if topics.contains_key(&topic) {
+			return false;
+		}
+		let sink = SubscriptionSink::new(self.method, Default::default());
+		topics.insert(topic, Topic { sink, replay });
+		true
+	}
+
+	/// Remove a topic and drop its subscriptions, returns `false` if the topic doesn't exist.
+	pub fn remove_topic(&self, topic: &str) -> bool {
+		self.topics.lock().remove(topic).is_some()
+	}
+
+	/// Returns the names of the defined topics.
+	pub fn topics(&self) -> Vec<String> {
+		self.topics.lock().keys().cloned().collect()
+	}
+
+	/// Returns the number of subscriptions to a topic, `None` if the topic doesn't exist.
+	pub fn subscribers(&self, topic: &str) -> Option<usize> {
+		self.topics.lock().get(topic).map(|topic| topic.sink.subscribers.lock().len())
+	}
+
+	/// Send heartbeats on the quiet topics of these subscriptions, see [`SubscriptionSink::spawn_heartbeat`].
+	///
+	/// Topics added later are covered as well.
+	pub fn spawn_heartbeat(&self, interval: Duration) {
+		let topics = Arc::downgrade(&self.topics);
+		runtime::spawn(async move {
+			loop {
+				runtime::sleep(interval).await;
+				let topics = match topics.upgrade() {
+					Some(topics) => topics,
+					None => return,
+				};
+				// NOTE: the sinks are cloned to not hold the lock of all topics while sending.
+				let sinks: Vec<_> = topics.lock().values().map(|topic| topic.sink.clone()).collect();
+				for sink in sinks {
+					sink.heartbeat_if_quiet(interval);
+				}
+			}
+		});
+	}
+
+	/// Send `value` to all subscribers of `topic`.
+	pub fn publish<T>(&self, topic: &str, value: &T) -> anyhow::Result<()>
+	where
+		T: Serialize,
+	{
+		// NOTE: the topic is cloned to not hold the lock of all topics while sending.
+		let entry = self.topics.lock().get(topic).cloned();
+		match entry {
+			Some(mut entry) => entry.publish(value),
+			None => Err(anyhow::anyhow!("Unknown topic: {}", topic)),
+		}
+	}
+}
+
+/// Subscription notification that is serialized once and shared by all subscribers.
+///
+/// Only the subscription ID differs between the subscribers, so it's spliced into a reused
+/// buffer between the serialized envelope prefix and the serialized result.
+pub(crate) struct NotificationTemplate {
+	buf: String,
+	prefix_len: usize,
+	suffix: String,
+}
+
+impl NotificationTemplate {
+	const SUBSCRIPTION_FIELD: &'static str = r#""subscription":"#;
+
+	pub(crate) fn new(method: &str, result: &RawValue) -> Result<Self, serde_json::Error> {
+		let mut buf = serde_json::to_string(&JsonRpcNotification {
+			jsonrpc: TwoPointZero,
+			method,
+			params: JsonRpcNotificationParams { subscription: 0, result },
+		})?;
+
+		// NOTE: the method name is escaped and `params` is serialized before the result, so the first
+		// unescaped `"subscription":` is the subscription ID field.
+		let prefix_len = buf.find(Self::SUBSCRIPTION_FIELD).expect("Notification has a subscription field; qed")
+			+ Self::SUBSCRIPTION_FIELD.len();
+		let suffix = buf[prefix_len + 1..].to_owned();
+		buf.truncate(prefix_len);
+
+		Ok(Self { buf, prefix_len, suffix })
+	}
+
+	/// Returns the serialized notification for the given subscription ID.
+	pub(crate) fn render(&mut self, sub_id: SubscriptionId) -> &str {
+		self.buf.truncate(self.prefix_len);
+		write!(self.buf, "{}", sub_id).expect("Writing to a String is infallible; qed");
+		self.buf.push_str(&self.suffix);
+		&self.buf
+	}
+}
+
 pub struct Server {
 	root: RpcModule,
-	listener: TcpListener,
+	listener: Listener,
+	connections: Connections,
+	audit: Option<AuditLog>,
+	watermarks: Option<BufferWatermarks>,
+	handshake_limits: HandshakeLimits,
+	method_flags: MethodFlags,
+	method_normalization: MethodNormalization,
+	idempotency: Option<Idempotency>,
+	transformers: Option<Transformers>,
+	dispatcher: Option<Arc<dyn MethodDispatcher>>,
+	on_connect: Option<Arc<dyn OnConnect>>,
+	routes: HashMap<String, Route>,
+	scheduler: Option<FairScheduler>,
+	bandwidth: Option<BandwidthLimit>,
+	codecs: Vec<Arc<dyn Codec>>,
 }
 
 impl Server {
 	/// Create a new WebSocket RPC server, bound to the `addr`.
 	pub async fn new(addr: impl ToSocketAddrs) -> anyhow::Result<Self> {
-		let listener = TcpListener::bind(addr).await?;
+		let listener = Listener::bind(addr).await?;
 
-		Ok(Server { listener, root: RpcModule::new() })
+		Ok(Server {
+			listener,
+			root: RpcModule::new(),
+			connections: Connections::default(),
+			audit: None,
+			watermarks: None,
+			handshake_limits: HandshakeLimits::default(),
+			method_flags: MethodFlags::default(),
+			method_normalization: MethodNormalization::default(),
+			idempotency: None,
+			transformers: None,
+			dispatcher: None,
+			on_connect: None,
+			routes: HashMap::new(),
+			scheduler: None,
+			bandwidth: None,
+			codecs: Vec::new(),
+		})
 	}
 
 	/// Register a new RPC method, which responds with a given callback.
@@ -111,6 +400,16 @@ impl Server {
 		self.root.register_method(method_name, callback)
 	}
 
+	/// Register a new RPC method for the method names matching `pattern`, such as `debug_*`, see
+	/// [`RpcModule::register_wildcard_method`].
+	pub fn register_wildcard_method<F, R>(&mut self, pattern: &'static str, callback: F) -> Result<(), Error>
+	where
+		R: Serialize,
+		F: Fn(&str, RpcParams) -> Result<R, RpcError> + Send + Sync + 'static,
+	{
+		self.root.register_wildcard_method(pattern, callback)
+	}
+
 	/// Register a new RPC subscription, with subscribe and unsubscribe methods.
 	pub fn register_subscription(
 		&mut self,
@@ -120,6 +419,35 @@ impl Server {
 		self.root.register_subscription(subscribe_method_name, unsubscribe_method_name)
 	}
 
+	/// Register a new RPC subscription to named topics, with subscribe and unsubscribe methods.
+	pub fn register_topics(
+		&mut self,
+		subscribe_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+	) -> Result<Topics, Error> {
+		self.root.register_topics(subscribe_method_name, unsubscribe_method_name)
+	}
+
+	/// Let the clients pause and resume their subscriptions, buffering up to `buffer` notifications per paused
+	/// subscription, see [`RpcModule::register_subscription_pausing`].
+	pub fn register_subscription_pausing(&mut self, buffer: usize) -> Result<(), Error> {
+		self.root.register_subscription_pausing(buffer)
+	}
+
+	/// Register a new RPC method that the clients can cancel, see [`RpcModule::register_cancellable_method`].
+	pub fn register_cancellable_method<F, R>(&mut self, method_name: &'static str, callback: F) -> Result<(), Error>
+	where
+		R: Serialize,
+		F: Fn(RpcParams, &CancellationToken) -> Result<R, RpcError> + Send + Sync + 'static,
+	{
+		self.root.register_cancellable_method(method_name, callback)
+	}
+
+	/// Let the clients cancel their calls to the cancellable methods, see [`RpcModule::register_cancellation`].
+	pub fn register_cancellation(&mut self) -> Result<(), Error> {
+		self.root.register_cancellation()
+	}
+
 	/// Register all methods from a module on this server.
 	pub fn register_module(&mut self, module: RpcModule) -> Result<(), Error> {
 		self.root.merge(module)
@@ -130,47 +458,655 @@ impl Server {
 		self.listener.local_addr().map_err(Into::into)
 	}
 
+	/// Returns a handle to enumerate and close the connections of the server.
+	pub fn connections(&self) -> Connections {
+		self.connections.clone()
+	}
+
+	/// Returns a handle to enable and disable the methods of the server at runtime.
+	pub fn method_flags(&self) -> MethodFlags {
+		self.method_flags.clone()
+	}
+
+	/// Enable and disable the methods of the server through `flags`, for example to answer calls to disabled
+	/// methods with a custom error, replacing the handle returned by [`Server::method_flags`].
+	pub fn set_method_flags(&mut self, flags: MethodFlags) {
+		self.method_flags = flags;
+	}
+
+	/// Match the names of the called methods against the names of the registered methods according to
+	/// `normalization`, for example to accept `sayHello` for `say_hello`.
+	///
+	/// Default is [`MethodNormalization::Exact`].
+	pub fn set_method_normalization(&mut self, normalization: MethodNormalization) {
+		self.method_normalization = normalization;
+	}
+
+	/// Replay the responses to the calls retried with the same idempotency key, see [`Idempotency`].
+	pub fn set_idempotency(&mut self, idempotency: Idempotency) {
+		self.idempotency = Some(idempotency);
+	}
+
+	/// Rewrite the params and results of the calls to the registered methods with `transformers`, see
+	/// [`Transformers`].
+	pub fn set_transformers(&mut self, transformers: Transformers) {
+		self.transformers = Some(transformers);
+	}
+
+	/// Route the calls with `dispatcher` instead of the registered methods, which are not served then and
+	/// neither are the [`MethodFlags`] applied.
+	pub fn set_dispatcher(&mut self, dispatcher: impl MethodDispatcher) {
+		self.dispatcher = Some(Arc::new(dispatcher));
+	}
+
+	/// Serve the methods of `route` on the connections to `path`, such as `/admin`, instead of the methods of
+	/// the server.
+	///
+	/// The paths are matched exactly, ignoring the query string. The connections to paths without a route are
+	/// served the methods of the server and the methods selected by [`Server::set_on_connect`] take precedence
+	/// over the routes.
+	pub fn mount(&mut self, path: impl Into<String>, route: Route) -> Result<(), Error> {
+		match self.routes.entry(path.into()) {
+			Entry::Occupied(entry) => Err(Error::PathAlreadyMounted(entry.key().clone())),
+			Entry::Vacant(entry) => {
+				entry.insert(route);
+				Ok(())
+			}
+		}
+	}
+
+	/// Select the methods served on each connection with `on_connect`, the connections for which it returns
+	/// `None` are served the methods of the server.
+	pub fn set_on_connect(&mut self, on_connect: impl OnConnect) {
+		self.on_connect = Some(Arc::new(on_connect));
+	}
+
+	/// Emit one [`AuditRecord`] per call to `log`.
+	pub fn set_audit_log(&mut self, log: AuditLog) {
+		self.audit = Some(log);
+	}
+
+	/// Emit a [`BufferEvent`] when the outbound buffer of a connection crosses one of the `watermarks`.
+	pub fn set_buffer_watermarks(&mut self, watermarks: BufferWatermarks) {
+		self.watermarks = Some(watermarks);
+	}
+
+	/// Drop the connections exceeding `limits` before performing the WebSocket handshake.
+	pub fn set_handshake_limits(&mut self, limits: HandshakeLimits) {
+		self.handshake_limits = limits;
+	}
+
+	/// Limit the bytes per second sent to every connection to `limit`, see [`BandwidthLimit`].
+	pub fn set_bandwidth_limit(&mut self, limit: BandwidthLimit) {
+		self.bandwidth = Some(limit);
+	}
+
+	/// Accept `codec` to serialize the messages of the connections whose clients offer it, see [`Codec`].
+	///
+	/// The first codec offered by the client that is registered is used, the messages are exchanged as JSON if
+	/// the client offers none.
+	pub fn register_codec(&mut self, codec: impl Codec) {
+		self.codecs.push(Arc::new(codec));
+	}
+
+	/// Dispatch the calls of the connections in turn with `scheduler`, such that a connection flooding the server
+	/// doesn't starve the others, see [`FairScheduler`].
+	///
+	/// The weights of the connections are set with the IDs listed by [`Server::connections`]. A connection
+	/// waiting for its turn doesn't read further messages.
+	pub fn set_fair_scheduler(&mut self, scheduler: FairScheduler) {
+		self.scheduler = Some(scheduler);
+	}
+
+	/// Returns the options of the server that are plain data, see [`ServerConfig`].
+	pub fn to_config(&self) -> ServerConfig {
+		let millis = |duration: Duration| duration.as_millis() as u64;
+		ServerConfig {
+			handshake_limits: HandshakeLimitsConfig {
+				max_pending: self.handshake_limits.max_pending,
+				max_per_second: self.handshake_limits.max_per_second,
+				timeout_ms: self.handshake_limits.timeout.map(millis),
+			},
+			bandwidth_limit: self.bandwidth.map(|limit| BandwidthLimitConfig {
+				bytes_per_second: limit.bytes_per_second,
+				burst: Some(limit.burst),
+				policy: limit.policy,
+			}),
+			buffer_watermarks: self
+				.watermarks
+				.as_ref()
+				.map(|watermarks| BufferWatermarksConfig { high: watermarks.high, low: watermarks.low }),
+			method_normalization: self.method_normalization,
+			idempotency: self.idempotency.as_ref().map(|idempotency| {
+				let mut methods: Vec<_> = idempotency.methods().map(String::from).collect();
+				methods.sort();
+				IdempotencyConfig { methods, ttl_ms: millis(idempotency.ttl()) }
+			}),
+			fair_scheduler: self.scheduler.as_ref().map(|scheduler| FairSchedulerConfig {
+				max_concurrent: scheduler.max_concurrent(),
+				default_weight: Some(scheduler.default_weight()),
+			}),
+		}
+	}
+
+	/// Sets the options of `config`, replacing those set before, see [`ServerConfig`].
+	///
+	/// Fails without changing the server if an option is rejected, such as a bandwidth limit of `0` bytes per
+	/// second, or buffer watermarks while no callback was set with [`Server::set_buffer_watermarks`].
+	pub fn set_config(&mut self, config: ServerConfig) -> Result<(), InvalidConfig> {
+		// NOTE: destructured without `..` such that new options can't be left out.
+		let ServerConfig {
+			handshake_limits,
+			bandwidth_limit,
+			buffer_watermarks,
+			method_normalization,
+			idempotency,
+			fair_scheduler,
+		} = config;
+
+		if matches!(bandwidth_limit, Some(limit) if limit.bytes_per_second == 0) {
+			return Err(InvalidConfig {
+				option: "bandwidth_limit",
+				reason: "bytes_per_second has to be greater than 0",
+			});
+		}
+		let watermarks = match (buffer_watermarks, self.watermarks.as_ref()) {
+			(Some(levels), _) if levels.low > levels.high => {
+				return Err(InvalidConfig { option: "buffer_watermarks", reason: "low is greater than high" });
+			}
+			(Some(levels), Some(watermarks)) => Some(watermarks.with_levels(levels.high, levels.low)),
+			(Some(_), None) => {
+				return Err(InvalidConfig {
+					option: "buffer_watermarks",
+					reason: "the callback has to be set with `set_buffer_watermarks` first",
+				});
+			}
+			(None, _) => None,
+		};
+		if matches!(fair_scheduler, Some(scheduler) if scheduler.max_concurrent == 0) {
+			return Err(InvalidConfig { option: "fair_scheduler", reason: "max_concurrent has to be greater than 0" });
+		}
+
+		self.handshake_limits = HandshakeLimits {
+			max_pending: handshake_limits.max_pending,
+			max_per_second: handshake_limits.max_per_second,
+			timeout: handshake_limits.timeout_ms.map(Duration::from_millis),
+		};
+		self.bandwidth = bandwidth_limit.map(|limit| {
+			let bandwidth = BandwidthLimit::new(limit.bytes_per_second).policy(limit.policy);
+			match limit.burst {
+				Some(burst) => bandwidth.burst(burst),
+				None => bandwidth,
+			}
+		});
+		self.watermarks = watermarks;
+		self.method_normalization = method_normalization;
+		self.idempotency = idempotency
+			.map(|idempotency| Idempotency::new(idempotency.methods, Duration::from_millis(idempotency.ttl_ms)));
+		self.scheduler = fair_scheduler.map(|config| {
+			let scheduler = FairScheduler::new(config.max_concurrent);
+			match config.default_weight {
+				Some(weight) => scheduler.with_default_weight(weight),
+				None => scheduler,
+			}
+		});
+		Ok(())
+	}
+
+	/// Checks the configuration of the server before starting it: the handshake limits, the methods whose
+	/// names collide once normalized, the method flags, idempotency settings and transformers that refer to
+	/// unregistered methods, and the idempotency settings and transformers that select cancellable methods.
+	///
+	/// Duplicate methods and subscriptions across the registered modules are rejected when registering them
+	/// already.
+	pub fn validate(&self) -> ConfigReport {
+		let mut report = ConfigReport::default();
+		self.handshake_limits.check(&mut report);
+		if self.dispatcher.is_some() {
+			if self.root.method_names().next().is_some() {
+				report.issues.push(ConfigIssue::MethodsReplacedByDispatcher);
+			}
+			return report;
+		}
+		report.check_methods(
+			self.root.method_names(),
+			self.method_normalization,
+			&self.method_flags,
+			self.idempotency.as_ref(),
+			self.transformers.as_ref(),
+		);
+		report.check_deferred_methods(
+			self.root.cancellable_method_names(),
+			self.idempotency.as_ref(),
+			self.transformers.as_ref(),
+		);
+		report
+	}
+
 	/// Start responding to connections requests. This will block current thread until the server is stopped.
 	pub async fn start(self) {
-		let mut incoming = TcpListenerStream::new(self.listener);
-		let methods = Arc::new(self.root.into_methods());
-		let mut id = 0;
-
-		while let Some(socket) = incoming.next().await {
-			if let Ok(socket) = socket {
-				socket.set_nodelay(true).unwrap();
+		let dispatcher: Arc<dyn MethodDispatcher> = match self.dispatcher {
+			Some(dispatcher) => dispatcher,
+			None => {
+				let (mut methods, wildcards) = self.root.into_parts();
+				if let Some(transformers) = self.transformers.as_ref() {
+					methods = transformers.wrap(methods);
+				}
+				methods = self.method_flags.wrap(methods);
+				if let Some(idempotency) = self.idempotency.as_ref() {
+					methods = idempotency.wrap(methods);
+				}
+				match self.method_normalization {
+					MethodNormalization::Exact => Arc::new(wildcards.wrap(methods)),
+					normalization => Arc::new(wildcards.wrap(normalization.wrap(methods))),
+				}
+			}
+		};
+		let mut handler = ConnectionHandler::with_connections(dispatcher, self.connections);
+		handler.on_connect = self.on_connect;
+		handler.routes = Arc::new(self.routes.into_iter().map(|(path, route)| (path, route.into_endpoint())).collect());
+		handler.audit = self.audit;
+		handler.watermarks = self.watermarks;
+		handler.admission = Arc::new(Admission::new(self.handshake_limits));
+		handler.scheduler = self.scheduler;
+		handler.bandwidth = self.bandwidth;
+		handler.codecs = Arc::new(self.codecs);
 
-				let methods = methods.clone();
+		loop {
+			if let Ok((socket, remote_addr)) = self.listener.accept().await {
+				// NOTE: excess connections are dropped before spawning a task for them.
+				let pending = match handler.admission.try_admit() {
+					Some(pending) => pending,
+					None => {
+						log::debug!("Dropped connection from {:?}: handshake limits exceeded", remote_addr);
+						continue;
+					}
+				};
 
-				tokio::spawn(async move { background_task(socket, methods, id).await });
+				let handler = handler.clone();
 
-				id += 1;
+				runtime::spawn(async move {
+					let _ = handler.serve(socket, remote_addr, pending).await;
+				});
 			}
 		}
 	}
 }
 
-async fn background_task(socket: tokio::net::TcpStream, methods: Arc<Methods>, id: ConnectionId) -> anyhow::Result<()> {
+/// Serves the registered methods over connections that are accepted elsewhere, for example by a custom
+/// TLS acceptor or as substreams of a multiplexed connection.
+#[derive(Clone)]
+pub struct ConnectionHandler {
+	dispatcher: Arc<dyn MethodDispatcher>,
+	next_id: Arc<AtomicUsize>,
+	connections: Connections,
+	on_connect: Option<Arc<dyn OnConnect>>,
+	routes: Arc<HashMap<String, Endpoint>>,
+	audit: Option<AuditLog>,
+	watermarks: Option<BufferWatermarks>,
+	admission: Arc<Admission>,
+	scheduler: Option<FairScheduler>,
+	bandwidth: Option<BandwidthLimit>,
+	codecs: Arc<Vec<Arc<dyn Codec>>>,
+}
+
+impl ConnectionHandler {
+	/// Create a new connection handler serving the methods of `module`.
+	pub fn new(module: RpcModule) -> Self {
+		let (methods, wildcards) = module.into_parts();
+		Self::with_connections(Arc::new(wildcards.wrap(methods)), Connections::default())
+	}
+
+	/// Create a new connection handler routing the calls with `dispatcher`.
+	pub fn from_dispatcher(dispatcher: impl MethodDispatcher) -> Self {
+		Self::with_connections(Arc::new(dispatcher), Connections::default())
+	}
+
+	fn with_connections(dispatcher: Arc<dyn MethodDispatcher>, connections: Connections) -> Self {
+		Self {
+			dispatcher,
+			next_id: Arc::new(AtomicUsize::new(0)),
+			connections,
+			on_connect: None,
+			routes: Arc::new(HashMap::new()),
+			audit: None,
+			watermarks: None,
+			admission: Arc::new(Admission::new(HandshakeLimits::default())),
+			scheduler: None,
+			bandwidth: None,
+			codecs: Arc::new(Vec::new()),
+		}
+	}
+
+	/// Select the methods served on each connection with `on_connect`, the connections for which it returns
+	/// `None` are served the methods of the handler.
+	pub fn with_on_connect(mut self, on_connect: impl OnConnect) -> Self {
+		self.on_connect = Some(Arc::new(on_connect));
+		self
+	}
+
+	/// Emit one [`AuditRecord`] per call to `log`.
+	pub fn with_audit_log(mut self, log: AuditLog) -> Self {
+		self.audit = Some(log);
+		self
+	}
+
+	/// Emit a [`BufferEvent`] when the outbound buffer of a connection crosses one of the `watermarks`.
+	pub fn with_buffer_watermarks(mut self, watermarks: BufferWatermarks) -> Self {
+		self.watermarks = Some(watermarks);
+		self
+	}
+
+	/// Reject the connections exceeding `limits` before performing the WebSocket handshake.
+	pub fn with_handshake_limits(mut self, limits: HandshakeLimits) -> Self {
+		self.admission = Arc::new(Admission::new(limits));
+		self
+	}
+
+	/// Limit the bytes per second sent to every connection to `limit`, see [`BandwidthLimit`].
+	pub fn with_bandwidth_limit(mut self, limit: BandwidthLimit) -> Self {
+		self.bandwidth = Some(limit);
+		self
+	}
+
+	/// Accept `codec` to serialize the messages, see [`Server::register_codec`].
+	pub fn with_codec(mut self, codec: impl Codec) -> Self {
+		Arc::make_mut(&mut self.codecs).push(Arc::new(codec));
+		self
+	}
+
+	/// Dispatch the calls of the connections in turn with `scheduler`, see [`Server::set_fair_scheduler`].
+	pub fn with_fair_scheduler(mut self, scheduler: FairScheduler) -> Self {
+		self.scheduler = Some(scheduler);
+		self
+	}
+
+	/// Returns a handle to enumerate and close the connections served by this handler.
+	pub fn connections(&self) -> Connections {
+		self.connections.clone()
+	}
+
+	/// Perform the WebSocket handshake on `stream` and respond to requests until the connection is closed.
+	///
+	/// `remote_info` describes the remote peer and is only used for logging.
+	///
+	/// Returns an error without reading from `stream` if the connection exceeds the
+	/// [`HandshakeLimits`] of the handler.
+	pub async fn serve_connection<S>(&self, stream: S, remote_info: impl fmt::Debug) -> anyhow::Result<()>
+	where
+		S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+	{
+		match self.admission.try_admit() {
+			Some(pending) => self.serve(stream, remote_info, pending).await,
+			None => Err(anyhow::anyhow!("Handshake limits exceeded")),
+		}
+	}
+
+	async fn serve<S>(&self, stream: S, remote_info: impl fmt::Debug, pending: PendingHandshake) -> anyhow::Result<()>
+	where
+		S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+	{
+		// NOTE: `fetch_add` wraps on overflow which is intended.
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+		log::debug!("Accepted connection {} from {:?}", id, remote_info);
+		let remote = format!("{:?}", remote_info);
+		let audit = self.audit.clone().map(|log| Arc::new(ConnectionAudit::new(log, id, remote.clone())));
+		let (close_rx, stats) = self.connections.insert(id, remote.clone());
+		let ctx = ConnectionContext {
+			id,
+			dispatcher: self.dispatcher.clone(),
+			scheduler: self.scheduler.clone(),
+			close_rx,
+			audit,
+			level: BufferLevel::new(self.watermarks.clone(), stats, id),
+			bandwidth: self.bandwidth.map(Bandwidth::new),
+			pending,
+			handshake_timeout: self.admission.timeout(),
+			connect: PendingConnect { on_connect: self.on_connect.as_deref(), routes: &self.routes, remote: &remote },
+			codecs: &self.codecs,
+		};
+		let res = background_task(stream, ctx).await;
+		self.connections.remove(id);
+		if let Some(scheduler) = self.scheduler.as_ref() {
+			scheduler.remove(id);
+		}
+		log::debug!("Connection {} from {:?} closed: {:?}", id, remote_info, res);
+		res
+	}
+}
+
+/// Handle to enumerate and close the open connections of a server.
+#[derive(Clone, Default)]
+pub struct Connections {
+	inner: Arc<Mutex<FxHashMap<ConnectionId, ConnectionEntry>>>,
+}
+
+struct ConnectionEntry {
+	remote: String,
+	close: oneshot::Sender<CloseReason>,
+	stats: Arc<ConnectionStats>,
+}
+
+/// Information about an open connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionInfo {
+	/// Connection ID.
+	pub id: ConnectionId,
+	/// Description of the remote peer.
+	pub remote: String,
+}
+
+/// Reason why the server closed a connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CloseReason {
+	/// Close code.
+	pub code: u16,
+	/// Close reason.
+	pub reason: String,
+}
+
+impl Connections {
+	/// Returns the open connections.
+	pub fn list(&self) -> Vec<ConnectionInfo> {
+		self.inner.lock().iter().map(|(id, entry)| ConnectionInfo { id: *id, remote: entry.remote.clone() }).collect()
+	}
+
+	/// Returns the subscriptions and the outbound buffer of the open connections, to diagnose leaks and
+	/// size the buffers of long-running servers.
+	pub fn memory_stats(&self) -> Vec<ConnectionMemoryStats> {
+		self.inner.lock().iter().map(|(id, entry)| entry.stats.snapshot(*id)).collect()
+	}
+
+	/// Close the connection `id`, returns `false` if the connection is not open.
+	///
+	/// Subscriptions of the connection are dropped the next time a notification is sent to them.
+	///
+	/// **Note**: `soketto` doesn't support custom close frames thus the peer always receives the close code
+	/// `1000` (normal closure), `code` and `reason` are only logged.
+	pub fn close(&self, id: ConnectionId, code: u16, reason: impl Into<String>) -> bool {
+		match self.inner.lock().remove(&id) {
+			Some(entry) => entry.close.send(CloseReason { code, reason: reason.into() }).is_ok(),
+			None => false,
+		}
+	}
+
+	fn insert(&self, id: ConnectionId, remote: String) -> (oneshot::Receiver<CloseReason>, Arc<ConnectionStats>) {
+		let (close, close_rx) = oneshot::channel();
+		let stats = Arc::new(ConnectionStats::default());
+		self.inner.lock().insert(id, ConnectionEntry { remote, close, stats: stats.clone() });
+		(close_rx, stats)
+	}
+
+	fn remove(&self, id: ConnectionId) {
+		self.inner.lock().remove(&id);
+	}
+}
+
+/// State of a connection served by [`background_task`], from the handshake until it's closed.
+struct ConnectionContext<'a> {
+	/// ID of the connection.
+	id: ConnectionId,
+	/// Dispatcher of the calls, unless the connection selects other methods during the handshake.
+	dispatcher: Arc<dyn MethodDispatcher>,
+	/// Scheduler dispatching the calls of the connections in turn.
+	scheduler: Option<FairScheduler>,
+	/// Receives the reason when the connection is closed through [`Connections::close`].
+	close_rx: oneshot::Receiver<CloseReason>,
+	/// Audit of the calls of the connection.
+	audit: Option<Arc<ConnectionAudit>>,
+	/// Size of the outbound buffer.
+	level: BufferLevel,
+	/// Outbound bandwidth limit.
+	bandwidth: Option<Bandwidth>,
+	/// Counts the connection towards the handshake limits until the handshake completes.
+	pending: PendingHandshake,
+	/// Maximum duration of the handshake.
+	handshake_timeout: Option<Duration>,
+	/// Selects the methods served on the connection during the handshake.
+	connect: PendingConnect<'a>,
+	/// Codecs that the connection can negotiate.
+	codecs: &'a [Arc<dyn Codec>],
+}
+
+async fn background_task<S>(socket: S, ctx: ConnectionContext<'_>) -> anyhow::Result<()>
+where
+	S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+	let ConnectionContext {
+		id,
+		dispatcher,
+		scheduler,
+		mut close_rx,
+		audit,
+		mut level,
+		mut bandwidth,
+		pending,
+		handshake_timeout,
+		connect,
+		codecs,
+	} = ctx;
+
 	// For each incoming background_task we perform a handshake.
-	let mut server = SokettoServer::new(BufReader::new(BufWriter::new(socket.compat())));
+	let mut server = SokettoServer::new(BufReader::new(BufWriter::new(socket)));
+	for codec in codecs {
+		server.add_protocol(codec.subprotocol());
+	}
 
-	let websocket_key = {
+	let handshake = async {
 		let req = server.receive_request().await?;
-		req.into_key()
-	};
+		let admitted = match connect.select(id, req.path()) {
+			Ok(admitted) => admitted,
+			Err(err) => {
+				server.send_response(&Response::Reject { status_code: 503 }).await?;
+				return Err(err);
+			}
+		};
+		// NOTE: only the registered codecs are listed, in the order offered by the client.
+		let codec = req.protocols().next().and_then(|offered| codecs.iter().find(|c| c.subprotocol() == offered));
+		let websocket_key = req.into_key();
 
-	// Here we accept the client unconditionally.
-	let accept = Response::Accept { key: &websocket_key, protocol: None };
-	server.send_response(&accept).await?;
+		// Here we accept the client unconditionally.
+		let accept = Response::Accept { key: &websocket_key, protocol: codec.map(|codec| codec.subprotocol()) };
+		server.send_response(&accept).await?;
+		Ok::<_, anyhow::Error>((admitted, codec.cloned()))
+	};
+	// NOTE: held until the connection is closed, such that it counts towards the limits of its route.
+	let (admitted, codec) = match handshake_timeout {
+		Some(timeout) => {
+			runtime::timeout(timeout, handshake).await.ok_or_else(|| anyhow::anyhow!("Handshake timed out"))??
+		}
+		None => handshake.await?,
+	};
+	drop(pending);
+	let dispatcher = admitted.as_ref().map_or(dispatcher, |admitted| admitted.table.0.clone());
 
 	// And we can finally transition to a websocket background_task.
-	let (mut sender, mut receiver) = server.into_builder().finish();
+	let (sender, mut receiver) = server.into_builder().finish();
 	let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+	level.stats().set_sender(&tx);
+	// NOTE: the connection is closed when this is dropped, i.e. when this function returns.
+	let (_stop_tx, mut stop_rx) = oneshot::channel::<()>();
+	let writer_audit = audit.clone();
+	let writer_codec = codec.clone();
+
+	// NOTE: messages are moved from the channel to `buffered` while a write is in progress, so that the
+	// size of the outbound buffer is known.
+	runtime::spawn(async move {
+		let mut buffered = VecDeque::<String>::new();
+		let mut sender = Some(sender);
+		let mut write: future::Fuse<BoxFuture<_>> = future::Fuse::terminated();
+		// NOTE: set while the connection waits for its bandwidth limit.
+		let mut throttle: future::Fuse<BoxFuture<()>> = future::Fuse::terminated();
+		let mut closed = false;
+
+		loop {
+			if write.is_terminated() && throttle.is_terminated() {
+				let limited = match (buffered.front(), bandwidth.as_mut()) {
+					(Some(response), Some(bandwidth)) => {
+						bandwidth.try_send(response.len()).err().map(|wait| (wait, bandwidth.policy()))
+					}
+					_ => None,
+				};
+				match limited {
+					Some((_, BandwidthPolicy::Close)) => {
+						log::debug!("Closing connection {}: bandwidth limit exceeded", id);
+						break;
+					}
+					Some((wait, BandwidthPolicy::Throttle)) => throttle = runtime::sleep(wait).boxed().fuse(),
+					None => {}
+				}
+			}
+			if write.is_terminated() && throttle.is_terminated() {
+				match buffered.pop_front() {
+					Some(response) => {
+						let mut ws = sender.take().expect("The sender is only taken by the pending write; qed");
+						let codec = writer_codec.clone();
+						write = async move {
+							let len = response.len();
+							let message = match codec {
+								Some(codec) => match encode(&*codec, &response) {
+									Ok(message) => message,
+									Err(err) => {
+										log::warn!("Failed to encode response on connection {}: {:?}", id, err);
+										return (ws, len);
+									}
+								},
+								None => response.into_bytes(),
+							};
+							let _ = ws.send_binary_mut(message).await;
+							let _ = ws.flush().await;
+							(ws, len)
+						}
+						.boxed()
+						.fuse();
+					}
+					None if closed => break,
+					None => {}
+				}
+			}
 
-	tokio::spawn(async move {
-		while let Some(response) = rx.recv().await {
-			let _ = sender.send_binary_mut(response.into_bytes()).await;
-			let _ = sender.flush().await;
+			tokio::select! {
+				response = rx.recv(), if !closed => match response {
+					Some(response) => {
+						if let Some(audit) = writer_audit.as_ref() {
+							audit.responded(&response);
+						}
+						level.push(response.len());
+						buffered.push_back(response);
+					}
+					None => closed = true,
+				},
+				(ws, len) = &mut write, if !write.is_terminated() => {
+					sender = Some(ws);
+					level.pop(len);
+				}
+				_ = &mut throttle, if !throttle.is_terminated() => {}
+				_ = &mut stop_rx => break,
+			}
+		}
+
+		if let Some(mut sender) = sender {
+			let _ = sender.close().await;
 		}
 	});
 
@@ -179,16 +1115,46 @@ async fn background_task(socket: tokio::net::TcpStream, methods: Arc<Methods>, i
 	loop {
 		data.clear();
 
-		receiver.receive_data(&mut data).await?;
+		let received = {
+			let receive = receiver.receive_data(&mut data);
+			futures::pin_mut!(receive);
+			match future::select(receive, &mut close_rx).await {
+				Either::Left((received, _)) => received,
+				Either::Right((reason, _)) => {
+					log::debug!("Closing connection {}: {:?}", id, reason);
+					return Ok(());
+				}
+			}
+		};
+		received?;
+
+		if let Some(codec) = codec.as_ref() {
+			match codec.decode(&data) {
+				Ok(message) => data = serde_json::to_vec(&message).expect("JSON values serialize; qed"),
+				Err(_) => {
+					send_error(None, &tx, -32700, "Parse error");
+					continue;
+				}
+			}
+		}
 
 		match serde_json::from_slice::<JsonRpcRequest>(&data) {
 			Ok(req) => {
+				if let Some(audit) = audit.as_ref() {
+					audit.call(req.id, &req.method, req.params);
+				}
 				let params = RpcParams::new(req.params.map(|params| params.get()));
 
-				if let Some(method) = methods.get(&*req.method) {
-					(method)(req.id, params, &tx, id)?;
-				} else {
-					send_error(req.id, &tx, METHOD_NOT_FOUND_CODE, METHOD_NOT_FOUND_MSG);
+				// NOTE: held while the call is dispatched.
+				let _turn = match scheduler.as_ref() {
+					Some(scheduler) => Some(scheduler.acquire(id).await),
+					None => None,
+				};
+				if let Err(err) = dispatcher.dispatch(&req.method, req.id, params, &tx, id) {
+					if let Some(audit) = audit.as_ref() {
+						audit.failed(req.id, &err);
+					}
+					return Err(err);
 				}
 			}
 			Err(_) => {
@@ -202,3 +1168,9 @@ async fn background_task(socket: tokio::net::TcpStream, methods: Arc<Methods>, i
 		}
 	}
 }
+
+/// Serializes the JSON `response` with `codec`.
+fn encode(codec: &dyn Codec, response: &str) -> Result<Vec<u8>, Error> {
+	let message = serde_json::from_str(response).map_err(Error::ParseError)?;
+	codec.encode(&message)
+}
@@ -0,0 +1,320 @@
+use super::cancel;
+use jsonrpsee_types::redact::{LogRedactor, Redact};
+use jsonrpsee_types::v2::error::{INTERNAL_ERROR_CODE, INVALID_PARAMS_CODE};
+use jsonrpsee_types::v2::RpcError;
+use jsonrpsee_utils::server_utils::ConnectionId;
+use parking_lot::Mutex;
+use rustc_hash::{FxHashMap, FxHasher};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::collections::VecDeque;
+use std::hash::Hasher;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
+
+/// Destination of the [`AuditRecord`]s emitted by the server.
+pub trait AuditSink: Send + Sync + 'static {
+	/// Called once per audited call, after the response was handed to the connection.
+	fn record(&self, record: AuditRecord);
+}
+
+impl<F: Fn(AuditRecord) + Send + Sync + 'static> AuditSink for F {
+	fn record(&self, record: AuditRecord) {
+		self(record)
+	}
+}
+
+impl AuditSink for mpsc::UnboundedSender<AuditRecord> {
+	fn record(&self, record: AuditRecord) {
+		if self.send(record).is_err() {
+			log::warn!("Audit record dropped: receiver closed");
+		}
+	}
+}
+
+/// Sink writing every record as a line of JSON to `W`.
+pub struct AuditWriter<W>(Mutex<W>);
+
+impl<W: Write + Send + 'static> AuditWriter<W> {
+	/// Create a new sink writing to `writer`.
+	pub fn new(writer: W) -> Self {
+		Self(Mutex::new(writer))
+	}
+}
+
+impl<W: Write + Send + 'static> AuditSink for AuditWriter<W> {
+	fn record(&self, record: AuditRecord) {
+		let mut writer = self.0.lock();
+		let res = serde_json::to_writer(&mut *writer, &record)
+			.map_err(Into::into)
+			.and_then(|_| writer.write_all(b"\n"))
+			.and_then(|_| writer.flush());
+		if let Err(err) = res {
+			log::error!("Error writing audit record: {:?}", err);
+		}
+	}
+}
+
+/// One audited call.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AuditRecord {
+	/// When the call was received.
+	pub timestamp: SystemTime,
+	/// Connection the call was received on.
+	pub connection_id: ConnectionId,
+	/// Description of the remote peer.
+	pub peer: String,
+	/// Called method.
+	pub method: String,
+	/// Call parameters, see [`AuditLog::redact_params`].
+	pub params: AuditParams,
+	/// JSON-RPC error code of the response, `None` if the call succeeded.
+	pub code: Option<i32>,
+	/// Time from receiving the call until its response was handed to the connection.
+	pub duration: Duration,
+}
+
+/// Call parameters as they appear in an [`AuditRecord`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditParams {
+	/// The call had no parameters.
+	None,
+	/// Hash of the raw parameters, which is stable across runs but not cryptographically secure.
+	Hash(u64),
+	/// Parameters returned by the redaction function.
+	Redacted(String),
+}
+
+type RedactFn = dyn Fn(&str, &RawValue) -> String + Send + Sync;
+
+/// Configuration of the audit log: where records go and how parameters are recorded.
+#[derive(Clone)]
+pub struct AuditLog {
+	sink: Arc<dyn AuditSink>,
+	redact: Option<Arc<RedactFn>>,
+}
+
+impl AuditLog {
+	/// Create an audit log emitting to `sink`, recording a hash of the parameters.
+	pub fn new(sink: impl AuditSink) -> Self {
+		Self { sink: Arc::new(sink), redact: None }
+	}
+
+	/// Record the parameters returned by `redact`, called with the method name and the raw parameters,
+	/// instead of their hash.
+	pub fn redact_params<F>(mut self, redact: F) -> Self
+	where
+		F: Fn(&str, &RawValue) -> String + Send + Sync + 'static,
+	{
+		self.redact = Some(Arc::new(redact));
+		self
+	}
+
+	/// Record the parameters as JSON masked by `redact` instead of their hash, see
+	/// [`Redaction`](jsonrpsee_types::redact::Redaction) to mask parameters by position or object key.
+	pub fn redaction(self, redact: impl Redact) -> Self {
+		let redactor = LogRedactor::new(redact);
+		self.redact_params(move |method, params| redactor.params(method, &params).to_string())
+	}
+
+	fn params(&self, method: &str, params: Option<&RawValue>) -> AuditParams {
+		match (params, &self.redact) {
+			(None, _) => AuditParams::None,
+			(Some(params), Some(redact)) => AuditParams::Redacted(redact(method, params)),
+			(Some(params), None) => {
+				let mut hasher = FxHasher::default();
+				hasher.write(params.get().as_bytes());
+				AuditParams::Hash(hasher.finish())
+			}
+		}
+	}
+}
+
+/// Max number of calls of a connection tracked until their response, the oldest call is forgotten beyond.
+const MAX_PENDING_CALLS: usize = 1024;
+
+struct PendingCall {
+	timestamp: SystemTime,
+	started: Instant,
+	method: String,
+	params: AuditParams,
+}
+
+/// The parts of an outgoing message needed to match it with its call.
+#[derive(Deserialize)]
+struct ResponseSummary<'a> {
+	#[serde(borrow, default)]
+	method: Option<&'a RawValue>,
+	#[serde(borrow, default)]
+	id: Option<&'a RawValue>,
+	#[serde(default)]
+	error: Option<ErrorSummary>,
+}
+
+#[derive(Deserialize)]
+struct ErrorSummary {
+	code: i32,
+}
+
+/// Calls awaiting their response.
+#[derive(Default)]
+struct PendingCalls {
+	/// Calls by normalized request ID, in the order they were received.
+	calls: FxHashMap<String, VecDeque<PendingCall>>,
+	/// Number of calls in `calls`.
+	len: usize,
+}
+
+impl PendingCalls {
+	fn push(&mut self, key: String, call: PendingCall) {
+		if self.len == MAX_PENDING_CALLS {
+			self.forget_oldest();
+		}
+		self.calls.entry(key).or_default().push_back(call);
+		self.len += 1;
+	}
+
+	fn take(
+		&mut self,
+		key: &str,
+		pop: impl FnOnce(&mut VecDeque<PendingCall>) -> Option<PendingCall>,
+	) -> Option<PendingCall> {
+		let calls = self.calls.get_mut(key)?;
+		let call = pop(calls);
+		if calls.is_empty() {
+			self.calls.remove(key);
+		}
+		if call.is_some() {
+			self.len -= 1;
+		}
+		call
+	}
+
+	/// Forget the call waiting the longest for its response, which was most likely dropped.
+	fn forget_oldest(&mut self) {
+		let oldest = self
+			.calls
+			.iter()
+			.filter_map(|(key, calls)| calls.front().map(|call| (call.started, key)))
+			.min()
+			.map(|(_, key)| key.clone());
+		if let Some(call) = oldest.and_then(|key| self.take(&key, VecDeque::pop_front)) {
+			log::warn!("Audit of call to {} dropped: too many calls awaiting their response", call.method);
+		}
+	}
+}
+
+/// Audit state of a single connection.
+///
+/// Methods respond by pushing to the connection's outgoing channel, so calls are tracked by request ID
+/// until their response goes out. At most [`MAX_PENDING_CALLS`] calls are tracked, such that calls whose response
+/// is never sent don't accumulate.
+pub(crate) struct ConnectionAudit {
+	log: AuditLog,
+	connection_id: ConnectionId,
+	peer: String,
+	pending: Mutex<PendingCalls>,
+}
+
+impl ConnectionAudit {
+	pub(crate) fn new(log: AuditLog, connection_id: ConnectionId, peer: String) -> Self {
+		Self { log, connection_id, peer, pending: Mutex::new(PendingCalls::default()) }
+	}
+
+	/// Start tracking a call, must be called before the call is dispatched.
+	pub(crate) fn call(&self, id: Option<&RawValue>, method: &str, params: Option<&RawValue>) {
+		let call = PendingCall {
+			timestamp: SystemTime::now(),
+			started: Instant::now(),
+			method: method.to_owned(),
+			params: self.log.params(method, params),
+		};
+		self.pending.lock().push(id_key(id), call);
+	}
+
+	/// Record the most recent call with `id` as failed without a response.
+	pub(crate) fn failed(&self, id: Option<&RawValue>, err: &anyhow::Error) {
+		let code = match err.downcast_ref::<RpcError>() {
+			Some(RpcError::InvalidParams) => INVALID_PARAMS_CODE,
+			_ => INTERNAL_ERROR_CODE,
+		};
+		if let Some(call) = self.take(id, VecDeque::pop_back) {
+			self.emit(call, Some(code));
+		}
+	}
+
+	/// Record the call answered by the outgoing message `msg`, if any.
+	pub(crate) fn responded(&self, msg: &str) {
+		let summary = match serde_json::from_str::<ResponseSummary>(msg) {
+			Ok(summary) if summary.method.is_none() => summary,
+			_ => return,
+		};
+		if let Some(call) = self.take(summary.id, VecDeque::pop_front) {
+			self.emit(call, summary.error.map(|e| e.code));
+		}
+	}
+
+	fn take(
+		&self,
+		id: Option<&RawValue>,
+		pop: impl FnOnce(&mut VecDeque<PendingCall>) -> Option<PendingCall>,
+	) -> Option<PendingCall> {
+		self.pending.lock().take(&id_key(id), pop)
+	}
+
+	fn emit(&self, call: PendingCall, code: Option<i32>) {
+		self.log.sink.record(AuditRecord {
+			timestamp: call.timestamp,
+			connection_id: self.connection_id,
+			peer: self.peer.clone(),
+			method: call.method,
+			params: call.params,
+			code,
+			duration: call.started.elapsed(),
+		});
+	}
+}
+
+// Calls without ID are answered with a `null` ID, other IDs are normalized like the IDs of cancelled calls.
+fn id_key(id: Option<&RawValue>) -> String {
+	id.and_then(cancel::id_key).unwrap_or_else(|| "null".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{AuditLog, ConnectionAudit, MAX_PENDING_CALLS};
+	use serde_json::value::RawValue;
+	use tokio::sync::mpsc;
+
+	fn raw(json: &str) -> Box<RawValue> {
+		RawValue::from_string(json.to_owned()).unwrap()
+	}
+
+	#[test]
+	fn responses_match_calls_by_normalized_id() {
+		let (tx, mut rx) = mpsc::unbounded_channel();
+		let audit = ConnectionAudit::new(AuditLog::new(tx), 0, "peer".to_owned());
+		audit.call(Some(&raw(r#"[1, "a"]"#)), "say_hello", None);
+		audit.responded(r#"{"jsonrpc":"2.0","result":"hello","id":[1,"a"]}"#);
+		assert_eq!(rx.try_recv().unwrap().method, "say_hello");
+	}
+
+	#[test]
+	fn calls_awaiting_their_response_are_bounded() {
+		let (tx, mut rx) = mpsc::unbounded_channel();
+		let audit = ConnectionAudit::new(AuditLog::new(tx), 0, "peer".to_owned());
+		for id in 0..=MAX_PENDING_CALLS {
+			audit.call(Some(&raw(&id.to_string())), "say_hello", None);
+		}
+		assert_eq!(audit.pending.lock().len, MAX_PENDING_CALLS);
+
+		// The oldest call was forgotten, the others are still matched.
+		audit.responded(r#"{"jsonrpc":"2.0","result":"hello","id":0}"#);
+		assert!(rx.try_recv().is_err());
+		audit.responded(r#"{"jsonrpc":"2.0","result":"hello","id":1}"#);
+		assert_eq!(rx.try_recv().unwrap().method, "say_hello");
+	}
+}
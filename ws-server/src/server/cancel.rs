@@ -0,0 +1,67 @@
+use jsonrpsee_utils::server_utils::ConnectionId;
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use serde_json::value::RawValue;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Tells the handler of a call to a method registered with [`RpcModule::register_cancellable_method`] whether
+/// the client cancelled the call.
+///
+/// [`RpcModule::register_cancellable_method`]: crate::RpcModule::register_cancellable_method
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+	/// Returns `true` once the client cancelled the call.
+	pub fn is_cancelled(&self) -> bool {
+		self.0.load(Ordering::Relaxed)
+	}
+
+	fn cancel(&self) {
+		self.0.store(true, Ordering::Relaxed);
+	}
+}
+
+/// Calls in progress of a cancellable method, by connection and normalized request ID.
+pub(crate) type Calls = Arc<Mutex<FxHashMap<(ConnectionId, String), CancellationToken>>>;
+
+/// Calls in progress of the cancellable methods of a module, including the ones of the modules merged into it
+/// later, that the clients can cancel, see [`RpcModule::register_cancellation`].
+///
+/// [`RpcModule::register_cancellation`]: crate::RpcModule::register_cancellation
+#[derive(Clone, Default)]
+pub(crate) struct Cancellable(Arc<Mutex<Vec<Calls>>>);
+
+impl Cancellable {
+	pub(crate) fn push(&self, calls: Calls) {
+		self.0.lock().push(calls);
+	}
+
+	pub(crate) fn extend(&self, other: &Cancellable) {
+		let other = other.0.lock().clone();
+		self.0.lock().extend(other);
+	}
+
+	/// Cancels the call with the request ID `id` of `conn`, returns `false` if the call isn't in progress.
+	pub(crate) fn cancel(&self, conn: ConnectionId, id: &RawValue) -> bool {
+		let key = match id_key(id) {
+			Some(id) => (conn, id),
+			None => return false,
+		};
+		let mut found = false;
+		for calls in self.0.lock().iter() {
+			if let Some(token) = calls.lock().get(&key) {
+				token.cancel();
+				found = true;
+			}
+		}
+		found
+	}
+}
+
+/// Returns the request ID `id` without insignificant whitespace, such that the ID of a call matches the ID
+/// sent to cancel it.
+pub(crate) fn id_key(id: &RawValue) -> Option<String> {
+	serde_json::from_str::<serde_json::Value>(id.get()).ok().map(|id| id.to_string())
+}
@@ -0,0 +1,98 @@
+use crate::server::BandwidthPolicy;
+use jsonrpsee_utils::server_utils::MethodNormalization;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Options of a [`Server`](super::Server) as plain data, to load them with any serde format such as TOML, YAML
+/// or environment variables.
+///
+/// The fields are named after the methods of the server, durations are given in milliseconds. Missing fields
+/// take the defaults of the server and unknown fields are rejected. The config is read with
+/// [`Server::to_config`](super::Server::to_config) and applied with [`Server::set_config`](super::Server::set_config).
+///
+/// Options that aren't plain data are set on the server: the methods, routes, codecs, hooks, audit log,
+/// dispatcher, transformers, method flags and the callback of the buffer watermarks.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+	/// See [`Server::set_handshake_limits`](super::Server::set_handshake_limits).
+	pub handshake_limits: HandshakeLimitsConfig,
+	/// See [`Server::set_bandwidth_limit`](super::Server::set_bandwidth_limit).
+	pub bandwidth_limit: Option<BandwidthLimitConfig>,
+	/// See [`Server::set_buffer_watermarks`](super::Server::set_buffer_watermarks).
+	pub buffer_watermarks: Option<BufferWatermarksConfig>,
+	/// See [`Server::set_method_normalization`](super::Server::set_method_normalization).
+	pub method_normalization: MethodNormalization,
+	/// See [`Server::set_idempotency`](super::Server::set_idempotency).
+	pub idempotency: Option<IdempotencyConfig>,
+	/// See [`Server::set_fair_scheduler`](super::Server::set_fair_scheduler).
+	pub fair_scheduler: Option<FairSchedulerConfig>,
+}
+
+/// See [`HandshakeLimits`](crate::HandshakeLimits).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HandshakeLimitsConfig {
+	/// Max number of connections whose handshake is in progress.
+	pub max_pending: Option<usize>,
+	/// Max number of connections per second.
+	pub max_per_second: Option<u32>,
+	/// Max time to complete the handshake.
+	pub timeout_ms: Option<u64>,
+}
+
+/// See [`BandwidthLimit`](crate::BandwidthLimit).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BandwidthLimitConfig {
+	/// Max bytes per second sent to every connection, at least `1`.
+	pub bytes_per_second: u64,
+	/// Max bytes sent at once, defaults to `bytes_per_second`.
+	#[serde(default)]
+	pub burst: Option<u64>,
+	/// What happens to the connections exceeding the limit.
+	#[serde(default)]
+	pub policy: BandwidthPolicy,
+}
+
+/// Levels of the [`BufferWatermarks`](crate::BufferWatermarks), whose callback is set with
+/// [`Server::set_buffer_watermarks`](super::Server::set_buffer_watermarks).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BufferWatermarksConfig {
+	/// High watermark in bytes.
+	pub high: usize,
+	/// Low watermark in bytes, at most `high`.
+	pub low: usize,
+}
+
+/// See [`Idempotency`](crate::Idempotency).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IdempotencyConfig {
+	/// Methods whose calls are deduplicated.
+	pub methods: Vec<String>,
+	/// How long the responses are replayed.
+	pub ttl_ms: u64,
+}
+
+/// See [`FairScheduler`](crate::FairScheduler).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FairSchedulerConfig {
+	/// Max number of calls dispatched at the same time, at least `1`.
+	pub max_concurrent: usize,
+	/// Weight of the connections without a weight of their own, defaults to `1`.
+	#[serde(default)]
+	pub default_weight: Option<u32>,
+}
+
+/// Error when a [`ServerConfig`] holds an option the server rejects.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error("Invalid option `{option}`: {reason}")]
+pub struct InvalidConfig {
+	/// Field of the invalid option.
+	pub option: &'static str,
+	/// Why the option is invalid.
+	pub reason: &'static str,
+}
@@ -0,0 +1,200 @@
+use jsonrpsee_utils::server_utils::ConfigReport;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Limits on the connections that didn't complete the WebSocket handshake yet.
+///
+/// Connections exceeding the limits are dropped right after they are accepted, before anything is read from
+/// them, so that a flood of connection attempts can't starve the established connections.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HandshakeLimits {
+	pub(crate) max_pending: Option<usize>,
+	pub(crate) max_per_second: Option<u32>,
+	pub(crate) timeout: Option<Duration>,
+}
+
+impl HandshakeLimits {
+	/// Create limits that don't limit anything.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Accept at most `max` connections whose handshake is in progress at the same time.
+	///
+	/// Use together with [`HandshakeLimits::timeout`], otherwise peers that never send the handshake hold
+	/// on to their slot until they disconnect.
+	pub fn max_pending(mut self, max: usize) -> Self {
+		self.max_pending = Some(max);
+		self
+	}
+
+	/// Accept at most `max` connections per second.
+	pub fn max_per_second(mut self, max: u32) -> Self {
+		self.max_per_second = Some(max);
+		self
+	}
+
+	/// Drop connections that didn't complete the handshake within `timeout`.
+	pub fn timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = Some(timeout);
+		self
+	}
+
+	/// Reports the limits dropping every connection.
+	pub(crate) fn check(&self, report: &mut ConfigReport) {
+		if self.max_pending == Some(0) {
+			report.conflicting_limits("max pending handshakes is 0, every connection is dropped");
+		}
+		if self.max_per_second == Some(0) {
+			report.conflicting_limits("max connections per second is 0, every connection is dropped");
+		}
+		if self.timeout == Some(Duration::ZERO) {
+			report.conflicting_limits("handshake timeout is 0, every connection is dropped");
+		}
+	}
+}
+
+/// Admits the connections allowed by the [`HandshakeLimits`] of a server.
+#[derive(Debug)]
+pub(crate) struct Admission {
+	limits: HandshakeLimits,
+	pending: Arc<AtomicUsize>,
+	/// Start of the current one second window and the connections admitted in it.
+	window: Mutex<(Instant, u32)>,
+}
+
+impl Admission {
+	pub(crate) fn new(limits: HandshakeLimits) -> Self {
+		Self { limits, pending: Arc::new(AtomicUsize::new(0)), window: Mutex::new((Instant::now(), 0)) }
+	}
+
+	/// Timeout of the handshake.
+	pub(crate) fn timeout(&self) -> Option<Duration> {
+		self.limits.timeout
+	}
+
+	/// Returns `None` if a new connection exceeds the limits, otherwise a guard that holds a pending slot
+	/// until the handshake completed.
+	pub(crate) fn try_admit(&self) -> Option<PendingHandshake> {
+		if let Some(max) = self.limits.max_per_second {
+			let mut window = self.window.lock();
+			let now = Instant::now();
+			if now.duration_since(window.0) >= Duration::from_secs(1) {
+				*window = (now, 0);
+			}
+			if window.1 >= max {
+				return None;
+			}
+			window.1 += 1;
+		}
+
+		if let Some(max) = self.limits.max_pending {
+			if self.pending.fetch_add(1, Ordering::Relaxed) >= max {
+				self.pending.fetch_sub(1, Ordering::Relaxed);
+				return None;
+			}
+			return Some(PendingHandshake(Some(self.pending.clone())));
+		}
+		Some(PendingHandshake(None))
+	}
+}
+
+/// Pending slot of a connection, released when dropped.
+#[derive(Debug)]
+pub(crate) struct PendingHandshake(Option<Arc<AtomicUsize>>);
+
+impl Drop for PendingHandshake {
+	fn drop(&mut self) {
+		if let Some(pending) = self.0.take() {
+			pending.fetch_sub(1, Ordering::Relaxed);
+		}
+	}
+}
+
+/// What happens to a connection that exceeds its [`BandwidthLimit`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BandwidthPolicy {
+	/// Delay the messages until the connection is within the limit again, meanwhile they are buffered.
+	#[default]
+	Throttle,
+	/// Close the connection.
+	Close,
+}
+
+/// Limit on the bytes per second sent to every connection, mainly subscription notifications, to protect the
+/// total egress of servers with many subscribers.
+///
+/// The limit is enforced by a token bucket: up to `burst` bytes can be sent at once, after which the
+/// connection is held to `bytes_per_second`. Messages larger than the burst are sent once the bucket is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BandwidthLimit {
+	pub(crate) bytes_per_second: u64,
+	pub(crate) burst: u64,
+	pub(crate) policy: BandwidthPolicy,
+}
+
+impl BandwidthLimit {
+	/// Send at most `bytes_per_second` to every connection, with a burst of one second of traffic.
+	///
+	/// # Panics
+	///
+	/// Panics if `bytes_per_second` is `0`.
+	pub fn new(bytes_per_second: u64) -> Self {
+		assert!(bytes_per_second > 0, "bytes_per_second has to be greater than 0");
+		Self { bytes_per_second, burst: bytes_per_second, policy: BandwidthPolicy::default() }
+	}
+
+	/// Allow bursts of up to `bytes` sent at once, at least `1`.
+	pub fn burst(mut self, bytes: u64) -> Self {
+		self.burst = bytes.max(1);
+		self
+	}
+
+	/// Handle the connections exceeding the limit according to `policy`.
+	///
+	/// Default is [`BandwidthPolicy::Throttle`].
+	pub fn policy(mut self, policy: BandwidthPolicy) -> Self {
+		self.policy = policy;
+		self
+	}
+}
+
+/// Token bucket of a single connection enforcing a [`BandwidthLimit`].
+#[derive(Debug)]
+pub(crate) struct Bandwidth {
+	limit: BandwidthLimit,
+	/// Bytes that can be sent right away, negative after sending a message larger than the burst.
+	tokens: f64,
+	refilled: Instant,
+}
+
+impl Bandwidth {
+	pub(crate) fn new(limit: BandwidthLimit) -> Self {
+		Self { limit, tokens: limit.burst as f64, refilled: Instant::now() }
+	}
+
+	pub(crate) fn policy(&self) -> BandwidthPolicy {
+		self.limit.policy
+	}
+
+	/// Takes the tokens to send a message of `len` bytes, otherwise returns how long to wait until they are
+	/// available.
+	pub(crate) fn try_send(&mut self, len: usize) -> Result<(), Duration> {
+		let now = Instant::now();
+		let rate = self.limit.bytes_per_second as f64;
+		let burst = self.limit.burst as f64;
+		self.tokens = (self.tokens + now.duration_since(self.refilled).as_secs_f64() * rate).min(burst);
+		self.refilled = now;
+
+		let needed = (len as f64).min(burst);
+		if self.tokens >= needed {
+			self.tokens -= len as f64;
+			Ok(())
+		} else {
+			Err(Duration::from_secs_f64((needed - self.tokens) / rate))
+		}
+	}
+}
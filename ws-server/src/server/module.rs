@@ -1,21 +1,39 @@
-use crate::server::{Methods, RpcError, RpcParams, SubscriptionId, SubscriptionSink};
+use crate::server::cancel::{id_key, Calls, Cancellable, CancellationToken};
+use crate::server::pause::{Pausable, Subscriber, SubscriptionSet};
+use crate::server::runtime;
+use crate::server::{
+	LagPolicy, Methods, NotificationTemplate, RpcError, RpcParams, SubscriptionId, SubscriptionSink, Topics,
+};
 use jsonrpsee_types::error::Error;
+use jsonrpsee_types::jsonrpc::{CANCEL_METHOD, SUBSCRIPTION_PAUSE_METHOD, SUBSCRIPTION_RESUME_METHOD};
+use jsonrpsee_types::v2::error::{INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, INVALID_PARAMS_CODE, INVALID_PARAMS_MSG};
 use jsonrpsee_types::v2::traits::RpcMethod;
-use jsonrpsee_utils::server_utils::send_response;
+use jsonrpsee_utils::server_utils::{send_error, send_response, WildcardMethods};
 use parking_lot::Mutex;
 use rustc_hash::FxHashMap;
 use serde::Serialize;
+use serde_json::value::RawValue;
 use std::sync::Arc;
 
 #[derive(Default)]
 pub struct RpcModule {
 	methods: Methods,
+	wildcards: WildcardMethods,
+	pausable: Pausable,
+	cancellable: Cancellable,
+	cancellable_methods: Vec<&'static str>,
 }
 
 impl RpcModule {
 	/// Instantiate a new `RpcModule`.
 	pub fn new() -> Self {
-		RpcModule { methods: Methods::default() }
+		RpcModule {
+			methods: Methods::default(),
+			wildcards: WildcardMethods::default(),
+			pausable: Pausable::default(),
+			cancellable: Cancellable::default(),
+			cancellable_methods: Vec::new(),
+		}
 	}
 
 	/// Add context for this module, turning it into an `RpcContextModule`.
@@ -67,23 +85,92 @@ impl RpcModule {
 		self.verify_method_name(unsubscribe_method_name)?;
 
 		let subscribers = Arc::new(Mutex::new(FxHashMap::default()));
+		self.pausable.push(SubscriptionSet::Sink(subscribers.clone()));
 
 		{
 			let subscribers = subscribers.clone();
 			self.methods.insert(
 				subscribe_method_name,
 				Box::new(move |id, _, tx, conn| {
-					let sub_id = {
-						const JS_NUM_MASK: SubscriptionId = !0 >> 11;
+					let sub_id = new_subscription_id();
+					subscribers.lock().insert((conn, sub_id), Subscriber::new(tx.clone()));
+
+					send_response(id, tx, sub_id);
+
+					Ok(())
+				}),
+			);
+		}
+
+		{
+			let subscribers = subscribers.clone();
+			self.methods.insert(
+				unsubscribe_method_name,
+				Box::new(move |id, params, tx, conn| {
+					let sub_id = params.one()?;
+
+					subscribers.lock().remove(&(conn, sub_id));
+
+					send_response(id, tx, "Unsubscribed");
+
+					Ok(())
+				}),
+			);
+		}
+
+		Ok(SubscriptionSink::new(subscribe_method_name, subscribers))
+	}
 
-						let sub_id = rand::random::<SubscriptionId>() & JS_NUM_MASK;
+	/// Register a new RPC subscription to named topics, with subscribe and unsubscribe methods.
+	///
+	/// Clients pass the name of the topic as the single parameter of the subscribe method, topics
+	/// are defined and published to through the returned [`Topics`].
+	pub fn register_topics(
+		&mut self,
+		subscribe_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+	) -> Result<Topics, Error> {
+		if subscribe_method_name == unsubscribe_method_name {
+			return Err(Error::SubscriptionNameConflict(subscribe_method_name.into()));
+		}
 
-						subscribers.lock().insert((conn, sub_id), tx.clone());
+		self.verify_method_name(subscribe_method_name)?;
+		self.verify_method_name(unsubscribe_method_name)?;
+
+		let topics = Topics {
+			method: subscribe_method_name,
+			topics: Default::default(),
+			lag: Arc::new(Mutex::new((Topics::DEFAULT_SUBSCRIBER_CAPACITY, LagPolicy::default()))),
+		};
+		self.pausable.push(SubscriptionSet::Topics(topics.topics.clone()));
 
-						sub_id
+		{
+			let topics = topics.clone();
+			self.methods.insert(
+				subscribe_method_name,
+				Box::new(move |id, params, tx, conn| {
+					let topic: String = params.one()?;
+
+					let sub_id = new_subscription_id();
+					let entry = match topics.topics.lock().get(&topic) {
+						Some(entry) => entry.clone(),
+						None => {
+							send_error(id, tx, INVALID_PARAMS_CODE, "Unknown topic");
+							return Ok(());
+						}
 					};
 
+					let mut replay = entry.replay.as_ref().map(|replay| replay.lock());
+					let (capacity, policy) = *topics.lag.lock();
+					let subscriber = Subscriber::bounded(tx.clone(), capacity, policy);
+					entry.sink.subscribers.lock().insert((conn, sub_id), subscriber);
 					send_response(id, tx, sub_id);
+					if let Some(replay) = replay.as_mut() {
+						for value in replay.values() {
+							let mut notif = NotificationTemplate::new(subscribe_method_name, value)?;
+							let _ = tx.send(notif.render(sub_id).to_owned());
+						}
+					}
 
 					Ok(())
 				}),
@@ -91,13 +178,15 @@ impl RpcModule {
 		}
 
 		{
-			let subscribers = subscribers.clone();
+			let topics = topics.clone();
 			self.methods.insert(
 				unsubscribe_method_name,
 				Box::new(move |id, params, tx, conn| {
 					let sub_id = params.one()?;
 
-					subscribers.lock().remove(&(conn, sub_id));
+					for entry in topics.topics.lock().values() {
+						entry.sink.subscribers.lock().remove(&(conn, sub_id));
+					}
 
 					send_response(id, tx, "Unsubscribed");
 
@@ -106,26 +195,186 @@ impl RpcModule {
 			);
 		}
 
-		Ok(SubscriptionSink { method: subscribe_method_name, subscribers })
+		Ok(topics)
 	}
 
-	pub(crate) fn into_methods(self) -> Methods {
-		self.methods
+	/// Let the clients pause and resume their subscriptions with the [`SUBSCRIPTION_PAUSE_METHOD`] and
+	/// [`SUBSCRIPTION_RESUME_METHOD`] methods, such as the subscriptions of a hidden UI.
+	///
+	/// Up to `buffer` notifications are kept while a subscription is paused and sent when it's resumed, the
+	/// oldest are dropped beyond. Covers the subscriptions registered on this module, before or after, and
+	/// on the modules merged into it.
+	pub fn register_subscription_pausing(&mut self, buffer: usize) -> Result<(), Error> {
+		self.verify_method_name(SUBSCRIPTION_PAUSE_METHOD)?;
+		self.verify_method_name(SUBSCRIPTION_RESUME_METHOD)?;
+
+		{
+			let pausable = self.pausable.clone();
+			self.methods.insert(
+				SUBSCRIPTION_PAUSE_METHOD,
+				Box::new(move |id, params, tx, conn| {
+					let sub_id = params.one()?;
+					send_response(id, tx, pausable.pause(conn, sub_id, buffer));
+					Ok(())
+				}),
+			);
+		}
+
+		{
+			let pausable = self.pausable.clone();
+			self.methods.insert(
+				SUBSCRIPTION_RESUME_METHOD,
+				Box::new(move |id, params, tx, conn| {
+					let sub_id = params.one()?;
+					send_response(id, tx, pausable.resume(conn, sub_id));
+					Ok(())
+				}),
+			);
+		}
+
+		Ok(())
+	}
+
+	/// Register a new RPC method that the clients can cancel, which responds with a given callback called with
+	/// a token telling whether the client cancelled the call, see [`RpcModule::register_cancellation`].
+	///
+	/// The callback is called on a thread where blocking is acceptable, such that the cancellations are
+	/// received meanwhile, and is expected to check the token as it works and to return early once cancelled.
+	/// Its result is sent in any case.
+	///
+	/// **Note**: the result is sent after the method returns, thus the method can't be selected by the
+	/// idempotency settings nor the transformers of the server, see [`ConfigIssue::DeferredResponse`].
+	///
+	/// [`ConfigIssue::DeferredResponse`]: jsonrpsee_utils::server_utils::ConfigIssue::DeferredResponse
+	pub fn register_cancellable_method<F, R>(&mut self, method_name: &'static str, callback: F) -> Result<(), Error>
+	where
+		R: Serialize,
+		F: Fn(RpcParams, &CancellationToken) -> Result<R, RpcError> + Send + Sync + 'static,
+	{
+		self.verify_method_name(method_name)?;
+
+		let callback = Arc::new(callback);
+		let calls = Calls::default();
+		self.cancellable.push(calls.clone());
+		self.cancellable_methods.push(method_name);
+		self.methods.insert(
+			method_name,
+			Box::new(move |id, params, tx, conn| {
+				let token = CancellationToken::default();
+				// NOTE: registered before the call is started, such that it can be cancelled right away.
+				let key = id.and_then(id_key).map(|id| (conn, id));
+				if let Some(key) = key.clone() {
+					calls.lock().insert(key, token.clone());
+				}
+				let id = id.map(ToOwned::to_owned);
+				let params: Option<Box<RawValue>> = params.parse().ok();
+				let (callback, calls, tx) = (callback.clone(), calls.clone(), tx.clone());
+				runtime::spawn_blocking(move || {
+					let result = callback(RpcParams::new(params.as_deref().map(RawValue::get)), &token);
+					if let Some(key) = key {
+						calls.lock().remove(&key);
+					}
+					match result {
+						Ok(result) => send_response(id.as_deref(), &tx, result),
+						Err(RpcError::InvalidParams) => {
+							send_error(id.as_deref(), &tx, INVALID_PARAMS_CODE, INVALID_PARAMS_MSG)
+						}
+						Err(RpcError::Unknown) => {
+							send_error(id.as_deref(), &tx, INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG)
+						}
+					}
+				});
+				Ok(())
+			}),
+		);
+
+		Ok(())
+	}
+
+	/// Let the clients cancel their calls to the methods registered with
+	/// [`RpcModule::register_cancellable_method`] with the [`CANCEL_METHOD`] notification.
+	///
+	/// Covers the cancellable methods registered on this module, before or after, and on the modules merged
+	/// into it. Called as a method, it responds `false` if the call isn't in progress.
+	pub fn register_cancellation(&mut self) -> Result<(), Error> {
+		self.verify_method_name(CANCEL_METHOD)?;
+
+		let cancellable = self.cancellable.clone();
+		self.methods.insert(
+			CANCEL_METHOD,
+			Box::new(move |id, params, tx, conn| {
+				let call_id: Box<RawValue> = params.one()?;
+				let cancelled = cancellable.cancel(conn, &call_id);
+				if id.is_some() {
+					send_response(id, tx, cancelled);
+				}
+				Ok(())
+			}),
+		);
+
+		Ok(())
+	}
+
+	/// Register a new RPC method for the method names matching `pattern`, such as `debug_*`, which responds
+	/// with a given callback called with the name of the called method.
+	///
+	/// The pattern matches the names that start with the text before its single trailing `*`, the methods
+	/// registered by name take precedence.
+	pub fn register_wildcard_method<F, R>(&mut self, pattern: &'static str, callback: F) -> Result<(), Error>
+	where
+		R: Serialize,
+		F: Fn(&str, RpcParams) -> Result<R, RpcError> + Send + Sync + 'static,
+	{
+		self.wildcards.insert(
+			pattern,
+			Box::new(move |method, id, params, tx, _| {
+				let result = callback(method, params)?;
+
+				send_response(id, tx, result);
+
+				Ok(())
+			}),
+		)
+	}
+
+	/// Returns the methods registered by name and the methods registered for name patterns.
+	pub(crate) fn into_parts(self) -> (Methods, WildcardMethods) {
+		(self.methods, self.wildcards)
+	}
+
+	/// Returns the names of the methods registered by name.
+	pub(crate) fn method_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+		self.methods.keys().copied()
+	}
+
+	/// Returns the names of the methods registered with [`RpcModule::register_cancellable_method`].
+	pub(crate) fn cancellable_method_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+		self.cancellable_methods.iter().copied()
 	}
 
 	pub(crate) fn merge(&mut self, other: RpcModule) -> Result<(), Error> {
 		for name in other.methods.keys() {
 			self.verify_method_name(name)?;
 		}
+		self.wildcards.merge(other.wildcards)?;
 
 		for (name, callback) in other.methods {
 			self.methods.insert(name, callback);
 		}
+		self.pausable.extend(&other.pausable);
+		self.cancellable.extend(&other.cancellable);
+		self.cancellable_methods.extend(other.cancellable_methods);
 
 		Ok(())
 	}
 }
 
+fn new_subscription_id() -> SubscriptionId {
+	const JS_NUM_MASK: SubscriptionId = !0 >> 11;
+
+	rand::random::<SubscriptionId>() & JS_NUM_MASK
+}
+
 pub struct RpcContextModule<Context> {
 	ctx: Arc<Context>,
 	module: RpcModule,
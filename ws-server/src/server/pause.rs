@@ -0,0 +1,159 @@
+use crate::server::{runtime, LagPolicy, Subscribers, SubscriptionId, Topic};
+use jsonrpsee_utils::server_utils::ConnectionId;
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Subscription of a connection, whose notifications are buffered while it's paused.
+pub(crate) struct Subscriber {
+	tx: Outlet,
+	paused: Option<Paused>,
+}
+
+/// Where the notifications of a subscriber go.
+enum Outlet {
+	/// Straight to the connection.
+	Connection(mpsc::UnboundedSender<String>),
+	/// To a bounded queue forwarded to the connection by a separate task, see [`Subscriber::bounded`].
+	Queue(mpsc::Sender<String>, LagPolicy),
+}
+
+/// Notifications buffered while a subscription is paused, the oldest are dropped beyond `limit`.
+struct Paused {
+	limit: usize,
+	buffered: VecDeque<String>,
+}
+
+impl Subscriber {
+	pub(crate) fn new(tx: mpsc::UnboundedSender<String>) -> Self {
+		Self { tx: Outlet::Connection(tx), paused: None }
+	}
+
+	/// Create a subscriber queueing up to `capacity` notifications that weren't forwarded to the connection yet,
+	/// `policy` applies to the notifications beyond.
+	///
+	/// Must be called from within a runtime of the enabled `tokio1` or `async-std` feature.
+	pub(crate) fn bounded(tx: mpsc::UnboundedSender<String>, capacity: usize, policy: LagPolicy) -> Self {
+		let (queue_tx, mut queue_rx) = mpsc::channel::<String>(capacity.max(1));
+		// NOTE: stops once the subscriber is dropped and its queue is forwarded, or the connection is closed.
+		runtime::spawn(async move {
+			while let Some(msg) = queue_rx.recv().await {
+				if tx.send(msg).is_err() {
+					return;
+				}
+			}
+		});
+		Self { tx: Outlet::Queue(queue_tx, policy), paused: None }
+	}
+
+	/// Sends `msg` to the connection or buffers it while paused, returns `false` if the connection is closed.
+	pub(crate) fn send(&mut self, msg: String) -> bool {
+		match self.paused.as_mut() {
+			Some(paused) => {
+				if paused.limit > 0 {
+					if paused.buffered.len() >= paused.limit {
+						paused.buffered.pop_front();
+					}
+					paused.buffered.push_back(msg);
+				}
+				match &self.tx {
+					Outlet::Connection(tx) => !tx.is_closed(),
+					Outlet::Queue(tx, _) => !tx.is_closed(),
+				}
+			}
+			None => self.send_now(msg),
+		}
+	}
+
+	/// Sends `msg` to the connection even while paused, returns `false` if the connection is closed or the
+	/// subscriber lagged with [`LagPolicy::Unsubscribe`].
+	pub(crate) fn send_now(&self, msg: String) -> bool {
+		match &self.tx {
+			Outlet::Connection(tx) => tx.send(msg).is_ok(),
+			Outlet::Queue(tx, policy) => match tx.try_send(msg) {
+				Ok(()) => true,
+				Err(mpsc::error::TrySendError::Full(_)) => {
+					log::debug!("Subscriber lagging behind, notification queue full: {:?}", policy);
+					*policy == LagPolicy::DropNewest
+				}
+				Err(mpsc::error::TrySendError::Closed(_)) => false,
+			},
+		}
+	}
+
+	fn pause(&mut self, limit: usize) {
+		if self.paused.is_none() {
+			self.paused = Some(Paused { limit, buffered: VecDeque::new() });
+		}
+	}
+
+	fn resume(&mut self) {
+		if let Some(paused) = self.paused.take() {
+			for msg in paused.buffered {
+				let _ = self.send_now(msg);
+			}
+		}
+	}
+}
+
+/// Subscriptions registered on a module, either by [`RpcModule::register_subscription`] or by
+/// [`RpcModule::register_topics`].
+///
+/// [`RpcModule::register_subscription`]: crate::RpcModule::register_subscription
+/// [`RpcModule::register_topics`]: crate::RpcModule::register_topics
+#[derive(Clone)]
+pub(crate) enum SubscriptionSet {
+	Sink(Subscribers),
+	Topics(Arc<Mutex<FxHashMap<String, Topic>>>),
+}
+
+/// All subscriptions of a module, including the ones of the modules merged into it later, that the clients
+/// can pause, see [`RpcModule::register_subscription_pausing`].
+///
+/// [`RpcModule::register_subscription_pausing`]: crate::RpcModule::register_subscription_pausing
+#[derive(Clone, Default)]
+pub(crate) struct Pausable(Arc<Mutex<Vec<SubscriptionSet>>>);
+
+impl Pausable {
+	pub(crate) fn push(&self, set: SubscriptionSet) {
+		self.0.lock().push(set);
+	}
+
+	pub(crate) fn extend(&self, other: &Pausable) {
+		let other = other.0.lock().clone();
+		self.0.lock().extend(other);
+	}
+
+	/// Pauses the subscription `sub_id` of `conn`, buffering up to `limit` notifications, returns `false` if
+	/// the subscription doesn't exist.
+	pub(crate) fn pause(&self, conn: ConnectionId, sub_id: SubscriptionId, limit: usize) -> bool {
+		self.with_subscriber(conn, sub_id, |subscriber| subscriber.pause(limit))
+	}
+
+	/// Resumes the subscription `sub_id` of `conn` and sends its buffered notifications, returns `false` if the
+	/// subscription doesn't exist.
+	pub(crate) fn resume(&self, conn: ConnectionId, sub_id: SubscriptionId) -> bool {
+		self.with_subscriber(conn, sub_id, Subscriber::resume)
+	}
+
+	fn with_subscriber(&self, conn: ConnectionId, sub_id: SubscriptionId, mut f: impl FnMut(&mut Subscriber)) -> bool {
+		let mut found = false;
+		for set in self.0.lock().iter() {
+			let sinks = match set {
+				SubscriptionSet::Sink(subscribers) => vec![subscribers.clone()],
+				SubscriptionSet::Topics(topics) => {
+					topics.lock().values().map(|topic| topic.sink.subscribers.clone()).collect()
+				}
+			};
+			for subscribers in sinks {
+				if let Some(subscriber) = subscribers.lock().get_mut(&(conn, sub_id)) {
+					f(subscriber);
+					found = true;
+				}
+			}
+		}
+		found
+	}
+}
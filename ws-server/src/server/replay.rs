@@ -0,0 +1,63 @@
+use serde_json::value::RawValue;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Bounded buffer of the last values published to a topic, see [`Topics::add_topic_with_replay`].
+///
+/// New subscribers of the topic receive the buffered values before the values published after they
+/// subscribed.
+///
+/// [`Topics::add_topic_with_replay`]: crate::Topics::add_topic_with_replay
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayBuffer {
+	max_items: usize,
+	max_age: Option<Duration>,
+}
+
+impl ReplayBuffer {
+	/// Create a buffer of the last `max_items` values.
+	pub fn new(max_items: usize) -> Self {
+		Self { max_items, max_age: None }
+	}
+
+	/// Only replay the values published within the last `max_age`.
+	pub fn max_age(mut self, max_age: Duration) -> Self {
+		self.max_age = Some(max_age);
+		self
+	}
+}
+
+/// Values buffered according to a [`ReplayBuffer`].
+#[derive(Debug)]
+pub(crate) struct Replay {
+	config: ReplayBuffer,
+	items: VecDeque<(Instant, Box<RawValue>)>,
+}
+
+impl Replay {
+	pub(crate) fn new(config: ReplayBuffer) -> Self {
+		Self { config, items: VecDeque::with_capacity(config.max_items) }
+	}
+
+	/// Buffer a published value, dropping the oldest one if the buffer is full.
+	pub(crate) fn record(&mut self, value: Box<RawValue>) {
+		if self.config.max_items == 0 {
+			return;
+		}
+		if self.items.len() == self.config.max_items {
+			self.items.pop_front();
+		}
+		self.items.push_back((Instant::now(), value));
+	}
+
+	/// Returns the buffered values that aren't expired, oldest first.
+	pub(crate) fn values(&mut self) -> impl Iterator<Item = &RawValue> {
+		if let Some(max_age) = self.config.max_age {
+			let now = Instant::now();
+			while self.items.front().is_some_and(|(at, _)| now.duration_since(*at) > max_age) {
+				self.items.pop_front();
+			}
+		}
+		self.items.iter().map(|(_, value)| &**value)
+	}
+}
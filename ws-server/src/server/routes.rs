@@ -0,0 +1,82 @@
+use crate::server::{MethodTable, RpcModule};
+use jsonrpsee_utils::server_utils::{MethodDispatcher, MethodFlags};
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Methods served on the connections to a path of the server with their own limits, see
+/// [`Server::mount`](crate::WsServer::mount).
+pub struct Route {
+	module: RpcModule,
+	method_flags: MethodFlags,
+	dispatcher: Option<MethodTable>,
+	max_connections: Option<usize>,
+}
+
+impl Route {
+	/// Create a route serving the methods of `module`.
+	pub fn new(module: RpcModule) -> Self {
+		Self { module, method_flags: MethodFlags::default(), dispatcher: None, max_connections: None }
+	}
+
+	/// Create a route routing the calls with `dispatcher`, the [`MethodFlags`] of the route are not applied.
+	pub fn from_dispatcher(dispatcher: impl MethodDispatcher) -> Self {
+		Self { dispatcher: Some(MethodTable::from_dispatcher(dispatcher)), ..Self::new(RpcModule::new()) }
+	}
+
+	/// Enable and disable the methods of the route through `flags`, independently of the methods of the
+	/// server and of the other routes.
+	pub fn method_flags(mut self, flags: MethodFlags) -> Self {
+		self.method_flags = flags;
+		self
+	}
+
+	/// Sets the maximum number of open connections to this route, the handshake of further connections is
+	/// rejected with `503 Service Unavailable`.
+	///
+	/// Default is unlimited.
+	pub fn max_connections(mut self, max: usize) -> Self {
+		self.max_connections = Some(max);
+		self
+	}
+
+	pub(crate) fn into_endpoint(self) -> Endpoint {
+		let table = match self.dispatcher {
+			Some(table) => table,
+			None => {
+				let (methods, wildcards) = self.module.into_parts();
+				MethodTable(Arc::new(wildcards.wrap(self.method_flags.wrap(methods))))
+			}
+		};
+		Endpoint { table, connections: self.max_connections.map(|max| Arc::new(Semaphore::new(max))) }
+	}
+}
+
+/// Methods and limits of a path.
+pub(crate) struct Endpoint {
+	table: MethodTable,
+	connections: Option<Arc<Semaphore>>,
+}
+
+impl Endpoint {
+	/// Returns the methods of the route for a new connection, `None` if the route has too many connections.
+	pub(crate) fn admit(&self) -> Option<Admitted> {
+		let permit = match self.connections.as_ref() {
+			Some(connections) => Some(connections.clone().try_acquire_owned().ok()?),
+			None => None,
+		};
+		Some(Admitted { table: self.table.clone(), _permit: permit })
+	}
+}
+
+/// Methods selected for a connection, the connection counts towards the limits of its route until this
+/// is dropped.
+pub(crate) struct Admitted {
+	pub(crate) table: MethodTable,
+	_permit: Option<OwnedSemaphorePermit>,
+}
+
+impl From<MethodTable> for Admitted {
+	fn from(table: MethodTable) -> Self {
+		Self { table, _permit: None }
+	}
+}
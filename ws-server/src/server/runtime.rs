@@ -0,0 +1,78 @@
+//! Spawning, timers and sockets of the runtime selected with the `tokio1` or `async-std` feature.
+
+use futures::future::{self, Either, Future};
+use futures::io::{AsyncRead, AsyncWrite};
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+#[cfg(feature = "async-std")]
+pub use async_std::net::ToSocketAddrs;
+#[cfg(feature = "tokio1")]
+pub use tokio::net::ToSocketAddrs;
+
+/// Runs `future` in the background.
+pub(crate) fn spawn(future: impl Future<Output = ()> + Send + 'static) {
+	#[cfg(feature = "tokio1")]
+	tokio::spawn(future);
+	#[cfg(feature = "async-std")]
+	async_std::task::spawn(future);
+}
+
+/// Runs `f` on a thread where blocking is acceptable.
+pub(crate) fn spawn_blocking(f: impl FnOnce() + Send + 'static) {
+	#[cfg(feature = "tokio1")]
+	tokio::task::spawn_blocking(f);
+	#[cfg(feature = "async-std")]
+	async_std::task::spawn_blocking(f);
+}
+
+/// Waits for `duration`.
+pub(crate) async fn sleep(duration: Duration) {
+	#[cfg(feature = "tokio1")]
+	tokio::time::sleep(duration).await;
+	#[cfg(feature = "async-std")]
+	async_std::task::sleep(duration).await;
+}
+
+/// Returns the output of `future`, `None` if it didn't complete within `duration`.
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+	futures::pin_mut!(future);
+	let sleep = sleep(duration);
+	futures::pin_mut!(sleep);
+	match future::select(future, sleep).await {
+		Either::Left((output, _)) => Some(output),
+		Either::Right(_) => None,
+	}
+}
+
+/// TCP listener of the runtime.
+pub(crate) struct Listener {
+	#[cfg(feature = "tokio1")]
+	inner: tokio::net::TcpListener,
+	#[cfg(feature = "async-std")]
+	inner: async_std::net::TcpListener,
+}
+
+impl Listener {
+	pub(crate) async fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+		#[cfg(feature = "tokio1")]
+		let inner = tokio::net::TcpListener::bind(addr).await?;
+		#[cfg(feature = "async-std")]
+		let inner = async_std::net::TcpListener::bind(addr).await?;
+		Ok(Self { inner })
+	}
+
+	pub(crate) fn local_addr(&self) -> io::Result<SocketAddr> {
+		self.inner.local_addr()
+	}
+
+	/// Accepts the next connection, with `TCP_NODELAY` set.
+	pub(crate) async fn accept(&self) -> io::Result<(impl AsyncRead + AsyncWrite + Send + Unpin, SocketAddr)> {
+		let (socket, remote_addr) = self.inner.accept().await?;
+		socket.set_nodelay(true)?;
+		#[cfg(feature = "tokio1")]
+		let socket = tokio_util::compat::TokioAsyncReadCompatExt::compat(socket);
+		Ok((socket, remote_addr))
+	}
+}
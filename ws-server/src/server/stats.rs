@@ -0,0 +1,55 @@
+use jsonrpsee_utils::server_utils::ConnectionId;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::mpsc;
+
+/// Memory held by an open connection, see [`Connections::memory_stats`](crate::Connections::memory_stats).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionMemoryStats {
+	/// Connection ID.
+	pub id: ConnectionId,
+	/// Active subscriptions of the connection.
+	pub subscriptions: usize,
+	/// Responses and notifications produced but not yet written to the socket.
+	pub buffered_messages: usize,
+	/// Size of the buffered messages in bytes.
+	pub buffered_bytes: usize,
+}
+
+/// Counters of a connection, updated by its tasks and read by [`Connections`](crate::Connections).
+#[derive(Default)]
+pub(crate) struct ConnectionStats {
+	/// Sender of the responses of the connection, set once the handshake completed.
+	sender: Mutex<Option<mpsc::WeakUnboundedSender<String>>>,
+	buffered_messages: AtomicUsize,
+	buffered_bytes: AtomicUsize,
+}
+
+impl ConnectionStats {
+	pub(crate) fn set_sender(&self, tx: &mpsc::UnboundedSender<String>) {
+		*self.sender.lock() = Some(tx.downgrade());
+	}
+
+	/// A message of `len` bytes was buffered.
+	pub(crate) fn push(&self, len: usize) {
+		self.buffered_messages.fetch_add(1, Ordering::Relaxed);
+		self.buffered_bytes.fetch_add(len, Ordering::Relaxed);
+	}
+
+	/// A message of `len` bytes was written to the socket.
+	pub(crate) fn pop(&self, len: usize) {
+		self.buffered_messages.fetch_sub(1, Ordering::Relaxed);
+		self.buffered_bytes.fetch_sub(len, Ordering::Relaxed);
+	}
+
+	pub(crate) fn snapshot(&self, id: ConnectionId) -> ConnectionMemoryStats {
+		// NOTE: every subscription holds a clone of the sender of the connection.
+		let subscriptions = self.sender.lock().as_ref().map_or(0, |sender| sender.strong_count().saturating_sub(1));
+		ConnectionMemoryStats {
+			id,
+			subscriptions,
+			buffered_messages: self.buffered_messages.load(Ordering::Relaxed),
+			buffered_bytes: self.buffered_bytes.load(Ordering::Relaxed),
+		}
+	}
+}
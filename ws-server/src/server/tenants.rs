@@ -0,0 +1,86 @@
+use crate::server::routes::{Admitted, Endpoint};
+use crate::server::RpcModule;
+use jsonrpsee_utils::server_utils::{ConnectionId, MethodDispatcher};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Handshake request of a connection, passed to the [`OnConnect`] hook.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConnectRequest<'a> {
+	/// Connection ID.
+	pub id: ConnectionId,
+	/// Description of the remote peer.
+	pub remote: &'a str,
+	/// Requested path, including the query string.
+	pub path: &'a str,
+}
+
+/// Methods served on a connection, cheap to clone such that the same table can be selected for many
+/// connections.
+#[derive(Clone)]
+pub struct MethodTable(pub(crate) Arc<dyn MethodDispatcher>);
+
+impl MethodTable {
+	/// Create a table routing the calls with `dispatcher`.
+	pub fn from_dispatcher(dispatcher: impl MethodDispatcher) -> Self {
+		Self(Arc::new(dispatcher))
+	}
+}
+
+impl From<RpcModule> for MethodTable {
+	fn from(module: RpcModule) -> Self {
+		let (methods, wildcards) = module.into_parts();
+		Self(Arc::new(wildcards.wrap(methods)))
+	}
+}
+
+impl fmt::Debug for MethodTable {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("MethodTable")
+	}
+}
+
+/// Hook called once the handshake request of a connection is received, selecting the methods served on
+/// the connection, for example to expose a different API per tenant on the same port.
+pub trait OnConnect: Send + Sync + 'static {
+	/// Returns the methods served on the connection, `None` serves the methods of the server.
+	fn on_connect(&self, request: &ConnectRequest) -> Option<MethodTable>;
+}
+
+impl<F> OnConnect for F
+where
+	F: Fn(&ConnectRequest) -> Option<MethodTable> + Send + Sync + 'static,
+{
+	fn on_connect(&self, request: &ConnectRequest) -> Option<MethodTable> {
+		self(request)
+	}
+}
+
+/// [`OnConnect`] hook and routes of a connection that didn't complete the handshake yet.
+pub(crate) struct PendingConnect<'a> {
+	pub(crate) on_connect: Option<&'a dyn OnConnect>,
+	pub(crate) routes: &'a HashMap<String, Endpoint>,
+	pub(crate) remote: &'a str,
+}
+
+impl PendingConnect<'_> {
+	/// Returns the methods selected for the connection `id` requesting `path` by the hook, or else the
+	/// methods of the route mounted at `path`, `None` if neither selects any methods.
+	///
+	/// Returns an error if the route has too many connections.
+	pub(crate) fn select(&self, id: ConnectionId, path: &str) -> anyhow::Result<Option<Admitted>> {
+		if let Some(table) =
+			self.on_connect.and_then(|hook| hook.on_connect(&ConnectRequest { id, remote: self.remote, path }))
+		{
+			return Ok(Some(table.into()));
+		}
+		let path = path.split('?').next().unwrap_or_default();
+		match self.routes.get(path) {
+			Some(endpoint) => {
+				endpoint.admit().map(Some).ok_or_else(|| anyhow::anyhow!("Too many connections to {}", path))
+			}
+			None => Ok(None),
+		}
+	}
+}
@@ -0,0 +1,115 @@
+use crate::server::stats::ConnectionStats;
+use jsonrpsee_utils::server_utils::ConnectionId;
+use std::sync::Arc;
+
+/// Watermark crossed by the outbound buffer of a connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Watermark {
+	/// The buffer grew to at least the high watermark, producers should pause.
+	High,
+	/// The buffer drained to at most the low watermark after reaching the high watermark, producers
+	/// can resume.
+	Low,
+}
+
+/// Emitted when the outbound buffer of a connection crosses a watermark.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BufferEvent {
+	/// Connection the buffer belongs to.
+	pub connection_id: ConnectionId,
+	/// Crossed watermark.
+	pub watermark: Watermark,
+	/// Size of the buffered messages in bytes.
+	pub buffered: usize,
+}
+
+type Callback = dyn Fn(BufferEvent) + Send + Sync;
+
+/// High and low watermarks of the outbound buffer of every connection.
+///
+/// The buffer holds the responses and notifications that were produced but not yet written to the socket.
+/// Once it reaches `high` bytes a [`Watermark::High`] event is emitted, followed by a [`Watermark::Low`]
+/// event once it drained to `low` bytes, until it reaches `high` again.
+#[derive(Clone)]
+pub struct BufferWatermarks {
+	pub(crate) high: usize,
+	pub(crate) low: usize,
+	callback: Arc<Callback>,
+}
+
+impl BufferWatermarks {
+	/// Create watermarks calling `callback` with every [`BufferEvent`].
+	///
+	/// # Panics
+	///
+	/// Panics if `low` is greater than `high`.
+	pub fn new<F>(high: usize, low: usize, callback: F) -> Self
+	where
+		F: Fn(BufferEvent) + Send + Sync + 'static,
+	{
+		assert!(low <= high, "Low watermark {} is greater than high watermark {}", low, high);
+		Self { high, low, callback: Arc::new(callback) }
+	}
+
+	/// Returns watermarks at `high` and `low` calling the callback of `self`, `low` must not be greater than
+	/// `high`.
+	pub(crate) fn with_levels(&self, high: usize, low: usize) -> Self {
+		Self { high, low, callback: self.callback.clone() }
+	}
+}
+
+/// Outbound buffer level of a single connection.
+pub(crate) struct BufferLevel {
+	watermarks: Option<BufferWatermarks>,
+	stats: Arc<ConnectionStats>,
+	connection_id: ConnectionId,
+	buffered: usize,
+	paused: bool,
+}
+
+impl BufferLevel {
+	pub(crate) fn new(
+		watermarks: Option<BufferWatermarks>,
+		stats: Arc<ConnectionStats>,
+		connection_id: ConnectionId,
+	) -> Self {
+		Self { watermarks, stats, connection_id, buffered: 0, paused: false }
+	}
+
+	pub(crate) fn stats(&self) -> &ConnectionStats {
+		&self.stats
+	}
+
+	/// A message of `len` bytes was buffered.
+	pub(crate) fn push(&mut self, len: usize) {
+		self.stats.push(len);
+		self.buffered += len;
+		let high = match self.watermarks.as_ref() {
+			Some(watermarks) => watermarks.high,
+			None => return,
+		};
+		if !self.paused && self.buffered >= high {
+			self.paused = true;
+			self.emit(Watermark::High);
+		}
+	}
+
+	/// A message of `len` bytes was written to the socket.
+	pub(crate) fn pop(&mut self, len: usize) {
+		self.stats.pop(len);
+		self.buffered -= len;
+		let low = match self.watermarks.as_ref() {
+			Some(watermarks) => watermarks.low,
+			None => return,
+		};
+		if self.paused && self.buffered <= low {
+			self.paused = false;
+			self.emit(Watermark::Low);
+		}
+	}
+
+	fn emit(&self, watermark: Watermark) {
+		let watermarks = self.watermarks.as_ref().expect("Only emitted with watermarks; qed");
+		(watermarks.callback)(BufferEvent { connection_id: self.connection_id, watermark, buffered: self.buffered });
+	}
+}
@@ -1,11 +1,12 @@
 #![cfg(test)]
 
-use crate::WsServer;
+use crate::{ConnectionHandler, RpcModule, WsServer};
 use futures::channel::oneshot::{self, Sender};
 use jsonrpsee_test_utils::helpers::*;
 use jsonrpsee_test_utils::types::{Id, WebSocketTestClient};
 use jsonrpsee_types::{error::Error, jsonrpc::JsonValue};
 use std::net::SocketAddr;
+use tokio_util::compat::TokioAsyncReadCompatExt;
 
 /// Spawns a dummy `JSONRPC v2 WebSocket`
 /// It has two hardcoded methods: "say_hello" and "add"
@@ -46,6 +47,471 @@ async fn single_method_call_works() {
 	}
 }
 
+#[tokio::test]
+async fn serve_externally_accepted_connection_works() {
+	let mut module = RpcModule::new();
+	module.register_method("say_hello", |_| Ok("hello")).unwrap();
+	let handler = ConnectionHandler::new(module);
+
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let server_addr = listener.local_addr().unwrap();
+	tokio::spawn(async move {
+		let (socket, remote_addr) = listener.accept().await.unwrap();
+		handler.serve_connection(socket.compat(), remote_addr).await
+	});
+
+	let mut client = WebSocketTestClient::new(server_addr).await.unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = client.send_request_text(req).await.unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+}
+
+#[cfg(feature = "async-std")]
+#[test]
+fn async_std_runtime_works() {
+	use jsonrpsee_types::{jsonrpc::Params, traits::Client};
+	use jsonrpsee_ws_client::WsClientBuilder;
+
+	async_std::task::block_on(async {
+		let (server_started_tx, server_started_rx) = oneshot::channel::<SocketAddr>();
+		async_std::task::spawn(server(server_started_tx));
+		let server_addr = server_started_rx.await.unwrap();
+
+		let client = WsClientBuilder::default().build(&format!("ws://{}", server_addr)).await.unwrap();
+		let response: String = client.request("say_hello", Params::None).await.unwrap();
+		assert_eq!(response, "hello");
+	});
+}
+
+// NOTE: relies on the server tasks running on the current-thread runtime of the test.
+#[cfg(feature = "tokio1")]
+#[tokio::test]
+async fn close_connection_works() {
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server.register_method("say_hello", |_| Ok("hello")).unwrap();
+	let server_addr = server.local_addr().unwrap();
+	let connections = server.connections();
+	tokio::spawn(server.start());
+
+	let mut client = WebSocketTestClient::new(server_addr).await.unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = client.send_request_text(req).await.unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+
+	let open = connections.list();
+	assert_eq!(open.len(), 1);
+	assert!(connections.close(open[0].id, 1008, "misbehaving peer"));
+	assert!(!connections.close(open[0].id, 1008, "misbehaving peer"));
+	assert!(connections.list().is_empty());
+	assert!(client.send_request_text(req).await.is_err());
+}
+
+#[tokio::test]
+async fn memory_stats_report_subscriptions() {
+	use jsonrpsee_types::{jsonrpc::Params, traits::SubscriptionClient};
+	use jsonrpsee_ws_client::{WsClientBuilder, WsSubscription};
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	let mut sink = server.register_subscription("subscribe_hello", "unsubscribe_hello").unwrap();
+	let server_addr = server.local_addr().unwrap();
+	let connections = server.connections();
+	tokio::spawn(server.start());
+
+	let client = WsClientBuilder::default().build(&format!("ws://{}", server_addr)).await.unwrap();
+	let mut sub: WsSubscription<String> =
+		client.subscribe("subscribe_hello", Params::None, "unsubscribe_hello").await.unwrap();
+	sink.send(&"hello").unwrap();
+	assert_eq!(sub.next().await, Some("hello".to_string()));
+
+	let stats = connections.memory_stats();
+	assert_eq!(stats.len(), 1);
+	assert_eq!(stats[0].id, connections.list()[0].id);
+	assert_eq!(stats[0].subscriptions, 1);
+}
+
+#[tokio::test]
+async fn topics_publish_works() {
+	use jsonrpsee_types::{jsonrpc::Params, traits::SubscriptionClient};
+	use jsonrpsee_ws_client::{WsClientBuilder, WsSubscription};
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	let topics = server.register_topics("subscribe_topic", "unsubscribe_topic").unwrap();
+	assert!(topics.add_topic("news"));
+	assert!(!topics.add_topic("news"));
+	let server_addr = server.local_addr().unwrap();
+	tokio::spawn(server.start());
+
+	let client = WsClientBuilder::default().build(&format!("ws://{}", server_addr)).await.unwrap();
+	let mut sub: WsSubscription<String> =
+		client.subscribe("subscribe_topic", Params::Array(vec!["news".into()]), "unsubscribe_topic").await.unwrap();
+	let unknown: Result<WsSubscription<String>, Error> =
+		client.subscribe("subscribe_topic", Params::Array(vec!["weather".into()]), "unsubscribe_topic").await;
+	assert!(matches!(unknown, Err(Error::Request(_))));
+
+	assert_eq!(topics.subscribers("news"), Some(1));
+	topics.publish("news", &"hello").unwrap();
+	assert_eq!(sub.next().await, Some("hello".to_string()));
+	assert!(topics.publish("weather", &"sunny").is_err());
+
+	assert!(topics.remove_topic("news"));
+	assert!(topics.topics().is_empty());
+}
+
+#[tokio::test]
+async fn topics_apply_lag_policy_to_full_subscriber_queues() {
+	use crate::LagPolicy;
+	use jsonrpsee_types::{jsonrpc::Params, traits::SubscriptionClient};
+	use jsonrpsee_ws_client::{WsClientBuilder, WsSubscription};
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	let topics = server.register_topics("subscribe_topic", "unsubscribe_topic").unwrap();
+	assert!(topics.add_topic("news"));
+	let server_addr = server.local_addr().unwrap();
+	tokio::spawn(server.start());
+	let client = WsClientBuilder::default().build(&format!("ws://{}", server_addr)).await.unwrap();
+	let params = Params::Array(vec!["news".into()]);
+
+	// NOTE: the queues are forwarded by tasks of this single-threaded runtime, thus not before the next `await`.
+	topics.set_lag_policy(2, LagPolicy::DropNewest);
+	let mut sub: WsSubscription<u32> =
+		client.subscribe("subscribe_topic", params.clone(), "unsubscribe_topic").await.unwrap();
+	for value in 0..4 {
+		topics.publish("news", &value).unwrap();
+	}
+	assert_eq!(sub.next().await, Some(0));
+	assert_eq!(sub.next().await, Some(1));
+	topics.publish("news", &4).unwrap();
+	assert_eq!(sub.next().await, Some(4));
+	drop(sub);
+
+	topics.set_lag_policy(2, LagPolicy::Unsubscribe);
+	let mut sub: WsSubscription<u32> = client.subscribe("subscribe_topic", params, "unsubscribe_topic").await.unwrap();
+	assert_eq!(topics.subscribers("news"), Some(1));
+	for value in 0..3 {
+		topics.publish("news", &value).unwrap();
+	}
+	assert_eq!(topics.subscribers("news"), Some(0));
+	assert_eq!(sub.next().await, Some(0));
+	assert_eq!(sub.next().await, Some(1));
+}
+
+#[tokio::test]
+async fn heartbeats_keep_quiet_subscriptions_alive() {
+	use jsonrpsee_types::{jsonrpc::Params, traits::SubscriptionClient};
+	use jsonrpsee_ws_client::{WsClientBuilder, WsSubscription};
+	use std::time::Duration;
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	let mut sink = server.register_subscription("subscribe_hello", "unsubscribe_hello").unwrap();
+	sink.spawn_heartbeat(Duration::from_millis(20));
+	let server_addr = server.local_addr().unwrap();
+	tokio::spawn(server.start());
+
+	let client = WsClientBuilder::default()
+		.subscription_heartbeat(Duration::from_millis(100))
+		.build(&format!("ws://{}", server_addr))
+		.await
+		.unwrap();
+	let mut sub: WsSubscription<String> =
+		client.subscribe("subscribe_hello", Params::None, "unsubscribe_hello").await.unwrap();
+	// Quiet for longer than the timeout of the client, the heartbeats aren't delivered.
+	tokio::time::sleep(Duration::from_millis(300)).await;
+	sink.send(&"hello").unwrap();
+	assert_eq!(sub.next().await, Some("hello".to_string()));
+}
+
+#[tokio::test]
+async fn topics_replay_last_values_to_new_subscribers() {
+	use crate::ReplayBuffer;
+	use jsonrpsee_types::{jsonrpc::Params, traits::SubscriptionClient};
+	use jsonrpsee_ws_client::{WsClientBuilder, WsSubscription};
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	let topics = server.register_topics("subscribe_topic", "unsubscribe_topic").unwrap();
+	assert!(topics.add_topic_with_replay("heads", ReplayBuffer::new(2)));
+	let server_addr = server.local_addr().unwrap();
+	tokio::spawn(server.start());
+
+	for head in 0..3 {
+		topics.publish("heads", &head).unwrap();
+	}
+
+	let client = WsClientBuilder::default().build(&format!("ws://{}", server_addr)).await.unwrap();
+	let mut sub: WsSubscription<u32> =
+		client.subscribe("subscribe_topic", Params::Array(vec!["heads".into()]), "unsubscribe_topic").await.unwrap();
+	topics.publish("heads", &3).unwrap();
+	for head in 1..4 {
+		assert_eq!(sub.next().await, Some(head));
+	}
+}
+
+#[tokio::test]
+async fn audit_log_records_calls() {
+	use crate::{AuditLog, AuditParams};
+	use jsonrpsee_types::v2::error::METHOD_NOT_FOUND_CODE;
+
+	let (audit_tx, mut audit_rx) = tokio::sync::mpsc::unbounded_channel();
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server.register_method("say_hello", |_| Ok("hello")).unwrap();
+	server.set_audit_log(AuditLog::new(audit_tx).redact_params(|method, _| format!("<{} params>", method)));
+	let server_addr = server.local_addr().unwrap();
+	tokio::spawn(server.start());
+
+	let mut client = WebSocketTestClient::new(server_addr).await.unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","params":["secret"],"id":1}"#;
+	client.send_request_text(req).await.unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"say_bye","id":"two"}"#;
+	client.send_request_text(req).await.unwrap();
+
+	let record = audit_rx.recv().await.unwrap();
+	assert_eq!(record.method, "say_hello");
+	assert_eq!(record.params, AuditParams::Redacted("<say_hello params>".to_owned()));
+	assert_eq!(record.code, None);
+
+	let record = audit_rx.recv().await.unwrap();
+	assert_eq!(record.method, "say_bye");
+	assert_eq!(record.params, AuditParams::None);
+	assert_eq!(record.code, Some(METHOD_NOT_FOUND_CODE));
+	assert!(record.peer.contains("127.0.0.1"));
+}
+
+#[tokio::test]
+async fn audit_log_masks_params_with_redaction() {
+	use crate::{AuditLog, AuditParams};
+	use jsonrpsee_types::redact::Redaction;
+
+	let (audit_tx, mut audit_rx) = tokio::sync::mpsc::unbounded_channel();
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server.register_method("unlock", |_| Ok(true)).unwrap();
+	server.set_audit_log(AuditLog::new(audit_tx).redaction(Redaction::new().position("unlock", 1).key("otp")));
+	let server_addr = server.local_addr().unwrap();
+	tokio::spawn(server.start());
+
+	let mut client = WebSocketTestClient::new(server_addr).await.unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"unlock","params":["alice","hunter2",{"otp":123456}],"id":1}"#;
+	client.send_request_text(req).await.unwrap();
+
+	let record = audit_rx.recv().await.unwrap();
+	assert_eq!(record.params, AuditParams::Redacted(r#"["alice","[REDACTED]",{"otp":"[REDACTED]"}]"#.to_owned()));
+}
+
+#[tokio::test]
+async fn cancellable_method_is_answered_unless_cancelled() {
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server.register_cancellable_method("say_hello", |_, token| Ok(!token.is_cancelled())).unwrap();
+	server.register_cancellation().unwrap();
+	assert!(matches!(server.register_cancellation(), Err(Error::MethodAlreadyRegistered(_))));
+	let server_addr = server.local_addr().unwrap();
+	tokio::spawn(server.start());
+
+	let mut client = WebSocketTestClient::new(server_addr).await.unwrap();
+	let response = client.send_request_text(r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#).await.unwrap();
+	assert_eq!(response, ok_response(JsonValue::Bool(true), Id::Num(1)));
+
+	// The call was already answered.
+	let req = r#"{"jsonrpc":"2.0","method":"rpc_cancel","params":[1],"id":2}"#;
+	let response = client.send_request_text(req).await.unwrap();
+	assert_eq!(response, ok_response(JsonValue::Bool(false), Id::Num(2)));
+}
+
+#[tokio::test]
+async fn handshake_limits_drop_excess_connections() {
+	use crate::HandshakeLimits;
+	use std::time::Duration;
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server.register_method("say_hello", |_| Ok("hello")).unwrap();
+	server.set_handshake_limits(HandshakeLimits::new().max_pending(1).timeout(Duration::from_millis(200)));
+	let server_addr = server.local_addr().unwrap();
+	tokio::spawn(server.start());
+
+	// Holds the only pending slot without sending the handshake.
+	let _silent = tokio::net::TcpStream::connect(server_addr).await.unwrap();
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	assert!(WebSocketTestClient::new(server_addr).await.is_err());
+
+	// The slot is released once the handshake timed out.
+	tokio::time::sleep(Duration::from_millis(300)).await;
+	let mut client = WebSocketTestClient::new(server_addr).await.unwrap();
+	let response = client.send_request_text(r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#).await.unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn handshake_rate_is_limited() {
+	use crate::HandshakeLimits;
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server.set_handshake_limits(HandshakeLimits::new().max_per_second(2));
+	let server_addr = server.local_addr().unwrap();
+	tokio::spawn(server.start());
+
+	assert!(WebSocketTestClient::new(server_addr).await.is_ok());
+	assert!(WebSocketTestClient::new(server_addr).await.is_ok());
+	assert!(WebSocketTestClient::new(server_addr).await.is_err());
+}
+
+#[tokio::test]
+async fn methods_can_be_disabled_at_runtime() {
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	let flags = server.method_flags();
+	server.register_method("say_hello", |_| Ok("hello")).unwrap();
+	server.register_method("admin_enableMethod", flags.enable_method()).unwrap();
+	server.register_method("admin_disableMethod", flags.disable_method()).unwrap();
+	let server_addr = server.local_addr().unwrap();
+	tokio::spawn(server.start());
+
+	let mut client = WebSocketTestClient::new(server_addr).await.unwrap();
+	let hello = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let disable = r#"{"jsonrpc":"2.0","method":"admin_disableMethod","params":["say_hello"],"id":2}"#;
+	let response = client.send_request_text(disable).await.unwrap();
+	assert_eq!(response, ok_response(JsonValue::Bool(true), Id::Num(2)));
+	assert!(!flags.is_enabled("say_hello"));
+
+	let response = client.send_request_text(hello).await.unwrap();
+	assert_eq!(response, r#"{"jsonrpc":"2.0","error":{"code":-32001,"message":"Method disabled"},"id":1}"#);
+
+	flags.enable("say_hello");
+	let response = client.send_request_text(hello).await.unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn custom_dispatcher_routes_calls() {
+	use crate::MethodDispatcher;
+	use jsonrpsee_types::v2::RpcParams;
+	use jsonrpsee_utils::server_utils::{send_response, ConnectionId, Methods, RpcId, RpcSender};
+
+	// Answers `<tenant>_hello` for any tenant, other calls are routed to the built-in router.
+	struct TenantRouter(Methods);
+
+	impl MethodDispatcher for TenantRouter {
+		fn dispatch(
+			&self,
+			method: &str,
+			id: RpcId,
+			params: RpcParams,
+			tx: RpcSender,
+			conn_id: ConnectionId,
+		) -> anyhow::Result<()> {
+			match method.strip_suffix("_hello") {
+				Some(tenant) => {
+					send_response(id, tx, format!("hello {}", tenant));
+					Ok(())
+				}
+				None => self.0.dispatch(method, id, params, tx, conn_id),
+			}
+		}
+	}
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server.register_method("say_goodbye", |_| Ok("goodbye")).unwrap();
+	server.set_dispatcher(TenantRouter(Methods::default()));
+	let server_addr = server.local_addr().unwrap();
+	tokio::spawn(server.start());
+
+	let mut client = WebSocketTestClient::new(server_addr).await.unwrap();
+	let response = client.send_request_text(r#"{"jsonrpc":"2.0","method":"acme_hello","id":1}"#).await.unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello acme".to_owned()), Id::Num(1)));
+	// The registered methods are not served.
+	let response = client.send_request_text(r#"{"jsonrpc":"2.0","method":"say_goodbye","id":2}"#).await.unwrap();
+	assert_eq!(response, method_not_found(Id::Num(2)));
+}
+
+#[tokio::test]
+async fn on_connect_selects_method_table() {
+	use crate::{ConnectRequest, MethodTable};
+	use jsonrpsee_types::{jsonrpc::Params, traits::Client};
+	use jsonrpsee_ws_client::WsClientBuilder;
+
+	let mut acme = RpcModule::new();
+	acme.register_method("say_hello", |_| Ok("hello acme")).unwrap();
+	let acme = MethodTable::from(acme);
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server.register_method("say_hello", |_| Ok("hello")).unwrap();
+	server.set_on_connect(move |req: &ConnectRequest| (req.path == "/acme").then(|| acme.clone()));
+	let server_addr = server.local_addr().unwrap();
+	tokio::spawn(server.start());
+
+	let uri = format!("ws://{}", server_addr);
+	let acme = WsClientBuilder::default().handshake_url("/acme".into()).build(&uri).await.unwrap();
+	let response: String = acme.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(response, "hello acme");
+	let other = WsClientBuilder::default().handshake_url("/other".into()).build(&uri).await.unwrap();
+	let response: String = other.request("say_hello", Params::None).await.unwrap();
+	assert_eq!(response, "hello");
+}
+
+#[tokio::test]
+async fn routes_are_served_at_their_path() {
+	use crate::Route;
+	use jsonrpsee_types::{jsonrpc::Params, traits::Client};
+	use jsonrpsee_ws_client::WsClientBuilder;
+
+	let mut admin = RpcModule::new();
+	admin.register_method("shutdown", |_| Ok(true)).unwrap();
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server.register_method("say_hello", |_| Ok("hello")).unwrap();
+	server.mount("/admin", Route::new(admin).max_connections(1)).unwrap();
+	assert!(matches!(server.mount("/admin", Route::new(RpcModule::new())), Err(Error::PathAlreadyMounted(_))));
+	let server_addr = server.local_addr().unwrap();
+	tokio::spawn(server.start());
+
+	let uri = format!("ws://{}", server_addr);
+	let admin = WsClientBuilder::default().handshake_url("/admin?token=1".into()).build(&uri).await.unwrap();
+	assert!(admin.request::<bool, _, _>("shutdown", Params::None).await.unwrap());
+	assert!(admin.request::<String, _, _>("say_hello", Params::None).await.is_err());
+	assert!(WsClientBuilder::default().handshake_url("/admin".into()).build(&uri).await.is_err());
+
+	let public = WsClientBuilder::default().build(&uri).await.unwrap();
+	assert_eq!(public.request::<String, _, _>("say_hello", Params::None).await.unwrap(), "hello");
+	assert!(public.request::<bool, _, _>("shutdown", Params::None).await.is_err());
+}
+
+// NOTE: relies on the server tasks running on the current-thread runtime of the test.
+#[cfg(feature = "tokio1")]
+#[tokio::test]
+async fn buffer_watermarks_are_emitted() {
+	use crate::{BufferWatermarks, Watermark};
+
+	const HIGH: usize = 1024 * 1024;
+
+	let (events_tx, mut events_rx) = tokio::sync::mpsc::unbounded_channel();
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	let mut sink = server.register_subscription("subscribe_hello", "unsubscribe_hello").unwrap();
+	server.set_buffer_watermarks(BufferWatermarks::new(HIGH, HIGH / 2, move |event| {
+		let _ = events_tx.send(event);
+	}));
+	let server_addr = server.local_addr().unwrap();
+	let connections = server.connections();
+	tokio::spawn(server.start());
+
+	let mut client = WebSocketTestClient::new(server_addr).await.unwrap();
+	client.send_request_text(r#"{"jsonrpc":"2.0","method":"subscribe_hello","id":1}"#).await.unwrap();
+
+	// The client doesn't read, so the notifications pile up once the socket buffers are full.
+	let notif = "a".repeat(16 * 1024);
+	for _ in 0..(4 * HIGH / notif.len()) {
+		sink.send(&notif).unwrap();
+	}
+	let high = events_rx.recv().await.unwrap();
+	assert_eq!(high.watermark, Watermark::High);
+	assert!(high.buffered >= HIGH);
+	assert!(connections.memory_stats()[0].buffered_bytes >= HIGH);
+
+	let low = loop {
+		client.receive().await.unwrap();
+		if let Ok(event) = events_rx.try_recv() {
+			break event;
+		}
+	};
+	assert_eq!(low.watermark, Watermark::Low);
+	assert_eq!(low.connection_id, high.connection_id);
+	assert!(low.buffered <= HIGH / 2);
+}
+
 #[tokio::test]
 async fn single_method_call_with_params_works() {
 	let (server_started_tx, server_started_rx) = oneshot::channel::<SocketAddr>();
@@ -158,3 +624,178 @@ async fn invalid_request_should_not_close_connection() {
 	let response = client.send_request_text(request).await.unwrap();
 	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(33)));
 }
+
+#[test]
+fn notification_template_matches_serialized_notification() {
+	use crate::server::NotificationTemplate;
+	use jsonrpsee_types::v2::{JsonRpcNotification, JsonRpcNotificationParams, TwoPointZero};
+
+	let method = r#"sub_"subscription":0"#;
+	let result = serde_json::value::to_raw_value(&serde_json::json!({ "subscription": 0, "data": [1, 2] })).unwrap();
+	let mut notif = NotificationTemplate::new(method, &result).unwrap();
+
+	for sub_id in [1_u64, 0, u64::MAX, 42].iter().copied() {
+		let expected = serde_json::to_string(&JsonRpcNotification {
+			jsonrpc: TwoPointZero,
+			method,
+			params: JsonRpcNotificationParams { subscription: sub_id, result: &result },
+		})
+		.unwrap();
+		assert_eq!(notif.render(sub_id), expected);
+	}
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn fair_scheduler_serves_other_connections_in_turn() {
+	use crate::FairScheduler;
+	use std::sync::{mpsc, Mutex};
+	use std::time::Duration;
+
+	// Calls to `slow` block until they are released, `block_in_place` lets the other connections run meanwhile.
+	let (entered_tx, mut entered_rx) = tokio::sync::mpsc::unbounded_channel();
+	let (release_tx, release_rx) = mpsc::channel::<()>();
+	let release_rx = Mutex::new(release_rx);
+	let scheduler = FairScheduler::new(1);
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server
+		.register_method("slow", move |_| {
+			entered_tx.send(()).unwrap();
+			tokio::task::block_in_place(|| release_rx.lock().unwrap().recv().unwrap());
+			Ok("done")
+		})
+		.unwrap();
+	server.register_method("say_hello", |_| Ok("hello")).unwrap();
+	server.set_fair_scheduler(scheduler.clone());
+	let server_addr = server.local_addr().unwrap();
+	tokio::spawn(server.start());
+
+	let mut noisy = WebSocketTestClient::new(server_addr).await.unwrap();
+	for i in 0..3 {
+		noisy.send_text(format!(r#"{{"jsonrpc":"2.0","method":"slow","id":{}}}"#, i)).await.unwrap();
+	}
+	entered_rx.recv().await.unwrap();
+
+	let mut quiet = WebSocketTestClient::new(server_addr).await.unwrap();
+	let call = tokio::spawn(async move {
+		quiet.send_request_text(r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#).await.unwrap()
+	});
+	while scheduler.waiting() == 0 {
+		tokio::time::sleep(Duration::from_millis(1)).await;
+	}
+
+	// The call gets the turn of the first call of the noisy connection, its other calls are still blocked.
+	release_tx.send(()).unwrap();
+	assert_eq!(call.await.unwrap(), ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+	assert_eq!(noisy.receive().await.unwrap(), ok_response(JsonValue::String("done".to_owned()), Id::Num(0)));
+
+	for i in 1..3 {
+		release_tx.send(()).unwrap();
+		assert_eq!(noisy.receive().await.unwrap(), ok_response(JsonValue::String("done".to_owned()), Id::Num(i)));
+	}
+}
+
+#[tokio::test]
+async fn validate_reports_configuration_issues() {
+	use crate::HandshakeLimits;
+	use jsonrpsee_utils::server_utils::{ConfigIssue, Idempotency, Methods};
+	use std::time::Duration;
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server.register_method("say_hello", |_| Ok("hello")).unwrap();
+	server.set_idempotency(Idempotency::new(["transfer"], Duration::from_secs(60)));
+	server.set_handshake_limits(HandshakeLimits::new().max_per_second(0));
+	let report = server.validate();
+	assert!(matches!(report.issues[0], ConfigIssue::ConflictingLimits(_)));
+	assert_eq!(
+		report.issues[1],
+		ConfigIssue::UnknownMethod { setting: "idempotency settings", method: "transfer".into() }
+	);
+	assert_eq!(report.issues.len(), 2);
+
+	server.set_handshake_limits(HandshakeLimits::new());
+	server.set_dispatcher(Methods::default());
+	assert_eq!(server.validate().issues, vec![ConfigIssue::MethodsReplacedByDispatcher]);
+}
+
+#[tokio::test]
+async fn validate_rejects_wrapped_cancellable_methods() {
+	use jsonrpsee_utils::server_utils::{ConfigIssue, Idempotency, Transformers};
+	use std::time::Duration;
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	server.register_method("say_hello", |_| Ok("hello")).unwrap();
+	server.register_cancellable_method("compute", |_, token| Ok(!token.is_cancelled())).unwrap();
+	server.set_idempotency(Idempotency::new(["compute", "say_hello"], Duration::from_secs(60)));
+	server.set_transformers(Transformers::new().result("compute", |result| result));
+	assert_eq!(
+		server.validate().issues,
+		vec![
+			ConfigIssue::DeferredResponse { setting: "idempotency settings", method: "compute".into() },
+			ConfigIssue::DeferredResponse { setting: "transformers", method: "compute".into() },
+		]
+	);
+
+	server.set_idempotency(Idempotency::new(["say_hello"], Duration::from_secs(60)));
+	server.set_transformers(Transformers::new().params("say_hello", |params| params));
+	assert!(server.validate().is_ok());
+}
+
+#[tokio::test]
+async fn config_round_trips_through_server() {
+	use crate::{BufferWatermarks, ServerConfig};
+
+	let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+	assert_eq!(server.to_config(), ServerConfig::default());
+	assert!(serde_json::from_str::<ServerConfig>(r#"{"max_connections":1}"#).is_err());
+
+	let config: ServerConfig = serde_json::from_str(
+		r#"{
+			"handshake_limits": { "max_pending": 16, "timeout_ms": 5000 },
+			"bandwidth_limit": { "bytes_per_second": 1024, "burst": 4096, "policy": "Close" },
+			"buffer_watermarks": { "high": 1024, "low": 256 },
+			"method_normalization": "CaseInsensitive",
+			"idempotency": { "methods": ["transfer"], "ttl_ms": 60000 },
+			"fair_scheduler": { "max_concurrent": 4, "default_weight": 2 }
+		}"#,
+	)
+	.unwrap();
+	// The callback of the buffer watermarks isn't plain data.
+	assert_eq!(server.set_config(config.clone()).unwrap_err().option, "buffer_watermarks");
+	assert_eq!(server.to_config(), ServerConfig::default());
+	server.set_buffer_watermarks(BufferWatermarks::new(1, 0, |_| ()));
+	server.set_config(config.clone()).unwrap();
+	assert_eq!(server.to_config(), config);
+
+	let invalid: ServerConfig = serde_json::from_str(r#"{"bandwidth_limit":{"bytes_per_second":0}}"#).unwrap();
+	assert_eq!(server.set_config(invalid).unwrap_err().option, "bandwidth_limit");
+	assert_eq!(server.to_config(), config);
+}
+
+#[tokio::test]
+async fn bandwidth_limit_throttles_or_closes_connections() {
+	use crate::{BandwidthLimit, BandwidthPolicy};
+	use std::time::{Duration, Instant};
+
+	async fn server(limit: BandwidthLimit) -> SocketAddr {
+		let mut server = WsServer::new("127.0.0.1:0").await.unwrap();
+		server.register_method("blob", |_| Ok("x".repeat(2_000))).unwrap();
+		server.set_bandwidth_limit(limit);
+		let server_addr = server.local_addr().unwrap();
+		tokio::spawn(server.start());
+		server_addr
+	}
+	let blob = r#"{"jsonrpc":"2.0","method":"blob","id":1}"#;
+
+	let mut client = WebSocketTestClient::new(server(BandwidthLimit::new(10_000).burst(1_000)).await).await.unwrap();
+	let started = Instant::now();
+	for _ in 0..3 {
+		assert!(client.send_request_text(blob).await.unwrap().contains("xxx"));
+	}
+	// The first response empties the bucket, the others wait for 1_000 bytes of debt plus 1_000 bytes of burst.
+	assert!(started.elapsed() >= Duration::from_millis(350));
+
+	let limit = BandwidthLimit::new(10_000).burst(1_000).policy(BandwidthPolicy::Close);
+	let mut client = WebSocketTestClient::new(server(limit).await).await.unwrap();
+	assert!(client.send_request_text(blob).await.unwrap().contains("xxx"));
+	assert!(client.send_request_text(blob).await.is_err());
+}